@@ -0,0 +1,118 @@
+use crate::*;
+use near_sdk::Allowance;
+
+/// The methods operator keys can call on the contract.
+const OPERATOR_KEY_METHODS: &str = "create_campaign";
+
+/// A function call access key of the contract's own account, e.g. of an operations bot.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OperatorKey {
+    pub public_key: PublicKey,
+    /// The gas allowance the key was added with
+    pub allowance: NearToken,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OperatorKeyEvent {
+    pub public_key: PublicKey,
+    /// `false` when the key was removed
+    pub added: bool,
+}
+
+impl MerkleClaim {
+    /// Whether the call was signed with an operator key of the contract's own account.
+    pub(crate) fn is_operator_key_call(&self) -> bool {
+        env::predecessor_account_id() == env::current_account_id()
+            && self.operator_keys.contains_key(&env::signer_account_pk())
+    }
+
+    /// Panics unless the caller may create a campaign directly: the owner, or an operator key
+    /// while multisig confirmations are disabled, as campaigns must then be proposed.
+    pub(crate) fn assert_campaign_creator(&self) {
+        if !self.is_operator_key_call() {
+            self.assert_owner_action();
+            return;
+        }
+
+        require!(
+            self.multisig.is_none(),
+            "Operator keys cannot create campaigns while multisig confirmations are enabled"
+        );
+    }
+
+    fn log_operator_key(public_key: PublicKey, added: bool) {
+        let event = OperatorKeyEvent { public_key, added };
+
+        Event::OperatorKey(event).emit();
+    }
+
+    /// Adds an operator key for `add_operator_key` or a confirmed multisig action.
+    pub(crate) fn internal_add_operator_key(
+        &mut self,
+        public_key: PublicKey,
+        allowance: NearToken,
+    ) {
+        let limited =
+            Allowance::limited(allowance).expect("Operator key allowance must not be zero");
+        require!(
+            !self.operator_keys.contains_key(&public_key),
+            "Public key is already an operator key"
+        );
+        self.operator_keys.insert(public_key.clone(), allowance);
+        self.record_admin_action("add_operator_key", String::from(&public_key));
+
+        Promise::new(env::current_account_id()).add_access_key_allowance(
+            public_key.clone(),
+            limited,
+            env::current_account_id(),
+            OPERATOR_KEY_METHODS.to_string(),
+        );
+
+        Self::log_operator_key(public_key, true);
+    }
+
+    /// Deletes an operator key for `remove_operator_key` or a confirmed multisig action.
+    pub(crate) fn internal_remove_operator_key(&mut self, public_key: PublicKey) {
+        require!(
+            self.operator_keys.remove(&public_key).is_some(),
+            "Public key is not an operator key"
+        );
+        self.record_admin_action("remove_operator_key", String::from(&public_key));
+
+        Promise::new(env::current_account_id()).delete_key(public_key.clone());
+
+        Self::log_operator_key(public_key, false);
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Adds `public_key` to the contract's account as a function call access key that can only
+    /// call `create_campaign` and spend at most `allowance` on gas, e.g. for a bot creating
+    /// campaigns without any real account key. Operator keys cannot create campaigns while
+    /// multisig confirmations are enabled.
+    #[payable]
+    pub fn add_operator_key(&mut self, public_key: PublicKey, allowance: NearToken) {
+        self.assert_owner_action();
+        self.internal_add_operator_key(public_key, allowance);
+    }
+
+    /// Deletes an operator key added with `add_operator_key` from the contract's account.
+    #[payable]
+    pub fn remove_operator_key(&mut self, public_key: PublicKey) {
+        self.assert_owner_action();
+        self.internal_remove_operator_key(public_key);
+    }
+
+    pub fn get_operator_keys(&self) -> Vec<OperatorKey> {
+        self.operator_keys
+            .iter()
+            .map(|(public_key, allowance)| OperatorKey {
+                public_key: public_key.clone(),
+                allowance: *allowance,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,342 @@
+use crate::config::ConfigPatch;
+use crate::*;
+
+pub type ProposalId = u32;
+
+/// How long proposals can be confirmed unless configured otherwise, 7 days in nanoseconds.
+pub const DEFAULT_PROPOSAL_LIFETIME: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+fn default_proposal_lifetime() -> U64 {
+    U64(DEFAULT_PROPOSAL_LIFETIME)
+}
+
+/// Signers who confirm owner actions instead of the owner.
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers=[borsh, json])]
+pub struct Multisig {
+    pub signers: Vec<AccountId>,
+    /// The number of signers that must confirm an action before it is executed
+    pub threshold: u32,
+    /// How long a proposal can be confirmed, in nanoseconds
+    #[serde(default = "default_proposal_lifetime")]
+    pub proposal_lifetime: U64,
+}
+
+impl Multisig {
+    fn validated(self) -> Self {
+        require!(
+            !self.signers.is_empty(),
+            "Multisig signers must not be empty"
+        );
+        let mut signers = self.signers.clone();
+        signers.sort_unstable();
+        signers.dedup();
+        require!(
+            signers.len() == self.signers.len(),
+            "Multisig signers must be distinct"
+        );
+        require!(
+            self.threshold > 0 && self.threshold as usize <= self.signers.len(),
+            "Multisig threshold must be between 1 and the number of signers"
+        );
+        require!(
+            self.proposal_lifetime.0 > 0,
+            "Proposal lifetime must not be zero"
+        );
+        self
+    }
+}
+
+/// An owner action executed once enough multisig signers confirmed it, taking the arguments of
+/// the method of the same name.
+#[derive(Clone)]
+#[near(serializers=[borsh, json])]
+pub enum OwnerAction {
+    Withdraw,
+    WithdrawTo {
+        receiver: AccountId,
+    },
+    WithdrawAmount {
+        amount: NearToken,
+        /// The account paid instead of the default withdraw receiver or owner
+        receiver: Option<AccountId>,
+    },
+    RequestWithdraw {
+        amount: U128,
+    },
+    WithdrawStorageSurplus {
+        amount: Option<NearToken>,
+    },
+    CreateCampaign {
+        merkle_root: MerkleRootInput,
+        claim_end: Option<U64>,
+        options: Option<CampaignOptions>,
+    },
+    CancelCampaign {
+        campaign_id: CampaignId,
+    },
+    ExtendCampaign {
+        campaign_id: CampaignId,
+        claim_end: U64,
+    },
+    SetCampaignMemo {
+        campaign_id: CampaignId,
+        memo: Option<String>,
+    },
+    UpdateConfig {
+        patch: ConfigPatch,
+    },
+    AddOperatorKey {
+        public_key: PublicKey,
+        allowance: NearToken,
+    },
+    RemoveOperatorKey {
+        public_key: PublicKey,
+    },
+    /// Replaces the signers, or hands owner actions back to the owner if `None`
+    SetMultisig {
+        multisig: Option<Multisig>,
+    },
+}
+
+#[derive(Clone)]
+#[near(serializers=[borsh, json])]
+pub struct Proposal {
+    pub action: OwnerAction,
+    pub proposer: AccountId,
+    /// The signers that confirmed the action, including the proposer
+    pub confirmations: Vec<AccountId>,
+    /// The timestamp after which the proposal can no longer be confirmed
+    pub expires_at: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ActionProposedEvent {
+    pub proposal_id: ProposalId,
+    pub proposer: AccountId,
+    pub action: OwnerAction,
+    pub expires_at: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ActionConfirmedEvent {
+    pub proposal_id: ProposalId,
+    pub account_id: AccountId,
+    pub confirmations: u32,
+    /// Whether the confirmation reached the threshold and the action was executed
+    pub executed: bool,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MultisigUpdatedEvent {
+    /// `None` when owner actions were handed back to the owner
+    pub multisig: Option<Multisig>,
+}
+
+impl MerkleClaim {
+    /// Panics unless the owner may call an owner action directly, which it may not while
+    /// multisig confirmations are enabled. Owner actions need 1 yoctoNEAR attached, so only a
+    /// full access key can call them.
+    pub(crate) fn assert_owner_action(&self) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(
+            self.multisig.is_none(),
+            "Owner actions must be proposed to the multisig signers"
+        );
+    }
+
+    /// Returns the multisig, panicking unless the caller is one of its signers.
+    fn assert_signer(&self) -> &Multisig {
+        let multisig = self
+            .multisig
+            .as_ref()
+            .expect("Multisig confirmations are not enabled");
+        require!(
+            multisig.signers.contains(&env::predecessor_account_id()),
+            "Only multisig signers can call this method"
+        );
+        multisig
+    }
+
+    fn update_multisig(&mut self, multisig: Option<Multisig>) {
+        self.multisig = multisig.map(Multisig::validated);
+        self.record_admin_action(
+            "set_multisig",
+            self.multisig
+                .as_ref()
+                .map_or("disabled".to_string(), |multisig| {
+                    format!(
+                        "{} of {} signers",
+                        multisig.threshold,
+                        multisig.signers.len()
+                    )
+                }),
+        );
+
+        let updated = MultisigUpdatedEvent {
+            multisig: self.multisig.clone(),
+        };
+        Event::MultisigUpdated(updated).emit();
+    }
+
+    /// Records the caller's confirmation and executes the action once the signers that
+    /// confirmed it reach the threshold. Confirmations of former signers do not count.
+    fn confirm_proposal(&mut self, proposal_id: ProposalId, mut proposal: Proposal) {
+        let account_id = env::predecessor_account_id();
+        require!(
+            !proposal.confirmations.contains(&account_id),
+            "Action already confirmed by this signer"
+        );
+        proposal.confirmations.push(account_id.clone());
+
+        let multisig = self.multisig.as_ref().unwrap();
+        let confirmations = proposal
+            .confirmations
+            .iter()
+            .filter(|signer| multisig.signers.contains(signer))
+            .count() as u32;
+        let executed = confirmations >= multisig.threshold;
+
+        let confirmed = ActionConfirmedEvent {
+            proposal_id,
+            account_id,
+            confirmations,
+            executed,
+        };
+        Event::ActionConfirmed(confirmed).emit();
+
+        if executed {
+            self.proposals.remove(&proposal_id);
+            self.execute_action(proposal.action);
+        } else {
+            self.proposals.insert(proposal_id, proposal);
+        }
+    }
+
+    fn execute_action(&mut self, action: OwnerAction) {
+        match action {
+            OwnerAction::Withdraw => self.internal_withdraw(None, false),
+            OwnerAction::WithdrawTo { receiver } => self.internal_withdraw(Some(receiver), false),
+            OwnerAction::WithdrawAmount { amount, receiver } => {
+                self.internal_withdraw_amount(amount, receiver, false)
+            }
+            OwnerAction::RequestWithdraw { amount } => self.internal_request_withdraw(amount),
+            OwnerAction::WithdrawStorageSurplus { amount } => {
+                self.internal_withdraw_storage_surplus(amount)
+            }
+            OwnerAction::CreateCampaign {
+                merkle_root,
+                claim_end,
+                options,
+            } => self.internal_create_campaign(merkle_root, claim_end, options),
+            OwnerAction::CancelCampaign { campaign_id } => {
+                self.internal_cancel_campaign(campaign_id);
+            }
+            OwnerAction::ExtendCampaign {
+                campaign_id,
+                claim_end,
+            } => self.internal_extend_campaign(campaign_id, claim_end),
+            OwnerAction::SetCampaignMemo { campaign_id, memo } => {
+                self.internal_set_campaign_memo(campaign_id, memo)
+            }
+            OwnerAction::UpdateConfig { patch } => self.internal_update_config(patch),
+            OwnerAction::AddOperatorKey {
+                public_key,
+                allowance,
+            } => self.internal_add_operator_key(public_key, allowance),
+            OwnerAction::RemoveOperatorKey { public_key } => {
+                self.internal_remove_operator_key(public_key)
+            }
+            OwnerAction::SetMultisig { multisig } => self.update_multisig(multisig),
+        }
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Hands every owner action, see `OwnerAction`, over to `multisig`, whose signers propose
+    /// and confirm them with `propose_action` and `confirm_action`. Afterwards only the signers
+    /// can change or disable the multisig.
+    #[payable]
+    pub fn enable_multisig(&mut self, multisig: Multisig) {
+        self.assert_owner_action();
+        self.update_multisig(Some(multisig));
+    }
+
+    /// Proposes `action` and returns the id of the proposal, which counts as the proposer's
+    /// confirmation. With a threshold of 1 the action is executed right away.
+    #[payable]
+    pub fn propose_action(&mut self, action: OwnerAction) -> ProposalId {
+        assert_one_yocto();
+        let multisig = self.assert_signer();
+        let expires_at = env::block_timestamp() + multisig.proposal_lifetime.0;
+
+        self.last_proposal_id = self
+            .last_proposal_id
+            .checked_add(1)
+            .expect("Proposal id value overflows");
+        let proposal_id = self.last_proposal_id;
+
+        let proposed = ActionProposedEvent {
+            proposal_id,
+            proposer: env::predecessor_account_id(),
+            action: action.clone(),
+            expires_at: U64(expires_at),
+        };
+        Event::ActionProposed(proposed).emit();
+
+        let proposal = Proposal {
+            action,
+            proposer: env::predecessor_account_id(),
+            confirmations: vec![],
+            expires_at: U64(expires_at),
+        };
+        self.confirm_proposal(proposal_id, proposal);
+
+        proposal_id
+    }
+
+    /// Confirms a pending proposal, executing its action if this reaches the threshold.
+    #[payable]
+    pub fn confirm_action(&mut self, proposal_id: ProposalId) {
+        assert_one_yocto();
+        self.assert_signer();
+        let proposal = self
+            .proposals
+            .get(&proposal_id)
+            .cloned()
+            .expect("Proposal does not exist");
+        require!(
+            env::block_timestamp() <= proposal.expires_at.0,
+            "Proposal has expired"
+        );
+
+        self.confirm_proposal(proposal_id, proposal);
+    }
+
+    /// Removes an expired proposal, releasing its storage. Anyone can call it.
+    pub fn remove_expired_proposal(&mut self, proposal_id: ProposalId) {
+        let proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect("Proposal does not exist");
+        require!(
+            env::block_timestamp() > proposal.expires_at.0,
+            "Proposal has not expired yet"
+        );
+
+        self.proposals.remove(&proposal_id);
+    }
+
+    pub fn get_multisig(&self) -> Option<&Multisig> {
+        self.multisig.as_ref()
+    }
+
+    pub fn get_proposal(&self, proposal_id: ProposalId) -> Option<&Proposal> {
+        self.proposals.get(&proposal_id)
+    }
+}
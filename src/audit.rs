@@ -0,0 +1,49 @@
+use crate::*;
+
+/// How many admin actions the admin log keeps, the oldest being overwritten first
+pub const ADMIN_LOG_CAPACITY: u64 = 500;
+const DEFAULT_ADMIN_LOG_LIMIT: u32 = 100;
+
+#[derive(Clone)]
+#[near(serializers=[borsh,json])]
+pub struct AdminAction {
+    /// The position of the action among all admin actions, starting at 0
+    pub id: U64,
+    pub method: String,
+    pub caller: AccountId,
+    pub timestamp: U64,
+    pub summary: String,
+}
+
+impl MerkleClaim {
+    /// Appends a privileged call of `method` by the caller to the admin log, overwriting the
+    /// oldest entry once the log is full.
+    pub(crate) fn record_admin_action(&mut self, method: &str, summary: String) {
+        let id = self.admin_log_length;
+        let action = AdminAction {
+            id: U64(id),
+            method: method.to_string(),
+            caller: env::predecessor_account_id(),
+            timestamp: U64(env::block_timestamp()),
+            summary,
+        };
+
+        self.admin_log.insert(id % ADMIN_LOG_CAPACITY, action);
+        self.admin_log_length += 1;
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Returns up to `limit` admin actions, 100 by default, oldest first, starting at the action
+    /// with id `from` or the oldest one kept. Only the last 500 actions are kept.
+    pub fn get_admin_log(&self, from: Option<U64>, limit: Option<u32>) -> Vec<AdminAction> {
+        let oldest = self.admin_log_length.saturating_sub(ADMIN_LOG_CAPACITY);
+        let from = from.map_or(oldest, |from| from.0.max(oldest));
+
+        (from..self.admin_log_length)
+            .take(limit.unwrap_or(DEFAULT_ADMIN_LOG_LIMIT) as usize)
+            .map(|id| self.admin_log[&(id % ADMIN_LOG_CAPACITY)].clone())
+            .collect()
+    }
+}
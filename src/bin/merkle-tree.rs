@@ -0,0 +1,51 @@
+//! Builds the tree of a campaign from a CSV of `account,lockup,amount` rows.
+//!
+//! ```bash
+//! cargo run --features tree --bin merkle-tree -- allocations.csv tree.json '{"campaign_id": 1}'
+//! ```
+//!
+//! The optional last argument is the `LeafFormat` of the campaign as JSON, fields left out take
+//! their defaults. Formats whose leaves commit to the campaign id must set it, as no campaign has
+//! the default id 0.
+
+use near_merkle_claim::tree::CampaignFile;
+use near_merkle_claim::LeafFormat;
+use std::process::exit;
+
+fn run(args: &[String]) -> Result<String, String> {
+    let (input, output, format) = match args {
+        [input, output] => (input, output, LeafFormat::default()),
+        [input, output, format] => (
+            input,
+            output,
+            near_sdk::serde_json::from_str(format)
+                .map_err(|error| format!("Invalid leaf format: {error}"))?,
+        ),
+        _ => return Err("Usage: merkle-tree <allocations.csv> <tree.json> [leaf format]".into()),
+    };
+    if format.binds_campaign_id() && format.campaign_id == 0 {
+        return Err(
+            "The leaf format must set the campaign_id of the campaign, e.g. '{\"campaign_id\": 1}'"
+                .into(),
+        );
+    }
+
+    let csv = std::fs::read_to_string(input)
+        .map_err(|error| format!("Failed to read {input}: {error}"))?;
+    let file = CampaignFile::from_csv(&csv, format).map_err(|error| format!("{input}: {error}"))?;
+    std::fs::write(output, file.to_json() + "\n")
+        .map_err(|error| format!("Failed to write {output}: {error}"))?;
+
+    Ok(file.create_campaign_args())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(create_campaign_args) => println!("create_campaign arguments:\n{create_campaign_args}"),
+        Err(error) => {
+            eprintln!("{error}");
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::*;
+
+/// Number of leaf indices tracked by a single bitmap word.
+const BITS_PER_WORD: u32 = 256;
+
+/// Locates the word, byte and mask of the bit tracking `index`.
+fn bit_position(index: u32) -> (u32, usize, u8) {
+    let bit = index % BITS_PER_WORD;
+    (index / BITS_PER_WORD, (bit / 8) as usize, 1 << (bit % 8))
+}
+
+impl MerkleClaim {
+    pub(crate) fn set_index_claimed(&mut self, campaign_id: CampaignId, index: u32) {
+        let (word, byte, mask) = bit_position(index);
+        let mut bits = self
+            .claim_bitmaps
+            .get(&(campaign_id, word))
+            .copied()
+            .unwrap_or_default();
+
+        bits[byte] |= mask;
+        self.claim_bitmaps.insert((campaign_id, word), bits);
+    }
+
+    pub(crate) fn unset_index_claimed(&mut self, campaign_id: CampaignId, index: u32) {
+        let (word, byte, mask) = bit_position(index);
+
+        if let Some(bits) = self.claim_bitmaps.get_mut(&(campaign_id, word)) {
+            bits[byte] &= !mask;
+        }
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Returns whether the leaf at `index` of a bitmap campaign has been claimed.
+    pub fn is_index_claimed(&self, campaign_id: CampaignId, index: u32) -> bool {
+        let (word, byte, mask) = bit_position(index);
+
+        self.claim_bitmaps
+            .get(&(campaign_id, word))
+            .is_some_and(|bits| bits[byte] & mask != 0)
+    }
+}
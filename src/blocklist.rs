@@ -0,0 +1,59 @@
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountBlockedEvent {
+    pub account_id: AccountId,
+    /// `false` when the account was unblocked
+    pub blocked: bool,
+}
+
+impl MerkleClaim {
+    /// Panics if the owner blocked `account_id` from claiming.
+    pub(crate) fn assert_not_blocked(&self, account_id: &AccountId) {
+        require!(
+            !self.blocked_accounts.contains(account_id),
+            "Account is blocked from claiming"
+        );
+    }
+
+    fn log_account_blocked(account_id: AccountId, blocked: bool) {
+        let event = AccountBlockedEvent {
+            account_id,
+            blocked,
+        };
+
+        Event::AccountBlocked(event).emit();
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Blocks `account_id` from claiming in every campaign, e.g. for sanctioned accounts. Its
+    /// allocations stay unclaimed and can be claimed once it is unblocked.
+    pub fn block_account(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        require!(
+            self.blocked_accounts.insert(account_id.clone()),
+            "Account is already blocked"
+        );
+        self.record_admin_action("block_account", account_id.to_string());
+
+        Self::log_account_blocked(account_id, true);
+    }
+
+    pub fn unblock_account(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        require!(
+            self.blocked_accounts.remove(&account_id),
+            "Account is not blocked"
+        );
+        self.record_admin_action("unblock_account", account_id.to_string());
+
+        Self::log_account_blocked(account_id, false);
+    }
+
+    pub fn is_blocked(&self, account_id: AccountId) -> bool {
+        self.blocked_accounts.contains(&account_id)
+    }
+}
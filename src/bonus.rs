@@ -0,0 +1,104 @@
+use crate::config::{apply_bps, BPS_DENOMINATOR};
+use crate::*;
+
+/// A bonus on top of early claims, paid from the campaign's bonus pool while it lasts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub struct ClaimBonus {
+    /// The bonus as a share of the paid amount, in basis points
+    pub bps: u16,
+    /// When set, only the first `max_claims` claims of the campaign earn the bonus
+    pub max_claims: Option<u64>,
+    /// When set, only claims made before this timestamp earn the bonus
+    pub window_end: Option<U64>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BonusPoolFundedEvent {
+    pub campaign_id: CampaignId,
+    pub amount: U128,
+    pub bonus_pool: U128,
+}
+
+impl RewardCampaign {
+    /// Panics unless `bonus` is a positive share limited to early claims.
+    pub(crate) fn assert_valid_bonus(bonus: &ClaimBonus) {
+        require!(
+            bonus.bps > 0 && bonus.bps <= BPS_DENOMINATOR,
+            "Bonus must be between 1 and 10000 bps"
+        );
+        require!(
+            bonus.max_claims.is_some() || bonus.window_end.is_some(),
+            "Bonus requires a claim limit or a window end"
+        );
+    }
+
+    /// Takes the bonus earned by the claim at `claim_index` paying `amount` out of the bonus
+    /// pool, which may leave the last bonus short once the pool runs dry.
+    pub(crate) fn take_bonus(
+        &mut self,
+        claim_index: u64,
+        amount: Balance,
+        timestamp: u64,
+    ) -> Balance {
+        let Some(bonus) = self.bonus else {
+            return 0;
+        };
+
+        let eligible = bonus
+            .max_claims
+            .is_none_or(|max_claims| claim_index < max_claims)
+            && bonus
+                .window_end
+                .is_none_or(|window_end| timestamp < window_end.0);
+        if !eligible {
+            return 0;
+        }
+
+        let amount = apply_bps(amount, bonus.bps).min(self.bonus_pool.0);
+        self.bonus_pool.0 -= amount;
+        amount
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Adds the attached deposit to a campaign's bonus pool and returns the new pool balance.
+    /// Bonuses are only ever paid out of the pool, never out of the funds for base allocations.
+    #[payable]
+    pub fn fund_bonus_pool(&mut self, campaign_id: CampaignId) -> U128 {
+        self.assert_owner_or_financial_operator();
+
+        let amount = env::attached_deposit().as_yoctonear();
+        require!(amount > 0, "Attach a deposit to fund the bonus pool");
+
+        let campaign = self
+            .campaigns
+            .get_mut(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(campaign.bonus.is_some(), "Campaign has no bonus");
+
+        campaign.bonus_pool.0 = campaign
+            .bonus_pool
+            .0
+            .checked_add(amount)
+            .expect("Bonus pool overflows");
+        let funded = BonusPoolFundedEvent {
+            campaign_id,
+            amount: U128(amount),
+            bonus_pool: campaign.bonus_pool,
+        };
+        self.reserved_balance += amount;
+        self.debug_assert_reserve_consistent();
+        self.record_admin_action(
+            "fund_bonus_pool",
+            format!("campaign {campaign_id}, {amount} yoctoNEAR"),
+        );
+
+        let bonus_pool = funded.bonus_pool;
+        Event::BonusPoolFunded(funded).emit();
+
+        bonus_pool
+    }
+}
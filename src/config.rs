@@ -1,5 +1,26 @@
+use crate::withdrawal::WithdrawLimit;
 use crate::*;
-use near_sdk::{AccountId, NearToken};
+use near_sdk::{AccountId, AccountIdRef, NearToken};
+
+/// Maximum number of hashes in a merkle proof unless configured otherwise, enough for a tree of
+/// 2^64 leaves.
+pub const DEFAULT_MAX_PROOF_LENGTH: u32 = 64;
+
+fn default_max_proof_length() -> u32 {
+    DEFAULT_MAX_PROOF_LENGTH
+}
+
+/// The highest claim fee the owner can set, in basis points.
+pub const MAX_CLAIM_FEE_BPS: u16 = 1000;
+
+pub(crate) const BPS_DENOMINATOR: u16 = 10_000;
+
+/// Returns `bps` basis points of `amount`, rounded down.
+pub(crate) fn apply_bps(amount: Balance, bps: u16) -> Balance {
+    let (bps, denominator) = (Balance::from(bps), Balance::from(BPS_DENOMINATOR));
+    // Split `amount * bps / 10_000` so the product never overflows
+    amount / denominator * bps + amount % denominator * bps / denominator
+}
 
 #[derive(Debug, Clone)]
 #[near(serializers=[borsh, json])]
@@ -9,6 +30,446 @@ pub struct Config {
 
     /// The minimum amount in NEAR required for storage
     pub min_storage_deposit: NearToken,
+
+    /// When set, lockup contracts must be direct sub-accounts of this account, e.g. `lockup.near`
+    pub allowed_lockup_suffix: Option<String>,
+
+    /// The maximum number of hashes accepted in a merkle proof
+    #[serde(default = "default_max_proof_length")]
+    pub max_proof_length: u32,
+
+    /// The share of every claim paid to `fee_receiver`, in basis points
+    #[serde(default)]
+    pub claim_fee_bps: u16,
+
+    /// The account receiving claim fees, no fee is taken while it is unset
+    #[serde(default)]
+    pub fee_receiver: Option<AccountId>,
+
+    /// Claims of smaller allocations are rejected, they cost more gas than they are worth
+    #[serde(default)]
+    pub min_claim_amount: Option<U128>,
+
+    /// Claims of larger allocations are rejected, guarding against trees generated with
+    /// wrong amounts
+    #[serde(default)]
+    pub max_claim_amount: Option<U128>,
+
+    /// The largest fee signed claims may pay their relayer, relayer fees are rejected while it
+    /// is unset
+    #[serde(default)]
+    pub max_relayer_fee: Option<U128>,
+
+    /// How long withdrawals wait between `request_withdraw` and `execute_withdraw`, in
+    /// nanoseconds. `withdraw` is only available while it is zero
+    #[serde(default)]
+    pub withdraw_timelock: U64,
+
+    /// The account withdrawals are sent to unless one is given, the treasury while it is unset
+    #[serde(default)]
+    pub default_withdraw_receiver: Option<AccountId>,
+
+    /// The account receiving swept campaigns, and claim fees and withdrawals unless their own
+    /// receiver is set, the owner while it is unset
+    #[serde(default)]
+    pub treasury_account_id: Option<AccountId>,
+
+    /// The most that can be withdrawn per period, unlimited while it is unset
+    #[serde(default)]
+    pub withdraw_limit: Option<WithdrawLimit>,
+
+    /// Claims are paused once the available balance drops below it after a claim, never while
+    /// it is unset
+    #[serde(default)]
+    pub low_balance_threshold: Option<NearToken>,
+
+    /// How far, in nanoseconds, a campaign can be extended past its original claim end in
+    /// total, unlimited while it is unset
+    #[serde(default)]
+    pub max_total_extension: Option<U64>,
+}
+
+/// Changes to the configuration made with `update_config`, where every omitted field keeps its
+/// value. Fields that can be unset take `null` to unset them.
+#[derive(Clone, Default)]
+#[near(serializers=[borsh, json])]
+#[serde(default)]
+pub struct ConfigPatch {
+    pub min_storage_deposit: Option<NearToken>,
+    #[serde(deserialize_with = "deserialize_unsettable")]
+    pub allowed_lockup_suffix: Option<Option<String>>,
+    pub max_proof_length: Option<u32>,
+    pub claim_fee_bps: Option<u16>,
+    #[serde(deserialize_with = "deserialize_unsettable")]
+    pub fee_receiver: Option<Option<AccountId>>,
+    #[serde(deserialize_with = "deserialize_unsettable")]
+    pub min_claim_amount: Option<Option<U128>>,
+    #[serde(deserialize_with = "deserialize_unsettable")]
+    pub max_claim_amount: Option<Option<U128>>,
+    #[serde(deserialize_with = "deserialize_unsettable")]
+    pub max_relayer_fee: Option<Option<U128>>,
+    pub withdraw_timelock: Option<U64>,
+    #[serde(deserialize_with = "deserialize_unsettable")]
+    pub default_withdraw_receiver: Option<Option<AccountId>>,
+    #[serde(deserialize_with = "deserialize_unsettable")]
+    pub treasury_account_id: Option<Option<AccountId>>,
+    #[serde(deserialize_with = "deserialize_unsettable")]
+    pub withdraw_limit: Option<Option<WithdrawLimit>>,
+    #[serde(deserialize_with = "deserialize_unsettable")]
+    pub low_balance_threshold: Option<Option<NearToken>>,
+    #[serde(deserialize_with = "deserialize_unsettable")]
+    pub max_total_extension: Option<Option<U64>>,
+}
+
+/// Reads a present field of a `ConfigPatch` as `Some`, so `null` unsets it instead of being
+/// taken for an omitted field.
+fn deserialize_unsettable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: near_sdk::serde::Deserialize<'de>,
+    D: near_sdk::serde::Deserializer<'de>,
+{
+    near_sdk::serde::Deserialize::deserialize(deserializer).map(Some)
+}
+
+/// Changes to the configuration that loosen withdrawals, which `update_config` holds back until
+/// the current `withdraw_timelock` and the period of the current `withdraw_limit` have passed, so
+/// guardians can cancel them in the meantime. Logged as the `ConfigChangeRequestedEvent` when
+/// requested.
+#[derive(Clone)]
+#[near(serializers=[borsh, json])]
+pub struct PendingConfigChange {
+    /// The lower timelock, which would otherwise let the owner withdraw without waiting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withdraw_timelock: Option<U64>,
+    /// The looser or removed limit, which would otherwise let the owner withdraw everything
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_unsettable"
+    )]
+    pub withdraw_limit: Option<Option<WithdrawLimit>>,
+    pub requested_at: U64,
+    pub executes_at: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConfigChangeCancelledEvent {
+    /// The owner or guardian that cancelled the change
+    pub account_id: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConfigUpdatedEvent {
+    /// The fields whose value changed
+    pub changed_fields: Vec<&'static str>,
+    pub config: Config,
+}
+
+impl Config {
+    /// Validates the configuration, stripping the leading dot of the lockup suffix.
+    pub(crate) fn validated(mut self) -> Self {
+        self.allowed_lockup_suffix = self
+            .allowed_lockup_suffix
+            .map(|suffix| normalize_lockup_suffix(&suffix));
+        assert_valid_max_proof_length(self.max_proof_length);
+        assert_valid_claim_fee(self.claim_fee_bps);
+        assert_valid_claim_amount_bounds(self.min_claim_amount, self.max_claim_amount);
+        if let Some(limit) = &self.withdraw_limit {
+            assert_valid_withdraw_limit(limit);
+        }
+        self
+    }
+
+    /// Returns the fee taken from a claim of `amount`, rounded down in favor of the claimant.
+    pub(crate) fn claim_fee(&self, amount: Balance) -> Balance {
+        if self.fee_destination().is_none() {
+            return 0;
+        }
+
+        apply_bps(amount, self.claim_fee_bps)
+    }
+
+    /// Returns the account claim fees are paid to, the fee receiver or else the treasury. No
+    /// fee is taken if neither is set.
+    pub(crate) fn fee_destination(&self) -> Option<&AccountId> {
+        self.fee_receiver
+            .as_ref()
+            .or(self.treasury_account_id.as_ref())
+    }
+
+    /// Returns the treasury, or the owner if none is set.
+    pub(crate) fn treasury(&self) -> &AccountId {
+        self.treasury_account_id
+            .as_ref()
+            .unwrap_or(&self.owner_account_id)
+    }
+
+    /// Panics if `merkle_proof` has more hashes than allowed.
+    pub(crate) fn assert_proof_length(&self, merkle_proof: &[CryptoHash]) {
+        require!(
+            merkle_proof.len() <= self.max_proof_length as usize,
+            "Merkle proof exceeds the maximum length"
+        );
+    }
+
+    /// Panics if a claimed allocation of `amount` is outside the configured bounds, which are
+    /// inclusive.
+    pub(crate) fn assert_claim_amount_bounds(&self, amount: Balance) {
+        require!(
+            self.min_claim_amount.is_none_or(|min| amount >= min.0),
+            "Claim amount is below the minimum"
+        );
+        require!(
+            self.max_claim_amount.is_none_or(|max| amount <= max.0),
+            "Claim amount is above the maximum"
+        );
+    }
+
+    /// Panics unless `lockup_contract` is allowed as the receiver of a claim.
+    pub(crate) fn assert_allowed_lockup(&self, lockup_contract: &AccountId) {
+        if let Some(suffix) = &self.allowed_lockup_suffix {
+            let parent = AccountIdRef::new_or_panic(suffix);
+
+            require!(
+                lockup_contract.is_sub_account_of(parent),
+                "Lockup contract is not a sub-account of the allowed lockup suffix"
+            );
+        }
+    }
+}
+
+/// Strips the leading dot of a suffix such as `.lockup.near` and checks the rest is an account id.
+fn normalize_lockup_suffix(suffix: &str) -> String {
+    let suffix = suffix.strip_prefix('.').unwrap_or(suffix);
+
+    require!(
+        AccountIdRef::new(suffix).is_ok(),
+        "Lockup suffix must be a valid account id"
+    );
+
+    suffix.to_string()
+}
+
+fn assert_valid_claim_fee(claim_fee_bps: u16) {
+    require!(
+        claim_fee_bps <= MAX_CLAIM_FEE_BPS,
+        "Claim fee must not exceed 1000 bps"
+    );
+}
+
+fn assert_valid_claim_amount_bounds(
+    min_claim_amount: Option<U128>,
+    max_claim_amount: Option<U128>,
+) {
+    if let (Some(min), Some(max)) = (min_claim_amount, max_claim_amount) {
+        require!(
+            min.0 <= max.0,
+            "Minimum claim amount must not exceed the maximum"
+        );
+    }
+}
+
+fn assert_valid_withdraw_limit(limit: &WithdrawLimit) {
+    require!(
+        limit.amount > NearToken::from_yoctonear(0),
+        "Withdraw limit must not be zero"
+    );
+    require!(limit.period.0 > 0, "Withdraw limit period must not be zero");
+}
+
+/// Whether `limit` lets more be withdrawn than `current`, by being removed, raised or enforced
+/// over a shorter period.
+fn loosens_withdraw_limit(current: Option<&WithdrawLimit>, limit: Option<&WithdrawLimit>) -> bool {
+    match (current, limit) {
+        (None, _) => false,
+        (Some(_), None) => true,
+        (Some(current), Some(limit)) => {
+            limit.amount > current.amount || limit.period.0 < current.period.0
+        }
+    }
+}
+
+fn assert_valid_max_proof_length(max_proof_length: u32) {
+    require!(
+        max_proof_length > 0,
+        "Maximum proof length must not be zero"
+    );
+}
+
+/// Sets `field` to `value` if there is one, recording `name` if the value changed.
+fn patch_field<T: PartialEq>(
+    field: &mut T,
+    value: Option<T>,
+    name: &'static str,
+    changed_fields: &mut Vec<&'static str>,
+) {
+    if let Some(value) = value.filter(|value| value != field) {
+        *field = value;
+        changed_fields.push(name);
+    }
+}
+
+impl MerkleClaim {
+    /// Applies `patch` for `update_config` or a confirmed multisig action. A lower withdraw
+    /// timelock or a looser withdraw limit is held back as a pending config change instead.
+    pub(crate) fn internal_update_config(&mut self, mut patch: ConfigPatch) {
+        let withdraw_timelock = patch
+            .withdraw_timelock
+            .take_if(|timelock| timelock.0 < self.config.withdraw_timelock.0);
+        let withdraw_limit = patch.withdraw_limit.take_if(|limit| {
+            loosens_withdraw_limit(self.config.withdraw_limit.as_ref(), limit.as_ref())
+        });
+        if withdraw_timelock.is_some() || withdraw_limit.is_some() {
+            self.request_config_change(withdraw_timelock, withdraw_limit);
+        }
+
+        self.apply_config_patch(patch);
+    }
+
+    /// Holds back the loosening changes of a patch until the current withdraw timelock and
+    /// withdraw limit period have passed, so a zero timelock doesn't lift the limit at once.
+    fn request_config_change(
+        &mut self,
+        withdraw_timelock: Option<U64>,
+        withdraw_limit: Option<Option<WithdrawLimit>>,
+    ) {
+        require!(
+            self.pending_config_change.is_none(),
+            "A config change is already pending"
+        );
+        if let Some(Some(limit)) = &withdraw_limit {
+            assert_valid_withdraw_limit(limit);
+        }
+
+        let limit_period = self
+            .config
+            .withdraw_limit
+            .as_ref()
+            .map_or(0, |limit| limit.period.0);
+        let requested_at = env::block_timestamp();
+        let executes_at = requested_at + self.config.withdraw_timelock.0.max(limit_period);
+        let change = PendingConfigChange {
+            withdraw_timelock,
+            withdraw_limit,
+            requested_at: U64(requested_at),
+            executes_at: U64(executes_at),
+        };
+        self.pending_config_change = Some(change.clone());
+        self.record_admin_action(
+            "request_config_change",
+            format!("executes at {executes_at}"),
+        );
+
+        Event::ConfigChangeRequested(change).emit();
+    }
+
+    /// Applies every field of `patch`, validating the result as a whole.
+    fn apply_config_patch(&mut self, patch: ConfigPatch) {
+        let allowed_lockup_suffix = patch
+            .allowed_lockup_suffix
+            .map(|suffix| suffix.map(|suffix| normalize_lockup_suffix(&suffix)));
+
+        let mut config = self.config.clone();
+        let mut changed_fields = vec![];
+        patch_field(
+            &mut config.min_storage_deposit,
+            patch.min_storage_deposit,
+            "min_storage_deposit",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.allowed_lockup_suffix,
+            allowed_lockup_suffix,
+            "allowed_lockup_suffix",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.max_proof_length,
+            patch.max_proof_length,
+            "max_proof_length",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.claim_fee_bps,
+            patch.claim_fee_bps,
+            "claim_fee_bps",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.fee_receiver,
+            patch.fee_receiver,
+            "fee_receiver",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.min_claim_amount,
+            patch.min_claim_amount,
+            "min_claim_amount",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.max_claim_amount,
+            patch.max_claim_amount,
+            "max_claim_amount",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.max_relayer_fee,
+            patch.max_relayer_fee,
+            "max_relayer_fee",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.withdraw_timelock,
+            patch.withdraw_timelock,
+            "withdraw_timelock",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.default_withdraw_receiver,
+            patch.default_withdraw_receiver,
+            "default_withdraw_receiver",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.treasury_account_id,
+            patch.treasury_account_id,
+            "treasury_account_id",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.withdraw_limit,
+            patch.withdraw_limit,
+            "withdraw_limit",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.low_balance_threshold,
+            patch.low_balance_threshold,
+            "low_balance_threshold",
+            &mut changed_fields,
+        );
+        patch_field(
+            &mut config.max_total_extension,
+            patch.max_total_extension,
+            "max_total_extension",
+            &mut changed_fields,
+        );
+        require!(
+            config.min_storage_deposit <= env::account_balance(),
+            "Minimum storage deposit must not exceed the contract balance"
+        );
+        self.config = config.validated();
+        self.record_admin_action("update_config", changed_fields.join(", "));
+
+        let updated = ConfigUpdatedEvent {
+            changed_fields,
+            config: self.config.clone(),
+        };
+        Event::ConfigUpdated(updated).emit();
+    }
 }
 
 #[near]
@@ -17,4 +478,119 @@ impl MerkleClaim {
     pub fn get_config(&self) -> &Config {
         &self.config
     }
+
+    /// Changes the fields of the configuration given in `patch`, validating the result as a
+    /// whole. The minimum storage deposit cannot exceed the contract balance.
+    #[payable]
+    pub fn update_config(&mut self, patch: ConfigPatch) {
+        self.assert_owner_action();
+        self.internal_update_config(patch);
+    }
+
+    /// Applies the pending config change once the timelock it waits for has passed.
+    #[payable]
+    pub fn execute_config_change(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        let change = self
+            .pending_config_change
+            .as_ref()
+            .expect("No config change is pending");
+        require!(
+            env::block_timestamp() >= change.executes_at.0,
+            "Config change is still timelocked"
+        );
+
+        let change = self.pending_config_change.take().unwrap();
+        self.apply_config_patch(ConfigPatch {
+            withdraw_timelock: change.withdraw_timelock,
+            withdraw_limit: change.withdraw_limit,
+            ..Default::default()
+        });
+    }
+
+    /// Cancels the pending config change. Guardians can cancel config changes as well.
+    pub fn cancel_config_change(&mut self) {
+        self.assert_owner_or_guardian();
+        self.pending_config_change
+            .take()
+            .expect("No config change is pending");
+        self.record_admin_action("cancel_config_change", String::new());
+
+        let cancelled = ConfigChangeCancelledEvent {
+            account_id: env::predecessor_account_id(),
+        };
+        Event::ConfigChangeCancelled(cancelled).emit();
+    }
+
+    pub fn get_pending_config_change(&self) -> Option<&PendingConfigChange> {
+        self.pending_config_change.as_ref()
+    }
+
+    /// Restricts lockup contracts to sub-accounts of `suffix`, or lifts the restriction if `None`.
+    #[payable]
+    pub fn set_allowed_lockup_suffix(&mut self, suffix: Option<String>) {
+        self.assert_owner_action();
+        self.config.allowed_lockup_suffix = suffix.map(|suffix| normalize_lockup_suffix(&suffix));
+        self.record_admin_action(
+            "set_allowed_lockup_suffix",
+            format!("{:?}", self.config.allowed_lockup_suffix),
+        );
+    }
+
+    /// Sets the maximum number of hashes accepted in a merkle proof.
+    #[payable]
+    pub fn set_max_proof_length(&mut self, max_proof_length: u32) {
+        self.assert_owner_action();
+        assert_valid_max_proof_length(max_proof_length);
+        self.config.max_proof_length = max_proof_length;
+        self.record_admin_action("set_max_proof_length", max_proof_length.to_string());
+    }
+
+    /// Takes `claim_fee_bps` of every claim for `fee_receiver`, or for the treasury if it is
+    /// `None`. No fees are taken if neither is set.
+    #[payable]
+    pub fn set_claim_fee(&mut self, claim_fee_bps: u16, fee_receiver: Option<AccountId>) {
+        self.assert_owner_action();
+        assert_valid_claim_fee(claim_fee_bps);
+        self.config.claim_fee_bps = claim_fee_bps;
+        self.config.fee_receiver = fee_receiver;
+        self.record_admin_action(
+            "set_claim_fee",
+            format!("{claim_fee_bps} bps to {:?}", self.config.fee_receiver),
+        );
+    }
+
+    /// Sets the smallest and largest allocations that can be claimed, `None` lifting a bound.
+    #[payable]
+    pub fn set_claim_amount_bounds(
+        &mut self,
+        min_claim_amount: Option<U128>,
+        max_claim_amount: Option<U128>,
+    ) {
+        self.assert_owner_action();
+        assert_valid_claim_amount_bounds(min_claim_amount, max_claim_amount);
+        self.config.min_claim_amount = min_claim_amount;
+        self.config.max_claim_amount = max_claim_amount;
+        self.record_admin_action(
+            "set_claim_amount_bounds",
+            format!(
+                "{:?} to {:?}",
+                min_claim_amount.map(|amount| amount.0),
+                max_claim_amount.map(|amount| amount.0)
+            ),
+        );
+    }
+
+    /// Sets the largest fee signed claims may pay their relayer, or rejects relayer fees if it
+    /// is `None`.
+    #[payable]
+    pub fn set_max_relayer_fee(&mut self, max_relayer_fee: Option<U128>) {
+        self.assert_owner_action();
+        self.config.max_relayer_fee = max_relayer_fee;
+        self.record_admin_action(
+            "set_max_relayer_fee",
+            format!("{:?}", max_relayer_fee.map(|fee| fee.0)),
+        );
+    }
 }
@@ -0,0 +1,68 @@
+use crate::config::{apply_bps, BPS_DENOMINATOR};
+use crate::*;
+
+/// Reduces the payout of late claims, linearly from the full amount at `start` down to
+/// `final_bps` of it at the campaign's `claim_end`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub struct ClaimDecay {
+    /// The timestamp until which claims are paid in full
+    pub start: U64,
+    /// The share of the amount paid by claims at `claim_end`, in basis points
+    pub final_bps: u16,
+}
+
+impl RewardCampaign {
+    /// Panics unless `decay` starts now or later and ends with the campaign's claim period.
+    pub(crate) fn assert_valid_decay(decay: &ClaimDecay, claim_end: Option<U64>) {
+        let claim_end = claim_end.unwrap_or_else(|| env::panic_str("Decay requires a claim end"));
+
+        require!(
+            env::block_timestamp() <= decay.start.0 && decay.start.0 < claim_end.0,
+            "Decay must start between now and the claim end"
+        );
+        require!(
+            decay.final_bps <= BPS_DENOMINATOR,
+            "Decay final share must not exceed 10000 bps"
+        );
+    }
+
+    /// Returns the share of a claim's amount paid at `timestamp`, in basis points.
+    pub(crate) fn claim_multiplier_bps(&self, timestamp: u64) -> u16 {
+        let (Some(decay), Some(claim_end)) = (self.decay, self.claim_end) else {
+            return BPS_DENOMINATOR;
+        };
+
+        if timestamp <= decay.start.0 {
+            return BPS_DENOMINATOR;
+        }
+        if timestamp >= claim_end.0 {
+            return decay.final_bps;
+        }
+
+        let elapsed = u128::from(timestamp - decay.start.0);
+        let duration = u128::from(claim_end.0 - decay.start.0);
+        let decayed = u128::from(BPS_DENOMINATOR - decay.final_bps) * elapsed / duration;
+
+        BPS_DENOMINATOR - decayed as u16
+    }
+
+    /// Returns what a claim of `amount` pays at `timestamp`, rounded down.
+    pub(crate) fn decayed_amount(&self, amount: Balance, timestamp: u64) -> Balance {
+        match self.claim_multiplier_bps(timestamp) {
+            BPS_DENOMINATOR => amount,
+            bps => apply_bps(amount, bps),
+        }
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Returns the share of their amount that claims of a campaign pay now, in basis points.
+    pub fn get_claim_multiplier(&self, campaign_id: CampaignId) -> u16 {
+        self.campaigns
+            .get(&campaign_id)
+            .expect("Campaign does not exist")
+            .claim_multiplier_bps(env::block_timestamp())
+    }
+}
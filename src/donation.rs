@@ -0,0 +1,67 @@
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CampaignFundedEvent {
+    pub campaign_id: CampaignId,
+    pub donor: AccountId,
+    pub amount: U128,
+    /// The donations the campaign holds after this one
+    pub new_total: U128,
+    pub block_timestamp: U64,
+}
+
+impl RewardCampaign {
+    /// Takes what donations cover of a claim paying `amount` out of the donation pool.
+    pub(crate) fn take_donation(&mut self, amount: Balance) -> Balance {
+        let amount = amount.min(self.donation_pool.0);
+        self.donation_pool.0 -= amount;
+        amount
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Adds the attached deposit to a campaign's donation pool and returns the new pool balance.
+    /// Anyone can donate to a running campaign that declares its `total_allocation`. Claims are
+    /// paid out of donations first, releasing as much of the campaign's reserved allocation,
+    /// and a sweep or cancellation releases what is left of them with the allocation.
+    #[payable]
+    pub fn donate_to_campaign(&mut self, campaign_id: CampaignId) -> U128 {
+        let amount = env::attached_deposit().as_yoctonear();
+        require!(amount > 0, "Attach a deposit to donate");
+
+        let campaign = self
+            .campaigns
+            .get_mut(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(
+            campaign.total_allocation.is_some(),
+            "Campaign does not declare its total allocation"
+        );
+        require!(
+            !campaign.has_ended(env::block_timestamp()) && !campaign.allocation_released,
+            "Campaign has already ended"
+        );
+
+        campaign.donation_pool.0 = campaign
+            .donation_pool
+            .0
+            .checked_add(amount)
+            .expect("Donation pool overflows");
+        let funded = CampaignFundedEvent {
+            campaign_id,
+            donor: env::predecessor_account_id(),
+            amount: U128(amount),
+            new_total: campaign.donation_pool,
+            block_timestamp: U64(env::block_timestamp()),
+        };
+        self.reserved_balance += amount;
+        self.debug_assert_reserve_consistent();
+
+        let new_total = funded.new_total;
+        Event::CampaignFunded(funded).emit();
+
+        new_total
+    }
+}
@@ -0,0 +1,207 @@
+use crate::*;
+
+/// The number of campaigns `emergency_shutdown` processes per call unless a limit is given.
+const DEFAULT_SHUTDOWN_BATCH: u32 = 20;
+
+/// The progress of an emergency shutdown, from its first approval until it completed.
+#[derive(Clone)]
+#[near(serializers=[borsh, json])]
+pub struct EmergencyShutdown {
+    /// The owner that approved the shutdown, whose approval lapses when ownership moves on
+    pub owner_approved: Option<AccountId>,
+    /// The guardian that approved the shutdown, required while there are guardians. The
+    /// approval lapses when the guardian is removed.
+    pub guardian_approved: Option<AccountId>,
+    /// The next campaign to cancel
+    pub next_campaign_id: CampaignId,
+    pub completed_at: Option<U64>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmergencyShutdownApprovedEvent {
+    pub account_id: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmergencyShutdownRevokedEvent {
+    pub account_id: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmergencyShutdownEvent {
+    /// The balance sent to the treasury, everything but the storage deposit
+    pub amount: U128,
+    pub receiver_id: AccountId,
+}
+
+impl EmergencyShutdown {
+    fn has_started(&self) -> bool {
+        self.next_campaign_id > 1 || self.completed_at.is_some()
+    }
+}
+
+impl MerkleClaim {
+    /// Drops the approvals of accounts that are no longer the owner or a guardian.
+    fn revalidate_shutdown_approvals(&self, shutdown: &mut EmergencyShutdown) {
+        shutdown
+            .owner_approved
+            .take_if(|owner| *owner != self.config.owner_account_id);
+        shutdown
+            .guardian_approved
+            .take_if(|guardian| !self.guardians.contains(guardian));
+    }
+
+    /// Cancels a running campaign, or releases the reserve of an ended one that was not swept.
+    fn shut_down_campaign(&mut self, campaign_id: CampaignId) {
+        let Some(campaign) = self.campaigns.get_mut(&campaign_id) else {
+            return;
+        };
+
+        if !campaign.has_ended(env::block_timestamp()) {
+            self.internal_cancel_campaign(campaign_id);
+        } else if !campaign.allocation_released {
+            self.reserved_balance -= campaign.release_reserve();
+            self.debug_assert_reserve_consistent();
+        }
+    }
+
+    fn log_shutdown_approval(account_id: AccountId) {
+        let event = EmergencyShutdownApprovedEvent { account_id };
+
+        Event::EmergencyShutdownApproved(event).emit();
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Shuts the contract down for good, e.g. after a critical bug or a legal order: pauses
+    /// everything, cancels every running campaign and sends the whole balance but the storage
+    /// deposit to the treasury, including funds reserved for vesting positions and
+    /// registrations.
+    ///
+    /// Needs the approval of the owner and, if there are guardians, of one guardian, each
+    /// calling this method. Approvals only count while their accounts are still the owner or a
+    /// guardian, and can be revoked until the shutdown started. Once both approved, at most
+    /// `limit` campaigns, 20 by default, are cancelled per call, so it can be called again until
+    /// it returns `true` when the balance was sent. Calls after that change nothing.
+    #[payable]
+    pub fn emergency_shutdown(&mut self, limit: Option<u32>) -> bool {
+        assert_one_yocto();
+        self.assert_owner_or_guardian();
+
+        let account_id = env::predecessor_account_id();
+        let mut shutdown = self
+            .emergency_shutdown
+            .clone()
+            .unwrap_or(EmergencyShutdown {
+                owner_approved: None,
+                guardian_approved: None,
+                next_campaign_id: 1,
+                completed_at: None,
+            });
+        if shutdown.completed_at.is_some() {
+            return true;
+        }
+
+        self.revalidate_shutdown_approvals(&mut shutdown);
+        if account_id == self.config.owner_account_id {
+            if shutdown.owner_approved.is_none() {
+                shutdown.owner_approved = Some(account_id.clone());
+                Self::log_shutdown_approval(account_id);
+            }
+        } else if shutdown.guardian_approved.is_none() {
+            shutdown.guardian_approved = Some(account_id.clone());
+            Self::log_shutdown_approval(account_id);
+        }
+
+        let approved = shutdown.owner_approved.is_some()
+            && (shutdown.guardian_approved.is_some() || self.guardians.is_empty());
+        if !approved {
+            self.emergency_shutdown = Some(shutdown);
+            return false;
+        }
+
+        if self.pause_flags != PauseFlags::ALL {
+            self.update_pause_flags("emergency_shutdown", PauseFlags::ALL);
+        }
+
+        let limit = limit.unwrap_or(DEFAULT_SHUTDOWN_BATCH);
+        let last = self.last_campaign_id.min(
+            shutdown
+                .next_campaign_id
+                .saturating_add(limit.saturating_sub(1)),
+        );
+        for campaign_id in shutdown.next_campaign_id..=last {
+            self.shut_down_campaign(campaign_id);
+        }
+        shutdown.next_campaign_id = last + 1;
+
+        if shutdown.next_campaign_id <= self.last_campaign_id {
+            self.emergency_shutdown = Some(shutdown);
+            return false;
+        }
+
+        shutdown.completed_at = Some(U64(env::block_timestamp()));
+        self.emergency_shutdown = Some(shutdown);
+
+        let amount = self.withdrawable_balance(true);
+        let receiver_id = self.config.treasury().clone();
+        if amount > 0 {
+            self.send_withdrawal(
+                "emergency_shutdown",
+                NearToken::from_yoctonear(amount),
+                Some(receiver_id.clone()),
+            );
+        }
+
+        let event = EmergencyShutdownEvent {
+            amount: U128(amount),
+            receiver_id,
+        };
+        Event::EmergencyShutdown(event).emit();
+
+        true
+    }
+
+    /// Withdraws the caller's approval of an emergency shutdown that has not started yet.
+    #[payable]
+    pub fn revoke_emergency_shutdown(&mut self) {
+        assert_one_yocto();
+        self.assert_owner_or_guardian();
+
+        let account_id = env::predecessor_account_id();
+        let mut shutdown = self
+            .emergency_shutdown
+            .clone()
+            .expect("No emergency shutdown was approved");
+        require!(
+            !shutdown.has_started(),
+            "Emergency shutdown has already started"
+        );
+        let approval = if shutdown.owner_approved.as_ref() == Some(&account_id) {
+            &mut shutdown.owner_approved
+        } else {
+            &mut shutdown.guardian_approved
+        };
+        require!(
+            approval
+                .take_if(|approver| *approver == account_id)
+                .is_some(),
+            "No emergency shutdown approval to revoke"
+        );
+
+        self.emergency_shutdown = (shutdown.owner_approved.is_some()
+            || shutdown.guardian_approved.is_some())
+        .then_some(shutdown);
+
+        let event = EmergencyShutdownRevokedEvent { account_id };
+        Event::EmergencyShutdownRevoked(event).emit();
+    }
+
+    pub fn get_emergency_shutdown(&self) -> Option<&EmergencyShutdown> {
+        self.emergency_shutdown.as_ref()
+    }
+}
@@ -0,0 +1,112 @@
+use crate::merkle::decode_hex;
+use crate::*;
+
+/// Returns the message an Ethereum address signs to authorize `claim_eth` of a campaign's
+/// allocation to `receiver_id`.
+pub fn eth_claim_message(
+    contract_id: &AccountId,
+    campaign_id: CampaignId,
+    receiver_id: &AccountId,
+) -> String {
+    format!("Claim campaign {campaign_id} of {contract_id} to {receiver_id}")
+}
+
+/// Returns the EIP-191 hash of a personal message,
+/// `keccak256("\x19Ethereum Signed Message:\n" || len(message) || message)`.
+pub fn eth_message_hash(message: &str) -> CryptoHash {
+    env::keccak256_array(
+        format!("\x19Ethereum Signed Message:\n{}{message}", message.len()).as_bytes(),
+    )
+}
+
+/// Returns the NEAR account of an Ethereum address, `0x` followed by its lowercase hex.
+pub(crate) fn eth_account_id(address: &[u8; 20]) -> AccountId {
+    let hex: String = address.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("0x{hex}").parse().unwrap()
+}
+
+/// Returns the Ethereum address of an account claiming a `V6` leaf, panicking unless it is the
+/// NEAR account of an Ethereum address.
+pub(crate) fn eth_address(account_id: &AccountId) -> [u8; 20] {
+    account_id
+        .as_str()
+        .strip_prefix("0x")
+        .filter(|_| account_id.get_account_type().is_implicit())
+        .map(|hex| decode_hex(hex, "V6 leaves are claimed by Ethereum addresses"))
+        .unwrap_or_else(|| merkle::panic_str("V6 leaves are claimed by Ethereum addresses"))
+}
+
+/// Decodes a `0x`-prefixed hex string of `N` bytes, panicking with `message` otherwise.
+fn decode_prefixed_hex<const N: usize>(hex: &str, message: &str) -> [u8; N] {
+    let hex = hex
+        .strip_prefix("0x")
+        .unwrap_or_else(|| env::panic_str(message));
+    decode_hex(hex, message)
+}
+
+/// Returns the address that signed `message` with a 65 byte `r || s || v` signature, where `v` is
+/// 27 or 28 as produced by Ethereum wallets, or 0 or 1.
+fn recover_eth_address(message: &str, signature: &[u8; 65]) -> [u8; 20] {
+    let v = match signature[64] {
+        v @ (0 | 1) => v,
+        v @ (27 | 28) => v - 27,
+        _ => env::panic_str("Invalid signature recovery id"),
+    };
+
+    let public_key = env::ecrecover(&eth_message_hash(message), &signature[..64], v, true)
+        .unwrap_or_else(|| env::panic_str("Invalid signature"));
+
+    env::keccak256_array(&public_key)[12..].try_into().unwrap()
+}
+
+#[near]
+impl MerkleClaim {
+    /// Claims the allocation of `eth_address` in a campaign with `V6` leaves and pays it to
+    /// `receiver_id`, authorized by the address' EIP-191 signature of `eth_claim_message`, given
+    /// as `0x`-prefixed hex of `r || s || v`. Anyone can submit the claim and pay its gas. Claims
+    /// are recorded for the NEAR account of the address, so each address claims once whichever
+    /// receiver it chose.
+    pub fn claim_eth(
+        &mut self,
+        eth_address: String,
+        amount: U128,
+        merkle_proof: Vec<CryptoHash>,
+        campaign_id: CampaignId,
+        receiver_id: AccountId,
+        signature: String,
+    ) {
+        require!(
+            self.campaigns
+                .get(&campaign_id)
+                .is_none_or(|campaign| campaign.leaf_version == LeafVersion::V6),
+            "Ethereum claims require V6 leaves"
+        );
+
+        let eth_address: [u8; 20] = decode_prefixed_hex(
+            &eth_address,
+            "Ethereum address must be 0x followed by 40 hex characters",
+        );
+        let signature: [u8; 65] = decode_prefixed_hex(
+            &signature,
+            "Signature must be 0x followed by 130 hex characters",
+        );
+
+        let message = eth_claim_message(&env::current_account_id(), campaign_id, &receiver_id);
+        require!(
+            recover_eth_address(&message, &signature) == eth_address,
+            "Invalid signature"
+        );
+
+        self.claim_for(
+            eth_account_id(&eth_address),
+            amount,
+            merkle_proof,
+            campaign_id,
+            None,
+            Some(ClaimOptions {
+                receiver_id: Some(receiver_id),
+                ..Default::default()
+            }),
+        );
+    }
+}
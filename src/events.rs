@@ -0,0 +1,148 @@
+use crate::access_key::OperatorKeyEvent;
+use crate::admin::{ActionConfirmedEvent, ActionProposedEvent, MultisigUpdatedEvent};
+use crate::blocklist::AccountBlockedEvent;
+use crate::bonus::BonusPoolFundedEvent;
+use crate::config::{ConfigChangeCancelledEvent, ConfigUpdatedEvent, PendingConfigChange};
+use crate::donation::CampaignFundedEvent;
+use crate::emergency::{
+    EmergencyShutdownApprovedEvent, EmergencyShutdownEvent, EmergencyShutdownRevokedEvent,
+};
+use crate::guardian::GuardianEvent;
+use crate::invalidation::LeafInvalidatedEvent;
+use crate::memo::CampaignMemoUpdatedEvent;
+use crate::operator::FinancialOperatorEvent;
+use crate::ownership::{OwnershipProposedEvent, OwnershipTransferredEvent};
+use crate::pause::{CircuitBreakerTrippedEvent, PausedEvent};
+use crate::payout::{ClaimFailedEvent, ClaimPayoutFailedEvent};
+use crate::record::{CampaignDeletedEvent, ClaimPurgedEvent};
+use crate::referral::{ReferralEvent, ReferralPoolFundedEvent};
+use crate::registration::ClaimRegisteredEvent;
+use crate::reserve::{CampaignCancelledEvent, CampaignExtendedEvent, CampaignSweptEvent};
+use crate::storage::{
+    StorageDepositEvent, StorageDepositReclaimedEvent, StorageSurplusWithdrawnEvent,
+    StorageWithdrawEvent,
+};
+use crate::vesting::VestingWithdrawEvent;
+use crate::withdrawal::{
+    ReservedFundsWithdrawnEvent, WithdrawCancelledEvent, WithdrawFailedEvent,
+    WithdrawRequestedEvent,
+};
+use crate::*;
+
+/// The NEP-297 standard name of the contract's events.
+pub const EVENT_STANDARD: &str = "merkle_claim";
+
+/// The version of the contract's events, raised whenever the data of an event changes, its
+/// major part when the change breaks consumers. `2.0.0` logs every amount as a string, `2.1.0`
+/// adds the `block_timestamp` of campaign lifecycle events. `3.0.0` logs the `ClaimEvent` of a
+/// paid claim once its payout is confirmed, and a `ClaimFailedEvent` if it is rolled back.
+/// `4.0.0` logs merkle roots as base58 strings instead of arrays of bytes. `5.0.0` logs every
+/// storage deposit and NEP-145 withdrawal, and renames the withdrawal of the storage surplus.
+/// `5.1.0` adds campaign memos to campaign creation and claims, and their updates. `6.0.0` drops
+/// the `merkle_root_b58` of campaign creation, which repeated `merkle_root`. `6.1.0` adds the
+/// requests and cancellations of pending config changes, `6.2.0` the revocation of emergency
+/// shutdown approvals.
+pub const EVENT_VERSION: &str = "6.2.0";
+
+/// The NEP-297 envelope an event is logged in, with the event as the only entry of `data`.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a, T> {
+    standard: &'static str,
+    version: &'static str,
+    event: &'static str,
+    data: [&'a T; 1],
+}
+
+/// Logs `data` as `EVENT_JSON:` followed by the NEP-297 envelope of the event `event`.
+fn log_event<T: Serialize>(event: &'static str, data: &T) {
+    let log = EventLog {
+        standard: EVENT_STANDARD,
+        version: EVENT_VERSION,
+        event,
+        data: [data],
+    };
+
+    env::log_str(&format!(
+        "EVENT_JSON:{}",
+        serde_json::to_string(&log).unwrap()
+    ));
+}
+
+macro_rules! near_events {
+    ($($variant:ident($event:ty) => $name:literal,)*) => {
+        /// An event the contract logs as a NEP-297 event, so indexers pick it up. Nothing is
+        /// logged until it is emitted.
+        #[must_use = "events are only logged once emitted"]
+        pub enum Event {
+            $($variant($event),)*
+        }
+
+        impl Event {
+            /// The NEP-297 names of every event, in declaration order.
+            #[cfg(test)]
+            pub const NAMES: &'static [&'static str] = &[$($name,)*];
+
+            /// Returns the NEP-297 event name.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Event::$variant(_) => $name,)*
+                }
+            }
+
+            /// Logs the event as `EVENT_JSON:` followed by its NEP-297 envelope.
+            pub fn emit(self) {
+                let name = self.name();
+                match &self {
+                    $(Event::$variant(data) => log_event(name, data),)*
+                }
+            }
+        }
+    };
+}
+
+near_events! {
+    CampaignCreated(CampaignCreatedEvent) => "campaign_created",
+    Claim(ClaimEvent) => "claim",
+    ClaimFailed(ClaimFailedEvent) => "claim_failed",
+    ClaimPayoutFailed(ClaimPayoutFailedEvent) => "claim_payout_failed",
+    ClaimRegistered(ClaimRegisteredEvent) => "claim_registered",
+    ClaimPurged(ClaimPurgedEvent) => "claim_purged",
+    Referral(ReferralEvent) => "referral",
+    VestingWithdraw(VestingWithdrawEvent) => "vesting_withdraw",
+    BonusPoolFunded(BonusPoolFundedEvent) => "bonus_pool_funded",
+    ReferralPoolFunded(ReferralPoolFundedEvent) => "referral_pool_funded",
+    CampaignFunded(CampaignFundedEvent) => "campaign_funded",
+    CampaignCancelled(CampaignCancelledEvent) => "campaign_cancelled",
+    CampaignExtended(CampaignExtendedEvent) => "campaign_extended",
+    CampaignMemoUpdated(CampaignMemoUpdatedEvent) => "campaign_memo_updated",
+    CampaignSwept(CampaignSweptEvent) => "campaign_swept",
+    CampaignDeleted(CampaignDeletedEvent) => "campaign_deleted",
+    LeafInvalidated(LeafInvalidatedEvent) => "leaf_invalidated",
+    AccountBlocked(AccountBlockedEvent) => "account_blocked",
+    Withdraw(WithdrawEvent) => "withdraw",
+    WithdrawFailed(WithdrawFailedEvent) => "withdraw_failed",
+    WithdrawRequested(WithdrawRequestedEvent) => "withdraw_requested",
+    WithdrawCancelled(WithdrawCancelledEvent) => "withdraw_cancelled",
+    ReservedFundsWithdrawn(ReservedFundsWithdrawnEvent) => "reserved_funds_withdrawn",
+    StorageDeposit(StorageDepositEvent) => "storage_deposit",
+    StorageDepositReclaimed(StorageDepositReclaimedEvent) => "storage_deposit_reclaimed",
+    StorageWithdraw(StorageWithdrawEvent) => "storage_withdraw",
+    StorageSurplusWithdrawn(StorageSurplusWithdrawnEvent) => "storage_surplus_withdrawn",
+    ConfigUpdated(ConfigUpdatedEvent) => "config_updated",
+    ConfigChangeRequested(PendingConfigChange) => "config_change_requested",
+    ConfigChangeCancelled(ConfigChangeCancelledEvent) => "config_change_cancelled",
+    Paused(PausedEvent) => "paused",
+    CircuitBreakerTripped(CircuitBreakerTrippedEvent) => "circuit_breaker_tripped",
+    Guardian(GuardianEvent) => "guardian",
+    FinancialOperator(FinancialOperatorEvent) => "financial_operator",
+    OperatorKey(OperatorKeyEvent) => "operator_key",
+    OwnershipProposed(OwnershipProposedEvent) => "ownership_proposed",
+    OwnershipTransferred(OwnershipTransferredEvent) => "ownership_transferred",
+    ActionProposed(ActionProposedEvent) => "action_proposed",
+    ActionConfirmed(ActionConfirmedEvent) => "action_confirmed",
+    MultisigUpdated(MultisigUpdatedEvent) => "multisig_updated",
+    EmergencyShutdownApproved(EmergencyShutdownApprovedEvent) => "emergency_shutdown_approved",
+    EmergencyShutdownRevoked(EmergencyShutdownRevokedEvent) => "emergency_shutdown_revoked",
+    EmergencyShutdown(EmergencyShutdownEvent) => "emergency_shutdown",
+}
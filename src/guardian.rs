@@ -0,0 +1,57 @@
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GuardianEvent {
+    pub account_id: AccountId,
+    /// `false` when the guardian was removed
+    pub added: bool,
+}
+
+impl MerkleClaim {
+    /// Panics unless the caller is the owner or a guardian.
+    pub(crate) fn assert_owner_or_guardian(&self) {
+        let account_id = env::predecessor_account_id();
+        require!(
+            account_id == self.config.owner_account_id || self.guardians.contains(&account_id),
+            "Only the owner or a guardian can call this method"
+        );
+    }
+
+    fn log_guardian(account_id: AccountId, added: bool) {
+        let event = GuardianEvent { account_id, added };
+
+        Event::Guardian(event).emit();
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Allows `account_id` to `pause` the contract, e.g. a monitoring bot. Guardians cannot
+    /// unpause the contract or call any other owner method.
+    pub fn add_guardian(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        require!(
+            self.guardians.insert(account_id.clone()),
+            "Account is already a guardian"
+        );
+        self.record_admin_action("add_guardian", account_id.to_string());
+
+        Self::log_guardian(account_id, true);
+    }
+
+    pub fn remove_guardian(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        require!(
+            self.guardians.remove(&account_id),
+            "Account is not a guardian"
+        );
+        self.record_admin_action("remove_guardian", account_id.to_string());
+
+        Self::log_guardian(account_id, false);
+    }
+
+    pub fn get_guardians(&self) -> Vec<AccountId> {
+        self.guardians.iter().cloned().collect()
+    }
+}
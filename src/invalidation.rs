@@ -0,0 +1,70 @@
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LeafInvalidatedEvent {
+    pub campaign_id: CampaignId,
+    pub account_id: AccountId,
+}
+
+impl MerkleClaim {
+    /// Returns the key recording the invalidation of `account_id`'s allocations in a campaign,
+    /// which covers all of its leaves whatever the campaign's claim key version.
+    fn invalidation_key(campaign_id: CampaignId, account_id: &AccountId) -> CryptoHash {
+        compute_claim_key(ClaimKeyVersion::V2, account_id, campaign_id, None)
+    }
+
+    /// Panics if the owner invalidated `account_id`'s allocations in a campaign.
+    pub(crate) fn assert_not_invalidated(&self, campaign_id: CampaignId, account_id: &AccountId) {
+        require!(
+            !self.is_invalidated(campaign_id, account_id.clone()),
+            "Allocation invalidated"
+        );
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Invalidates `account_id`'s allocations in a campaign, e.g. when they turn out to be
+    /// fraudulent after the root was published. The allocation is marked as claimed without
+    /// paying anything and later claims fail.
+    pub fn invalidate_leaf(&mut self, campaign_id: CampaignId, account_id: AccountId) {
+        self.assert_owner();
+        require!(
+            self.campaigns.contains_key(&campaign_id),
+            "Campaign does not exist"
+        );
+        require!(
+            !self.is_invalidated(campaign_id, account_id.clone()),
+            "Allocation already invalidated"
+        );
+        require!(
+            !self.has_claimed(campaign_id, account_id.clone()),
+            "Allocation already claimed"
+        );
+
+        if let Some(key) = self.claim_key(campaign_id, &account_id) {
+            self.insert_claim_record(campaign_id, key, ClaimRecord::Invalidated);
+        }
+        self.invalidated_leaves
+            .insert(Self::invalidation_key(campaign_id, &account_id));
+        self.record_admin_action(
+            "invalidate_leaf",
+            format!("campaign {campaign_id}, {account_id}"),
+        );
+
+        let invalidated = LeafInvalidatedEvent {
+            campaign_id,
+            account_id,
+        };
+
+        Event::LeafInvalidated(invalidated).emit();
+    }
+
+    /// Returns whether the owner invalidated `account_id`'s allocations in a campaign, as
+    /// opposed to them being claimed.
+    pub fn is_invalidated(&self, campaign_id: CampaignId, account_id: AccountId) -> bool {
+        self.invalidated_leaves
+            .contains(&Self::invalidation_key(campaign_id, &account_id))
+    }
+}
@@ -1,16 +1,76 @@
+mod access_key;
+mod admin;
+mod audit;
+mod bitmap;
+mod blocklist;
+mod bonus;
 mod config;
+mod decay;
+mod donation;
+mod emergency;
+mod eth;
+mod events;
+mod guardian;
+mod invalidation;
+mod lockup;
+mod memo;
 mod merkle;
+pub mod metadata;
+mod migrate;
+mod operator;
+mod ownership;
+mod pause;
+mod payout;
+mod period;
+mod receiver;
+mod record;
+mod referral;
+mod registration;
+mod reserve;
+mod signature;
+mod storage;
+mod vesting;
+#[cfg(any(test, feature = "wasm-client"))]
+pub mod wasm_client;
+mod withdrawal;
 
-use crate::config::Config;
-use near_sdk::json_types::U64;
-use near_sdk::store::{LookupMap, LookupSet};
+use crate::admin::{Multisig, Proposal, ProposalId};
+use crate::audit::AdminAction;
+use crate::bonus::ClaimBonus;
+use crate::config::{Config, PendingConfigChange, BPS_DENOMINATOR};
+use crate::decay::ClaimDecay;
+use crate::emergency::EmergencyShutdown;
+use crate::events::Event;
+use crate::merkle::{encode_hex_hash, Direction, MerkleRootInput, ProofFormat};
+use crate::ownership::OwnerChange;
+use crate::pause::PauseFlags;
+use crate::payout::PayoutCall;
+use crate::period::ClaimPeriods;
+use crate::record::{ClaimRecord, ClaimStorage};
+use crate::referral::ClaimReferral;
+use crate::registration::Registration;
+use crate::signature::RelayerFee;
+use crate::storage::MIN_STORAGE_BALANCE;
+use crate::vesting::VestingPosition;
+use crate::withdrawal::{PendingWithdrawal, WithdrawWindow};
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
+use near_sdk::store::{IterableMap, IterableSet, LookupMap, LookupSet};
 use near_sdk::{
-    borsh, env, near, require, serde_json, AccountId, BorshStorageKey, CryptoHash, NearToken,
-    PanicOnDefault, Promise,
+    assert_one_yocto, borsh, env, near, require, serde_json, AccountId, BorshStorageKey,
+    CryptoHash, NearToken, PanicOnDefault, Promise, PublicKey,
 };
 
 use near_sdk::serde::Serialize;
 
+pub use crate::merkle::{
+    hash_pair, verify_directional_proof, verify_multi_proof, verify_proof, HashAlgo, LeafEncoding,
+    LeafHashing, LeafVersion, TreeHasher,
+};
+
+/// The off-chain tree builder, enabled by the `tree` feature outside wasm builds.
+#[cfg(any(test, all(feature = "tree", not(target_arch = "wasm32"))))]
+pub use crate::merkle::tree;
+
 /// Raw type for balance in yocto NEAR.
 pub type Balance = u128;
 /// Raw type for unique identifier for campaigns
@@ -21,16 +81,320 @@ pub type CampaignId = u32;
 enum StorageKeys {
     Claims,
     Campaigns,
+    ClaimBitmaps,
+    ClaimedAmounts,
+    VestingPositions,
+    SignatureNonces,
+    InvalidatedLeaves,
+    BlockedAccounts,
+    CampaignClaims,
+    CampaignClaimRecords { campaign_id: CampaignId },
+    Registrations,
+    Guardians,
+    FinancialOperators,
+    AdminLog,
+    Proposals,
+    OperatorKeys,
+    StorageBalances,
+    RecordPayers,
+    StorageDeposits,
+}
+
+/// Leaf payload of `LeafVersion::V1` campaigns, and an allocation given to the tree builder.
+#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Debug, Serialize)]
+#[near(serializers=[borsh])]
+#[serde(crate = "near_sdk::serde")]
+pub struct MerkleTreeData {
+    pub account: String,
+    pub lockup: String,
+    #[serde(serialize_with = "merkle::serialize_decimal")]
+    pub amount: Balance,
+}
+
+/// Leaf payload of `LeafVersion::V2` campaigns, which also commits to the campaign.
+#[near(serializers=[borsh])]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct MerkleTreeDataV2 {
+    campaign_id: CampaignId,
+    account: String,
+    lockup: String,
+    #[serde(serialize_with = "merkle::serialize_decimal")]
+    amount: Balance,
+}
+
+/// Leaf payload of `LeafVersion::V3` campaigns, which pay an account committed in the tree that
+/// may differ from the claimer.
+#[near(serializers=[borsh])]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct MerkleTreeDataV3 {
+    campaign_id: CampaignId,
+    claimer: String,
+    receiver: String,
+    #[serde(serialize_with = "merkle::serialize_decimal")]
+    amount: Balance,
+}
+
+/// Leaf payload of `LeafVersion::V4` campaigns, which also commits to a deadline of the
+/// allocation.
+#[near(serializers=[borsh])]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct MerkleTreeDataV4 {
+    campaign_id: CampaignId,
+    account: String,
+    lockup: String,
+    #[serde(serialize_with = "merkle::serialize_decimal")]
+    amount: Balance,
+    #[serde(serialize_with = "merkle::serialize_decimal")]
+    deadline: u64,
+}
+
+/// Leaf payload of `LeafVersion::V5` campaigns, in which an account may hold several
+/// allocations told apart by their nonce.
+#[near(serializers=[borsh])]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct MerkleTreeDataV5 {
+    campaign_id: CampaignId,
+    account: String,
+    lockup: String,
+    #[serde(serialize_with = "merkle::serialize_decimal")]
+    amount: Balance,
+    nonce: u32,
+}
+
+/// Leaf payload of `LeafVersion::V6` campaigns, whose allocations belong to Ethereum addresses
+/// that choose their receiver when claiming.
+#[near(serializers=[borsh])]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct MerkleTreeDataV6 {
+    campaign_id: CampaignId,
+    eth_address: [u8; 20],
+    #[serde(serialize_with = "merkle::serialize_decimal")]
+    amount: Balance,
+}
+
+/// Leaf payload of `LeafVersion::V7` campaigns, whose allocations belong to ed25519 public keys
+/// that choose their receiver when claiming.
+#[near(serializers=[borsh])]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct MerkleTreeDataV7 {
+    campaign_id: CampaignId,
+    public_key: [u8; 32],
+    #[serde(serialize_with = "merkle::serialize_decimal")]
+    amount: Balance,
+}
+
+/// Leaf payload of `AllocationMode::Fixed` campaigns, which pay every account the same amount
+/// so leaves do not carry it.
+#[near(serializers=[borsh])]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct MerkleTreeDataFixed {
+    campaign_id: CampaignId,
+    account: String,
+    lockup: String,
 }
 
-#[derive(Ord, PartialOrd, Eq, PartialEq, Clone)]
+/// Leaf payload of `ClaimTracking::Bitmap` campaigns, which also commits to the bit tracking
+/// the claim.
 #[near(serializers=[borsh])]
-struct MerkleTreeData {
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct MerkleTreeDataIndexed {
+    campaign_id: CampaignId,
+    index: u32,
     account: String,
     lockup: String,
+    #[serde(serialize_with = "merkle::serialize_decimal")]
     amount: Balance,
 }
 
+/// How a campaign records which allocations have been claimed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub enum ClaimTracking {
+    /// A claim key per account is stored in `claims`
+    #[default]
+    Keys,
+    /// Every leaf carries a unique index and claims are bits in a per-campaign bitmap, which is
+    /// far cheaper in storage for large campaigns
+    Bitmap,
+}
+
+/// Where the amount of an allocation comes from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub enum AllocationMode {
+    /// Every leaf commits to its own amount
+    #[default]
+    PerLeaf,
+    /// Every account on the list receives the same amount and leaves only commit to
+    /// `(campaign_id, account, lockup)`
+    Fixed(U128),
+}
+
+/// Optional settings of a campaign, every field falls back to its default when omitted.
+#[derive(Clone, Default)]
+#[near(serializers=[borsh, json])]
+#[serde(default)]
+pub struct CampaignOptions {
+    pub hash_algo: HashAlgo,
+    pub proof_format: ProofFormat,
+    /// The number of leaves in the tree, required by `RsMerkle` campaigns
+    pub leaf_count: Option<u32>,
+    pub leaf_encoding: LeafEncoding,
+    pub leaf_hashing: LeafHashing,
+    pub leaf_version: LeafVersion,
+    pub claim_tracking: ClaimTracking,
+    pub verify_lockup_owner: bool,
+    pub verify_receiver_exists: bool,
+    pub allow_partial_claims: bool,
+    /// When set, claimed allocations are released linearly from the campaign start until this
+    /// timestamp
+    pub vesting_end: Option<U64>,
+    /// When set, claims made after the decay start pay a share of their amount that shrinks
+    /// until the claim end
+    pub decay: Option<ClaimDecay>,
+    /// When set, early claims earn a bonus paid from the campaign's bonus pool
+    pub bonus: Option<ClaimBonus>,
+    /// Whether claims paying a lockup contract may send part of it to the claimant instead
+    pub allow_liquid_split: bool,
+    /// When set, every allocation can be claimed once per period, and the campaign ends with
+    /// its last period
+    pub periods: Option<ClaimPeriods>,
+    pub allocation_mode: AllocationMode,
+    /// The sum of all allocations in the tree, when set it is reserved until claimed, cancelled
+    /// or swept and claims can never exceed it
+    pub total_allocation: Option<U128>,
+    /// When set, claims pay their receiver by calling this method instead of a transfer
+    pub payout_call: Option<PayoutCall>,
+    /// Whether accounts must register with a storage deposit before they can claim
+    pub require_registration: bool,
+    /// When set, referred claims earn their referrer a bonus paid from the campaign's referral
+    /// pool
+    pub referral: Option<ClaimReferral>,
+    /// Whether claimants pay for the storage of their claim records with a deposit attached to
+    /// `claim`
+    pub claimant_pays_storage: bool,
+    /// A note for claimants, e.g. what the campaign rewards, echoed by every claim event and
+    /// limited to `MAX_CAMPAIGN_MEMO_LENGTH` bytes
+    pub memo: Option<String>,
+    /// When set, the campaign is only created under this id, which the leaves of its tree commit
+    /// to unless they are `V1` or `OzStandard` leaves
+    pub expected_campaign_id: Option<CampaignId>,
+}
+
+/// Optional claim inputs that only some campaigns use.
+#[derive(Clone, Default)]
+#[near(serializers=[borsh, json])]
+#[serde(default)]
+pub struct ClaimOptions {
+    /// The leaf index, required by bitmap campaigns
+    pub index: Option<u32>,
+    /// The account paid by the leaf, required by `V3` campaigns
+    pub receiver_id: Option<AccountId>,
+    /// The part of the allocation to claim in campaigns allowing partial claims, defaults to the
+    /// whole remainder
+    pub claim_amount: Option<U128>,
+    /// The deadline committed in the leaf, required by `V4` campaigns
+    pub deadline: Option<U64>,
+    /// The nonce of the leaf, required by `V5` campaigns
+    pub nonce: Option<u32>,
+    /// The share of a lockup payout sent to the claimant instead, in basis points, in campaigns
+    /// allowing liquid splits
+    pub liquid_bps: Option<u16>,
+    /// The account that referred the claimant, paid a bonus in campaigns with a referral bonus
+    pub referrer: Option<AccountId>,
+    /// The side of every hash of the proof, required by directional campaigns
+    pub directions: Option<Vec<Direction>>,
+    /// The position of the leaf in the tree, required by `RsMerkle` campaigns
+    pub leaf_position: Option<u32>,
+}
+
+/// One of the allocations claimed together by `claim_multi`.
+#[derive(Clone)]
+#[near(serializers=[json])]
+pub struct ClaimAllocation {
+    pub amount: U128,
+    pub lockup_contract: Option<AccountId>,
+    pub options: Option<ClaimOptions>,
+}
+
+/// Whether a campaign accepts claims.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers=[json])]
+pub enum CampaignStatus {
+    /// Claims are accepted, indefinitely for perpetual campaigns
+    Active,
+    /// The claim period has concluded
+    Ended,
+}
+
+/// What a campaign's claims amount to, see `get_campaign_stats`.
+#[derive(Debug, PartialEq)]
+#[near(serializers=[json])]
+pub struct CampaignStats {
+    pub claim_count: u64,
+    /// The allocations claimed so far, only counted when `total_allocation` is declared
+    pub claimed_total: U128,
+    /// The storage the campaign and its claim records use in bytes
+    pub storage_bytes: u64,
+    /// What `storage_bytes` costs at the current byte cost
+    pub storage_cost: NearToken,
+}
+
+/// What a claims UI shows about an account's claim in a campaign, see `get_claim_context`.
+#[near(serializers=[json])]
+pub struct ClaimContext {
+    pub campaign: CampaignView,
+    pub status: CampaignStatus,
+    pub has_claimed: bool,
+    /// The seconds left until the claim end, 0 once it has passed and `None` for perpetual
+    /// campaigns
+    pub seconds_remaining: Option<U64>,
+    /// Whether any operation of the contract is paused
+    pub paused: bool,
+    /// Whether claims are paused, in which case they fail even in active campaigns
+    pub claims_paused: bool,
+    /// The amount the claim record stores, `None` unless it was recorded with one
+    pub claimed_amount: Option<U128>,
+    /// When the claim completed, `None` unless its record stores it
+    pub claimed_at: Option<U64>,
+}
+
+/// A leaf computed by the contract, see `compute_leaf`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers=[json])]
+pub struct ComputedLeaf {
+    pub leaf_hex: String,
+    pub leaf_b58: Base58CryptoHash,
+}
+
+/// The outcome of checking a claim without submitting it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers=[json])]
+pub enum ClaimCheck {
+    Claimable,
+    CampaignNotFound,
+    AlreadyClaimed,
+    /// The owner invalidated the account's allocations
+    Invalidated,
+    /// The owner blocked the account from claiming
+    Blocked,
+    /// The campaign requires registration and the account has not registered
+    NotRegistered,
+    /// The campaign's claim period has concluded
+    CampaignEnded,
+    /// The deadline of the leaf has passed although the campaign is still running
+    LeafExpired,
+    InvalidProof,
+}
+
 #[derive(Clone)]
 #[near(serializers=[borsh,json])]
 pub struct RewardCampaign {
@@ -38,34 +402,568 @@ pub struct RewardCampaign {
     pub id: CampaignId,
     /// The timestamp that starts the claim period, this is generated automatically
     pub claim_start: U64,
-    /// The timestamp for when the claim period has concluded
-    pub claim_end: U64,
-    /// The merkle root of the tree containing the rewards for each account_id
+    /// The timestamp for when the claim period has concluded, `None` for perpetual campaigns
+    pub claim_end: Option<U64>,
+    /// The timestamp the claim period concluded at when the campaign was created, which
+    /// extensions are capped against
+    pub original_claim_end: Option<U64>,
+    /// The merkle root of the tree containing the rewards for each account_id, base58 encoded in
+    /// JSON
+    #[serde(
+        serialize_with = "merkle::serialize_b58_hash",
+        deserialize_with = "merkle::deserialize_b58_hash"
+    )]
     pub merkle_root: CryptoHash,
+    /// The hash function this campaign's tree is built with
+    pub hash_algo: HashAlgo,
+    /// How this campaign's proofs combine nodes
+    pub proof_format: ProofFormat,
+    /// The number of leaves in the tree of `RsMerkle` campaigns
+    pub leaf_count: Option<u32>,
+    /// How leaf payloads of this campaign's tree are serialized
+    pub leaf_encoding: LeafEncoding,
+    /// How leaves of this campaign's tree are hashed
+    pub leaf_hashing: LeafHashing,
+    /// Which data the leaves of this campaign's tree commit to
+    pub leaf_version: LeafVersion,
+    /// How claims of this campaign are recorded
+    pub claim_tracking: ClaimTracking,
+    /// Whether claims only pay lockup contracts owned by the claimant, which is checked with a
+    /// cross-contract call before the claim is recorded
+    pub verify_lockup_owner: bool,
+    /// Whether claims paying a named account first check that it exists with an empty transfer,
+    /// and are only recorded once it succeeded
+    pub verify_receiver_exists: bool,
+    /// How keys of this campaign's claims are derived
+    pub claim_key_version: ClaimKeyVersion,
+    /// Whether allocations may be claimed in several parts
+    pub allow_partial_claims: bool,
+    /// The timestamp at which claimed allocations are fully released, `None` when claims pay out
+    /// at once
+    pub vesting_end: Option<U64>,
+    /// The number of claims recorded, counted since the campaign was created or migrated
+    pub claim_count: u64,
+    /// How the payout of late claims decays, `None` when claims are paid in full
+    pub decay: Option<ClaimDecay>,
+    /// Which claims earn a bonus, `None` when there is no bonus
+    pub bonus: Option<ClaimBonus>,
+    /// The balance left to pay bonuses, funded with `fund_bonus_pool`
+    pub bonus_pool: U128,
+    /// Whether claims paying a lockup contract may send part of it to the claimant instead
+    pub allow_liquid_split: bool,
+    /// The periods in which allocations can be claimed again, `None` when they are claimed once
+    pub periods: Option<ClaimPeriods>,
+    /// Whether leaves carry their amount or every account receives the same amount
+    pub allocation_mode: AllocationMode,
+    /// The sum of all allocations in the tree, `None` when it was not declared
+    pub total_allocation: Option<U128>,
+    /// The allocations claimed so far, only counted when `total_allocation` is declared
+    pub claimed_total: U128,
+    /// Whether the unclaimed part of `total_allocation` was released by a cancellation or sweep
+    pub allocation_released: bool,
+    /// The part of this campaign's vesting positions that has not been released yet
+    pub vesting_reserved: U128,
+    /// Where the records of this campaign's claims are stored
+    pub claim_storage: ClaimStorage,
+    /// The method claims call on their receiver with the payout attached, `None` when they
+    /// transfer it
+    pub payout_call: Option<PayoutCall>,
+    /// Whether accounts must register with a storage deposit before they can claim
+    pub require_registration: bool,
+    /// The deposits of registrations that were neither used by a claim nor refunded
+    pub registration_deposits: U128,
+    /// Which share referred claims pay their referrer, `None` when there is no referral bonus
+    pub referral: Option<ClaimReferral>,
+    /// The balance left to pay referral bonuses, funded with `fund_referral_pool`
+    pub referral_pool: U128,
+    /// Whether claimants pay for the storage of their claim records
+    pub claimant_pays_storage: bool,
+    /// The donations left to pay claims, received with `donate_to_campaign`
+    pub donation_pool: U128,
+    /// The storage the campaign and the records of its claims use in bytes, measured since the
+    /// campaign was created or migrated
+    pub storage_bytes: u64,
+    /// The owner's note for claimants, replaced with `set_campaign_memo`
+    pub memo: Option<String>,
+}
+
+/// A campaign as returned by views, with its root also encoded as strings.
+#[near(serializers=[json])]
+pub struct CampaignView {
+    #[serde(flatten)]
+    pub campaign: RewardCampaign,
+    /// The merkle root as `0x`-prefixed hex
+    pub merkle_root_hex: String,
+    pub merkle_root_b58: Base58CryptoHash,
+}
+
+impl RewardCampaign {
+    /// Returns whether the claim period has concluded at `timestamp`, which never happens for
+    /// perpetual campaigns.
+    fn has_ended(&self, timestamp: u64) -> bool {
+        self.claim_end
+            .is_some_and(|claim_end| timestamp >= claim_end.0)
+    }
+
+    /// Returns the account paid by a claim, panicking if the claim inputs do not match the
+    /// campaign's leaves or the claim would pay the contract itself, which would mark the
+    /// allocation claimed while keeping its funds in the contract.
+    fn resolve_receiver(
+        &self,
+        account_id: &AccountId,
+        lockup_contract: Option<&AccountId>,
+        receiver_id: Option<AccountId>,
+    ) -> AccountId {
+        let receiver_id = match (self.leaf_version, receiver_id) {
+            (LeafVersion::V3, Some(receiver_id)) => {
+                require!(
+                    lockup_contract.is_none(),
+                    "V3 leaves pay their receiver instead of a lockup contract"
+                );
+                receiver_id
+            }
+            (LeafVersion::V3, None) => env::panic_str("Receiver is required by V3 leaves"),
+            (LeafVersion::V6 | LeafVersion::V7, Some(receiver_id)) => {
+                require!(
+                    lockup_contract.is_none(),
+                    "V6 and V7 leaves pay their receiver instead of a lockup contract"
+                );
+                receiver_id
+            }
+            (LeafVersion::V6 | LeafVersion::V7, None) => {
+                env::panic_str("Receiver is required by V6 and V7 leaves")
+            }
+            (_, Some(_)) => env::panic_str("Receiver is only accepted by V3, V6 and V7 leaves"),
+            (_, None) => lockup_contract.unwrap_or(account_id).clone(),
+        };
+
+        require!(
+            receiver_id != env::current_account_id(),
+            "Claims cannot pay the claim contract itself"
+        );
+        receiver_id
+    }
+
+    /// Returns the deadline committed in the leaf, panicking unless it is given exactly for `V4`
+    /// leaves.
+    fn leaf_deadline(&self, deadline: Option<U64>) -> Option<u64> {
+        match (self.leaf_version, deadline) {
+            (LeafVersion::V4, Some(deadline)) => Some(deadline.0),
+            (LeafVersion::V4, None) => env::panic_str("Deadline is required by V4 leaves"),
+            (_, Some(_)) => env::panic_str("Deadline is only accepted by V4 leaves"),
+            (_, None) => None,
+        }
+    }
+
+    /// Returns the nonce of the leaf, panicking unless it is given exactly for `V5` leaves.
+    fn leaf_nonce(&self, nonce: Option<u32>) -> Option<u32> {
+        match (self.leaf_version, nonce) {
+            (LeafVersion::V5, Some(nonce)) => Some(nonce),
+            (LeafVersion::V5, None) => env::panic_str("Nonce is required by V5 leaves"),
+            (_, Some(_)) => env::panic_str("Nonce is only accepted by V5 leaves"),
+            (_, None) => None,
+        }
+    }
+
+    /// Verifies that `leaf` is part of this campaign's tree, panicking unless `directions` are
+    /// given exactly for directional campaigns, one for every hash of `merkle_proof`, and
+    /// `leaf_position` exactly for `RsMerkle` campaigns.
+    fn verify_leaf(
+        &self,
+        leaf: &CryptoHash,
+        merkle_proof: &[CryptoHash],
+        directions: Option<&[Direction]>,
+        leaf_position: Option<u32>,
+    ) -> bool {
+        require!(
+            self.proof_format == ProofFormat::RsMerkle || leaf_position.is_none(),
+            "Leaf position is only accepted by RsMerkle campaigns"
+        );
+
+        let hasher = self.tree_hasher();
+        match (self.proof_format, directions) {
+            (ProofFormat::Commutative, None) => {
+                hasher.verify_proof(leaf, merkle_proof, &self.merkle_root)
+            }
+            (ProofFormat::RsMerkle, None) => hasher.verify_rs_merkle_proof(
+                leaf,
+                leaf_position.expect("Leaf position is required by RsMerkle campaigns"),
+                self.leaf_count
+                    .expect("RsMerkle campaigns have a leaf count"),
+                merkle_proof,
+                &self.merkle_root,
+            ),
+            (ProofFormat::Commutative | ProofFormat::RsMerkle, Some(_)) => {
+                env::panic_str("Proof directions are only accepted by directional campaigns")
+            }
+            (ProofFormat::Directional, Some(directions)) => {
+                require!(
+                    directions.len() == merkle_proof.len(),
+                    "Proof directions must match the proof length"
+                );
+                let path: Vec<_> = merkle_proof
+                    .iter()
+                    .copied()
+                    .zip(directions.iter().copied())
+                    .collect();
+                hasher.verify_directional_proof(leaf, &path, &self.merkle_root)
+            }
+            (ProofFormat::Directional, None) => {
+                env::panic_str("Proof directions are required by directional campaigns")
+            }
+        }
+    }
+
+    /// Returns how this campaign's tree hashes leaves and internal nodes.
+    fn tree_hasher(&self) -> TreeHasher {
+        TreeHasher {
+            hash_algo: self.hash_algo,
+            leaf_hashing: self.leaf_hashing,
+        }
+    }
+
+    /// Returns how this campaign's leaves are computed.
+    fn leaf_format(&self) -> LeafFormat {
+        LeafFormat {
+            campaign_id: self.id,
+            allocation_mode: self.allocation_mode,
+            leaf_version: self.leaf_version,
+            leaf_encoding: self.leaf_encoding,
+            hash_algo: self.hash_algo,
+            leaf_hashing: self.leaf_hashing,
+        }
+    }
+
+    /// Computes the leaf of an allocation in this campaign's tree.
+    fn compute_leaf(
+        &self,
+        index: Option<u32>,
+        account: &AccountId,
+        receiver: &AccountId,
+        amount: Balance,
+        deadline: Option<u64>,
+        nonce: Option<u32>,
+    ) -> CryptoHash {
+        self.leaf_format()
+            .compute_leaf(index, account, receiver, amount, deadline, nonce)
+    }
+}
+
+/// Everything the leaves of a campaign's tree depend on, so off-chain tree builders compute
+/// them exactly as the campaign does.
+#[derive(Clone, Copy, Debug, Default)]
+#[near(serializers=[json])]
+#[serde(default)]
+pub struct LeafFormat {
+    pub campaign_id: CampaignId,
+    pub allocation_mode: AllocationMode,
+    pub leaf_version: LeafVersion,
+    pub leaf_encoding: LeafEncoding,
+    pub hash_algo: HashAlgo,
+    pub leaf_hashing: LeafHashing,
+}
+
+impl LeafFormat {
+    /// Returns whether leaves of this format commit to `campaign_id`, so their tree can only be
+    /// claimed in the campaign created with that id.
+    pub fn binds_campaign_id(&self) -> bool {
+        self.leaf_encoding != LeafEncoding::OzStandard
+            && (matches!(self.allocation_mode, AllocationMode::Fixed(_))
+                || self.leaf_version != LeafVersion::V1)
+    }
+
+    /// Returns how trees of this format hash leaves and internal nodes.
+    pub fn tree_hasher(&self) -> TreeHasher {
+        TreeHasher {
+            hash_algo: self.hash_algo,
+            leaf_hashing: self.leaf_hashing,
+        }
+    }
+
+    /// Computes the leaf of an allocation in trees of this format. `index` is only part of the
+    /// leaf in bitmap campaigns, `deadline` in `V4` campaigns and `nonce` in `V5` campaigns, while
+    /// `amount` is left out in fixed amount campaigns.
+    pub fn compute_leaf(
+        &self,
+        index: Option<u32>,
+        account: &AccountId,
+        receiver: &AccountId,
+        amount: Balance,
+        deadline: Option<u64>,
+        nonce: Option<u32>,
+    ) -> CryptoHash {
+        if self.leaf_encoding == LeafEncoding::OzStandard {
+            return self.tree_hasher().hash_leaf(&merkle::oz_standard_payload(
+                account.as_str(),
+                receiver.as_str(),
+                amount,
+            ));
+        }
+
+        let payload = match (self.allocation_mode, self.leaf_version, index) {
+            (AllocationMode::Fixed(_), _, _) => self.serialize_leaf(&MerkleTreeDataFixed {
+                campaign_id: self.campaign_id,
+                account: account.to_string(),
+                lockup: receiver.to_string(),
+            }),
+            (_, LeafVersion::V1, _) => self.serialize_leaf(&MerkleTreeData {
+                account: account.to_string(),
+                lockup: receiver.to_string(),
+                amount,
+            }),
+            (_, LeafVersion::V2, None) => self.serialize_leaf(&MerkleTreeDataV2 {
+                campaign_id: self.campaign_id,
+                account: account.to_string(),
+                lockup: receiver.to_string(),
+                amount,
+            }),
+            (_, LeafVersion::V2, Some(index)) => self.serialize_leaf(&MerkleTreeDataIndexed {
+                campaign_id: self.campaign_id,
+                index,
+                account: account.to_string(),
+                lockup: receiver.to_string(),
+                amount,
+            }),
+            (_, LeafVersion::V3, _) => self.serialize_leaf(&MerkleTreeDataV3 {
+                campaign_id: self.campaign_id,
+                claimer: account.to_string(),
+                receiver: receiver.to_string(),
+                amount,
+            }),
+            (_, LeafVersion::V4, _) => self.serialize_leaf(&MerkleTreeDataV4 {
+                campaign_id: self.campaign_id,
+                account: account.to_string(),
+                lockup: receiver.to_string(),
+                amount,
+                deadline: deadline.expect("Deadline is required by V4 leaves"),
+            }),
+            (_, LeafVersion::V5, _) => self.serialize_leaf(&MerkleTreeDataV5 {
+                campaign_id: self.campaign_id,
+                account: account.to_string(),
+                lockup: receiver.to_string(),
+                amount,
+                nonce: nonce.expect("Nonce is required by V5 leaves"),
+            }),
+            (_, LeafVersion::V6, _) => self.serialize_leaf(&MerkleTreeDataV6 {
+                campaign_id: self.campaign_id,
+                eth_address: eth::eth_address(account),
+                amount,
+            }),
+            (_, LeafVersion::V7, _) => self.serialize_leaf(&MerkleTreeDataV7 {
+                campaign_id: self.campaign_id,
+                public_key: signature::implicit_public_key(account),
+                amount,
+            }),
+        };
+
+        self.tree_hasher().hash_leaf(&payload)
+    }
+
+    /// Serializes a leaf payload with the format's leaf encoding. `JsonCanonical` payloads are
+    /// compact JSON objects with the fields in declaration order.
+    fn serialize_leaf<T: borsh::BorshSerialize + Serialize>(&self, data: &T) -> Vec<u8> {
+        match self.leaf_encoding {
+            LeafEncoding::JsonCanonical => {
+                serde_json::to_vec(data).expect("Failed to serialize data")
+            }
+            _ => borsh::to_vec(data).expect("Failed to serialize data"),
+        }
+    }
+}
+
+/// How a campaign derives the keys its claim records are stored under.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub enum ClaimKeyVersion {
+    /// `keccak256(account_id || campaign_id)`, used by campaigns created before keys were length
+    /// prefixed
+    V1,
+    /// `keccak256(borsh((account_id, campaign_id)))`, where the account id is length prefixed
+    #[default]
+    V2,
+    /// `keccak256(borsh((account_id, campaign_id, nonce)))`, used by `V5` campaigns where an
+    /// account may claim several leaves
+    V3,
+}
+
+/// Derives the key an account's claim record in a campaign is stored under. The campaign id is
+/// always encoded as u32 little-endian.
+///
+/// `V1` keys concatenate the raw account bytes with the campaign id. The campaign id used to be
+/// encoded with `to_ne_bytes`, which on wasm32 is little-endian, so keys of claims recorded by
+/// earlier deployments are unchanged. `V2` keys prefix the account with its length so field
+/// boundaries never depend on the width of the fields that follow. `V3` keys also commit to the
+/// leaf nonce, which the other versions ignore.
+pub fn compute_claim_key(
+    version: ClaimKeyVersion,
+    account_id: &AccountId,
+    campaign_id: CampaignId,
+    nonce: Option<u32>,
+) -> CryptoHash {
+    match version {
+        ClaimKeyVersion::V1 => {
+            env::keccak256_array(&[account_id.as_bytes(), &campaign_id.to_le_bytes()].concat())
+        }
+        ClaimKeyVersion::V2 => env::keccak256_array(
+            &borsh::to_vec(&(account_id.as_str(), campaign_id))
+                .expect("Failed to serialize claim key"),
+        ),
+        ClaimKeyVersion::V3 => env::keccak256_array(
+            &borsh::to_vec(&(
+                account_id.as_str(),
+                campaign_id,
+                nonce.expect("Nonce is required by V3 claim keys"),
+            ))
+            .expect("Failed to serialize claim key"),
+        ),
+    }
+}
+
+/// A claim whose proof has been verified, waiting to be recorded and paid.
+#[derive(Clone)]
+#[near(serializers=[json])]
+pub struct VerifiedClaim {
+    pub campaign_id: CampaignId,
+    pub account_id: AccountId,
+    /// `None` when the allocation is paid directly to the claimant
+    pub lockup_contract: Option<AccountId>,
+    pub receiver_id: AccountId,
+    /// The whole allocation of the leaf
+    pub allocation: U128,
+    /// The part of the allocation claimed
+    pub amount: U128,
+    /// What the claim pays for `amount`, less than it once the campaign's decay started
+    pub paid_amount: U128,
+    pub index: Option<u32>,
+    /// The leaf nonce of `V5` campaigns or the period of periodic campaigns
+    pub nonce: Option<u32>,
+    /// The share of the payout sent to the claimant instead of the lockup contract
+    pub liquid_bps: Option<u16>,
+    /// The full access key of the receiver account the payout creates, `None` when the
+    /// receiver already exists
+    pub new_account_key: Option<PublicKey>,
+    /// The fee paid to the relayer of a signed claim out of the payout, `None` when there is none
+    pub relayer_fee: Option<RelayerFee>,
+    pub referrer: Option<AccountId>,
+    /// The bonus paid to the referrer once the payout succeeded, `None` when none is earned
+    pub referral_bonus: Option<U128>,
+    /// The part of the payout taken from the campaign's donation pool, `None` when none is
+    pub donated: Option<U128>,
+    /// The position of the claim among the campaign's claims, `None` until it is recorded
+    pub claim_index: Option<U64>,
 }
 
-// Define the contract structure
+// Define the contract structure, whose NEP-330 source metadata lists the standards below, see
+// `metadata`
 #[derive(PanicOnDefault)]
-#[near(contract_state)]
+#[near(
+    contract_state,
+    contract_metadata(
+        standard(standard = "nep145", version = "1.0.0"),
+        standard(standard = "nep297", version = "1.0.0"),
+    )
+)]
 pub struct MerkleClaim {
     config: Config,
-    /// A set of accounts who have claimed or have yet to claim their NEAR rewards where the key is
-    /// a hash of the campaign_id & account_id
-    claims: LookupSet<CryptoHash>,
+    /// Records of the claims made in campaigns with `Shared` claim storage, where the key is a
+    /// hash of the campaign_id & account_id
+    claims: LookupMap<CryptoHash, ClaimRecord>,
+    /// Records of the claims made in campaigns with their own claim storage, by campaign
+    campaign_claims: LookupMap<CampaignId, IterableMap<CryptoHash, ClaimRecord>>,
     /// A map all the reward campaings
     campaigns: LookupMap<CampaignId, RewardCampaign>,
+    /// Claimed bits of bitmap campaigns, each word tracks 256 consecutive leaf indices
+    claim_bitmaps: LookupMap<(CampaignId, u32), [u8; 32]>,
+    /// Amounts claimed so far in campaigns allowing partial claims, by claim key
+    claimed_amounts: LookupMap<CryptoHash, Balance>,
+    /// Vesting positions opened by claims of vesting campaigns, by claim key
+    vesting_positions: LookupMap<CryptoHash, VestingPosition>,
+    /// The nonce the next signed claim of each account must use
+    signature_nonces: LookupMap<AccountId, u64>,
+    /// Allocations invalidated by the owner, by `V2` claim key of the account and campaign
+    invalidated_leaves: LookupSet<CryptoHash>,
+    /// Accounts the owner blocked from claiming in any campaign
+    blocked_accounts: LookupSet<AccountId>,
+    /// Claim registrations of campaigns requiring them, by `V2` claim key of the account and
+    /// campaign
+    registrations: LookupMap<CryptoHash, Registration>,
+    /// Funds set aside for bonus and referral pools, unreleased vesting positions and
+    /// registration deposits, which claims cannot use
+    reserved_balance: Balance,
     /// The last campaign_id generated
     last_campaign_id: CampaignId,
-    /// An only owner variable that pauses the contract in case of security issues
-    paused: bool,
+    /// The operations the owner paused in case of security issues
+    pause_flags: PauseFlags,
+    /// Accounts that may pause the contract besides the owner
+    guardians: IterableSet<AccountId>,
+    /// Accounts that may fund pools and sweep campaigns besides the owner
+    financial_operators: LookupSet<AccountId>,
+    /// The last `ADMIN_LOG_CAPACITY` privileged actions, by id modulo the capacity
+    admin_log: LookupMap<u64, AdminAction>,
+    /// The number of privileged actions ever recorded in the admin log
+    admin_log_length: u64,
+    /// The signers confirming owner actions instead of the owner, when enabled
+    multisig: Option<Multisig>,
+    /// Owner actions proposed to the multisig signers and awaiting confirmation
+    proposals: LookupMap<ProposalId, Proposal>,
+    /// The last proposal_id generated
+    last_proposal_id: ProposalId,
+    /// The withdrawal requested by the owner, until it is executed or cancelled
+    pending_withdrawal: Option<PendingWithdrawal>,
+    /// The config change loosening withdrawals, until it is executed or cancelled
+    pending_config_change: Option<PendingConfigChange>,
+    /// The account the owner proposed to transfer ownership to, until it accepts
+    pending_owner: Option<AccountId>,
+    /// The last `OWNER_HISTORY_CAPACITY` owners and when they became the owner
+    owner_history: Vec<OwnerChange>,
+    /// The emergency shutdown, once the owner or a guardian approved it
+    emergency_shutdown: Option<EmergencyShutdown>,
+    /// The window the `withdraw_limit` is enforced in
+    withdraw_window: WithdrawWindow,
+    /// The function call access keys of the contract's account that can create campaigns, with
+    /// their gas allowance
+    operator_keys: IterableMap<PublicKey, NearToken>,
+    /// The NEP-145 storage balances of accounts, which pay for their claim registrations
+    storage_balances: LookupMap<AccountId, NearToken>,
+    /// The sum of `storage_balances`, which neither claims nor withdrawals can use
+    storage_balances_total: Balance,
+    /// The sponsors that registered claimants who then claimed, by registration key, to refund
+    /// the storage of the claim records they paid for
+    record_payers: LookupMap<CryptoHash, AccountId>,
+    /// What each account deposited to the contract's storage deposit and did not reclaim
+    storage_deposits: LookupMap<AccountId, NearToken>,
 }
 
 #[derive(Serialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct CampaignCreatedEvent {
     pub campaign_id: CampaignId,
+    #[serde(serialize_with = "merkle::serialize_b58_hash")]
     pub merkle_root: CryptoHash,
-    pub claim_end: U64,
+    pub merkle_root_hex: String,
+    pub claim_end: Option<U64>,
+    pub hash_algo: HashAlgo,
+    pub proof_format: ProofFormat,
+    pub leaf_count: Option<u32>,
+    pub leaf_encoding: LeafEncoding,
+    pub leaf_hashing: LeafHashing,
+    pub leaf_version: LeafVersion,
+    pub claim_tracking: ClaimTracking,
+    pub verify_lockup_owner: bool,
+    pub verify_receiver_exists: bool,
+    pub allow_partial_claims: bool,
+    pub vesting_end: Option<U64>,
+    pub decay: Option<ClaimDecay>,
+    pub bonus: Option<ClaimBonus>,
+    pub allow_liquid_split: bool,
+    pub periods: Option<ClaimPeriods>,
+    pub allocation_mode: AllocationMode,
+    pub total_allocation: Option<U128>,
+    pub payout_call: Option<PayoutCall>,
+    pub require_registration: bool,
+    pub referral: Option<ClaimReferral>,
+    pub claimant_pays_storage: bool,
+    pub memo: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -73,8 +971,47 @@ pub struct CampaignCreatedEvent {
 pub struct ClaimEvent {
     pub campaign_id: CampaignId,
     pub account_id: AccountId,
-    pub lockup_contract: AccountId,
-    pub amount: Balance,
+    /// `None` when the allocation was paid directly to the claimant
+    pub lockup_contract: Option<AccountId>,
+    pub receiver_id: AccountId,
+    pub amount: U128,
+    /// What the claim pays for `amount` after decay, omitted when it is paid in full
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paid_amount: Option<U128>,
+    /// The bonus paid to the receiver on top of the paid amount, omitted when none is earned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bonus: Option<U128>,
+    /// The part of the paid amount sent to the fee receiver, omitted when no fee is taken
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<U128>,
+    /// The fee receiver or treasury the fee is sent to, omitted when no fee is taken
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_receiver: Option<AccountId>,
+    /// The part of the paid amount sent to the receiver, omitted when no fee is taken
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub net_amount: Option<U128>,
+    /// The part of the payout sent to the lockup contract, omitted unless the claim is split
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lockup_amount: Option<U128>,
+    /// The part of the payout sent to the claimant, omitted unless the claim is split
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liquid_amount: Option<U128>,
+    /// The part of the payout sent to the relayer of a signed claim, omitted when none is paid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relayer_fee: Option<U128>,
+    /// The largest relayer fee the claimant signed for, omitted when no relayer fee is paid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_relayer_fee: Option<U128>,
+    /// The period the claim was made in, omitted for campaigns without periods
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period: Option<u32>,
+    /// The position of the claim among the campaign's claims, starting at 0
+    pub claim_index: U64,
+    /// The campaign's memo when the claim was confirmed, omitted when it has none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    pub block_timestamp: U64,
+    pub block_height: U64,
 }
 
 #[derive(Serialize)]
@@ -82,6 +1019,10 @@ pub struct ClaimEvent {
 pub struct WithdrawEvent {
     pub balance: NearToken,
     pub withdrawn: NearToken,
+    pub receiver_id: AccountId,
+    /// The total withdrawn in the current window, omitted without a `withdraw_limit`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_withdrawn: Option<U128>,
 }
 
 // Implement the contract structure
@@ -106,13 +1047,52 @@ impl MerkleClaim {
             Promise::new(env::predecessor_account_id()).transfer(refund);
         }
 
-        Self {
-            config,
-            claims: LookupSet::new(StorageKeys::Claims),
+        let event = storage::StorageDepositEvent {
+            depositor: env::predecessor_account_id(),
+            account_id: env::current_account_id(),
+            attached: amount,
+            credited: min_balance,
+            refunded: refund,
+            min_storage_deposit: Some(min_balance),
+        };
+        Event::StorageDeposit(event).emit();
+
+        let mut contract = Self {
+            config: config.validated(),
+            claims: LookupMap::new(StorageKeys::Claims),
+            campaign_claims: LookupMap::new(StorageKeys::CampaignClaims),
             campaigns: LookupMap::new(StorageKeys::Campaigns),
+            claim_bitmaps: LookupMap::new(StorageKeys::ClaimBitmaps),
+            claimed_amounts: LookupMap::new(StorageKeys::ClaimedAmounts),
+            vesting_positions: LookupMap::new(StorageKeys::VestingPositions),
+            signature_nonces: LookupMap::new(StorageKeys::SignatureNonces),
+            invalidated_leaves: LookupSet::new(StorageKeys::InvalidatedLeaves),
+            blocked_accounts: LookupSet::new(StorageKeys::BlockedAccounts),
+            registrations: LookupMap::new(StorageKeys::Registrations),
+            reserved_balance: 0,
             last_campaign_id: 0,
-            paused: false,
-        }
+            pause_flags: PauseFlags::NONE,
+            guardians: IterableSet::new(StorageKeys::Guardians),
+            financial_operators: LookupSet::new(StorageKeys::FinancialOperators),
+            admin_log: LookupMap::new(StorageKeys::AdminLog),
+            admin_log_length: 0,
+            multisig: None,
+            proposals: LookupMap::new(StorageKeys::Proposals),
+            last_proposal_id: 0,
+            pending_withdrawal: None,
+            pending_config_change: None,
+            pending_owner: None,
+            owner_history: Vec::new(),
+            emergency_shutdown: None,
+            withdraw_window: WithdrawWindow::default(),
+            operator_keys: IterableMap::new(StorageKeys::OperatorKeys),
+            storage_balances: LookupMap::new(StorageKeys::StorageBalances),
+            storage_balances_total: 0,
+            record_payers: LookupMap::new(StorageKeys::RecordPayers),
+            storage_deposits: LookupMap::new(StorageKeys::StorageDeposits),
+        };
+        contract.record_owner_change();
+        contract
     }
 
     pub fn assert_owner(&self) {
@@ -122,161 +1102,1186 @@ impl MerkleClaim {
         );
     }
 
-    pub fn assert_unpaused(&self) {
-        require!(!self.paused, "Contract is paused");
-    }
-
-    pub fn pause(&mut self) {
-        self.assert_owner();
-        self.paused = true;
+    /// Creates a new campaign, which never ends if `claim_end` is `None`. The root may be given as
+    /// bytes, base58 or `0x`-prefixed hex. Without `options` leaves are double hashed `V2` leaves
+    /// and claims are tracked by key. Operator keys, see `add_operator_key`, can create
+    /// campaigns as well unless multisig confirmations are enabled.
+    #[payable]
+    pub fn create_campaign(
+        &mut self,
+        merkle_root: MerkleRootInput,
+        claim_end: Option<U64>,
+        options: Option<CampaignOptions>,
+    ) {
+        self.assert_campaign_creator();
+        self.internal_create_campaign(merkle_root, claim_end, options);
     }
 
-    pub fn unpause(&mut self) {
-        self.assert_owner();
-        self.paused = false;
-    }
+    /// Creates a campaign for `create_campaign` or a confirmed multisig action.
+    fn internal_create_campaign(
+        &mut self,
+        merkle_root: MerkleRootInput,
+        claim_end: Option<U64>,
+        options: Option<CampaignOptions>,
+    ) {
+        self.assert_not_paused(PauseFlags::CAMPAIGN_CREATION);
 
-    pub fn create_campaign(&mut self, merkle_root: CryptoHash, claim_end: U64) {
-        self.assert_owner();
-        self.assert_unpaused();
+        let merkle_root = merkle_root.to_hash();
 
         require!(
-            env::block_timestamp() < claim_end.into(),
+            claim_end.is_none_or(|claim_end| env::block_timestamp() < claim_end.0),
             "Claim end timestamp must be some time in the future"
         );
 
+        let CampaignOptions {
+            hash_algo,
+            proof_format,
+            leaf_count,
+            leaf_encoding,
+            leaf_hashing,
+            leaf_version,
+            claim_tracking,
+            verify_lockup_owner,
+            verify_receiver_exists,
+            allow_partial_claims,
+            vesting_end,
+            decay,
+            bonus,
+            allow_liquid_split,
+            periods,
+            allocation_mode,
+            total_allocation,
+            payout_call,
+            require_registration,
+            referral,
+            claimant_pays_storage,
+            memo,
+            expected_campaign_id,
+        } = options.unwrap_or_default();
+
+        require!(
+            claim_tracking == ClaimTracking::Keys || leaf_version == LeafVersion::V2,
+            "Bitmap campaigns require V2 leaves"
+        );
+        require!(
+            (proof_format == ProofFormat::RsMerkle) == leaf_count.is_some_and(|count| count > 0),
+            "RsMerkle campaigns require a positive leaf count, which other campaigns do not accept"
+        );
+        require!(
+            leaf_encoding != LeafEncoding::OzStandard
+                || (leaf_version == LeafVersion::V1
+                    && leaf_hashing == LeafHashing::Double
+                    && hash_algo == HashAlgo::Keccak256
+                    && allocation_mode == AllocationMode::PerLeaf),
+            "OpenZeppelin leaves require V1 leaves, double hashing, keccak256 and per leaf amounts"
+        );
+        require!(
+            leaf_encoding != LeafEncoding::JsonCanonical
+                || !matches!(leaf_version, LeafVersion::V6 | LeafVersion::V7),
+            "JSON leaves do not support V6 and V7 leaves"
+        );
+        require!(
+            claim_tracking == ClaimTracking::Keys || !allow_partial_claims,
+            "Partial claims require claim keys"
+        );
+        if let AllocationMode::Fixed(amount) = allocation_mode {
+            require!(amount.0 > 0, "Fixed amount must not be zero");
+            require!(
+                leaf_version == LeafVersion::V2 && claim_tracking == ClaimTracking::Keys,
+                "Fixed amount campaigns require V2 leaves and claim keys"
+            );
+        }
+        require!(
+            leaf_version != LeafVersion::V5 || (!allow_partial_claims && vesting_end.is_none()),
+            "V5 leaves do not support partial claims or vesting"
+        );
+
+        if let Some(vesting_end) = vesting_end {
+            require!(
+                env::block_timestamp() < vesting_end.0,
+                "Vesting end timestamp must be some time in the future"
+            );
+            require!(
+                claim_tracking == ClaimTracking::Keys && !allow_partial_claims,
+                "Vesting campaigns require claim keys and whole claims"
+            );
+        }
+
+        let claim_end = match &periods {
+            Some(periods) => {
+                require!(
+                    claim_end.is_none(),
+                    "Periodic campaigns end with their last period"
+                );
+                require!(
+                    claim_tracking == ClaimTracking::Keys
+                        && leaf_version != LeafVersion::V5
+                        && !allow_partial_claims
+                        && vesting_end.is_none(),
+                    "Periodic campaigns require claim keys, whole claims and no vesting or V5 leaves"
+                );
+                Some(U64(periods.assert_valid(env::block_timestamp())))
+            }
+            None => claim_end,
+        };
+
+        if let Some(decay) = &decay {
+            RewardCampaign::assert_valid_decay(decay, claim_end);
+        }
+        if let Some(bonus) = &bonus {
+            RewardCampaign::assert_valid_bonus(bonus);
+        }
+        if let Some(referral) = &referral {
+            RewardCampaign::assert_valid_referral(referral);
+        }
+        require!(
+            !allow_liquid_split || vesting_end.is_none(),
+            "Vesting campaigns do not support liquid splits"
+        );
+        if let Some(payout_call) = &payout_call {
+            payout_call.assert_valid();
+            require!(
+                vesting_end.is_none(),
+                "Vesting campaigns do not support payout calls"
+            );
+        }
+        require!(
+            !require_registration
+                || (!allow_partial_claims && leaf_version != LeafVersion::V5 && periods.is_none()),
+            "Registration requires whole claims and no V5 leaves or periods"
+        );
+        require!(
+            !claimant_pays_storage || (!verify_lockup_owner && !verify_receiver_exists),
+            "Claimant paid storage does not support lockup owner or receiver checks"
+        );
+        RewardCampaign::assert_valid_memo(&memo);
+
         let campaign_id = self.last_campaign_id + 1;
+        if let Some(expected_campaign_id) = expected_campaign_id {
+            if expected_campaign_id != campaign_id {
+                env::panic_str(&format!(
+                    "Campaign would be created with id {campaign_id}, not the expected id {expected_campaign_id}"
+                ));
+            }
+        }
 
         let campaign = RewardCampaign {
             id: campaign_id,
             claim_start: env::block_timestamp().into(),
             claim_end,
+            original_claim_end: claim_end,
             merkle_root,
+            hash_algo,
+            proof_format,
+            leaf_count,
+            leaf_encoding,
+            leaf_hashing,
+            leaf_version,
+            claim_tracking,
+            verify_lockup_owner,
+            verify_receiver_exists,
+            claim_key_version: if leaf_version == LeafVersion::V5 || periods.is_some() {
+                ClaimKeyVersion::V3
+            } else {
+                ClaimKeyVersion::default()
+            },
+            allow_partial_claims,
+            vesting_end,
+            claim_count: 0,
+            decay,
+            bonus,
+            bonus_pool: U128(0),
+            allow_liquid_split,
+            periods,
+            allocation_mode,
+            total_allocation,
+            claimed_total: U128(0),
+            allocation_released: false,
+            vesting_reserved: U128(0),
+            claim_storage: ClaimStorage::default(),
+            payout_call: payout_call.clone(),
+            require_registration,
+            registration_deposits: U128(0),
+            referral,
+            referral_pool: U128(0),
+            claimant_pays_storage,
+            donation_pool: U128(0),
+            storage_bytes: 0,
+            memo: memo.clone(),
         };
 
-        self.campaigns.insert(campaign_id, campaign.into());
+        let storage_before = env::storage_usage();
+        self.campaigns.insert(campaign_id, campaign);
+        self.campaigns.flush();
+        self.campaigns.get_mut(&campaign_id).unwrap().storage_bytes =
+            env::storage_usage().saturating_sub(storage_before);
+        // The allocation is reserved out of funds the contract holds, so claims never draw on
+        // other campaigns' reserves
+        let reserved = total_allocation.map_or(0, |total| total.0);
+        require!(
+            self.available_balance() >= reserved,
+            "Insufficient contract balance to reserve the total allocation"
+        );
+        self.reserved_balance += reserved;
         self.last_campaign_id = self
             .last_campaign_id
             .checked_add(1)
             .expect("Campaign id value overflows");
+        self.debug_assert_reserve_consistent();
+        self.record_admin_action(
+            "create_campaign",
+            format!(
+                "campaign {campaign_id}, root {}",
+                encode_hex_hash(&merkle_root)
+            ),
+        );
 
         let create = CampaignCreatedEvent {
             campaign_id,
             merkle_root,
+            merkle_root_hex: encode_hex_hash(&merkle_root),
             claim_end,
+            hash_algo,
+            proof_format,
+            leaf_count,
+            leaf_encoding,
+            leaf_hashing,
+            leaf_version,
+            claim_tracking,
+            verify_lockup_owner,
+            verify_receiver_exists,
+            allow_partial_claims,
+            vesting_end,
+            decay,
+            bonus,
+            allow_liquid_split,
+            periods,
+            allocation_mode,
+            total_allocation,
+            payout_call,
+            require_registration,
+            referral,
+            claimant_pays_storage,
+            memo,
         };
 
-        env::log_str(&serde_json::to_string(&create).unwrap());
+        Event::CampaignCreated(create).emit();
     }
 
+    /// Claims an allocation of a campaign. Without a `lockup_contract` the allocation is paid to
+    /// the claimant, whose leaf then lists the claimant as its lockup. Campaigns with `V3` leaves
+    /// pay the `receiver_id` given in `options` instead, and bitmap campaigns need the leaf's
+    /// `index`.
+    ///
+    /// Vesting campaigns only release the part of the allocation that has vested, the rest is
+    /// withdrawn later with `withdraw_vested`.
+    ///
+    /// Campaigns allowing partial claims pay `claim_amount` out of the allocation, or whatever
+    /// remains of it, and accept further claims until it is exhausted.
+    ///
+    /// In campaigns verifying lockup owners the claim is only recorded and paid once the lockup
+    /// contract confirms the claimant owns it.
+    ///
+    /// `merkle_proof` may hold at most `max_proof_length` hashes, see `get_config`.
+    ///
+    /// Campaigns whose claimants pay for storage require a deposit covering the storage of the
+    /// claim record, see `get_claim_storage_deposit`, and refund what it did not use.
+    #[payable]
     pub fn claim(
         &mut self,
-        amount: near_sdk::json_types::U128,
+        amount: U128,
         merkle_proof: Vec<CryptoHash>,
         campaign_id: CampaignId,
-        lockup_contract: AccountId,
+        lockup_contract: Option<AccountId>,
+        options: Option<ClaimOptions>,
     ) {
-        self.assert_unpaused();
-        let user_account_id = env::predecessor_account_id();
-        let key = env::keccak256_array(
-            &[
-                user_account_id.as_bytes().to_vec(),
-                campaign_id.to_ne_bytes().to_vec(),
-            ]
-            .concat(),
+        self.claim_for(
+            env::predecessor_account_id(),
+            amount,
+            merkle_proof,
+            campaign_id,
+            lockup_contract,
+            options,
         );
+    }
 
-        // Check claim parameters
-        require!(amount.0 > 0, "Amount must not be zero");
-        require!(
-            self.campaigns.contains_key(&campaign_id) == true,
-            "Campaign does not exist"
+    /// Same as `claim`, with every hash of `merkle_proof` given as a base58 string.
+    #[payable]
+    pub fn claim_b58(
+        &mut self,
+        amount: U128,
+        merkle_proof: Vec<String>,
+        campaign_id: CampaignId,
+        lockup_contract: Option<AccountId>,
+        options: Option<ClaimOptions>,
+    ) {
+        let merkle_proof = merkle_proof
+            .iter()
+            .enumerate()
+            .map(|(i, hash)| {
+                Base58CryptoHash::try_from(hash.as_str())
+                    .unwrap_or_else(|_| {
+                        env::panic_str(&format!("Invalid base58 hash at proof index {i}"))
+                    })
+                    .into()
+            })
+            .collect();
+
+        self.claim_for(
+            env::predecessor_account_id(),
+            amount,
+            merkle_proof,
+            campaign_id,
+            lockup_contract,
+            options,
         );
-        require!(!self.claims.contains(&key), "Already claimed rewards");
+    }
 
-        require!(merkle_proof.len() > 0, "Merkle proof supplied is empty");
+    /// Same as `claim` for campaigns paying a fixed amount per account, whose leaves do not
+    /// carry an amount.
+    #[payable]
+    pub fn claim_fixed(
+        &mut self,
+        merkle_proof: Vec<CryptoHash>,
+        campaign_id: CampaignId,
+        lockup_contract: Option<AccountId>,
+        options: Option<ClaimOptions>,
+    ) {
+        let amount = match self.campaigns.get(&campaign_id).map(|c| c.allocation_mode) {
+            Some(AllocationMode::Fixed(amount)) => amount,
+            Some(AllocationMode::PerLeaf) => env::panic_str("Campaign does not pay a fixed amount"),
+            None => env::panic_str("Campaign does not exist"),
+        };
 
-        let selected_campaign = self.campaigns.get(&campaign_id).unwrap();
+        self.claim_for(
+            env::predecessor_account_id(),
+            amount,
+            merkle_proof,
+            campaign_id,
+            lockup_contract,
+            options,
+        );
+    }
 
+    /// Claims several allocations of the caller in a campaign, e.g. the leaves of a `V5`
+    /// campaign, with a single multiproof instead of a proof per allocation, see
+    /// `verify_multi_proof`. `allocations` must be in the order the multiproof takes their leaves,
+    /// which is the order of the `leaves` `getMultiProof` of `@openzeppelin/merkle-tree` returns
+    /// along with `proof` and `proofFlags`. Each allocation is claimed as by `claim`. Claims need
+    /// 60 Tgas per allocation. Campaigns whose claimants pay for storage are not supported, as
+    /// their claims take a deposit.
+    pub fn claim_multi(
+        &mut self,
+        campaign_id: CampaignId,
+        allocations: Vec<ClaimAllocation>,
+        proof: Vec<CryptoHash>,
+        proof_flags: Vec<bool>,
+    ) {
+        require!(!allocations.is_empty(), "Allocations must not be empty");
         require!(
-            env::block_timestamp() < selected_campaign.claim_end.into(),
-            "Claim period has concluded"
+            self.campaigns
+                .get(&campaign_id)
+                .is_none_or(|campaign| !campaign.claimant_pays_storage),
+            "Multi claims are not supported by campaigns whose claimants pay for storage"
+        );
+        require!(
+            env::prepaid_gas() >= payout::MIN_CLAIM_GAS.saturating_mul(allocations.len() as u64),
+            "Multi claims require 60 Tgas per allocation"
         );
+        self.config.assert_proof_length(&proof);
 
-        // Calculate leaf to be checked alongside provided proof
-        let data = MerkleTreeData {
-            account: user_account_id.to_string(),
-            lockup: lockup_contract.to_string(),
-            amount: amount.0,
-        };
+        let account_id = env::predecessor_account_id();
+        let (claims, leaves): (Vec<VerifiedClaim>, Vec<CryptoHash>) = allocations
+            .into_iter()
+            .map(|allocation| {
+                self.prepare_claim(
+                    account_id.clone(),
+                    allocation.amount,
+                    campaign_id,
+                    allocation.lockup_contract,
+                    allocation.options,
+                )
+            })
+            .unzip();
 
-        let serialized_data: Vec<u8> = borsh::to_vec(&data).expect("Failed to serialize data");
-        let leaf = env::keccak256_array(&serialized_data);
+        // Every claim is checked before any is recorded, so the same allocation must not be
+        // claimed twice
+        let mut allocation_ids: Vec<_> = claims
+            .iter()
+            .map(|claim| (claim.index, claim.nonce))
+            .collect();
+        allocation_ids.sort_unstable();
+        allocation_ids.dedup();
+        require!(
+            allocation_ids.len() == claims.len(),
+            "Allocations must be distinct"
+        );
 
+        let campaign = &self.campaigns[&campaign_id];
+        require!(
+            campaign.proof_format == ProofFormat::Commutative,
+            "Multi claims require commutative proofs"
+        );
         require!(
-            Self::verify_proof(leaf, merkle_proof, selected_campaign.merkle_root),
+            campaign.tree_hasher().verify_multi_proof(
+                &leaves,
+                &proof,
+                &proof_flags,
+                &campaign.merkle_root
+            ),
             "Invalid Proof"
         );
+        self.assert_sufficient_balance(
+            campaign,
+            claims.iter().map(|claim| claim.paid_amount.0).sum(),
+        );
 
-        // Mark as claimed and send NEAR to account
-        self.claims.insert(key);
-        Promise::new(lockup_contract.clone()).transfer(NearToken::from_yoctonear(amount.0));
-
-        let claim = ClaimEvent {
-            campaign_id,
-            account_id: user_account_id,
-            lockup_contract,
-            amount: amount.0,
-        };
-
-        env::log_str(&serde_json::to_string(&claim).unwrap());
+        for claim in claims {
+            self.execute_claim(claim);
+        }
     }
 
-    pub fn withdraw(&mut self) {
-        self.assert_owner();
-        let available_balance =
-            env::account_balance().saturating_sub(self.config.min_storage_deposit);
-
-        if available_balance > NearToken::from_near(0) {
-            Promise::new(env::predecessor_account_id()).transfer(available_balance);
-
-            let withdraw = WithdrawEvent {
-                balance: env::account_balance(),
-                withdrawn: available_balance,
-            };
-
-            env::log_str(&serde_json::to_string(&withdraw).unwrap());
-        } else {
-            env::panic_str("The remaining balance is required for contract storage");
-        }
+    /// Sends the contract balance to the default withdraw receiver, or the owner if there is
+    /// none, except what is needed for storage and reserved for campaigns, see
+    /// `get_reserved_balance`. With `force` the reserved funds are sent as well, which leaves
+    /// campaigns unable to pay their claims.
+    #[payable]
+    pub fn withdraw(&mut self, force: Option<bool>) {
+        self.assert_owner_action();
+        self.internal_withdraw(None, force.unwrap_or(false));
     }
 
-    pub fn get_campaign(&self, campaign_id: CampaignId) -> Option<RewardCampaign> {
-        self.campaigns.get(&campaign_id).cloned()
+    /// Withdraws as `withdraw` but to `receiver`, e.g. an operational hot wallet.
+    #[payable]
+    pub fn withdraw_to(&mut self, receiver: AccountId) {
+        self.assert_owner_action();
+        self.internal_withdraw(Some(receiver), false);
     }
 
-    pub fn has_claimed(&self, campaign_id: CampaignId, account_id: AccountId) -> bool {
-        let key = env::keccak256_array(
-            &[
-                account_id.as_bytes().to_vec(),
-                campaign_id.to_ne_bytes().to_vec(),
-            ]
-            .concat(),
+    /// Withdraws for `withdraw`, `withdraw_to` or a confirmed multisig action, at most what the
+    /// `withdraw_limit` still allows.
+    fn internal_withdraw(&mut self, receiver: Option<AccountId>, force: bool) {
+        self.assert_direct_withdraw();
+        let withdrawable = self.withdrawable_balance(force);
+        require!(
+            withdrawable > 0,
+            "The remaining balance is required for contract storage"
         );
 
-        self.claims.contains(&key)
+        let withdrawable = self
+            .withdraw_allowance()
+            .map_or(withdrawable, |allowance| withdrawable.min(allowance));
+        require!(withdrawable > 0, "Withdrawal exceeds the withdraw limit");
+        self.send_withdrawal(
+            "withdraw",
+            NearToken::from_yoctonear(withdrawable),
+            receiver,
+        );
     }
 
-    pub fn get_last_campaign_id(&self) -> CampaignId {
-        self.last_campaign_id
+    /// Sends exactly `amount` as `withdraw` does, which must not exceed the balance `withdraw`
+    /// would send, e.g. to leave funds for an upcoming campaign.
+    #[payable]
+    pub fn withdraw_amount(&mut self, amount: NearToken, force: Option<bool>) {
+        self.assert_owner_action();
+        self.internal_withdraw_amount(amount, None, force.unwrap_or(false));
+    }
+
+    /// Withdraws as `withdraw_amount` but to `receiver`.
+    #[payable]
+    pub fn withdraw_amount_to(&mut self, amount: NearToken, receiver: AccountId) {
+        self.assert_owner_action();
+        self.internal_withdraw_amount(amount, Some(receiver), false);
+    }
+
+    /// Withdraws for `withdraw_amount`, `withdraw_amount_to` or a confirmed multisig action.
+    fn internal_withdraw_amount(
+        &mut self,
+        amount: NearToken,
+        receiver: Option<AccountId>,
+        force: bool,
+    ) {
+        self.assert_direct_withdraw();
+        require!(
+            amount > NearToken::from_near(0),
+            "Withdrawal amount must not be zero"
+        );
+        require!(
+            amount.as_yoctonear() <= self.withdrawable_balance(force),
+            "Withdrawal amount exceeds the available balance"
+        );
+        self.assert_withdraw_allowance(amount.as_yoctonear());
+
+        self.send_withdrawal("withdraw_amount", amount, receiver);
+    }
+
+    pub fn get_campaign(&self, campaign_id: CampaignId) -> Option<CampaignView> {
+        self.campaigns
+            .get(&campaign_id)
+            .map(|campaign| CampaignView {
+                merkle_root_hex: encode_hex_hash(&campaign.merkle_root),
+                merkle_root_b58: campaign.merkle_root.into(),
+                campaign: campaign.clone(),
+            })
+    }
+
+    /// Returns whether a campaign still accepts claims, or `None` if it does not exist.
+    pub fn get_campaign_status(&self, campaign_id: CampaignId) -> Option<CampaignStatus> {
+        self.campaigns.get(&campaign_id).map(|campaign| {
+            if campaign.has_ended(env::block_timestamp()) {
+                CampaignStatus::Ended
+            } else {
+                CampaignStatus::Active
+            }
+        })
+    }
+
+    /// Returns the claims and storage usage of a campaign, or `None` if it does not exist.
+    pub fn get_campaign_stats(&self, campaign_id: CampaignId) -> Option<CampaignStats> {
+        self.campaigns
+            .get(&campaign_id)
+            .map(|campaign| CampaignStats {
+                claim_count: campaign.claim_count,
+                claimed_total: campaign.claimed_total,
+                storage_bytes: campaign.storage_bytes,
+                storage_cost: env::storage_byte_cost()
+                    .saturating_mul(u128::from(campaign.storage_bytes)),
+            })
+    }
+
+    /// Returns a campaign together with `account_id`'s claim in it and whether claims are
+    /// paused, so frontends need a single call, or `None` if the campaign does not exist.
+    pub fn get_claim_context(
+        &self,
+        campaign_id: CampaignId,
+        account_id: AccountId,
+    ) -> Option<ClaimContext> {
+        let campaign = self.get_campaign(campaign_id)?;
+        let timestamp = env::block_timestamp();
+        let record = self.get_claim_record(campaign_id, account_id.clone());
+        let (claimed_amount, claimed_at) = match record {
+            Some(ClaimRecord::Claimed {
+                amount, timestamp, ..
+            }) => (Some(amount), Some(timestamp)),
+            _ => (None, None),
+        };
+
+        Some(ClaimContext {
+            status: self.get_campaign_status(campaign_id)?,
+            has_claimed: self.has_claimed(campaign_id, account_id),
+            seconds_remaining: campaign
+                .campaign
+                .claim_end
+                .map(|claim_end| U64(claim_end.0.saturating_sub(timestamp) / 1_000_000_000)),
+            paused: self.is_paused(),
+            claims_paused: self.pause_flags.contains(PauseFlags::CLAIMS),
+            claimed_amount,
+            claimed_at,
+            campaign,
+        })
+    }
+
+    pub fn has_claimed(&self, campaign_id: CampaignId, account_id: AccountId) -> bool {
+        self.claim_key(campaign_id, &account_id)
+            .is_some_and(|key| self.claim_record(campaign_id, &key).is_some())
+    }
+
+    /// Returns whether the leaf of `account_id` with `nonce` in a `V5` campaign has been claimed.
+    pub fn is_nonce_claimed(
+        &self,
+        campaign_id: CampaignId,
+        account_id: AccountId,
+        nonce: u32,
+    ) -> bool {
+        self.leaf_claim_key(campaign_id, &account_id, Some(nonce))
+            .is_some_and(|key| self.claim_record(campaign_id, &key).is_some())
+    }
+
+    /// Returns the key recording `account_id`'s claim in a `Keys` campaign, or `None` if the
+    /// campaign does not exist.
+    pub fn get_claim_key(
+        &self,
+        campaign_id: CampaignId,
+        account_id: AccountId,
+    ) -> Option<Base58CryptoHash> {
+        self.claim_key(campaign_id, &account_id).map(Into::into)
+    }
+
+    /// Checks whether `account_id` could claim an allocation with the given inputs right now,
+    /// without claiming it. Inputs that do not match the campaign's leaves panic as in `claim`.
+    pub fn check_claim(
+        &self,
+        account_id: AccountId,
+        amount: U128,
+        merkle_proof: Vec<CryptoHash>,
+        campaign_id: CampaignId,
+        lockup_contract: Option<AccountId>,
+        options: Option<ClaimOptions>,
+    ) -> ClaimCheck {
+        let ClaimOptions {
+            index,
+            receiver_id,
+            deadline,
+            nonce,
+            directions,
+            leaf_position,
+            ..
+        } = options.unwrap_or_default();
+
+        let Some(campaign) = self.campaigns.get(&campaign_id) else {
+            return ClaimCheck::CampaignNotFound;
+        };
+        let nonce = campaign.claim_nonce(nonce, env::block_timestamp());
+
+        if self.is_blocked(account_id.clone()) {
+            return ClaimCheck::Blocked;
+        }
+        if self.is_invalidated(campaign_id, account_id.clone()) {
+            return ClaimCheck::Invalidated;
+        }
+        if self.registration_missing(campaign, &account_id) {
+            return ClaimCheck::NotRegistered;
+        }
+
+        let claimed = match (index, nonce) {
+            (Some(index), _) => self.is_index_claimed(campaign_id, index),
+            (None, Some(nonce)) => self.is_nonce_claimed(campaign_id, account_id.clone(), nonce),
+            (None, None) => self.has_claimed(campaign_id, account_id.clone()),
+        };
+        if claimed {
+            return ClaimCheck::AlreadyClaimed;
+        }
+
+        let now = env::block_timestamp();
+        if campaign.has_ended(now) {
+            return ClaimCheck::CampaignEnded;
+        }
+
+        let deadline = campaign.leaf_deadline(deadline);
+        if deadline.is_some_and(|deadline| now >= deadline) {
+            return ClaimCheck::LeafExpired;
+        }
+
+        let receiver_id =
+            campaign.resolve_receiver(&account_id, lockup_contract.as_ref(), receiver_id);
+        let leaf =
+            campaign.compute_leaf(index, &account_id, &receiver_id, amount.0, deadline, nonce);
+
+        if campaign.verify_leaf(&leaf, &merkle_proof, directions.as_deref(), leaf_position) {
+            ClaimCheck::Claimable
+        } else {
+            ClaimCheck::InvalidProof
+        }
+    }
+
+    /// Returns the leaf `claim` computes for an allocation of `account_id` in a campaign, with the
+    /// same inputs as `claim`, so tree builders can compare it with their own. Inputs that do not
+    /// match the campaign's leaves panic as in `claim`.
+    pub fn compute_leaf(
+        &self,
+        account_id: AccountId,
+        amount: U128,
+        campaign_id: CampaignId,
+        lockup_contract: Option<AccountId>,
+        options: Option<ClaimOptions>,
+    ) -> ComputedLeaf {
+        let ClaimOptions {
+            index,
+            receiver_id,
+            deadline,
+            nonce,
+            ..
+        } = options.unwrap_or_default();
+
+        let campaign = self
+            .campaigns
+            .get(&campaign_id)
+            .expect("Campaign does not exist");
+        match campaign.claim_tracking {
+            ClaimTracking::Keys => require!(
+                index.is_none(),
+                "Index is only accepted by bitmap campaigns"
+            ),
+            ClaimTracking::Bitmap => {
+                require!(index.is_some(), "Index is required by bitmap campaigns")
+            }
+        }
+
+        let receiver_id =
+            campaign.resolve_receiver(&account_id, lockup_contract.as_ref(), receiver_id);
+        let leaf = campaign.compute_leaf(
+            index,
+            &account_id,
+            &receiver_id,
+            amount.0,
+            campaign.leaf_deadline(deadline),
+            campaign.leaf_nonce(nonce),
+        );
+
+        ComputedLeaf {
+            leaf_hex: encode_hex_hash(&leaf),
+            leaf_b58: leaf.into(),
+        }
+    }
+
+    pub fn get_last_campaign_id(&self) -> CampaignId {
+        self.last_campaign_id
+    }
+
+    /// Returns how much of an allocation has been claimed so far in a campaign allowing partial
+    /// claims.
+    pub fn get_claimed_amount(&self, campaign_id: CampaignId, account_id: AccountId) -> U128 {
+        U128(self.claimed_amount(campaign_id, &account_id))
+    }
+
+    /// Returns how much of an allocation of `amount` is left to claim.
+    pub fn get_remaining_amount(
+        &self,
+        campaign_id: CampaignId,
+        account_id: AccountId,
+        amount: U128,
+    ) -> U128 {
+        if self.has_claimed(campaign_id, account_id.clone()) {
+            return U128(0);
+        }
+
+        U128(
+            amount
+                .0
+                .saturating_sub(self.claimed_amount(campaign_id, &account_id)),
+        )
+    }
+}
+
+impl MerkleClaim {
+    /// Verifies and executes a claim of `user_account_id`'s allocation, see `claim`.
+    pub(crate) fn claim_for(
+        &mut self,
+        user_account_id: AccountId,
+        amount: U128,
+        merkle_proof: Vec<CryptoHash>,
+        campaign_id: CampaignId,
+        lockup_contract: Option<AccountId>,
+        options: Option<ClaimOptions>,
+    ) {
+        let claim = self.verify_claim(
+            user_account_id,
+            amount,
+            merkle_proof,
+            campaign_id,
+            lockup_contract,
+            options,
+        );
+
+        self.execute_claim(claim);
+    }
+
+    /// Records and pays a verified claim, after checking its lockup owner or receiver if the
+    /// campaign requires it.
+    pub(crate) fn execute_claim(&mut self, claim: VerifiedClaim) {
+        let campaign = &self.campaigns[&claim.campaign_id];
+
+        if campaign.verify_lockup_owner && claim.lockup_contract.is_some() {
+            Self::claim_after_lockup_owner_check(claim);
+        } else if campaign.verify_receiver_exists
+            && !claim.receiver_id.get_account_type().is_implicit()
+        {
+            Self::claim_after_receiver_check(claim);
+        } else {
+            self.record_claim(claim);
+        }
+    }
+
+    /// Checks a claim of `user_account_id`'s allocation and returns it, panicking if it cannot
+    /// be made.
+    pub(crate) fn verify_claim(
+        &self,
+        user_account_id: AccountId,
+        amount: U128,
+        merkle_proof: Vec<CryptoHash>,
+        campaign_id: CampaignId,
+        lockup_contract: Option<AccountId>,
+        options: Option<ClaimOptions>,
+    ) -> VerifiedClaim {
+        let (directions, leaf_position) = options.as_ref().map_or((None, None), |options| {
+            (options.directions.clone(), options.leaf_position)
+        });
+        let (claim, leaf) = self.prepare_claim(
+            user_account_id,
+            amount,
+            campaign_id,
+            lockup_contract,
+            options,
+        );
+
+        require!(!merkle_proof.is_empty(), "Merkle proof supplied is empty");
+        self.config.assert_proof_length(&merkle_proof);
+        require!(
+            self.campaigns[&campaign_id].verify_leaf(
+                &leaf,
+                &merkle_proof,
+                directions.as_deref(),
+                leaf_position
+            ),
+            "Invalid Proof"
+        );
+
+        claim
+    }
+
+    /// Checks everything about a claim of `user_account_id`'s allocation but its proof, and
+    /// returns it together with the leaf the proof must be for.
+    fn prepare_claim(
+        &self,
+        user_account_id: AccountId,
+        amount: U128,
+        campaign_id: CampaignId,
+        lockup_contract: Option<AccountId>,
+        options: Option<ClaimOptions>,
+    ) -> (VerifiedClaim, CryptoHash) {
+        self.assert_not_paused(PauseFlags::CLAIMS);
+        require!(
+            env::prepaid_gas() >= payout::MIN_CLAIM_GAS,
+            "Claims require at least 60 Tgas"
+        );
+        self.assert_not_blocked(&user_account_id);
+        let ClaimOptions {
+            index,
+            receiver_id,
+            claim_amount,
+            deadline,
+            nonce,
+            liquid_bps,
+            referrer,
+            // Checked together with the proof
+            directions: _,
+            leaf_position: _,
+        } = options.unwrap_or_default();
+
+        // Check claim parameters
+        require!(amount.0 > 0, "Amount must not be zero");
+        require!(
+            referrer.as_ref() != Some(&user_account_id),
+            "Self-referrals are not allowed"
+        );
+        self.config.assert_claim_amount_bounds(amount.0);
+        require!(
+            self.campaigns.contains_key(&campaign_id),
+            "Campaign does not exist"
+        );
+        self.assert_not_invalidated(campaign_id, &user_account_id);
+
+        let selected_campaign = self.campaigns.get(&campaign_id).unwrap();
+        self.assert_registered(selected_campaign, &user_account_id);
+        if let Some(payout_call) = &selected_campaign.payout_call {
+            require!(
+                env::prepaid_gas() >= payout::MIN_CLAIM_GAS.saturating_add(payout_call.gas),
+                "Claims require the payout call's gas on top of 60 Tgas"
+            );
+        }
+        let nonce = selected_campaign.claim_nonce(nonce, env::block_timestamp());
+
+        if let AllocationMode::Fixed(fixed_amount) = selected_campaign.allocation_mode {
+            require!(
+                amount == fixed_amount,
+                "Amount must equal the campaign's fixed amount"
+            );
+        }
+
+        self.assert_unclaimed(selected_campaign, &user_account_id, index, nonce);
+
+        require!(
+            !selected_campaign.has_ended(env::block_timestamp()),
+            "Claim period has concluded"
+        );
+
+        let deadline = selected_campaign.leaf_deadline(deadline);
+        require!(
+            deadline.is_none_or(|deadline| env::block_timestamp() < deadline),
+            "Leaf deadline has passed"
+        );
+
+        let receiver_id = selected_campaign.resolve_receiver(
+            &user_account_id,
+            lockup_contract.as_ref(),
+            receiver_id,
+        );
+
+        if let Some(lockup_contract) = &lockup_contract {
+            self.config.assert_allowed_lockup(lockup_contract);
+        }
+
+        if let Some(liquid_bps) = liquid_bps {
+            require!(
+                selected_campaign.allow_liquid_split,
+                "Campaign does not allow liquid splits"
+            );
+            require!(
+                lockup_contract.is_some(),
+                "Liquid splits require a lockup contract"
+            );
+            require!(
+                liquid_bps <= BPS_DENOMINATOR,
+                "Liquid share must not exceed 10000 bps"
+            );
+        }
+
+        // Calculate leaf to be checked alongside provided proof
+        let leaf = selected_campaign.compute_leaf(
+            index,
+            &user_account_id,
+            &receiver_id,
+            amount.0,
+            deadline,
+            nonce,
+        );
+
+        let payout = self.claimable_amount(
+            selected_campaign,
+            &user_account_id,
+            amount.0,
+            claim_amount.map(|claim_amount| claim_amount.0),
+        );
+        let paid_amount = selected_campaign.decayed_amount(payout, env::block_timestamp());
+        selected_campaign.assert_allocation_available(payout);
+        self.assert_sufficient_balance(selected_campaign, paid_amount);
+
+        let claim = VerifiedClaim {
+            campaign_id,
+            account_id: user_account_id,
+            lockup_contract,
+            receiver_id,
+            allocation: amount,
+            amount: U128(payout),
+            paid_amount: U128(paid_amount),
+            index,
+            nonce,
+            liquid_bps,
+            new_account_key: None,
+            relayer_fee: None,
+            referrer,
+            referral_bonus: None,
+            donated: None,
+            claim_index: None,
+        };
+
+        (claim, leaf)
+    }
+
+    /// Returns the key of `account_id`'s claim in a campaign, in its current period if it is
+    /// periodic, or `None` if it does not exist.
+    fn claim_key(&self, campaign_id: CampaignId, account_id: &AccountId) -> Option<CryptoHash> {
+        let period = self
+            .campaigns
+            .get(&campaign_id)?
+            .current_period(env::block_timestamp());
+
+        self.leaf_claim_key(campaign_id, account_id, period)
+    }
+
+    /// Returns the key of the claim of `account_id`'s leaf with `nonce`, or `None` if the campaign
+    /// does not exist or needs a nonce that is not given.
+    fn leaf_claim_key(
+        &self,
+        campaign_id: CampaignId,
+        account_id: &AccountId,
+        nonce: Option<u32>,
+    ) -> Option<CryptoHash> {
+        self.campaigns
+            .get(&campaign_id)
+            .filter(|campaign| campaign.claim_key_version != ClaimKeyVersion::V3 || nonce.is_some())
+            .map(|campaign| {
+                compute_claim_key(campaign.claim_key_version, account_id, campaign_id, nonce)
+            })
+    }
+
+    /// Returns how much of `account_id`'s allocation has been claimed in a campaign allowing
+    /// partial claims.
+    fn claimed_amount(&self, campaign_id: CampaignId, account_id: &AccountId) -> Balance {
+        self.claim_key(campaign_id, account_id)
+            .and_then(|key| self.claimed_amounts.get(&key).copied())
+            .unwrap_or_default()
+    }
+
+    /// Returns the amount a claim of `claim_amount` out of a verified `allocation` pays, which is
+    /// the whole allocation unless the campaign allows partial claims.
+    fn claimable_amount(
+        &self,
+        campaign: &RewardCampaign,
+        account_id: &AccountId,
+        allocation: Balance,
+        claim_amount: Option<Balance>,
+    ) -> Balance {
+        if !campaign.allow_partial_claims {
+            require!(
+                claim_amount.is_none(),
+                "Claim amount is only accepted by campaigns allowing partial claims"
+            );
+            return allocation;
+        }
+
+        let remaining = allocation.saturating_sub(self.claimed_amount(campaign.id, account_id));
+        let claim_amount = claim_amount.unwrap_or(remaining);
+
+        require!(claim_amount > 0, "Claim amount must not be zero");
+        require!(
+            claim_amount <= remaining,
+            "Claim amount exceeds the remaining allocation"
+        );
+
+        claim_amount
+    }
+
+    /// Panics if the allocation of `account_id`, or the leaf at `index` in bitmap campaigns, has
+    /// already been claimed. `nonce` selects the allocation in `V5` campaigns.
+    fn assert_unclaimed(
+        &self,
+        campaign: &RewardCampaign,
+        account_id: &AccountId,
+        index: Option<u32>,
+        nonce: Option<u32>,
+    ) {
+        match campaign.claim_tracking {
+            ClaimTracking::Keys => {
+                require!(
+                    index.is_none(),
+                    "Index is only accepted by bitmap campaigns"
+                );
+                let key = self
+                    .leaf_claim_key(campaign.id, account_id, nonce)
+                    .expect("Campaign does not exist");
+                require!(
+                    self.claim_record(campaign.id, &key).is_none(),
+                    "Already claimed rewards"
+                );
+            }
+            ClaimTracking::Bitmap => {
+                let index = index.expect("Index is required by bitmap campaigns");
+                require!(
+                    !self.is_index_claimed(campaign.id, index),
+                    "Already claimed rewards"
+                );
+            }
+        }
+    }
+
+    /// Records a verified claim and sends its amount to the receiver. The allocation is marked
+    /// as claimed once nothing of it remains.
+    fn record_claim(&mut self, mut claim: VerifiedClaim) {
+        let VerifiedClaim {
+            campaign_id,
+            account_id,
+            receiver_id,
+            allocation,
+            amount,
+            paid_amount,
+            index,
+            nonce,
+            ..
+        } = claim.clone();
+
+        let campaign = self
+            .campaigns
+            .get_mut(&campaign_id)
+            .expect("Campaign does not exist");
+        let allow_partial_claims = campaign.allow_partial_claims;
+        let vesting = campaign.vesting_end.is_some();
+        let claimant_pays_storage = campaign.claimant_pays_storage;
+        let claim_index = campaign.claim_count;
+        campaign.claim_count += 1;
+        let bonus = campaign.take_bonus(claim_index, paid_amount.0, env::block_timestamp());
+        let referral_bonus = match claim.referrer {
+            Some(_) => campaign.take_referral_bonus(paid_amount.0),
+            None => 0,
+        };
+        claim.referral_bonus = (referral_bonus > 0).then_some(U128(referral_bonus));
+        let donated = campaign.take_donation(paid_amount.0);
+        claim.donated = (donated > 0).then_some(U128(donated));
+        let used = campaign.count_claimed(amount.0);
+        self.reserved_balance -= bonus + referral_bonus + donated + used;
+        self.debug_assert_reserve_consistent();
+
+        let storage_before = self.claim_storage_usage(campaign_id);
+        match index {
+            Some(index) => self.set_index_claimed(campaign_id, index),
+            None => {
+                let key = self
+                    .leaf_claim_key(campaign_id, &account_id, nonce)
+                    .expect("Campaign does not exist");
+
+                if allow_partial_claims {
+                    let claimed = self
+                        .claimed_amounts
+                        .get(&key)
+                        .copied()
+                        .unwrap_or_default()
+                        .checked_add(amount.0)
+                        .expect("Claimed amount overflows");
+                    require!(
+                        claimed <= allocation.0,
+                        "Claim amount exceeds the remaining allocation"
+                    );
+
+                    self.claimed_amounts.insert(key, claimed);
+                    if claimed == allocation.0 {
+                        self.insert_claim_record(campaign_id, key, claim.record(allocation));
+                    }
+                } else {
+                    self.insert_claim_record(campaign_id, key, claim.record(amount));
+                }
+            }
+        }
+        let storage_used = self.count_claim_storage(campaign_id, storage_before);
+        if claimant_pays_storage {
+            self.charge_claim_storage(&account_id, storage_used);
+        }
+
+        let fee = self.config.claim_fee(paid_amount.0);
+        claim.claim_index = Some(U64(claim_index));
+
+        if vesting {
+            // Vesting claims pay nothing yet, so they are confirmed once recorded
+            self.claim_event(&claim, fee, bonus).emit();
+            self.pay_fee(fee);
+            self.pay_relayer_fee(&claim);
+            self.pay_referral_bonus(&claim);
+            self.refund_registration(campaign_id, &account_id);
+            let total = claim.payout(fee, bonus);
+            let storage_before = self.claim_storage_usage(campaign_id);
+            self.open_vesting_position(campaign_id, account_id, receiver_id, total);
+            self.count_claim_storage(campaign_id, storage_before);
+            self.check_low_balance();
+        } else {
+            self.pay_claim(claim, fee, bonus);
+        }
     }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    use near_sdk::{json_types, testing_env, AccountId, NearToken, VMContext};
-    use std::convert::TryInto;
+    use crate::admin::{OwnerAction, DEFAULT_PROPOSAL_LIFETIME};
+    use crate::audit::ADMIN_LOG_CAPACITY;
+    use crate::config::{ConfigPatch, MAX_CLAIM_FEE_BPS};
+    use crate::events::{EVENT_STANDARD, EVENT_VERSION};
+    use crate::memo::MAX_CAMPAIGN_MEMO_LENGTH;
+    use crate::merkle::tree::{
+        parse_csv, validate_export, validate_export_file, CampaignFile, EntryError, EntryMismatch,
+        MerkleTree,
+    };
+    use crate::metadata::ContractSourceMetadata;
+    use crate::ownership::OWNER_HISTORY_CAPACITY;
+    use crate::reserve::BalanceBreakdown;
+    use crate::signature::ClaimSignature;
+    use crate::storage::StorageBalance;
+    use ed25519_dalek::{Signer, SigningKey};
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{get_created_receipts, get_logs};
+    use near_sdk::{
+        json_types, test_vm_config, testing_env, AccountId, CurveType, Gas, NearToken,
+        PromiseError, PromiseResult, PublicKey, RuntimeFeesConfig, VMContext,
+    };
+    use proptest::prelude::*;
     use std::str::FromStr;
     use test_utils::*;
 
@@ -301,19 +2306,198 @@ mod tests {
     }
 
     fn claims_contract_setup() -> (VMContext, MerkleClaim) {
-        let context = basic_context();
+        let mut context = basic_context();
         testing_env!(context.clone());
 
         let config = Config {
             owner_account_id: account_owner(),
             min_storage_deposit: MIN_STORAGE_DEPOSIT,
+            allowed_lockup_suffix: None,
+            max_proof_length: 64,
+            claim_fee_bps: 0,
+            fee_receiver: None,
+            min_claim_amount: None,
+            max_claim_amount: None,
+            max_relayer_fee: None,
+            withdraw_timelock: json_types::U64(0),
+            default_withdraw_receiver: None,
+            treasury_account_id: None,
+            withdraw_limit: None,
+            low_balance_threshold: None,
+            max_total_extension: None,
         };
 
         let contract = MerkleClaim::new(config);
 
+        // Owner actions require 1 yoctoNEAR
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        testing_env!(context.clone());
+
         (context, contract)
     }
 
+    fn mock_leaf(
+        account: &AccountId,
+        lockup: &AccountId,
+        amount: Balance,
+        leaf_hashing: LeafHashing,
+    ) -> CryptoHash {
+        let data = MerkleTreeData {
+            account: account.to_string(),
+            lockup: lockup.to_string(),
+            amount,
+        };
+
+        leaf_hashing.hash_leaf(&borsh::to_vec(&data).expect("Failed to serialize data"))
+    }
+
+    fn mock_leaf_v2(
+        campaign_id: CampaignId,
+        account: &AccountId,
+        lockup: &AccountId,
+        amount: Balance,
+    ) -> CryptoHash {
+        mock_leaf_v2_with(HashAlgo::Keccak256, campaign_id, account, lockup, amount)
+    }
+
+    fn mock_leaf_v2_with(
+        hash_algo: HashAlgo,
+        campaign_id: CampaignId,
+        account: &AccountId,
+        lockup: &AccountId,
+        amount: Balance,
+    ) -> CryptoHash {
+        let data = MerkleTreeDataV2 {
+            campaign_id,
+            account: account.to_string(),
+            lockup: lockup.to_string(),
+            amount,
+        };
+
+        LeafHashing::Double.hash_leaf_with(
+            hash_algo,
+            &borsh::to_vec(&data).expect("Failed to serialize data"),
+        )
+    }
+
+    fn mock_leaf_indexed(
+        campaign_id: CampaignId,
+        index: u32,
+        account: &AccountId,
+        lockup: &AccountId,
+        amount: Balance,
+    ) -> CryptoHash {
+        let data = MerkleTreeDataIndexed {
+            campaign_id,
+            index,
+            account: account.to_string(),
+            lockup: lockup.to_string(),
+            amount,
+        };
+
+        LeafHashing::Double.hash_leaf(&borsh::to_vec(&data).expect("Failed to serialize data"))
+    }
+
+    fn mock_leaf_v3(
+        campaign_id: CampaignId,
+        claimer: &AccountId,
+        receiver: &AccountId,
+        amount: Balance,
+    ) -> CryptoHash {
+        let data = MerkleTreeDataV3 {
+            campaign_id,
+            claimer: claimer.to_string(),
+            receiver: receiver.to_string(),
+            amount,
+        };
+
+        LeafHashing::Double.hash_leaf(&borsh::to_vec(&data).expect("Failed to serialize data"))
+    }
+
+    fn mock_leaf_v4(
+        campaign_id: CampaignId,
+        account: &AccountId,
+        lockup: &AccountId,
+        amount: Balance,
+        deadline: u64,
+    ) -> CryptoHash {
+        let data = MerkleTreeDataV4 {
+            campaign_id,
+            account: account.to_string(),
+            lockup: lockup.to_string(),
+            amount,
+            deadline,
+        };
+
+        LeafHashing::Double.hash_leaf(&borsh::to_vec(&data).expect("Failed to serialize data"))
+    }
+
+    fn mock_leaf_v5(
+        campaign_id: CampaignId,
+        account: &AccountId,
+        lockup: &AccountId,
+        amount: Balance,
+        nonce: u32,
+    ) -> CryptoHash {
+        let data = MerkleTreeDataV5 {
+            campaign_id,
+            account: account.to_string(),
+            lockup: lockup.to_string(),
+            amount,
+            nonce,
+        };
+
+        LeafHashing::Double.hash_leaf(&borsh::to_vec(&data).expect("Failed to serialize data"))
+    }
+
+    /// Builds a commutative keccak tree, returning its root and the proof of every leaf. An odd
+    /// node at the end of a level is carried up unchanged.
+    fn build_tree(leaves: &[CryptoHash]) -> (CryptoHash, Vec<Vec<CryptoHash>>) {
+        build_tree_with(TreeHasher::default(), leaves)
+    }
+
+    fn build_tree_with(
+        hasher: TreeHasher,
+        leaves: &[CryptoHash],
+    ) -> (CryptoHash, Vec<Vec<CryptoHash>>) {
+        let tree = MerkleTree::new(hasher, leaves.to_vec());
+        let proofs = (0..leaves.len()).map(|i| tree.proof(i)).collect();
+
+        (tree.root(), proofs)
+    }
+
+    fn indexed_claimants(count: u32) -> Vec<AccountId> {
+        (0..count)
+            .map(|i| AccountId::from_str(&format!("claimant-{i}")).unwrap())
+            .collect()
+    }
+
+    /// Creates campaign 1 with `options` over the leaves of `claimant()`'s 1000 and
+    /// `non_owner()`'s 500, ending with the mock campaign unless it has periods, and makes the
+    /// claimant the caller. Returns the sibling proving the claimant's leaf.
+    fn options_campaign_setup(options: CampaignOptions) -> (VMContext, MerkleClaim, CryptoHash) {
+        lockup_options_campaign_setup(&claimant(), options)
+    }
+
+    /// Sets up a campaign as `options_campaign_setup` does, paying the claimant's allocation to
+    /// `lockup`.
+    fn lockup_options_campaign_setup(
+        lockup: &AccountId,
+        options: CampaignOptions,
+    ) -> (VMContext, MerkleClaim, CryptoHash) {
+        let (mut context, mut contract) = claims_contract_setup();
+        let leaf = mock_leaf_v2(1, &claimant(), lockup, 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        let (_, _, end) = build_mock_campaign();
+        let claim_end = options.periods.is_none().then_some(end);
+
+        set_caller(&mut context, account_owner());
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), claim_end, Some(options));
+        set_caller(&mut context, claimant());
+
+        (context, contract, sibling)
+    }
+
     fn build_mock_campaign() -> (u32, CryptoHash, U64) {
         let data = MerkleTreeData {
             account: account_owner().to_string(),
@@ -335,14 +2519,14 @@ mod tests {
 
         context.predecessor_account_id = account_owner();
         context.signer_account_id = account_owner();
-        context.signer_account_pk = public_key(1).try_into().unwrap();
+        context.signer_account_pk = public_key(1);
         testing_env!(context.clone());
 
         let mock_campaign = build_mock_campaign();
 
-        contract.create_campaign(mock_campaign.1, mock_campaign.2);
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
 
-        let current_campaign = contract.get_campaign(mock_campaign.0).unwrap();
+        let current_campaign = contract.get_campaign(mock_campaign.0).unwrap().campaign;
 
         assert_eq!(current_campaign.id, mock_campaign.0);
         assert_eq!(current_campaign.merkle_root, mock_campaign.1);
@@ -350,7 +2534,7 @@ mod tests {
             current_campaign.claim_start,
             json_types::U64(to_ts(GENESIS_TIME_IN_DAYS))
         );
-        assert_eq!(current_campaign.claim_end, mock_campaign.2);
+        assert_eq!(current_campaign.claim_end, Some(mock_campaign.2));
     }
 
     #[test]
@@ -359,13 +2543,13 @@ mod tests {
         let (mut context, mut contract) = claims_contract_setup();
         context.predecessor_account_id = non_owner();
         context.signer_account_id = non_owner();
-        context.signer_account_pk = public_key(2).try_into().unwrap();
+        context.signer_account_pk = public_key(2);
         context.attached_deposit = NearToken::from_yoctonear(1);
 
         testing_env!(context.clone());
         let mock_campaign = build_mock_campaign();
 
-        contract.create_campaign(mock_campaign.1, mock_campaign.2);
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
     }
 
     #[test]
@@ -374,14 +2558,14 @@ mod tests {
         let (mut context, mut contract) = claims_contract_setup();
         context.predecessor_account_id = account_owner();
         context.signer_account_id = account_owner();
-        context.signer_account_pk = public_key(1).try_into().unwrap();
+        context.signer_account_pk = public_key(1);
 
         let mock_campaign = build_mock_campaign();
         // Change the block timestamp to be the claim end period
         context.block_timestamp = mock_campaign.2.into();
 
         testing_env!(context.clone());
-        contract.create_campaign(mock_campaign.1, mock_campaign.2);
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
     }
 
     #[test]
@@ -391,28 +2575,31 @@ mod tests {
 
         context.predecessor_account_id = account_owner();
         context.signer_account_id = account_owner();
-        context.signer_account_pk = public_key(1).try_into().unwrap();
+        context.signer_account_pk = public_key(1);
         testing_env!(context.clone());
-        let end = json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64));
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
 
         contract.create_campaign(
             [
                 158, 236, 219, 170, 25, 1, 253, 172, 46, 71, 82, 30, 201, 181, 15, 59, 58, 254,
                 170, 207, 59, 87, 184, 46, 81, 28, 122, 202, 227, 92, 92, 128,
-            ],
+            ]
+            .into(),
             end,
+            None,
         );
 
         context.predecessor_account_id = claimant();
         context.signer_account_id = claimant();
-        context.signer_account_pk = public_key(123).try_into().unwrap();
+        context.signer_account_pk = public_key(123);
         testing_env!(context.clone());
 
         contract.claim(
             json_types::U128(1000u128),
             FAKE_MERKLE_PROOF.to_vec(),
             1u32,
-            AccountId::from_str("lockup-contract").unwrap(),
+            Some(AccountId::from_str("lockup-contract").unwrap()),
+            None,
         );
     }
 
@@ -423,23 +2610,24 @@ mod tests {
 
         context.predecessor_account_id = account_owner();
         context.signer_account_id = account_owner();
-        context.signer_account_pk = public_key(1).try_into().unwrap();
+        context.signer_account_pk = public_key(1);
         testing_env!(context.clone());
 
         let mock_campaign = build_mock_campaign();
 
-        contract.create_campaign(mock_campaign.1, mock_campaign.2);
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
 
         context.predecessor_account_id = claimant();
         context.signer_account_id = claimant();
-        context.signer_account_pk = public_key(123).try_into().unwrap();
+        context.signer_account_pk = public_key(123);
         testing_env!(context.clone());
 
         contract.claim(
             json_types::U128(0u128),
             FAKE_MERKLE_PROOF.to_vec(),
             1u32,
-            AccountId::from_str("lockup-contract").unwrap(),
+            Some(AccountId::from_str("lockup-contract").unwrap()),
+            None,
         );
     }
 
@@ -450,23 +2638,24 @@ mod tests {
 
         context.predecessor_account_id = account_owner();
         context.signer_account_id = account_owner();
-        context.signer_account_pk = public_key(1).try_into().unwrap();
+        context.signer_account_pk = public_key(1);
         testing_env!(context.clone());
 
         let mock_campaign = build_mock_campaign();
 
-        contract.create_campaign(mock_campaign.1, mock_campaign.2);
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
 
         context.predecessor_account_id = claimant();
         context.signer_account_id = claimant();
-        context.signer_account_pk = public_key(123).try_into().unwrap();
+        context.signer_account_pk = public_key(123);
         testing_env!(context.clone());
 
         contract.claim(
             json_types::U128(1000u128),
             FAKE_MERKLE_PROOF.to_vec(),
             2u32,
-            AccountId::from_str("lockup-contract").unwrap(),
+            Some(AccountId::from_str("lockup-contract").unwrap()),
+            None,
         );
     }
 
@@ -477,23 +2666,24 @@ mod tests {
 
         context.predecessor_account_id = account_owner();
         context.signer_account_id = account_owner();
-        context.signer_account_pk = public_key(1).try_into().unwrap();
+        context.signer_account_pk = public_key(1);
         testing_env!(context.clone());
 
         let mock_campaign = build_mock_campaign();
 
-        contract.create_campaign(mock_campaign.1, mock_campaign.2);
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
 
         context.predecessor_account_id = claimant();
         context.signer_account_id = claimant();
-        context.signer_account_pk = public_key(123).try_into().unwrap();
+        context.signer_account_pk = public_key(123);
         testing_env!(context.clone());
 
         contract.claim(
             json_types::U128(1000u128),
             [].to_vec(),
             1u32,
-            AccountId::from_str("lockup-contract").unwrap(),
+            Some(AccountId::from_str("lockup-contract").unwrap()),
+            None,
         );
     }
 
@@ -504,16 +2694,16 @@ mod tests {
 
         context.predecessor_account_id = account_owner();
         context.signer_account_id = account_owner();
-        context.signer_account_pk = public_key(1).try_into().unwrap();
+        context.signer_account_pk = public_key(1);
         testing_env!(context.clone());
 
         let mock_campaign = build_mock_campaign();
 
-        contract.create_campaign(mock_campaign.1, mock_campaign.2);
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
 
         context.predecessor_account_id = claimant();
         context.signer_account_id = claimant();
-        context.signer_account_pk = public_key(123).try_into().unwrap();
+        context.signer_account_pk = public_key(123);
         context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 40u64);
         testing_env!(context.clone());
 
@@ -521,7 +2711,8 @@ mod tests {
             json_types::U128(1000u128),
             FAKE_MERKLE_PROOF.to_vec(),
             1u32,
-            AccountId::from_str("lockup-contract").unwrap(),
+            Some(AccountId::from_str("lockup-contract").unwrap()),
+            None,
         );
     }
 
@@ -532,46 +2723,46 @@ mod tests {
 
         context.predecessor_account_id = non_owner();
         context.signer_account_id = non_owner();
-        context.signer_account_pk = public_key(1).try_into().unwrap();
+        context.signer_account_pk = public_key(1);
         testing_env!(context.clone());
 
-        contract.withdraw();
+        contract.withdraw(None);
     }
 
     #[test]
-    #[should_panic(expected = "Contract is paused")]
+    #[should_panic(expected = "Campaign creation is paused")]
     fn test_create_campaign_failure_paused() {
         let (mut context, mut contract) = claims_contract_setup();
 
         context.predecessor_account_id = account_owner();
         context.signer_account_id = account_owner();
-        context.signer_account_pk = public_key(1).try_into().unwrap();
+        context.signer_account_pk = public_key(1);
         testing_env!(context.clone());
 
         contract.pause();
         let mock_campaign = build_mock_campaign();
 
-        contract.create_campaign(mock_campaign.1, mock_campaign.2);
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
     }
 
     #[test]
-    #[should_panic(expected = "Contract is paused")]
+    #[should_panic(expected = "Claims are paused")]
     fn test_claim_failure_paused() {
         let (mut context, mut contract) = claims_contract_setup();
 
         context.predecessor_account_id = account_owner();
         context.signer_account_id = account_owner();
-        context.signer_account_pk = public_key(1).try_into().unwrap();
+        context.signer_account_pk = public_key(1);
         testing_env!(context.clone());
 
-        contract.pause();
         let mock_campaign = build_mock_campaign();
 
-        contract.create_campaign(mock_campaign.1, mock_campaign.2);
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+        contract.pause();
 
         context.predecessor_account_id = claimant();
         context.signer_account_id = claimant();
-        context.signer_account_pk = public_key(123).try_into().unwrap();
+        context.signer_account_pk = public_key(123);
         context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 40u64);
         testing_env!(context.clone());
 
@@ -579,7 +2770,12013 @@ mod tests {
             json_types::U128(1000u128),
             FAKE_MERKLE_PROOF.to_vec(),
             1u32,
-            AccountId::from_str("lockup-contract").unwrap(),
+            Some(AccountId::from_str("lockup-contract").unwrap()),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_claim_success_double_hashed_leaf() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        context.signer_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let leaf = mock_leaf(&claimant(), &lockup, 1000, LeafHashing::Double);
+        let sibling = mock_leaf(&non_owner(), &lockup, 500, LeafHashing::Double);
+        let root = hash_pair(&leaf, &sibling);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(
+            root.into(),
+            end,
+            Some(CampaignOptions {
+                leaf_version: LeafVersion::V1,
+                ..Default::default()
+            }),
+        );
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.leaf_hashing,
+            LeafHashing::Double
+        );
+
+        context.predecessor_account_id = claimant();
+        context.signer_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_single_hashed_tree_on_double_campaign_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        context.signer_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let leaf = mock_leaf(&claimant(), &lockup, 1000, LeafHashing::Single);
+        let sibling = mock_leaf(&non_owner(), &lockup, 500, LeafHashing::Single);
+        let root = hash_pair(&leaf, &sibling);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(
+            root.into(),
+            end,
+            Some(CampaignOptions {
+                leaf_hashing: LeafHashing::Double,
+                leaf_version: LeafVersion::V1,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = claimant();
+        context.signer_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_claim_success_single_hashed_leaf() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        context.signer_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let leaf = mock_leaf(&claimant(), &lockup, 1000, LeafHashing::Single);
+        let sibling = mock_leaf(&non_owner(), &lockup, 500, LeafHashing::Single);
+        let root = hash_pair(&leaf, &sibling);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(
+            root.into(),
+            end,
+            Some(CampaignOptions {
+                leaf_hashing: LeafHashing::Single,
+                leaf_version: LeafVersion::V1,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = claimant();
+        context.signer_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    fn test_internal_node_forged_as_leaf() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let accounts = [claimant(), non_owner(), account_owner(), system_account()];
+
+        for leaf_hashing in [LeafHashing::Single, LeafHashing::Double] {
+            let leaves: Vec<CryptoHash> = accounts
+                .iter()
+                .map(|account| mock_leaf(account, &lockup, 1000, leaf_hashing))
+                .collect();
+            let left = hash_pair(&leaves[0], &leaves[1]);
+            let right = hash_pair(&leaves[2], &leaves[3]);
+            let root = hash_pair(&left, &right);
+
+            // A 64 byte payload made of two sibling leaves hashes to their parent node when
+            // leaves are hashed once, and so passes as a leaf one level up the tree.
+            let forged_payload = merkle::commutative_preimage(&leaves[0], &leaves[1]);
+            assert_eq!(forged_payload.len(), 64);
+
+            let forged_leaf = leaf_hashing.hash_leaf(&forged_payload);
+            let verified = MerkleClaim::verify_proof(forged_leaf, vec![right], root);
+
+            assert_eq!(verified, leaf_hashing == LeafHashing::Single);
+        }
+    }
+
+    #[test]
+    fn test_migrate_keeps_existing_campaigns_single_hashed() {
+        #[near(serializers=[borsh])]
+        struct ConfigV1 {
+            owner_account_id: AccountId,
+            min_storage_deposit: NearToken,
+        }
+
+        #[near(serializers=[borsh])]
+        struct RewardCampaignV1 {
+            id: CampaignId,
+            claim_start: U64,
+            claim_end: U64,
+            merkle_root: CryptoHash,
+        }
+
+        #[near(serializers=[borsh])]
+        struct MerkleClaimV1 {
+            config: ConfigV1,
+            claims: LookupSet<CryptoHash>,
+            campaigns: LookupMap<CampaignId, RewardCampaignV1>,
+            last_campaign_id: CampaignId,
+            paused: bool,
+        }
+
+        let mut context = basic_context();
+        testing_env!(context.clone());
+
+        let mock_campaign = build_mock_campaign();
+        let mut old = MerkleClaimV1 {
+            config: ConfigV1 {
+                owner_account_id: account_owner(),
+                min_storage_deposit: MIN_STORAGE_DEPOSIT,
+            },
+            claims: LookupSet::new(StorageKeys::Claims),
+            campaigns: LookupMap::new(StorageKeys::Campaigns),
+            last_campaign_id: 1,
+            paused: false,
+        };
+        old.campaigns.insert(
+            mock_campaign.0,
+            RewardCampaignV1 {
+                id: mock_campaign.0,
+                claim_start: json_types::U64(to_ts(GENESIS_TIME_IN_DAYS)),
+                claim_end: mock_campaign.2,
+                merkle_root: mock_campaign.1,
+            },
+        );
+        old.campaigns.flush();
+        old.claims.insert(compute_claim_key(
+            ClaimKeyVersion::V1,
+            &claimant(),
+            mock_campaign.0,
+            None,
+        ));
+        env::state_write(&old);
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let contract = MerkleClaim::migrate();
+        let campaign = contract.get_campaign(mock_campaign.0).unwrap().campaign;
+
+        assert_eq!(campaign.merkle_root, mock_campaign.1);
+        assert_eq!(campaign.claim_end, Some(mock_campaign.2));
+        assert_eq!(campaign.leaf_hashing, LeafHashing::Single);
+        assert_eq!(campaign.leaf_version, LeafVersion::V1);
+        assert_eq!(campaign.claim_key_version, ClaimKeyVersion::V1);
+        assert!(contract.has_claimed(mock_campaign.0, claimant()));
+        assert!(!contract.has_claimed(mock_campaign.0, non_owner()));
+        assert_eq!(
+            contract.get_claim_record(mock_campaign.0, claimant()),
+            Some(ClaimRecord::Legacy)
+        );
+        assert_eq!(contract.get_last_campaign_id(), 1);
+        assert_eq!(contract.get_config().owner_account_id, account_owner());
+        assert_eq!(contract.get_config().allowed_lockup_suffix, None);
+    }
+
+    #[test]
+    fn test_claim_key_little_endian_campaign_id() {
+        testing_env!(basic_context());
+
+        let campaign_id: CampaignId = 0x0102_0304;
+        let expected =
+            env::keccak256_array(&[claimant().as_bytes(), &[0x04, 0x03, 0x02, 0x01]].concat());
+
+        assert_eq!(
+            compute_claim_key(ClaimKeyVersion::V1, &claimant(), campaign_id, None),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_claim_key_matches_wasm_deployments() {
+        testing_env!(basic_context());
+
+        // keccak256("claimant" || [1, 0, 0, 0]), the key a v1.0.0 deployment on wasm32 stores
+        // for `claimant` claiming campaign 1.
+        let stored_on_chain: CryptoHash = [
+            197, 1, 112, 250, 87, 245, 24, 58, 249, 254, 222, 0, 22, 240, 240, 124, 190, 149, 3,
+            94, 34, 144, 132, 72, 254, 72, 103, 81, 191, 4, 51, 171,
+        ];
+
+        assert_eq!(
+            compute_claim_key(ClaimKeyVersion::V1, &claimant(), 1, None),
+            stored_on_chain
+        );
+    }
+
+    #[test]
+    fn test_claim_success_v2_leaf() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        context.signer_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let leaf = mock_leaf_v2(1, &claimant(), &lockup, 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &lockup, 500);
+        let root = hash_pair(&leaf, &sibling);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(root.into(), end, None);
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.leaf_version,
+            LeafVersion::V2
+        );
+
+        context.predecessor_account_id = claimant();
+        context.signer_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_v2_leaf_replay_across_campaigns_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        context.signer_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let leaf = mock_leaf_v2(1, &claimant(), &lockup, 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &lockup, 500);
+        let root = hash_pair(&leaf, &sibling);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        // The same root is mistakenly published for a second campaign
+        contract.create_campaign(root.into(), end, None);
+        contract.create_campaign(root.into(), end, None);
+
+        context.predecessor_account_id = claimant();
+        context.signer_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup.clone()),
+            None,
+        );
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            2u32,
+            Some(lockup),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_claim_v1_leaf_replays_across_campaigns() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        context.signer_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let leaf = mock_leaf(&claimant(), &lockup, 1000, LeafHashing::Double);
+        let sibling = mock_leaf(&non_owner(), &lockup, 500, LeafHashing::Double);
+        let root = hash_pair(&leaf, &sibling);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(
+            root.into(),
+            end,
+            Some(CampaignOptions {
+                leaf_version: LeafVersion::V1,
+                ..Default::default()
+            }),
+        );
+        contract.create_campaign(
+            root.into(),
+            end,
+            Some(CampaignOptions {
+                leaf_version: LeafVersion::V1,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = claimant();
+        context.signer_account_id = claimant();
+        testing_env!(context.clone());
+
+        // V1 leaves do not commit to the campaign, so one proof is valid for both
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup.clone()),
+            None,
+        );
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            2u32,
+            Some(lockup),
+            None,
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert!(contract.has_claimed(2, claimant()));
+    }
+
+    #[test]
+    fn test_claim_bitmap_success() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let accounts = indexed_claimants(3);
+        let leaves: Vec<CryptoHash> = (0..3)
+            .map(|i| mock_leaf_indexed(1, i, &accounts[i as usize], &lockup, 1000))
+            .collect();
+        let (root, proofs) = build_tree(&leaves);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(
+            root.into(),
+            end,
+            Some(CampaignOptions {
+                claim_tracking: ClaimTracking::Bitmap,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = accounts[2].clone();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            proofs[2].clone(),
+            1u32,
+            Some(lockup),
+            Some(ClaimOptions {
+                index: Some(2),
+                ..Default::default()
+            }),
+        );
+
+        assert!(contract.is_index_claimed(1, 2));
+        assert!(!contract.is_index_claimed(1, 1));
+        assert!(!contract.is_index_claimed(2, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Already claimed rewards")]
+    fn test_claim_bitmap_twice_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let accounts = indexed_claimants(2);
+        let leaves: Vec<CryptoHash> = (0..2)
+            .map(|i| mock_leaf_indexed(1, i, &accounts[i as usize], &lockup, 1000))
+            .collect();
+        let (root, proofs) = build_tree(&leaves);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(
+            root.into(),
+            end,
+            Some(CampaignOptions {
+                claim_tracking: ClaimTracking::Bitmap,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = accounts[0].clone();
+        testing_env!(context.clone());
+
+        let claim = |contract: &mut MerkleClaim| {
+            contract.claim(
+                json_types::U128(1000u128),
+                proofs[0].clone(),
+                1u32,
+                Some(lockup.clone()),
+                Some(ClaimOptions {
+                    index: Some(0),
+                    ..Default::default()
+                }),
+            )
+        };
+
+        claim(&mut contract);
+        claim(&mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index is required by bitmap campaigns")]
+    fn test_claim_bitmap_missing_index_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let mock_campaign = build_mock_campaign();
+
+        contract.create_campaign(
+            mock_campaign.1.into(),
+            Some(mock_campaign.2),
+            Some(CampaignOptions {
+                claim_tracking: ClaimTracking::Bitmap,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            FAKE_MERKLE_PROOF.to_vec(),
+            1u32,
+            Some(AccountId::from_str("lockup-contract").unwrap()),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_claim_bitmap_uses_less_storage_than_keys() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let accounts = indexed_claimants(16);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+        let mut storage_used = vec![];
+
+        for claim_tracking in [ClaimTracking::Keys, ClaimTracking::Bitmap] {
+            let (mut context, mut contract) = claims_contract_setup();
+
+            context.predecessor_account_id = account_owner();
+            testing_env!(context.clone());
+
+            let leaves: Vec<CryptoHash> = (0..16)
+                .map(|i| match claim_tracking {
+                    ClaimTracking::Keys => mock_leaf_v2(1, &accounts[i as usize], &lockup, 1000),
+                    ClaimTracking::Bitmap => {
+                        mock_leaf_indexed(1, i, &accounts[i as usize], &lockup, 1000)
+                    }
+                })
+                .collect();
+            let (root, proofs) = build_tree(&leaves);
+
+            contract.create_campaign(
+                root.into(),
+                end,
+                Some(CampaignOptions {
+                    claim_tracking,
+                    ..Default::default()
+                }),
+            );
+
+            let mut used = 0;
+
+            for (i, account) in accounts.iter().enumerate() {
+                context.predecessor_account_id = account.clone();
+                testing_env!(context.clone());
+
+                let before = env::storage_usage();
+                let index = (claim_tracking == ClaimTracking::Bitmap).then_some(i as u32);
+                contract.claim(
+                    json_types::U128(1000u128),
+                    proofs[i].clone(),
+                    1u32,
+                    Some(lockup.clone()),
+                    Some(ClaimOptions {
+                        index,
+                        ..Default::default()
+                    }),
+                );
+                // Bitmap words and claim records are cached until their maps are flushed
+                contract.claim_bitmaps.flush();
+                if let Some(records) = contract.campaign_claims.get_mut(&1) {
+                    records.flush();
+                }
+                contract.campaign_claims.flush();
+                used += env::storage_usage() - before;
+            }
+
+            storage_used.push(used);
+        }
+
+        assert!(storage_used[1] > 0);
+        assert!(storage_used[1] * 8 < storage_used[0]);
+    }
+
+    #[test]
+    fn test_claim_direct_and_lockup_payouts() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let lockup_leaf = mock_leaf_v2(1, &claimant(), &lockup, 1000);
+        let direct_leaf = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        let root = hash_pair(&lockup_leaf, &direct_leaf);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(root.into(), end, None);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![direct_leaf],
+            1u32,
+            Some(lockup.clone()),
+            None,
+        );
+
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["lockup_contract"], lockup.to_string());
+        assert_eq!(event["receiver_id"], lockup.to_string());
+        assert_eq!(event["amount"], "1000");
+        assert_eq!(event["claim_index"], "0");
+        assert_eq!(
+            event["block_timestamp"],
+            context.block_timestamp.to_string()
+        );
+        assert_eq!(event["block_height"], context.block_index.to_string());
+
+        context.predecessor_account_id = non_owner();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(500u128),
+            vec![lockup_leaf],
+            1u32,
+            None,
+            None,
+        );
+
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert!(event["lockup_contract"].is_null());
+        assert_eq!(event["receiver_id"], non_owner().to_string());
+        assert_eq!(event["amount"], "500");
+        assert_eq!(event["claim_index"], "1");
+        assert_eq!(contract.get_campaign(1).unwrap().campaign.claim_count, 2);
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert!(contract.has_claimed(1, non_owner()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_direct_with_lockup_leaf_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let lockup_leaf = mock_leaf_v2(1, &claimant(), &lockup, 1000);
+        let direct_leaf = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        let root = hash_pair(&lockup_leaf, &direct_leaf);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(root.into(), end, None);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        // The allocation is bound to the lockup and cannot be redirected to the claimant
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![direct_leaf],
+            1u32,
+            None,
+            None,
+        );
+    }
+
+    fn lockup_suffix_campaign_setup(lockup: &AccountId) -> (VMContext, MerkleClaim, CryptoHash) {
+        let (mut context, mut contract, sibling) =
+            lockup_options_campaign_setup(lockup, CampaignOptions::default());
+
+        set_caller(&mut context, account_owner());
+        contract.set_allowed_lockup_suffix(Some(".lockup.near".to_string()));
+        set_caller(&mut context, claimant());
+
+        (context, contract, sibling)
+    }
+
+    #[test]
+    fn test_claim_allowed_lockup_suffix_success() {
+        let lockup = AccountId::from_str("a1b2c3.lockup.near").unwrap();
+        let (_, mut contract, sibling) = lockup_suffix_campaign_setup(&lockup);
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Lockup contract is not a sub-account of the allowed lockup suffix")]
+    fn test_claim_lockup_suffix_mismatch_failure() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (_, mut contract, sibling) = lockup_suffix_campaign_setup(&lockup);
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Lockup contract is not a sub-account of the allowed lockup suffix")]
+    fn test_claim_lockup_suffix_lookalike_failure() {
+        let lockup = AccountId::from_str("evil-lockup.near").unwrap();
+        let (_, mut contract, sibling) = lockup_suffix_campaign_setup(&lockup);
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Lockup contract is not a sub-account of the allowed lockup suffix")]
+    fn test_claim_lockup_suffix_nested_account_failure() {
+        let lockup = AccountId::from_str("evil.a1b2c3.lockup.near").unwrap();
+        let (_, mut contract, sibling) = lockup_suffix_campaign_setup(&lockup);
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_claim_direct_ignores_lockup_suffix() {
+        let lockup = AccountId::from_str("a1b2c3.lockup.near").unwrap();
+        let (mut context, mut contract, _) = lockup_suffix_campaign_setup(&lockup);
+
+        context.predecessor_account_id = non_owner();
+        testing_env!(context.clone());
+
+        let leaf = mock_leaf_v2(1, &claimant(), &lockup, 1000);
+        contract.claim(json_types::U128(500u128), vec![leaf], 1u32, None, None);
+
+        assert!(contract.has_claimed(1, non_owner()));
+    }
+
+    #[test]
+    fn test_set_allowed_lockup_suffix() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.set_allowed_lockup_suffix(Some(".lockup.near".to_string()));
+        assert_eq!(
+            contract.get_config().allowed_lockup_suffix,
+            Some("lockup.near".to_string())
+        );
+
+        contract.set_allowed_lockup_suffix(Some("lockup.testnet".to_string()));
+        assert_eq!(
+            contract.get_config().allowed_lockup_suffix,
+            Some("lockup.testnet".to_string())
+        );
+
+        contract.set_allowed_lockup_suffix(None);
+        assert_eq!(contract.get_config().allowed_lockup_suffix, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Lockup suffix must be a valid account id")]
+    fn test_set_allowed_lockup_suffix_invalid_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.set_allowed_lockup_suffix(Some("..lockup.near".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_set_allowed_lockup_suffix_non_owner_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = non_owner();
+        testing_env!(context.clone());
+
+        contract.set_allowed_lockup_suffix(None);
+    }
+
+    fn verified_lockup_campaign_setup(lockup: &AccountId) -> (VMContext, MerkleClaim, CryptoHash) {
+        lockup_options_campaign_setup(
+            lockup,
+            CampaignOptions {
+                verify_lockup_owner: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// The claim of `claimant()`'s allocation in `verified_lockup_campaign_setup`.
+    fn verified_lockup_claim(lockup: &AccountId) -> VerifiedClaim {
+        VerifiedClaim {
+            campaign_id: 1,
+            account_id: claimant(),
+            lockup_contract: Some(lockup.clone()),
+            receiver_id: lockup.clone(),
+            allocation: json_types::U128(1000u128),
+            amount: json_types::U128(1000u128),
+            paid_amount: json_types::U128(1000u128),
+            index: None,
+            nonce: None,
+            liquid_bps: None,
+            new_account_key: None,
+            relayer_fee: None,
+            referrer: None,
+            referral_bonus: None,
+            donated: None,
+            claim_index: None,
+        }
+    }
+
+    #[test]
+    fn test_claim_verify_lockup_owner_success() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (mut context, mut contract, sibling) = verified_lockup_campaign_setup(&lockup);
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup.clone()),
+            None,
+        );
+
+        // Nothing is recorded until the lockup contract confirms its owner
+        assert!(!contract.has_claimed(1, claimant()));
+        assert!(get_logs().is_empty());
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.on_lockup_owner(verified_lockup_claim(&lockup), Ok(claimant()));
+
+        assert!(contract.has_claimed(1, claimant()));
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["receiver_id"], lockup.to_string());
+    }
+
+    #[test]
+    fn test_claim_verify_lockup_owner_skipped_for_direct_claims() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (mut context, mut contract, _) = verified_lockup_campaign_setup(&lockup);
+
+        context.predecessor_account_id = non_owner();
+        testing_env!(context.clone());
+
+        let leaf = mock_leaf_v2(1, &claimant(), &lockup, 1000);
+        contract.claim(json_types::U128(500u128), vec![leaf], 1u32, None, None);
+
+        assert!(contract.has_claimed(1, non_owner()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Lockup contract is not owned by the claimant")]
+    fn test_claim_verify_lockup_owner_mismatch_failure() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (mut context, mut contract, _) = verified_lockup_campaign_setup(&lockup);
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.on_lockup_owner(verified_lockup_claim(&lockup), Ok(non_owner()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to fetch the lockup owner")]
+    fn test_claim_verify_lockup_owner_query_failure() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (mut context, mut contract, _) = verified_lockup_campaign_setup(&lockup);
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.on_lockup_owner(verified_lockup_claim(&lockup), Err(PromiseError::Failed));
+    }
+
+    #[test]
+    #[should_panic(expected = "Already claimed rewards")]
+    fn test_claim_verify_lockup_owner_concurrent_claim_failure() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (mut context, mut contract, sibling) = verified_lockup_campaign_setup(&lockup);
+
+        // Two claims are submitted before either owner check has returned
+        for _ in 0..2 {
+            contract.claim(
+                json_types::U128(1000u128),
+                vec![sibling],
+                1u32,
+                Some(lockup.clone()),
+                None,
+            );
+        }
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        for _ in 0..2 {
+            contract.on_lockup_owner(verified_lockup_claim(&lockup), Ok(claimant()));
+        }
+    }
+
+    fn receiver_campaign_setup(receiver: &AccountId) -> (VMContext, MerkleClaim, CryptoHash) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaf = mock_leaf_v3(1, &claimant(), receiver, 1000);
+        let sibling = mock_leaf_v3(1, &non_owner(), &non_owner(), 500);
+        let root = hash_pair(&leaf, &sibling);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(
+            root.into(),
+            end,
+            Some(CampaignOptions {
+                leaf_version: LeafVersion::V3,
+                ..Default::default()
+            }),
+        );
+
+        (context, contract, sibling)
+    }
+
+    #[test]
+    fn test_claim_v3_pays_receiver() {
+        let treasury = AccountId::from_str("treasury.near").unwrap();
+        let (mut context, mut contract, sibling) = receiver_campaign_setup(&treasury);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                receiver_id: Some(treasury.clone()),
+                ..Default::default()
+            }),
+        );
+
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["account_id"], claimant().to_string());
+        assert_eq!(event["receiver_id"], treasury.to_string());
+        assert!(event["lockup_contract"].is_null());
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_v3_by_receiver_failure() {
+        let treasury = AccountId::from_str("treasury.near").unwrap();
+        let (mut context, mut contract, sibling) = receiver_campaign_setup(&treasury);
+
+        // Only the committed claimer may trigger the claim, not the receiver
+        context.predecessor_account_id = treasury.clone();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                receiver_id: Some(treasury),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_v3_redirected_receiver_failure() {
+        let treasury = AccountId::from_str("treasury.near").unwrap();
+        let (mut context, mut contract, sibling) = receiver_campaign_setup(&treasury);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                receiver_id: Some(claimant()),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver is required by V3 leaves")]
+    fn test_claim_v3_missing_receiver_failure() {
+        let treasury = AccountId::from_str("treasury.near").unwrap();
+        let (mut context, mut contract, sibling) = receiver_campaign_setup(&treasury);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(treasury),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_claim_key_v2_length_prefixes_account() {
+        testing_env!(basic_context());
+
+        let alice = AccountId::from_str("alice").unwrap();
+        let alice1 = AccountId::from_str("alice1").unwrap();
+
+        // V1 does not encode where the account ends: the preimage of ("alice", 0x31) starts with
+        // the bytes of "alice1". It stays unambiguous only because the campaign id is a
+        // fixed-width suffix, which V2 no longer relies on.
+        let v1_preimage = [alice.as_bytes(), &0x31u32.to_le_bytes()].concat();
+        assert!(v1_preimage.starts_with(alice1.as_bytes()));
+
+        let v2_preimage = borsh::to_vec(&(alice.as_str(), 0x31u32)).unwrap();
+        assert_eq!(v2_preimage[..4], 5u32.to_le_bytes());
+        assert!(!v2_preimage.starts_with(alice1.as_bytes()));
+        assert_eq!(
+            compute_claim_key(ClaimKeyVersion::V2, &alice, 0x31, None),
+            env::keccak256_array(&v2_preimage)
+        );
+
+        for campaign_id in [0u32, 0x31, 0x3131_3131, u32::MAX] {
+            for version in [ClaimKeyVersion::V1, ClaimKeyVersion::V2] {
+                assert_ne!(
+                    compute_claim_key(version, &alice, campaign_id, None),
+                    compute_claim_key(version, &alice1, campaign_id >> 8, None),
+                );
+            }
+            assert_ne!(
+                compute_claim_key(ClaimKeyVersion::V1, &alice, campaign_id, None),
+                compute_claim_key(ClaimKeyVersion::V2, &alice, campaign_id, None),
+            );
+        }
+    }
+
+    #[test]
+    fn test_claim_records_v2_claim_key() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let leaf = mock_leaf_v2(1, &claimant(), &lockup, 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &lockup, 500);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), end, None);
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.claim_key_version,
+            ClaimKeyVersion::V2
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+
+        assert!(contract
+            .claim_record(
+                1,
+                &compute_claim_key(ClaimKeyVersion::V2, &claimant(), 1, None)
+            )
+            .is_some());
+        assert!(contract
+            .claim_record(
+                1,
+                &compute_claim_key(ClaimKeyVersion::V1, &claimant(), 1, None)
+            )
+            .is_none());
+    }
+
+    fn partial_claim_campaign_setup() -> (VMContext, MerkleClaim, CryptoHash) {
+        options_campaign_setup(CampaignOptions {
+            allow_partial_claims: true,
+            ..Default::default()
+        })
+    }
+
+    fn partial_claim(contract: &mut MerkleClaim, sibling: CryptoHash, claim_amount: u128) {
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                claim_amount: Some(json_types::U128(claim_amount)),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Already claimed rewards")]
+    fn test_partial_claims_up_to_allocation() {
+        let (context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        partial_claim(&mut contract, sibling, 400);
+        assert!(!contract.has_claimed(1, claimant()));
+        assert_eq!(contract.get_claimed_amount(1, claimant()).0, 400);
+        assert_eq!(
+            contract
+                .get_remaining_amount(1, claimant(), json_types::U128(1000u128))
+                .0,
+            600
+        );
+
+        partial_claim(&mut contract, sibling, 600);
+        assert!(contract.has_claimed(1, claimant()));
+        assert_eq!(
+            contract
+                .get_remaining_amount(1, claimant(), json_types::U128(1000u128))
+                .0,
+            0
+        );
+
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["amount"], "600");
+
+        testing_env!(context);
+        partial_claim(&mut contract, sibling, 1);
+    }
+
+    #[test]
+    fn test_partial_claim_defaults_to_remainder() {
+        let (_, mut contract, sibling) = partial_claim_campaign_setup();
+
+        partial_claim(&mut contract, sibling, 250);
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert_eq!(contract.get_claimed_amount(1, claimant()).0, 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim amount exceeds the remaining allocation")]
+    fn test_partial_claim_exceeding_remainder_failure() {
+        let (_, mut contract, sibling) = partial_claim_campaign_setup();
+
+        partial_claim(&mut contract, sibling, 700);
+        partial_claim(&mut contract, sibling, 301);
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim amount is only accepted by campaigns allowing partial claims")]
+    fn test_partial_claim_not_allowed_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), end, None);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        partial_claim(&mut contract, sibling, 400);
+    }
+
+    /// Creates a campaign vesting over 10 days in which `claimant()` is allocated 1000.
+    fn vesting_campaign_setup() -> (VMContext, MerkleClaim, CryptoHash) {
+        options_campaign_setup(CampaignOptions {
+            vesting_end: Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 10u64))),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_vesting_claim_at_start_releases_nothing() {
+        let (_, mut contract, sibling) = vesting_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        assert!(contract.has_claimed(1, claimant()));
+        let position = contract.get_vesting_position(1, claimant()).unwrap();
+        assert_eq!(position.total.0, 1000);
+        assert_eq!(position.withdrawn.0, 0);
+        assert_eq!(contract.get_vested_claimable(1, claimant()).0, 0);
+        // Only the claim event, nothing was released
+        assert_eq!(get_logs().len(), 1);
+    }
+
+    #[test]
+    fn test_vesting_withdrawals_over_time() {
+        let (mut context, mut contract, sibling) = vesting_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 5u64);
+        testing_env!(context.clone());
+
+        assert_eq!(contract.get_vested_claimable(1, claimant()).0, 500);
+        assert_eq!(contract.withdraw_vested(1).0, 500);
+        assert_eq!(contract.get_vested_claimable(1, claimant()).0, 0);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 8u64);
+        testing_env!(context.clone());
+
+        assert_eq!(contract.withdraw_vested(1).0, 300);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 40u64);
+        testing_env!(context.clone());
+
+        assert_eq!(contract.withdraw_vested(1).0, 200);
+        assert_eq!(
+            contract
+                .get_vesting_position(1, claimant())
+                .unwrap()
+                .withdrawn
+                .0,
+            1000
+        );
+    }
+
+    #[test]
+    fn test_vesting_claim_midway_releases_vested_part() {
+        let (mut context, mut contract, sibling) = vesting_campaign_setup();
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 4u64);
+        testing_env!(context.clone());
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["amount"], "400");
+        assert_eq!(
+            contract
+                .get_vesting_position(1, claimant())
+                .unwrap()
+                .withdrawn
+                .0,
+            400
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "No vested amount to withdraw")]
+    fn test_vesting_repeated_withdrawal_failure() {
+        let (mut context, mut contract, sibling) = vesting_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 5u64);
+        testing_env!(context.clone());
+
+        contract.withdraw_vested(1);
+        contract.withdraw_vested(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "No vesting position")]
+    fn test_vesting_withdrawal_without_claim_failure() {
+        let (_, mut contract, _) = vesting_campaign_setup();
+
+        contract.withdraw_vested(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Vesting end timestamp must be some time in the future")]
+    fn test_vesting_end_in_past_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, root, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                vesting_end: Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS))),
+                ..Default::default()
+            }),
+        );
+    }
+
+    /// Creates a `V4` campaign ending after 30 days in which `claimant()`'s leaf expires after
+    /// `deadline_days`.
+    fn deadline_campaign_setup(deadline_days: u64) -> (VMContext, MerkleClaim, CryptoHash, u64) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let deadline = to_ts(GENESIS_TIME_IN_DAYS + deadline_days);
+        let leaf = mock_leaf_v4(1, &claimant(), &claimant(), 1000, deadline);
+        let sibling = mock_leaf_v4(1, &non_owner(), &non_owner(), 500, deadline);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+
+        contract.create_campaign(
+            hash_pair(&leaf, &sibling).into(),
+            end,
+            Some(CampaignOptions {
+                leaf_version: LeafVersion::V4,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        (context, contract, sibling, deadline)
+    }
+
+    fn deadline_options(deadline: u64) -> Option<ClaimOptions> {
+        Some(ClaimOptions {
+            deadline: Some(json_types::U64(deadline)),
+            ..Default::default()
+        })
+    }
+
+    fn check_deadline_claim(
+        contract: &MerkleClaim,
+        sibling: CryptoHash,
+        deadline: u64,
+    ) -> ClaimCheck {
+        contract.check_claim(
+            claimant(),
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            deadline_options(deadline),
+        )
+    }
+
+    #[test]
+    fn test_claim_before_leaf_deadline() {
+        let (mut context, mut contract, sibling, deadline) = deadline_campaign_setup(10);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 5u64);
+        testing_env!(context.clone());
+
+        assert_eq!(
+            check_deadline_claim(&contract, sibling, deadline),
+            ClaimCheck::Claimable
+        );
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            deadline_options(deadline),
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert_eq!(
+            check_deadline_claim(&contract, sibling, deadline),
+            ClaimCheck::AlreadyClaimed
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Leaf deadline has passed")]
+    fn test_claim_after_leaf_deadline_failure() {
+        let (mut context, mut contract, sibling, deadline) = deadline_campaign_setup(10);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 12u64);
+        testing_env!(context.clone());
+
+        assert_eq!(
+            check_deadline_claim(&contract, sibling, deadline),
+            ClaimCheck::LeafExpired
+        );
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            deadline_options(deadline),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim period has concluded")]
+    fn test_claim_leaf_deadline_after_campaign_end_failure() {
+        let (mut context, mut contract, sibling, deadline) = deadline_campaign_setup(40);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 35u64);
+        testing_env!(context.clone());
+
+        assert_eq!(
+            check_deadline_claim(&contract, sibling, deadline),
+            ClaimCheck::CampaignEnded
+        );
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            deadline_options(deadline),
+        );
+    }
+
+    #[test]
+    fn test_check_claim_rejects_extended_deadline() {
+        let (mut context, contract, sibling, deadline) = deadline_campaign_setup(10);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 12u64);
+        testing_env!(context.clone());
+
+        // A later deadline than the one committed in the leaf does not verify
+        assert_eq!(
+            check_deadline_claim(&contract, sibling, deadline + to_nanos(10)),
+            ClaimCheck::InvalidProof
+        );
+        assert_eq!(
+            contract.check_claim(
+                claimant(),
+                json_types::U128(1000u128),
+                vec![sibling],
+                2u32,
+                None,
+                None
+            ),
+            ClaimCheck::CampaignNotFound
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Deadline is required by V4 leaves")]
+    fn test_claim_v4_without_deadline_failure() {
+        let (_, mut contract, sibling, _) = deadline_campaign_setup(10);
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    /// The implicit account of `signing_key()`.
+    fn signer_account() -> AccountId {
+        signing_key()
+            .verifying_key()
+            .as_bytes()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()
+            .parse()
+            .unwrap()
+    }
+
+    /// Signs a direct claim of `signer_account()` in campaign 1 following the documented message
+    /// encoding.
+    fn sign_claim(amount: u128, merkle_proof: &[CryptoHash], nonce: u64) -> ClaimSignature {
+        let message = signed_claim_message(amount, merkle_proof, nonce);
+
+        let key = signing_key();
+        ClaimSignature {
+            public_key: PublicKey::from_parts(
+                CurveType::ED25519,
+                key.verifying_key().as_bytes().to_vec(),
+            )
+            .unwrap(),
+            signature: key.sign(&message).to_bytes().to_vec().into(),
+            nonce: json_types::U64(nonce),
+            max_relayer_fee: None,
+            relayer_fee: None,
+        }
+    }
+
+    fn signed_claim_message(amount: u128, merkle_proof: &[CryptoHash], nonce: u64) -> Vec<u8> {
+        borsh::to_vec(&(
+            account_owner(),
+            signer_account(),
+            amount,
+            merkle_proof.to_vec(),
+            1u32,
+            None::<AccountId>,
+            None::<ClaimOptions>,
+            nonce,
+        ))
+        .unwrap()
+    }
+
+    fn signed_claim_campaign_setup() -> (MerkleClaim, CryptoHash) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaf = mock_leaf_v2(1, &signer_account(), &signer_account(), 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), end, None);
+
+        // Any account can relay the signed claim
+        context.predecessor_account_id = non_owner();
+        testing_env!(context.clone());
+
+        (contract, sibling)
+    }
+
+    #[test]
+    fn test_claim_with_signature() {
+        let (mut contract, sibling) = signed_claim_campaign_setup();
+
+        contract.claim_with_signature(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            None,
+            sign_claim(1000, &[sibling], 0),
+        );
+
+        assert!(contract.has_claimed(1, signer_account()));
+        assert!(!contract.has_claimed(1, non_owner()));
+        assert_eq!(contract.get_signature_nonce(signer_account()).0, 1);
+
+        let event = confirmed_claim_event(&basic_context(), &mut contract);
+        assert_eq!(event["receiver_id"], signer_account().to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid nonce")]
+    fn test_claim_with_signature_replayed_nonce_failure() {
+        let (mut contract, sibling) = signed_claim_campaign_setup();
+
+        for _ in 0..2 {
+            contract.claim_with_signature(
+                json_types::U128(1000u128),
+                vec![sibling],
+                1u32,
+                None,
+                None,
+                sign_claim(1000, &[sibling], 0),
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_claim_with_signature_other_parameters_failure() {
+        let (mut contract, sibling) = signed_claim_campaign_setup();
+
+        contract.claim_with_signature(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            None,
+            sign_claim(500, &[sibling], 0),
+        );
+    }
+
+    /// Creates a campaign whose tree pairs `claimant()`'s leaf with `depth` siblings, returning
+    /// the proof of that leaf.
+    fn deep_campaign_setup(depth: u8) -> (MerkleClaim, Vec<CryptoHash>) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), 1000);
+        let proof: Vec<CryptoHash> = (0..depth).map(|i| [i; 32]).collect();
+        let root = proof
+            .iter()
+            .fold(leaf, |node, sibling| hash_pair(&node, sibling));
+        let end = Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)));
+        contract.create_campaign(root.into(), end, None);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        (contract, proof)
+    }
+
+    #[test]
+    fn test_claim_proof_at_max_length() {
+        let (mut contract, proof) = deep_campaign_setup(64);
+
+        contract.claim(json_types::U128(1000u128), proof, 1u32, None, None);
+
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Merkle proof exceeds the maximum length")]
+    fn test_claim_proof_over_max_length_failure() {
+        let (mut contract, proof) = deep_campaign_setup(65);
+
+        contract.claim(json_types::U128(1000u128), proof, 1u32, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Merkle proof exceeds the maximum length")]
+    fn test_set_max_proof_length() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.set_max_proof_length(1);
+        assert_eq!(contract.get_config().max_proof_length, 1);
+
+        contract.config.assert_proof_length(&FAKE_MERKLE_PROOF);
+    }
+
+    #[test]
+    fn test_config_max_proof_length_defaults() {
+        let config: Config = serde_json::from_str(
+            r#"{"owner_account_id": "owner.near", "min_storage_deposit": "1000"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.max_proof_length, 64);
+    }
+
+    #[test]
+    fn test_perpetual_campaign_claim_far_in_future() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), None, None);
+
+        assert_eq!(contract.get_campaign(1).unwrap().campaign.claim_end, None);
+
+        context.predecessor_account_id = claimant();
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 100 * 365);
+        testing_env!(context.clone());
+
+        assert_eq!(
+            contract.get_campaign_status(1),
+            Some(CampaignStatus::Active)
+        );
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    fn test_campaign_status() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, root, end) = build_mock_campaign();
+        contract.create_campaign(root.into(), Some(end), None);
+
+        assert_eq!(
+            contract.get_campaign_status(1),
+            Some(CampaignStatus::Active)
+        );
+        assert_eq!(contract.get_campaign_status(2), None);
+
+        context.block_timestamp = end.0;
+        testing_env!(context.clone());
+
+        assert_eq!(contract.get_campaign_status(1), Some(CampaignStatus::Ended));
+    }
+
+    #[test]
+    fn test_get_claim_key_matches_recorded_claim() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), Some(end), None);
+
+        let key: CryptoHash = contract.get_claim_key(1, claimant()).unwrap().into();
+        let other_key: CryptoHash = contract.get_claim_key(1, non_owner()).unwrap().into();
+        assert_eq!(
+            key,
+            compute_claim_key(ClaimKeyVersion::V2, &claimant(), 1, None)
+        );
+        assert_eq!(contract.get_claim_key(2, claimant()), None);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert!(contract.claim_record(1, &key).is_some());
+        assert!(contract.claim_record(1, &other_key).is_none());
+    }
+
+    fn b58_proof(proof: &[CryptoHash]) -> Vec<String> {
+        proof
+            .iter()
+            .map(|hash| String::from(&Base58CryptoHash::from(*hash)))
+            .collect()
+    }
+
+    #[test]
+    fn test_claim_b58() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaves = indexed_claimants(4)
+            .iter()
+            .map(|account| mock_leaf_v2(1, account, account, 1000))
+            .collect::<Vec<_>>();
+        let (root, proofs) = build_tree(&leaves);
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(root.into(), Some(end), None);
+
+        let claimant = indexed_claimants(4)[2].clone();
+        context.predecessor_account_id = claimant.clone();
+        testing_env!(context.clone());
+
+        let proof = b58_proof(&proofs[2]);
+        let decoded: Vec<CryptoHash> = proof
+            .iter()
+            .map(|hash| Base58CryptoHash::try_from(hash.as_str()).unwrap().into())
+            .collect();
+        assert_eq!(decoded, proofs[2]);
+
+        contract.claim_b58(json_types::U128(1000u128), proof, 1u32, None, None);
+
+        assert!(contract.has_claimed(1, claimant));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid base58 hash at proof index 1")]
+    fn test_claim_b58_invalid_hash_failure() {
+        let (mut contract, proof) = deep_campaign_setup(2);
+
+        let mut proof = b58_proof(&proof);
+        proof[1].push('0');
+
+        contract.claim_b58(json_types::U128(1000u128), proof, 1u32, None, None);
+    }
+
+    #[test]
+    fn test_merkle_root_string_round_trip() {
+        let (_, root, _) = build_mock_campaign();
+        let hex = encode_hex_hash(&root);
+        let b58 = String::from(&Base58CryptoHash::from(root));
+
+        for json in [
+            serde_json::to_string(&root).unwrap(),
+            serde_json::to_string(&hex).unwrap(),
+            serde_json::to_string(&b58).unwrap(),
+        ] {
+            let input: MerkleRootInput = serde_json::from_str(&json).unwrap();
+            assert_eq!(input.to_hash(), root);
+        }
+
+        assert_eq!(
+            MerkleRootInput::Encoded(hex.to_uppercase().replace("0X", "0x")).to_hash(),
+            root
+        );
+    }
+
+    #[test]
+    fn test_create_campaign_with_hex_root() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, root, end) = build_mock_campaign();
+        contract.create_campaign(
+            MerkleRootInput::Encoded(encode_hex_hash(&root)),
+            Some(end),
+            None,
+        );
+
+        let view = contract.get_campaign(1).unwrap();
+        assert_eq!(view.campaign.merkle_root, root);
+        assert_eq!(
+            MerkleRootInput::Encoded(view.merkle_root_hex).to_hash(),
+            root
+        );
+        assert_eq!(CryptoHash::from(view.merkle_root_b58), root);
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["merkle_root_hex"], encode_hex_hash(&root));
+    }
+
+    #[test]
+    #[should_panic(expected = "Hex merkle root must be 0x followed by 64 hex characters")]
+    fn test_merkle_root_short_hex_failure() {
+        let (_, root, _) = build_mock_campaign();
+        let mut hex = encode_hex_hash(&root);
+        hex.pop();
+
+        MerkleRootInput::Encoded(hex).to_hash();
+    }
+
+    #[test]
+    #[should_panic(expected = "Merkle root must be 32 bytes encoded as base58 or 0x-prefixed hex")]
+    fn test_merkle_root_invalid_base58_failure() {
+        MerkleRootInput::Encoded("not a root".to_string()).to_hash();
+    }
+
+    /// Creates a `V5` campaign in which `claimant()` holds a lockup allocation with nonce 0 and a
+    /// direct one with nonce 1, returning the proofs of both.
+    fn nonce_campaign_setup(lockup: &AccountId) -> (MerkleClaim, Vec<Vec<CryptoHash>>) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaves = [
+            mock_leaf_v5(1, &claimant(), lockup, 1000, 0),
+            mock_leaf_v5(1, &claimant(), &claimant(), 500, 1),
+            mock_leaf_v5(1, &non_owner(), &non_owner(), 200, 0),
+        ];
+        let (root, proofs) = build_tree(&leaves);
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                leaf_version: LeafVersion::V5,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        (contract, proofs)
+    }
+
+    fn nonce_options(nonce: u32) -> Option<ClaimOptions> {
+        Some(ClaimOptions {
+            nonce: Some(nonce),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_claim_several_leaves_of_one_account() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (mut contract, proofs) = nonce_campaign_setup(&lockup);
+
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.claim_key_version,
+            ClaimKeyVersion::V3
+        );
+
+        contract.claim(
+            json_types::U128(1000u128),
+            proofs[0].clone(),
+            1u32,
+            Some(lockup),
+            nonce_options(0),
+        );
+
+        assert!(contract.is_nonce_claimed(1, claimant(), 0));
+        assert!(!contract.is_nonce_claimed(1, claimant(), 1));
+
+        contract.claim(
+            json_types::U128(500u128),
+            proofs[1].clone(),
+            1u32,
+            None,
+            nonce_options(1),
+        );
+
+        assert!(contract.is_nonce_claimed(1, claimant(), 1));
+        assert!(!contract.is_nonce_claimed(1, non_owner(), 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Already claimed rewards")]
+    fn test_claim_same_nonce_twice_failure() {
+        let (mut contract, proofs) = nonce_campaign_setup(&claimant());
+
+        for _ in 0..2 {
+            contract.claim(
+                json_types::U128(500u128),
+                proofs[1].clone(),
+                1u32,
+                None,
+                nonce_options(1),
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_leaf_with_other_nonce_failure() {
+        let (mut contract, proofs) = nonce_campaign_setup(&claimant());
+
+        contract.claim(
+            json_types::U128(500u128),
+            proofs[1].clone(),
+            1u32,
+            None,
+            nonce_options(2),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Nonce is required by V5 leaves")]
+    fn test_claim_v5_without_nonce_failure() {
+        let (mut contract, proofs) = nonce_campaign_setup(&claimant());
+
+        contract.claim(
+            json_types::U128(500u128),
+            proofs[1].clone(),
+            1u32,
+            None,
+            None,
+        );
+    }
+
+    /// Sets up the environment of a callback receiving `result` from the promise it follows.
+    fn callback_context(context: VMContext, result: PromiseResult) {
+        callback_results_context(context, vec![result]);
+    }
+
+    fn callback_results_context(mut context: VMContext, results: Vec<PromiseResult>) {
+        context.predecessor_account_id = account_owner();
+        testing_env!(
+            context,
+            test_vm_config(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            results,
+        );
+    }
+
+    fn deleted_lockup_claim(campaign_id: CampaignId, index: Option<u32>) -> VerifiedClaim {
+        let lockup = AccountId::from_str("deleted-lockup").unwrap();
+
+        VerifiedClaim {
+            campaign_id,
+            account_id: claimant(),
+            lockup_contract: Some(lockup.clone()),
+            receiver_id: lockup,
+            allocation: json_types::U128(1000u128),
+            amount: json_types::U128(1000u128),
+            paid_amount: json_types::U128(1000u128),
+            index,
+            nonce: None,
+            liquid_bps: None,
+            new_account_key: None,
+            relayer_fee: None,
+            referrer: None,
+            referral_bonus: None,
+            donated: None,
+            claim_index: None,
+        }
+    }
+
+    #[test]
+    fn test_claim_again_after_failed_payout() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let lockup = AccountId::from_str("deleted-lockup").unwrap();
+        let leaf = mock_leaf_v2(1, &claimant(), &lockup, 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), Some(end), None);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup.clone()),
+            None,
+        );
+        assert!(contract.has_claimed(1, claimant()));
+
+        // The lockup did not exist, so the transfer failed and the claim is rolled back
+        callback_context(context.clone(), PromiseResult::Failed);
+        assert!(!contract.on_claim_payout(
+            deleted_lockup_claim(1, None),
+            json_types::U128(0),
+            json_types::U128(0)
+        ));
+        assert!(!contract.has_claimed(1, claimant()));
+
+        // Once the lockup is recreated the same proof claims again
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+
+        callback_context(context, PromiseResult::Successful(vec![]));
+        assert!(contract.on_claim_payout(
+            deleted_lockup_claim(1, None),
+            json_types::U128(0),
+            json_types::U128(0)
+        ));
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    fn test_failed_payout_rolls_back_bitmap_claim() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, root, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                claim_tracking: ClaimTracking::Bitmap,
+                ..Default::default()
+            }),
+        );
+        contract.set_index_claimed(1, 7);
+        contract.set_index_claimed(1, 8);
+
+        callback_context(context, PromiseResult::Failed);
+        contract.on_claim_payout(
+            deleted_lockup_claim(1, Some(7)),
+            json_types::U128(0),
+            json_types::U128(0),
+        );
+
+        assert!(!contract.is_index_claimed(1, 7));
+        assert!(contract.is_index_claimed(1, 8));
+    }
+
+    #[test]
+    fn test_failed_payout_restores_partial_allocation() {
+        let (context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        partial_claim(&mut contract, sibling, 400);
+        partial_claim(&mut contract, sibling, 600);
+        assert!(contract.has_claimed(1, claimant()));
+
+        let mut claim = deleted_lockup_claim(1, None);
+        claim.amount = json_types::U128(600u128);
+        callback_context(context, PromiseResult::Failed);
+        contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(0));
+
+        assert!(!contract.has_claimed(1, claimant()));
+        assert_eq!(contract.get_claimed_amount(1, claimant()).0, 400);
+    }
+
+    /// Returns the receiver and amount of every transfer scheduled so far.
+    /// Returns the data of a logged NEP-297 event after checking its envelope.
+    fn event_data(log: &str) -> serde_json::Value {
+        let log: serde_json::Value = serde_json::from_str(
+            log.strip_prefix("EVENT_JSON:")
+                .expect("Events are logged with the NEP-297 prefix"),
+        )
+        .unwrap();
+        assert_eq!(log["standard"], EVENT_STANDARD);
+        assert_eq!(log["version"], EVENT_VERSION);
+        assert!(log["event"].is_string());
+        assert_eq!(log["data"].as_array().unwrap().len(), 1);
+
+        log["data"][0].clone()
+    }
+
+    fn scheduled_transfers() -> Vec<(AccountId, u128)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                receipt
+                    .actions
+                    .into_iter()
+                    .filter_map(move |action| match action {
+                        MockAction::Transfer { deposit, .. } => {
+                            Some((receipt.receiver_id.clone(), deposit.as_yoctonear()))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the arguments of the `on_claim_payout` callback scheduled by the last claim.
+    fn pending_payout() -> (VerifiedClaim, json_types::U128, json_types::U128) {
+        let args = get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                MockAction::FunctionCallWeight {
+                    method_name, args, ..
+                } if method_name == b"on_claim_payout" => Some(args),
+                _ => None,
+            })
+            .next_back()
+            .expect("No payout callback was scheduled");
+        let args: serde_json::Value = serde_json::from_slice(&args).unwrap();
+
+        (
+            serde_json::from_value(args["claim"].clone()).unwrap(),
+            serde_json::from_value(args["fee"].clone()).unwrap(),
+            serde_json::from_value(args["bonus"].clone()).unwrap(),
+        )
+    }
+
+    /// Completes the payout of the last claim with every transfer succeeding, leaving the logs of
+    /// the callback.
+    fn confirm_payout(context: &VMContext, contract: &mut MerkleClaim) -> bool {
+        let (claim, fee, bonus) = pending_payout();
+        let results = (0..2).map(|_| PromiseResult::Successful(vec![])).collect();
+        callback_results_context(context.clone(), results);
+
+        contract.on_claim_payout(claim, fee, bonus)
+    }
+
+    /// Confirms the payout of the last claim and returns its claim event.
+    fn confirmed_claim_event(context: &VMContext, contract: &mut MerkleClaim) -> serde_json::Value {
+        assert!(confirm_payout(context, contract));
+        event_data(&get_logs()[0])
+    }
+
+    fn fee_config(claim_fee_bps: u16, fee_receiver: Option<AccountId>) -> Config {
+        Config {
+            owner_account_id: account_owner(),
+            min_storage_deposit: MIN_STORAGE_DEPOSIT,
+            allowed_lockup_suffix: None,
+            max_proof_length: 64,
+            claim_fee_bps,
+            fee_receiver,
+            min_claim_amount: None,
+            max_claim_amount: None,
+            max_relayer_fee: None,
+            withdraw_timelock: json_types::U64(0),
+            default_withdraw_receiver: None,
+            treasury_account_id: None,
+            withdraw_limit: None,
+            low_balance_threshold: None,
+            max_total_extension: None,
+        }
+    }
+
+    #[test]
+    fn test_claim_fee_rounds_down() {
+        let treasury = AccountId::from_str("treasury.near").unwrap();
+
+        let config = fee_config(1, Some(treasury.clone()));
+        assert_eq!(config.claim_fee(9_999), 0);
+        assert_eq!(config.claim_fee(10_000), 1);
+        assert_eq!(config.claim_fee(19_999), 1);
+
+        let config = fee_config(MAX_CLAIM_FEE_BPS, Some(treasury));
+        assert_eq!(config.claim_fee(9), 0);
+        assert_eq!(config.claim_fee(10), 1);
+        assert_eq!(config.claim_fee(u128::MAX), u128::MAX / 10);
+    }
+
+    #[test]
+    fn test_claim_fee_without_receiver() {
+        assert_eq!(fee_config(500, None).claim_fee(10_000), 0);
+    }
+
+    #[test]
+    fn test_claim_with_fee() {
+        let treasury = AccountId::from_str("treasury.near").unwrap();
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+        contract.set_claim_fee(250, Some(treasury.clone()));
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert_eq!(scheduled_transfers(), vec![(claimant(), 975)]);
+
+        // The fee is only paid once the payout succeeded
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["amount"], "1000");
+        assert_eq!(event["fee"], "25");
+        assert_eq!(event["net_amount"], "975");
+        assert_eq!(scheduled_transfers(), vec![(treasury, 25)]);
+    }
+
+    #[test]
+    fn test_claim_without_fee_keeps_event() {
+        let (context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        assert_eq!(scheduled_transfers(), vec![(claimant(), 1000)]);
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert!(event.get("fee").is_none());
+        assert!(event.get("net_amount").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim fee must not exceed 1000 bps")]
+    fn test_set_claim_fee_above_cap_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.set_claim_fee(MAX_CLAIM_FEE_BPS + 1, Some(non_owner()));
+    }
+
+    /// Creates a campaign ending after 90 days whose claims decay to 50% from day 30 on.
+    /// Decays claims from day 10 to half of the allocation at the claim end on day 30.
+    fn decay_campaign_setup() -> (VMContext, MerkleClaim, CryptoHash) {
+        options_campaign_setup(CampaignOptions {
+            decay: Some(ClaimDecay {
+                start: json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 10u64)),
+                final_bps: 5000,
+            }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_claim_multiplier_at_decay_boundaries() {
+        let (mut context, contract, _) = decay_campaign_setup();
+
+        // The decayed share is rounded down in favor of the claimant
+        for (timestamp, multiplier) in [
+            (to_ts(GENESIS_TIME_IN_DAYS), 10_000),
+            (to_ts(GENESIS_TIME_IN_DAYS + 10u64), 10_000),
+            (to_ts(GENESIS_TIME_IN_DAYS + 10u64) + 1, 10_000),
+            (to_ts(GENESIS_TIME_IN_DAYS + 20u64), 7_500),
+            (to_ts(GENESIS_TIME_IN_DAYS + 30u64) - 1, 5_001),
+            (to_ts(GENESIS_TIME_IN_DAYS + 30u64), 5_000),
+            (to_ts(GENESIS_TIME_IN_DAYS + 60u64), 5_000),
+        ] {
+            context.block_timestamp = timestamp;
+            testing_env!(context.clone());
+            assert_eq!(contract.get_claim_multiplier(1), multiplier);
+        }
+    }
+
+    #[test]
+    fn test_claim_multiplier_without_decay() {
+        let (_, contract, _) = partial_claim_campaign_setup();
+        assert_eq!(contract.get_claim_multiplier(1), 10_000);
+    }
+
+    #[test]
+    fn test_claim_before_decay_pays_in_full() {
+        let (mut context, mut contract, sibling) = decay_campaign_setup();
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 10u64);
+        testing_env!(context.clone());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        assert_eq!(scheduled_transfers(), vec![(claimant(), 1000)]);
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["amount"], "1000");
+        assert!(event.get("paid_amount").is_none());
+    }
+
+    #[test]
+    fn test_claim_during_decay_pays_reduced_amount() {
+        let (mut context, mut contract, sibling) = decay_campaign_setup();
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 20u64);
+        testing_env!(context.clone());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        assert_eq!(scheduled_transfers(), vec![(claimant(), 750)]);
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["amount"], "1000");
+        assert_eq!(event["paid_amount"], "750");
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Decay requires a claim end")]
+    fn test_decay_without_claim_end_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.create_campaign(
+            [0u8; 32].into(),
+            None,
+            Some(CampaignOptions {
+                decay: Some(ClaimDecay {
+                    start: json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)),
+                    final_bps: 5000,
+                }),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Decay must start between now and the claim end")]
+    fn test_decay_start_after_claim_end_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.create_campaign(
+            [0u8; 32].into(),
+            Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64))),
+            Some(CampaignOptions {
+                decay: Some(ClaimDecay {
+                    start: json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)),
+                    final_bps: 5000,
+                }),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Decay final share must not exceed 10000 bps")]
+    fn test_decay_final_share_above_full_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.create_campaign(
+            [0u8; 32].into(),
+            Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 90u64))),
+            Some(CampaignOptions {
+                decay: Some(ClaimDecay {
+                    start: json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64)),
+                    final_bps: 10_001,
+                }),
+                ..Default::default()
+            }),
+        );
+    }
+
+    /// Creates a campaign of three 1000 yocto allocations whose claims earn `bonus`, funding
+    /// its bonus pool with `pool`. Returns the claimants and their proofs.
+    fn bonus_campaign_setup(
+        bonus: ClaimBonus,
+        pool: u128,
+    ) -> (VMContext, MerkleClaim, Vec<AccountId>, Vec<Vec<CryptoHash>>) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let claimants = indexed_claimants(3);
+        let leaves: Vec<CryptoHash> = claimants
+            .iter()
+            .map(|account| mock_leaf_v2(1, account, account, 1000))
+            .collect();
+        let (root, proofs) = build_tree(&leaves);
+        let (_, _, end) = build_mock_campaign();
+
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                bonus: Some(bonus),
+                ..Default::default()
+            }),
+        );
+
+        context.attached_deposit = NearToken::from_yoctonear(pool);
+        testing_env!(context.clone());
+        contract.fund_bonus_pool(1);
+
+        (context, contract, claimants, proofs)
+    }
+
+    /// Claims 1000 as `claimant` and confirms the payout, returning the claim event and the
+    /// transfers scheduled by the claim.
+    fn bonus_claim(
+        context: &mut VMContext,
+        contract: &mut MerkleClaim,
+        claimant: &AccountId,
+        proof: &[CryptoHash],
+    ) -> (serde_json::Value, Vec<(AccountId, u128)>) {
+        context.predecessor_account_id = claimant.clone();
+        testing_env!(context.clone());
+
+        contract.claim(json_types::U128(1000u128), proof.to_vec(), 1u32, None, None);
+        let transfers = scheduled_transfers();
+        assert!(confirm_payout(context, contract));
+
+        (event_data(&get_logs()[0]), transfers)
+    }
+
+    #[test]
+    fn test_bonus_pool_exhausted_mid_campaign() {
+        let bonus = ClaimBonus {
+            bps: 1000,
+            max_claims: Some(3),
+            window_end: None,
+        };
+        let (mut context, mut contract, claimants, proofs) = bonus_campaign_setup(bonus, 150);
+
+        let (event, transfers) =
+            bonus_claim(&mut context, &mut contract, &claimants[0], &proofs[0]);
+        assert_eq!(event["amount"], "1000");
+        assert_eq!(event["bonus"], "100");
+        assert_eq!(transfers, vec![(claimants[0].clone(), 1100)]);
+
+        // The pool only covers half of the second bonus
+        let (event, _) = bonus_claim(&mut context, &mut contract, &claimants[1], &proofs[1]);
+        assert_eq!(event["bonus"], "50");
+        assert_eq!(contract.get_campaign(1).unwrap().campaign.bonus_pool.0, 0);
+
+        let (event, transfers) =
+            bonus_claim(&mut context, &mut contract, &claimants[2], &proofs[2]);
+        assert_eq!(event["amount"], "1000");
+        assert!(event.get("bonus").is_none());
+        assert_eq!(transfers, vec![(claimants[2].clone(), 1000)]);
+    }
+
+    #[test]
+    fn test_bonus_limited_to_first_claims() {
+        let bonus = ClaimBonus {
+            bps: 500,
+            max_claims: Some(1),
+            window_end: None,
+        };
+        let (mut context, mut contract, claimants, proofs) = bonus_campaign_setup(bonus, 1000);
+
+        let (event, _) = bonus_claim(&mut context, &mut contract, &claimants[0], &proofs[0]);
+        assert_eq!(event["bonus"], "50");
+
+        let (event, _) = bonus_claim(&mut context, &mut contract, &claimants[1], &proofs[1]);
+        assert!(event.get("bonus").is_none());
+        assert_eq!(contract.get_campaign(1).unwrap().campaign.bonus_pool.0, 950);
+    }
+
+    #[test]
+    fn test_bonus_limited_to_window() {
+        let bonus = ClaimBonus {
+            bps: 500,
+            max_claims: None,
+            window_end: Some(json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 1u64))),
+        };
+        let (mut context, mut contract, claimants, proofs) = bonus_campaign_setup(bonus, 1000);
+
+        let (event, _) = bonus_claim(&mut context, &mut contract, &claimants[0], &proofs[0]);
+        assert_eq!(event["bonus"], "50");
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 1u64);
+        let (event, _) = bonus_claim(&mut context, &mut contract, &claimants[1], &proofs[1]);
+        assert!(event.get("bonus").is_none());
+    }
+
+    #[test]
+    fn test_failed_payout_returns_bonus_to_pool() {
+        let bonus = ClaimBonus {
+            bps: 1000,
+            max_claims: Some(3),
+            window_end: None,
+        };
+        let (mut context, mut contract, claimants, proofs) = bonus_campaign_setup(bonus, 150);
+        context.predecessor_account_id = claimants[0].clone();
+        testing_env!(context.clone());
+        contract.claim(
+            json_types::U128(1000u128),
+            proofs[0].clone(),
+            1u32,
+            None,
+            None,
+        );
+
+        let mut claim = deleted_lockup_claim(1, None);
+        claim.account_id = claimants[0].clone();
+        claim.lockup_contract = None;
+        claim.receiver_id = claimants[0].clone();
+        callback_context(context, PromiseResult::Failed);
+
+        assert!(!contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(100)));
+        assert_eq!(contract.get_campaign(1).unwrap().campaign.bonus_pool.0, 150);
+        assert!(!contract.has_claimed(1, claimants[0].clone()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign has no bonus")]
+    fn test_fund_bonus_pool_without_bonus_failure() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.fund_bonus_pool(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bonus requires a claim limit or a window end")]
+    fn test_unlimited_bonus_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.create_campaign(
+            [0u8; 32].into(),
+            None,
+            Some(CampaignOptions {
+                bonus: Some(ClaimBonus {
+                    bps: 1000,
+                    max_claims: None,
+                    window_end: None,
+                }),
+                ..Default::default()
+            }),
+        );
+    }
+
+    fn split_campaign_setup() -> (VMContext, MerkleClaim, AccountId, CryptoHash) {
+        let lockup = AccountId::from_str("claimant.lockup.near").unwrap();
+        let (context, contract, sibling) = lockup_options_campaign_setup(
+            &lockup,
+            CampaignOptions {
+                allow_liquid_split: true,
+                ..Default::default()
+            },
+        );
+
+        (context, contract, lockup, sibling)
+    }
+
+    fn split_claim(
+        contract: &mut MerkleClaim,
+        lockup: &AccountId,
+        sibling: CryptoHash,
+        liquid_bps: u16,
+    ) {
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup.clone()),
+            Some(ClaimOptions {
+                liquid_bps: Some(liquid_bps),
+                ..Default::default()
+            }),
+        );
+    }
+
+    fn split_verified_claim(lockup: &AccountId, liquid_bps: u16) -> VerifiedClaim {
+        VerifiedClaim {
+            liquid_bps: Some(liquid_bps),
+            ..verified_lockup_claim(lockup)
+        }
+    }
+
+    #[test]
+    fn test_claim_split_between_lockup_and_claimant() {
+        let (context, mut contract, lockup, sibling) = split_campaign_setup();
+
+        split_claim(&mut contract, &lockup, sibling, 3000);
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(lockup, 700), (claimant(), 300)]
+        );
+
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["amount"], "1000");
+        assert_eq!(event["lockup_amount"], "700");
+        assert_eq!(event["liquid_amount"], "300");
+    }
+
+    #[test]
+    fn test_claim_split_fully_locked() {
+        let (context, mut contract, lockup, sibling) = split_campaign_setup();
+
+        split_claim(&mut contract, &lockup, sibling, 0);
+        assert_eq!(scheduled_transfers(), vec![(lockup, 1000)]);
+
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["lockup_amount"], "1000");
+        assert_eq!(event["liquid_amount"], "0");
+    }
+
+    #[test]
+    fn test_claim_split_fully_liquid() {
+        let (context, mut contract, lockup, sibling) = split_campaign_setup();
+
+        split_claim(&mut contract, &lockup, sibling, 10_000);
+        assert_eq!(scheduled_transfers(), vec![(claimant(), 1000)]);
+
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["lockup_amount"], "0");
+        assert_eq!(event["liquid_amount"], "1000");
+    }
+
+    #[test]
+    fn test_claim_split_rounding_keeps_total() {
+        let lockup = AccountId::from_str("claimant.lockup.near").unwrap();
+
+        for (payout, liquid_bps) in [(999, 3333), (1, 5000), (10_001, 9999), (u128::MAX, 1)] {
+            let (lockup_amount, liquid_amount) =
+                split_verified_claim(&lockup, liquid_bps).split_payout(payout);
+            assert_eq!(lockup_amount + liquid_amount, payout);
+        }
+
+        assert_eq!(
+            split_verified_claim(&lockup, 3333).split_payout(999),
+            (667, 332)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign does not allow liquid splits")]
+    fn test_claim_split_not_allowed_failure() {
+        let (_, mut contract, sibling) = partial_claim_campaign_setup();
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                liquid_bps: Some(5000),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Liquid splits require a lockup contract")]
+    fn test_claim_split_without_lockup_failure() {
+        let (_, mut contract, _, sibling) = split_campaign_setup();
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                liquid_bps: Some(5000),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn test_split_payout_partly_failed_keeps_claim() {
+        let (context, mut contract, lockup, sibling) = split_campaign_setup();
+        split_claim(&mut contract, &lockup, sibling, 3000);
+
+        callback_results_context(
+            context,
+            vec![PromiseResult::Failed, PromiseResult::Successful(vec![])],
+        );
+        let claim = split_verified_claim(&lockup, 3000);
+        assert!(!contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(0)));
+
+        // The claim stays recorded, so it is logged along with the undelivered part
+        assert_eq!(event_name(&get_logs()[0]), "claim");
+        let event: serde_json::Value = event_data(&get_logs()[1]);
+        assert_eq!(event["undelivered"], "700");
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    fn test_split_payout_failed_rolls_back_claim() {
+        let (context, mut contract, lockup, sibling) = split_campaign_setup();
+        split_claim(&mut contract, &lockup, sibling, 3000);
+
+        callback_results_context(context, vec![PromiseResult::Failed, PromiseResult::Failed]);
+        let claim = split_verified_claim(&lockup, 3000);
+        assert!(!contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(0)));
+
+        assert!(!contract.has_claimed(1, claimant()));
+        assert_eq!(get_logs().len(), 1);
+        assert_eq!(event_name(&get_logs()[0]), "claim_failed");
+    }
+
+    /// Creates a campaign of four weekly periods paying `claimant()` 1000 yocto per period.
+    fn periodic_campaign_setup() -> (VMContext, MerkleClaim, CryptoHash) {
+        options_campaign_setup(CampaignOptions {
+            periods: Some(ClaimPeriods {
+                period_length: json_types::U64(to_nanos(7)),
+                num_periods: 4,
+            }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_periodic_claims_once_per_period() {
+        let (mut context, mut contract, sibling) = periodic_campaign_setup();
+
+        assert_eq!(contract.get_current_period(1), Some(0));
+        assert!(!contract.has_claimed_in_period(1, claimant()));
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(contract.has_claimed_in_period(1, claimant()));
+
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["period"], 0);
+
+        // The last nanosecond of the first period
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 7u64) - 1;
+        testing_env!(context.clone());
+        assert_eq!(contract.get_current_period(1), Some(0));
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 7u64);
+        testing_env!(context.clone());
+        assert_eq!(contract.get_current_period(1), Some(1));
+        assert!(!contract.has_claimed_in_period(1, claimant()));
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(contract.has_claimed_in_period(1, claimant()));
+        assert_eq!(scheduled_transfers(), vec![(claimant(), 1000)]);
+
+        // Skipped periods are forfeited, a late claim is for the current period
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 27u64);
+        testing_env!(context.clone());
+        assert_eq!(contract.get_current_period(1), Some(3));
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Already claimed rewards")]
+    fn test_periodic_claim_twice_in_period_failure() {
+        let (mut context, mut contract, sibling) = periodic_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 6u64);
+        testing_env!(context.clone());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim period has concluded")]
+    fn test_periodic_claim_after_last_period_failure() {
+        let (mut context, mut contract, sibling) = periodic_campaign_setup();
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 28u64);
+        testing_env!(context.clone());
+        assert_eq!(contract.get_current_period(1), None);
+        assert_eq!(contract.get_campaign_status(1), Some(CampaignStatus::Ended));
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    fn test_periodic_check_claim() {
+        let (mut context, mut contract, sibling) = periodic_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        let check = || {
+            contract.check_claim(
+                claimant(),
+                json_types::U128(1000u128),
+                vec![sibling],
+                1u32,
+                None,
+                None,
+            )
+        };
+        assert_eq!(check(), ClaimCheck::AlreadyClaimed);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 7u64);
+        testing_env!(context.clone());
+        assert_eq!(check(), ClaimCheck::Claimable);
+    }
+
+    #[test]
+    #[should_panic(expected = "Periodic campaigns end with their last period")]
+    fn test_periodic_campaign_with_claim_end_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(
+            [0u8; 32].into(),
+            Some(end),
+            Some(CampaignOptions {
+                periods: Some(ClaimPeriods {
+                    period_length: json_types::U64(to_nanos(7)),
+                    num_periods: 4,
+                }),
+                ..Default::default()
+            }),
+        );
+    }
+
+    /// Invalidates `claimant()`'s allocation in campaign 1 and returns the event.
+    fn invalidate_claimant(
+        context: &mut VMContext,
+        contract: &mut MerkleClaim,
+    ) -> serde_json::Value {
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.invalidate_leaf(1, claimant());
+        let event = event_data(&get_logs()[0]);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        event
+    }
+
+    #[test]
+    fn test_invalidate_leaf_before_claim() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        let event = invalidate_claimant(&mut context, &mut contract);
+        assert_eq!(event["campaign_id"], 1);
+        assert_eq!(event["account_id"], "claimant");
+        assert!(contract.is_invalidated(1, claimant()));
+        assert!(contract.has_claimed(1, claimant()));
+        assert_eq!(
+            contract.get_claim_record(1, claimant()),
+            Some(ClaimRecord::Invalidated)
+        );
+        assert!(!contract.is_invalidated(1, non_owner()));
+        assert_eq!(
+            contract.check_claim(
+                claimant(),
+                json_types::U128(1000u128),
+                vec![sibling],
+                1u32,
+                None,
+                None
+            ),
+            ClaimCheck::Invalidated
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Allocation invalidated")]
+    fn test_claim_invalidated_leaf_failure() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        invalidate_claimant(&mut context, &mut contract);
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Allocation already claimed")]
+    fn test_invalidate_leaf_after_claim_failure() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(!contract.is_invalidated(1, claimant()));
+
+        invalidate_claimant(&mut context, &mut contract);
+    }
+
+    #[test]
+    fn test_invalidate_leaf_after_partial_claim() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        partial_claim(&mut contract, sibling, 400);
+        invalidate_claimant(&mut context, &mut contract);
+
+        assert!(contract.is_invalidated(1, claimant()));
+        assert_eq!(contract.get_claimed_amount(1, claimant()).0, 400);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_invalidate_leaf_non_owner_failure() {
+        let (_, mut contract, _) = partial_claim_campaign_setup();
+
+        contract.invalidate_leaf(1, non_owner());
+    }
+
+    fn set_claimant_blocked(context: &mut VMContext, contract: &mut MerkleClaim, blocked: bool) {
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        if blocked {
+            contract.block_account(claimant());
+        } else {
+            contract.unblock_account(claimant());
+        }
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["account_id"], "claimant");
+        assert_eq!(event["blocked"], blocked);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is blocked from claiming")]
+    fn test_claim_blocked_account_failure() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_claimant_blocked(&mut context, &mut contract, true);
+        assert!(contract.is_blocked(claimant()));
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    fn test_claim_after_unblock() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_claimant_blocked(&mut context, &mut contract, true);
+        assert_eq!(
+            contract.check_claim(
+                claimant(),
+                json_types::U128(1000u128),
+                vec![sibling],
+                1u32,
+                None,
+                None
+            ),
+            ClaimCheck::Blocked
+        );
+
+        set_claimant_blocked(&mut context, &mut contract, false);
+        assert!(!contract.is_blocked(claimant()));
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is not blocked")]
+    fn test_unblock_unknown_account_failure() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        set_claimant_blocked(&mut context, &mut contract, false);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_block_account_non_owner_failure() {
+        let (_, mut contract, _) = partial_claim_campaign_setup();
+
+        contract.block_account(non_owner());
+    }
+
+    fn mock_leaf_fixed(
+        campaign_id: CampaignId,
+        account: &AccountId,
+        lockup: &AccountId,
+    ) -> CryptoHash {
+        let data = MerkleTreeDataFixed {
+            campaign_id,
+            account: account.to_string(),
+            lockup: lockup.to_string(),
+        };
+
+        LeafHashing::Double.hash_leaf(&borsh::to_vec(&data).expect("Failed to serialize data"))
+    }
+
+    /// Creates a fixed amount campaign paying 5000 yocto per account, followed by a per-leaf
+    /// campaign with the same accounts. Returns the siblings of `claimant()`'s leaves.
+    fn fixed_amount_campaigns_setup() -> (VMContext, MerkleClaim, CryptoHash, CryptoHash) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, _, end) = build_mock_campaign();
+
+        let fixed_sibling = mock_leaf_fixed(1, &non_owner(), &non_owner());
+        contract.create_campaign(
+            hash_pair(
+                &mock_leaf_fixed(1, &claimant(), &claimant()),
+                &fixed_sibling,
+            )
+            .into(),
+            Some(end),
+            Some(CampaignOptions {
+                allocation_mode: AllocationMode::Fixed(json_types::U128(5000)),
+                ..Default::default()
+            }),
+        );
+
+        let sibling = mock_leaf_v2(2, &non_owner(), &non_owner(), 500);
+        contract.create_campaign(
+            hash_pair(&mock_leaf_v2(2, &claimant(), &claimant(), 1000), &sibling).into(),
+            Some(end),
+            None,
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        (context, contract, fixed_sibling, sibling)
+    }
+
+    #[test]
+    fn test_fixed_and_per_leaf_campaigns_coexist() {
+        let (context, mut contract, fixed_sibling, sibling) = fixed_amount_campaigns_setup();
+
+        let view = serde_json::to_value(contract.get_campaign(1).unwrap()).unwrap();
+        assert_eq!(
+            view["allocation_mode"],
+            serde_json::json!({ "Fixed": "5000" })
+        );
+        let view = serde_json::to_value(contract.get_campaign(2).unwrap()).unwrap();
+        assert_eq!(view["allocation_mode"], "PerLeaf");
+
+        contract.claim_fixed(vec![fixed_sibling], 1u32, None, None);
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["amount"], "5000");
+
+        testing_env!(context.clone());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 2u32, None, None);
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["amount"], "1000");
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert!(contract.has_claimed(2, claimant()));
+    }
+
+    #[test]
+    fn test_claim_fixed_amount_with_claim() {
+        let (_, mut contract, fixed_sibling, _) = fixed_amount_campaigns_setup();
+
+        contract.claim(
+            json_types::U128(5000u128),
+            vec![fixed_sibling],
+            1u32,
+            None,
+            None,
+        );
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount must equal the campaign's fixed amount")]
+    fn test_claim_fixed_amount_mismatch_failure() {
+        let (_, mut contract, fixed_sibling, _) = fixed_amount_campaigns_setup();
+
+        contract.claim(
+            json_types::U128(6000u128),
+            vec![fixed_sibling],
+            1u32,
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign does not pay a fixed amount")]
+    fn test_claim_fixed_per_leaf_campaign_failure() {
+        let (_, mut contract, _, sibling) = fixed_amount_campaigns_setup();
+
+        contract.claim_fixed(vec![sibling], 2u32, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Fixed amount campaigns require V2 leaves and claim keys")]
+    fn test_fixed_amount_bitmap_campaign_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.create_campaign(
+            [0u8; 32].into(),
+            None,
+            Some(CampaignOptions {
+                allocation_mode: AllocationMode::Fixed(json_types::U128(5000)),
+                claim_tracking: ClaimTracking::Bitmap,
+                ..Default::default()
+            }),
+        );
+    }
+
+    /// Sets the claim amount bounds of `partial_claim_campaign_setup`, whose allocation is 1000.
+    fn set_claim_amount_bounds(
+        context: &mut VMContext,
+        contract: &mut MerkleClaim,
+        min: u128,
+        max: u128,
+    ) {
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.set_claim_amount_bounds(Some(json_types::U128(min)), Some(json_types::U128(max)));
+        assert_eq!(
+            contract.get_config().min_claim_amount,
+            Some(json_types::U128(min))
+        );
+        assert_eq!(
+            contract.get_config().max_claim_amount,
+            Some(json_types::U128(max))
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+    }
+
+    #[test]
+    fn test_claim_at_minimum_amount() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_claim_amount_bounds(&mut context, &mut contract, 1000, 2000);
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    fn test_claim_at_maximum_amount() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_claim_amount_bounds(&mut context, &mut contract, 1, 1000);
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim amount is below the minimum")]
+    fn test_claim_below_minimum_amount_failure() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_claim_amount_bounds(&mut context, &mut contract, 1001, 2000);
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim amount is above the maximum")]
+    fn test_claim_above_maximum_amount_failure() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_claim_amount_bounds(&mut context, &mut contract, 1, 999);
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    fn test_claim_after_lifting_amount_bounds() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_claim_amount_bounds(&mut context, &mut contract, 1, 999);
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+        contract.set_claim_amount_bounds(None, None);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Minimum claim amount must not exceed the maximum")]
+    fn test_set_inverted_claim_amount_bounds_failure() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        set_claim_amount_bounds(&mut context, &mut contract, 1001, 1000);
+    }
+
+    #[test]
+    fn test_claim_with_exactly_sufficient_balance() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        context.account_balance =
+            MIN_STORAGE_DEPOSIT.saturating_add(NearToken::from_yoctonear(1000));
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        testing_env!(context.clone());
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient contract balance to fulfill claim")]
+    fn test_claim_insufficient_balance_failure() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        context.account_balance =
+            MIN_STORAGE_DEPOSIT.saturating_add(NearToken::from_yoctonear(999));
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        testing_env!(context.clone());
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient contract balance to fulfill claim")]
+    fn test_claim_cannot_use_reserved_bonus_pool_failure() {
+        let bonus = ClaimBonus {
+            bps: 1000,
+            max_claims: Some(3),
+            window_end: None,
+        };
+        let (mut context, mut contract, claimants, proofs) = bonus_campaign_setup(bonus, 5000);
+
+        // Enough for the claim only if the bonus pool could be used
+        context.account_balance =
+            MIN_STORAGE_DEPOSIT.saturating_add(NearToken::from_yoctonear(5999));
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        bonus_claim(&mut context, &mut contract, &claimants[0], &proofs[0]);
+    }
+
+    /// Creates a campaign of 1500 declaring `total` as its total allocation, of which
+    /// `claimant()` holds 1000.
+    fn total_allocation_campaign_setup(total: u128) -> (VMContext, MerkleClaim, CryptoHash) {
+        options_campaign_setup(CampaignOptions {
+            total_allocation: Some(json_types::U128(total)),
+            ..Default::default()
+        })
+    }
+
+    fn claimant_claim(campaign_id: CampaignId) -> VerifiedClaim {
+        let mut claim = deleted_lockup_claim(campaign_id, None);
+        claim.lockup_contract = None;
+        claim.receiver_id = claimant();
+        claim
+    }
+
+    fn cancel_campaign_as_owner(context: &VMContext, contract: &mut MerkleClaim) -> u128 {
+        let mut context = context.clone();
+        context.predecessor_account_id = account_owner();
+        testing_env!(context);
+
+        contract.cancel_campaign(1).0
+    }
+
+    #[test]
+    fn test_reserved_balance_claim_rollback_cancel() {
+        let (context, mut contract, sibling) = total_allocation_campaign_setup(1500);
+        assert_eq!(contract.get_reserved_balance().0, 1500);
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert_eq!(contract.get_reserved_balance().0, 500);
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.claimed_total.0,
+            1000
+        );
+
+        callback_context(context.clone(), PromiseResult::Failed);
+        contract.on_claim_payout(claimant_claim(1), json_types::U128(0), json_types::U128(0));
+        assert_eq!(contract.get_reserved_balance().0, 1500);
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.claimed_total.0,
+            0
+        );
+
+        assert_eq!(cancel_campaign_as_owner(&context, &mut contract), 1500);
+        assert_eq!(contract.get_reserved_balance().0, 0);
+        assert_eq!(contract.get_campaign_status(1), Some(CampaignStatus::Ended));
+    }
+
+    #[test]
+    fn test_reserved_balance_rollback_after_cancel() {
+        let (context, mut contract, sibling) = total_allocation_campaign_setup(1500);
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert_eq!(cancel_campaign_as_owner(&context, &mut contract), 500);
+        assert_eq!(contract.get_reserved_balance().0, 0);
+
+        // The cancelled campaign no longer reserves the allocation whose payout failed
+        callback_context(context, PromiseResult::Failed);
+        contract.on_claim_payout(claimant_claim(1), json_types::U128(0), json_types::U128(0));
+        assert_eq!(contract.get_reserved_balance().0, 0);
+        assert!(!contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    fn test_reserved_pools_rollback_after_cancel() {
+        let (mut context, mut contract, sibling) = options_campaign_setup(CampaignOptions {
+            total_allocation: Some(json_types::U128(1500)),
+            bonus: Some(ClaimBonus {
+                bps: 1000,
+                max_claims: Some(2),
+                window_end: None,
+            }),
+            referral: Some(ClaimReferral { bps: 1000 }),
+            ..Default::default()
+        });
+
+        set_caller(&mut context, account_owner());
+        context.attached_deposit = NearToken::from_yoctonear(1000);
+        testing_env!(context.clone());
+        contract.fund_bonus_pool(1);
+        contract.fund_referral_pool(1);
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        donate(&mut context, &mut contract, non_owner(), 1, 600);
+
+        set_caller(&mut context, claimant());
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                referrer: Some(referrer()),
+                ..Default::default()
+            }),
+        );
+        let (claim, fee, bonus) = pending_payout();
+        assert_eq!(bonus.0, 100);
+        assert_eq!(claim.referral_bonus, Some(json_types::U128(100)));
+        assert_eq!(claim.donated, Some(json_types::U128(600)));
+
+        // The cancellation releases the allocation left and the rest of the pools
+        assert_eq!(cancel_campaign_as_owner(&context, &mut contract), 2300);
+        assert_eq!(contract.get_reserved_balance().0, 0);
+
+        // The failed payout's bonus, referral bonus and donations stay released
+        callback_context(context, PromiseResult::Failed);
+        contract.on_claim_payout(claim, fee, bonus);
+        assert_eq!(contract.get_reserved_balance().0, 0);
+        let campaign = contract.get_campaign(1).unwrap().campaign;
+        assert_eq!(campaign.bonus_pool.0, 0);
+        assert_eq!(campaign.referral_pool.0, 0);
+        assert_eq!(campaign.donation_pool.0, 0);
+        assert!(!contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    fn test_sweep_ended_campaign() {
+        let (mut context, mut contract, sibling) = total_allocation_campaign_setup(1500);
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        context.predecessor_account_id = account_owner();
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        testing_env!(context.clone());
+
+        assert_eq!(contract.sweep_campaign(1).0, 500);
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 500)]);
+        assert_eq!(contract.get_reserved_balance().0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign has not ended yet")]
+    fn test_sweep_active_campaign_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.sweep_campaign(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign has already been swept or cancelled")]
+    fn test_sweep_cancelled_campaign_failure() {
+        let (context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        cancel_campaign_as_owner(&context, &mut contract);
+        contract.sweep_campaign(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Claims exceed the campaign's total allocation")]
+    fn test_claim_exceeding_total_allocation_failure() {
+        let (_, mut contract, sibling) = total_allocation_campaign_setup(999);
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient contract balance to reserve the total allocation")]
+    fn test_unfunded_total_allocation_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+        // The balance funds the first campaign's allocation, but not the second's
+        let total = contract.available_balance() / 2 + 1;
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        let options = CampaignOptions {
+            total_allocation: Some(json_types::U128(total)),
+            ..Default::default()
+        };
+
+        set_caller(&mut context, account_owner());
+        contract.create_campaign(
+            hash_pair(&leaf, &sibling).into(),
+            None,
+            Some(options.clone()),
+        );
+        assert_eq!(contract.get_reserved_balance().0, total);
+
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), None, Some(options));
+    }
+
+    #[test]
+    fn test_withdraw_keeps_reserved_balance() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        context.predecessor_account_id = account_owner();
+        context.account_balance =
+            MIN_STORAGE_DEPOSIT.saturating_add(NearToken::from_yoctonear(1800));
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        testing_env!(context.clone());
+
+        contract.withdraw(None);
+        // The attached yoctoNEAR is part of the balance
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 301)]);
+    }
+
+    #[test]
+    fn test_claim_with_min_gas() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        context.prepaid_gas = contract.get_min_claim_gas();
+        testing_env!(context.clone());
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Claims require at least 60 Tgas")]
+    fn test_claim_low_gas_failure() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        context.prepaid_gas = Gas::from_tgas(59);
+        testing_env!(context.clone());
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    fn test_claim_record_after_claim() {
+        let (_, mut contract, sibling) = partial_claim_campaign_setup();
+        assert_eq!(contract.get_claim_record(1, claimant()), None);
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        assert_eq!(
+            contract.get_claim_record(1, claimant()),
+            Some(ClaimRecord::Claimed {
+                amount: json_types::U128(1000u128),
+                lockup_contract: None,
+                timestamp: json_types::U64(env::block_timestamp()),
+            })
+        );
+        assert_eq!(contract.get_claim_record(1, non_owner()), None);
+    }
+
+    #[test]
+    fn test_claim_record_after_partial_claims() {
+        let (_, mut contract, sibling) = partial_claim_campaign_setup();
+
+        partial_claim(&mut contract, sibling, 400);
+        assert_eq!(contract.get_claim_record(1, claimant()), None);
+
+        partial_claim(&mut contract, sibling, 600);
+        assert_eq!(
+            contract.get_claim_record(1, claimant()),
+            Some(ClaimRecord::Claimed {
+                amount: json_types::U128(1000u128),
+                lockup_contract: None,
+                timestamp: json_types::U64(env::block_timestamp()),
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Already claimed rewards")]
+    fn test_legacy_claim_record() {
+        let (_, mut contract, sibling) = partial_claim_campaign_setup();
+
+        // An entry of the former claims set of a campaign migrated from v1.0.0, an empty value
+        // under the claim key
+        contract.campaigns.get_mut(&1).unwrap().claim_storage = ClaimStorage::Shared;
+        let key = contract.claim_key(1, &claimant()).unwrap();
+        env::storage_write(
+            &[borsh::to_vec(&StorageKeys::Claims).unwrap(), key.to_vec()].concat(),
+            &[],
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert_eq!(
+            contract.get_claim_record(1, claimant()),
+            Some(ClaimRecord::Legacy)
+        );
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    fn test_claim_record_borsh_roundtrip() {
+        let records = [
+            ClaimRecord::Legacy,
+            ClaimRecord::Claimed {
+                amount: json_types::U128(1000u128),
+                lockup_contract: Some(claimant()),
+                timestamp: json_types::U64(42),
+            },
+            ClaimRecord::Invalidated,
+        ];
+
+        for record in records {
+            let bytes = borsh::to_vec(&record).unwrap();
+            assert_eq!(borsh::from_slice::<ClaimRecord>(&bytes).unwrap(), record);
+        }
+        assert!(borsh::to_vec(&ClaimRecord::Legacy).unwrap().is_empty());
+    }
+
+    /// Creates campaigns 1 and 2, both claimed by the claimant and by non_owner, and returns a
+    /// context after they ended.
+    fn claimed_campaigns_setup() -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract) = claims_contract_setup();
+        let end = json_types::U64(to_ts(GENESIS_TIME_IN_DAYS + 30u64));
+
+        for campaign_id in 1..=2 {
+            context.predecessor_account_id = account_owner();
+            testing_env!(context.clone());
+
+            let leaf = mock_leaf_v2(campaign_id, &claimant(), &claimant(), 1000);
+            let sibling = mock_leaf_v2(campaign_id, &non_owner(), &non_owner(), 500);
+            contract.create_campaign(hash_pair(&leaf, &sibling).into(), Some(end), None);
+
+            for (account_id, amount, proof) in
+                [(claimant(), 1000, sibling), (non_owner(), 500, leaf)]
+            {
+                context.predecessor_account_id = account_id;
+                testing_env!(context.clone());
+
+                contract.claim(
+                    json_types::U128(amount),
+                    vec![proof],
+                    campaign_id,
+                    None,
+                    None,
+                );
+            }
+        }
+
+        context.predecessor_account_id = account_owner();
+        context.block_timestamp = end.0;
+        testing_env!(context.clone());
+
+        (context, contract)
+    }
+
+    /// Writes the cached campaigns and claim records to storage.
+    fn flush_claim_records(contract: &mut MerkleClaim) {
+        for campaign_id in 1..=contract.last_campaign_id {
+            if let Some(records) = contract.campaign_claims.get_mut(&campaign_id) {
+                records.flush();
+            }
+        }
+        contract.campaign_claims.flush();
+        contract.campaigns.flush();
+    }
+
+    #[test]
+    fn test_delete_campaign_releases_claim_storage() {
+        let (_, mut contract) = claimed_campaigns_setup();
+
+        flush_claim_records(&mut contract);
+        let before = env::storage_usage();
+
+        assert!(contract.delete_campaign(1, None));
+        flush_claim_records(&mut contract);
+
+        assert!(env::storage_usage() < before);
+        assert!(contract.get_campaign(1).is_none());
+        assert!(!contract.has_claimed(1, claimant()));
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"merkle_claim","version":"6.2.0","event":"campaign_deleted","data":[{"campaign_id":1}]}"#
+            ]
+        );
+
+        assert!(contract.has_claimed(2, claimant()));
+        assert!(contract.has_claimed(2, non_owner()));
+        assert!(matches!(
+            contract.get_claim_record(2, claimant()),
+            Some(ClaimRecord::Claimed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_delete_campaign_in_batches() {
+        let (_, mut contract) = claimed_campaigns_setup();
+
+        assert!(!contract.delete_campaign(1, Some(1)));
+        assert!(contract.get_campaign(1).is_some());
+        assert_eq!(
+            contract.has_claimed(1, claimant()) as u8 + contract.has_claimed(1, non_owner()) as u8,
+            1
+        );
+        assert!(get_logs().is_empty());
+
+        assert!(contract.delete_campaign(1, Some(1)));
+        assert!(contract.get_campaign(1).is_none());
+        assert!(contract.has_claimed(2, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign has not ended yet")]
+    fn test_delete_active_campaign_failure() {
+        let (mut context, mut contract) = claimed_campaigns_setup();
+
+        context.block_timestamp -= 1;
+        testing_env!(context);
+
+        contract.delete_campaign(1, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign still reserves funds")]
+    fn test_delete_campaign_reserving_funds_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        context.predecessor_account_id = account_owner();
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        testing_env!(context);
+
+        contract.delete_campaign(1, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_delete_campaign_non_owner_failure() {
+        let (mut context, mut contract) = claimed_campaigns_setup();
+
+        context.predecessor_account_id = non_owner();
+        testing_env!(context);
+
+        contract.delete_campaign(1, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Claims cannot pay the claim contract itself")]
+    fn test_claim_lockup_is_contract_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let contract_id = env::current_account_id();
+        let leaf = mock_leaf_v2(1, &claimant(), &contract_id, 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), Some(end), None);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(contract_id),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Claims cannot pay the claim contract itself")]
+    fn test_claim_v3_receiver_is_contract_failure() {
+        let contract_id = account_owner();
+        let (mut context, mut contract, sibling) = receiver_campaign_setup(&contract_id);
+        assert_eq!(contract_id, env::current_account_id());
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                receiver_id: Some(contract_id),
+                ..Default::default()
+            }),
+        );
+    }
+
+    fn verified_receiver_campaign_setup(
+        lockup: &AccountId,
+    ) -> (VMContext, MerkleClaim, CryptoHash) {
+        lockup_options_campaign_setup(
+            lockup,
+            CampaignOptions {
+                verify_receiver_exists: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_claim_verify_receiver_exists_success() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (context, mut contract, sibling) = verified_receiver_campaign_setup(&lockup);
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup.clone()),
+            None,
+        );
+
+        // Nothing is recorded until the empty transfer to the receiver succeeded
+        assert!(!contract.has_claimed(1, claimant()));
+        assert!(get_logs().is_empty());
+        assert_eq!(scheduled_transfers(), vec![(lockup.clone(), 0)]);
+
+        callback_context(context.clone(), PromiseResult::Successful(vec![]));
+        contract.on_receiver_checked(verified_lockup_claim(&lockup));
+
+        assert!(contract.has_claimed(1, claimant()));
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["receiver_id"], lockup.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Receiver account does not exist")]
+    fn test_claim_verify_receiver_exists_missing_failure() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (context, mut contract, _) = verified_receiver_campaign_setup(&lockup);
+
+        callback_context(context, PromiseResult::Failed);
+        contract.on_receiver_checked(verified_lockup_claim(&lockup));
+    }
+
+    #[test]
+    fn test_claim_verify_receiver_exists_skipped_for_implicit_accounts() {
+        let lockup = AccountId::from_str(&"ab".repeat(32)).unwrap();
+        let (_, mut contract, sibling) = verified_receiver_campaign_setup(&lockup);
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup.clone()),
+            None,
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert_eq!(scheduled_transfers(), vec![(lockup, 1000)]);
+    }
+
+    fn deposit_payout_call() -> PayoutCall {
+        PayoutCall {
+            method_name: "deposit".to_string(),
+            args_template: r#"{"beneficiary":"{account_id}","amount":"{amount}"}"#.to_string(),
+            gas: Gas::from_tgas(20),
+        }
+    }
+
+    fn payout_call_campaign_setup(
+        lockup: &AccountId,
+        payout_call: PayoutCall,
+    ) -> (VMContext, MerkleClaim, CryptoHash) {
+        lockup_options_campaign_setup(
+            lockup,
+            CampaignOptions {
+                payout_call: Some(payout_call),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Returns the receiver, method, arguments and deposit of every function call scheduled.
+    fn scheduled_function_calls() -> Vec<(AccountId, String, String, u128)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                receipt
+                    .actions
+                    .into_iter()
+                    .filter_map(move |action| match action {
+                        MockAction::FunctionCallWeight {
+                            method_name,
+                            args,
+                            attached_deposit,
+                            ..
+                        } => Some((
+                            receipt.receiver_id.clone(),
+                            String::from_utf8(method_name).unwrap(),
+                            String::from_utf8(args).unwrap(),
+                            attached_deposit.as_yoctonear(),
+                        )),
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_claim_pays_through_payout_call() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (_, mut contract, sibling) = payout_call_campaign_setup(&lockup, deposit_payout_call());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup.clone()),
+            None,
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert!(scheduled_transfers().is_empty());
+        assert_eq!(
+            scheduled_function_calls()[0],
+            (
+                lockup,
+                "deposit".to_string(),
+                r#"{"beneficiary":"claimant","amount":"1000"}"#.to_string(),
+                1000
+            )
+        );
+    }
+
+    #[test]
+    fn test_failed_payout_call_rolls_back_claim() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (context, mut contract, sibling) =
+            payout_call_campaign_setup(&lockup, deposit_payout_call());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup.clone()),
+            None,
+        );
+
+        callback_context(context, PromiseResult::Failed);
+        assert!(!contract.on_claim_payout(
+            verified_lockup_claim(&lockup),
+            json_types::U128(0),
+            json_types::U128(0),
+        ));
+
+        assert!(!contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Claims require the payout call's gas on top of 60 Tgas")]
+    fn test_claim_payout_call_low_gas_failure() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let (mut context, mut contract, sibling) =
+            payout_call_campaign_setup(&lockup, deposit_payout_call());
+
+        context.prepaid_gas = Gas::from_tgas(79);
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            Some(lockup),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Payout arguments must be a JSON template")]
+    fn test_create_campaign_invalid_payout_args_failure() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+
+        payout_call_campaign_setup(
+            &lockup,
+            PayoutCall {
+                args_template: "{account_id}".to_string(),
+                ..deposit_payout_call()
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Payout call gas must be positive and at most 100 Tgas")]
+    fn test_create_campaign_payout_call_gas_failure() {
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+
+        payout_call_campaign_setup(
+            &lockup,
+            PayoutCall {
+                gas: Gas::from_tgas(101),
+                ..deposit_payout_call()
+            },
+        );
+    }
+
+    /// The private key of the example account of the web3.js documentation.
+    const ETH_SECRET_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const ETH_ADDRESS: &str = "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23";
+
+    fn eth_secret_key() -> secp256k1::SecretKey {
+        secp256k1::SecretKey::from_str(ETH_SECRET_KEY).unwrap()
+    }
+
+    fn eth_address_of(secret_key: &secp256k1::SecretKey) -> [u8; 20] {
+        let public_key = secret_key.public_key(&secp256k1::Secp256k1::new());
+        env::keccak256_array(&public_key.serialize_uncompressed()[1..])[12..]
+            .try_into()
+            .unwrap()
+    }
+
+    /// Signs `message` as an Ethereum wallet's `personal_sign` does.
+    fn eth_sign(secret_key: &secp256k1::SecretKey, message: &str) -> String {
+        let hash = secp256k1::Message::from_slice(&eth::eth_message_hash(message)).unwrap();
+        let (recovery_id, signature) = secp256k1::Secp256k1::new()
+            .sign_ecdsa_recoverable(&hash, secret_key)
+            .serialize_compact();
+        let v = recovery_id.to_i32() as u8 + 27;
+
+        let hex: String = signature
+            .iter()
+            .chain([v].iter())
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        format!("0x{hex}")
+    }
+
+    fn mock_leaf_v6(campaign_id: CampaignId, eth_address: [u8; 20], amount: Balance) -> CryptoHash {
+        let data = MerkleTreeDataV6 {
+            campaign_id,
+            eth_address,
+            amount,
+        };
+
+        LeafHashing::Double.hash_leaf(&borsh::to_vec(&data).expect("Failed to serialize data"))
+    }
+
+    fn eth_campaign_setup() -> (VMContext, MerkleClaim, CryptoHash) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaf = mock_leaf_v6(1, eth_address_of(&eth_secret_key()), 1000);
+        let sibling = mock_leaf_v6(1, [0x22; 20], 500);
+        let (_, _, end) = build_mock_campaign();
+
+        contract.create_campaign(
+            hash_pair(&leaf, &sibling).into(),
+            Some(end),
+            Some(CampaignOptions {
+                leaf_version: LeafVersion::V6,
+                ..Default::default()
+            }),
+        );
+
+        // Anyone can submit Ethereum claims
+        context.predecessor_account_id = non_owner();
+        testing_env!(context.clone());
+
+        (context, contract, sibling)
+    }
+
+    fn eth_claim(contract: &mut MerkleClaim, sibling: CryptoHash, receiver_id: AccountId) {
+        let message = eth::eth_claim_message(&env::current_account_id(), 1, &receiver_id);
+
+        contract.claim_eth(
+            ETH_ADDRESS.to_string(),
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            receiver_id,
+            eth_sign(&eth_secret_key(), &message),
+        );
+    }
+
+    #[test]
+    fn test_eth_known_keypair_and_message_hash() {
+        let address = eth_address_of(&eth_secret_key());
+
+        assert_eq!(
+            eth::eth_account_id(&address).as_str(),
+            ETH_ADDRESS.to_lowercase()
+        );
+        assert_eq!(
+            encode_hex_hash(&eth::eth_message_hash("Some data")),
+            "0x1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655"
+        );
+        assert_eq!(
+            eth::eth_claim_message(&account_owner(), 1, &claimant()),
+            format!("Claim campaign 1 of {} to claimant", account_owner())
+        );
+    }
+
+    #[test]
+    fn test_claim_eth() {
+        let (_, mut contract, sibling) = eth_campaign_setup();
+        let treasury = AccountId::from_str("treasury.near").unwrap();
+        let account_id = AccountId::from_str(&ETH_ADDRESS.to_lowercase()).unwrap();
+
+        eth_claim(&mut contract, sibling, treasury.clone());
+
+        assert!(contract.has_claimed(1, account_id.clone()));
+        assert_eq!(scheduled_transfers(), vec![(treasury.clone(), 1000)]);
+        let event = confirmed_claim_event(&basic_context(), &mut contract);
+        assert_eq!(event["account_id"], account_id.to_string());
+        assert_eq!(event["receiver_id"], treasury.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Already claimed rewards")]
+    fn test_claim_eth_twice_to_other_receiver_failure() {
+        let (_, mut contract, sibling) = eth_campaign_setup();
+
+        eth_claim(&mut contract, sibling, claimant());
+        eth_claim(&mut contract, sibling, non_owner());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_claim_eth_other_receiver_than_signed_failure() {
+        let (_, mut contract, sibling) = eth_campaign_setup();
+        let message = eth::eth_claim_message(&env::current_account_id(), 1, &claimant());
+
+        contract.claim_eth(
+            ETH_ADDRESS.to_string(),
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            non_owner(),
+            eth_sign(&eth_secret_key(), &message),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_claim_eth_other_signer_failure() {
+        let (_, mut contract, sibling) = eth_campaign_setup();
+        let message = eth::eth_claim_message(&env::current_account_id(), 1, &claimant());
+        let other_key = secp256k1::SecretKey::from_slice(&[0x33; 32]).unwrap();
+
+        contract.claim_eth(
+            ETH_ADDRESS.to_string(),
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            claimant(),
+            eth_sign(&other_key, &message),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "V6 leaves are claimed by Ethereum addresses")]
+    fn test_claim_v6_leaf_by_named_account_failure() {
+        let (_, mut contract, sibling) = eth_campaign_setup();
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                receiver_id: Some(claimant()),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Ethereum claims require V6 leaves")]
+    fn test_claim_eth_without_v6_leaves_failure() {
+        let (_, mut contract, sibling) = partial_claim_campaign_setup();
+
+        eth_claim(&mut contract, sibling, claimant());
+    }
+
+    fn mock_leaf_v7(campaign_id: CampaignId, public_key: [u8; 32], amount: Balance) -> CryptoHash {
+        let data = MerkleTreeDataV7 {
+            campaign_id,
+            public_key,
+            amount,
+        };
+
+        LeafHashing::Double.hash_leaf(&borsh::to_vec(&data).expect("Failed to serialize data"))
+    }
+
+    fn key_campaign_setup() -> (MerkleClaim, CryptoHash) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaf = mock_leaf_v7(1, signing_key().verifying_key().to_bytes(), 1000);
+        let sibling = mock_leaf_v7(1, [0x22; 32], 500);
+        let (_, _, end) = build_mock_campaign();
+
+        contract.create_campaign(
+            hash_pair(&leaf, &sibling).into(),
+            Some(end),
+            Some(CampaignOptions {
+                leaf_version: LeafVersion::V7,
+                ..Default::default()
+            }),
+        );
+
+        // Any account can relay the key claim
+        context.predecessor_account_id = non_owner();
+        testing_env!(context.clone());
+
+        (contract, sibling)
+    }
+
+    /// Signs a claim of `signing_key()`'s allocation in campaign 1 to `receiver_id` following
+    /// the documented message encoding.
+    fn sign_key_claim(receiver_id: &AccountId) -> json_types::Base64VecU8 {
+        let message = borsh::to_vec(&(account_owner(), 1u32, receiver_id.clone())).unwrap();
+
+        signing_key().sign(&message).to_bytes().to_vec().into()
+    }
+
+    fn signing_public_key() -> PublicKey {
+        PublicKey::from_parts(
+            CurveType::ED25519,
+            signing_key().verifying_key().as_bytes().to_vec(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_claim_with_key() {
+        let (mut contract, sibling) = key_campaign_setup();
+        let treasury = AccountId::from_str("treasury.near").unwrap();
+
+        contract.claim_with_key(
+            signing_public_key(),
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            treasury.clone(),
+            sign_key_claim(&treasury),
+        );
+
+        assert!(contract.has_claimed(1, signer_account()));
+        assert_eq!(scheduled_transfers(), vec![(treasury.clone(), 1000)]);
+        let event = confirmed_claim_event(&basic_context(), &mut contract);
+        assert_eq!(event["account_id"], signer_account().to_string());
+        assert_eq!(event["receiver_id"], treasury.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_claim_with_key_wrong_receiver_failure() {
+        let (mut contract, sibling) = key_campaign_setup();
+
+        contract.claim_with_key(
+            signing_public_key(),
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            non_owner(),
+            sign_key_claim(&claimant()),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Already claimed rewards")]
+    fn test_claim_with_key_twice_failure() {
+        let (mut contract, sibling) = key_campaign_setup();
+
+        for receiver_id in [claimant(), non_owner()] {
+            contract.claim_with_key(
+                signing_public_key(),
+                json_types::U128(1000u128),
+                vec![sibling],
+                1u32,
+                receiver_id.clone(),
+                sign_key_claim(&receiver_id),
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "V7 leaves are claimed by public keys")]
+    fn test_claim_v7_leaf_by_named_account_failure() {
+        let (mut contract, sibling) = key_campaign_setup();
+
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                receiver_id: Some(claimant()),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Key claims require V7 leaves")]
+    fn test_claim_with_key_without_v7_leaves_failure() {
+        let (mut contract, sibling) = signed_claim_campaign_setup();
+
+        contract.claim_with_key(
+            signing_public_key(),
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            claimant(),
+            sign_key_claim(&claimant()),
+        );
+    }
+
+    fn new_account() -> AccountId {
+        AccountId::from_str("alice.account_owner").unwrap()
+    }
+
+    fn create_account_and_claim(contract: &mut MerkleClaim, new_account_id: AccountId) {
+        contract.create_account_and_claim(
+            new_account_id.clone(),
+            signing_public_key(),
+            json_types::U128(1000u128),
+            vec![mock_leaf_v7(1, [0x22; 32], 500)],
+            1u32,
+            sign_key_claim(&new_account_id),
+        );
+    }
+
+    #[test]
+    fn test_create_account_and_claim() {
+        let (mut contract, _) = key_campaign_setup();
+
+        create_account_and_claim(&mut contract, new_account());
+
+        assert!(contract.has_claimed(1, signer_account()));
+        let receipts = get_created_receipts();
+        assert_eq!(receipts[0].receiver_id, new_account());
+        assert!(matches!(
+            receipts[0].actions[..],
+            [
+                MockAction::CreateAccount { .. },
+                MockAction::AddKeyWithFullAccess { .. },
+                MockAction::Transfer { .. },
+            ]
+        ));
+        assert_eq!(scheduled_transfers(), vec![(new_account(), 1000)]);
+    }
+
+    #[test]
+    fn test_create_account_and_claim_taken_name_rolls_back() {
+        let (mut contract, sibling) = key_campaign_setup();
+        let mut claim = contract.verify_claim(
+            signer_account(),
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            Some(ClaimOptions {
+                receiver_id: Some(new_account()),
+                ..Default::default()
+            }),
+        );
+        claim.new_account_key = Some(signing_public_key());
+
+        create_account_and_claim(&mut contract, new_account());
+        assert!(contract.has_claimed(1, signer_account()));
+
+        // The account already existed, so its creation failed and the claim is rolled back
+        callback_context(
+            get_context(account_owner(), to_ts(GENESIS_TIME_IN_DAYS)),
+            PromiseResult::Failed,
+        );
+        assert!(!contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(0)));
+        assert!(!contract.has_claimed(1, signer_account()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_create_account_and_claim_wrong_account_failure() {
+        let (mut contract, sibling) = key_campaign_setup();
+
+        contract.create_account_and_claim(
+            new_account(),
+            signing_public_key(),
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            sign_key_claim(&AccountId::from_str("bob.account_owner").unwrap()),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "New account must be a sub-account of the claim contract")]
+    fn test_create_account_and_claim_top_level_account_failure() {
+        let (mut contract, _) = key_campaign_setup();
+
+        create_account_and_claim(&mut contract, AccountId::from_str("alice").unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "Account creation claims require V7 leaves and no vesting")]
+    fn test_create_account_and_claim_without_v7_leaves_failure() {
+        let (mut contract, _) = signed_claim_campaign_setup();
+
+        create_account_and_claim(&mut contract, new_account());
+    }
+
+    const REGISTRATION_DEPOSIT: NearToken = NearToken::from_millinear(10);
+
+    fn registration_campaign_setup() -> (VMContext, MerkleClaim, CryptoHash) {
+        options_campaign_setup(CampaignOptions {
+            require_registration: true,
+            ..Default::default()
+        })
+    }
+
+    /// Registers `account_id` with a deposit paid by `payer`, which the contract balance then
+    /// holds on top of the default balance.
+    fn register(
+        context: &mut VMContext,
+        contract: &mut MerkleClaim,
+        payer: AccountId,
+        account_id: AccountId,
+    ) {
+        context.predecessor_account_id = payer;
+        context.attached_deposit = REGISTRATION_DEPOSIT;
+        testing_env!(context.clone());
+
+        contract.register_claim(1, Some(account_id));
+
+        context.account_balance = context.account_balance.saturating_add(REGISTRATION_DEPOSIT);
+        context.attached_deposit = NearToken::from_yoctonear(0);
+    }
+
+    #[test]
+    fn test_register_and_claim() {
+        let (mut context, mut contract, sibling) = registration_campaign_setup();
+
+        register(&mut context, &mut contract, claimant(), claimant());
+        assert_eq!(
+            contract.get_reserved_balance().0,
+            REGISTRATION_DEPOSIT.as_yoctonear()
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(contract.has_claimed(1, claimant()));
+        // The registration is kept until the payout succeeded
+        assert!(contract.get_registration(1, claimant()).is_some());
+
+        callback_context(context, PromiseResult::Successful(vec![]));
+        let claim = VerifiedClaim {
+            campaign_id: 1,
+            account_id: claimant(),
+            lockup_contract: None,
+            receiver_id: claimant(),
+            allocation: json_types::U128(1000),
+            amount: json_types::U128(1000),
+            paid_amount: json_types::U128(1000),
+            index: None,
+            nonce: None,
+            liquid_bps: None,
+            new_account_key: None,
+            relayer_fee: None,
+            referrer: None,
+            referral_bonus: None,
+            donated: None,
+            claim_index: None,
+        };
+        assert!(contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(0)));
+
+        assert!(contract.get_registration(1, claimant()).is_none());
+        assert_eq!(contract.get_reserved_balance().0, 0);
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(claimant(), REGISTRATION_DEPOSIT.as_yoctonear())]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is not registered for this campaign")]
+    fn test_claim_without_registration_failure() {
+        let (mut context, mut contract, sibling) = registration_campaign_setup();
+
+        // Registering another account does not register the claimant
+        register(&mut context, &mut contract, claimant(), non_owner());
+        assert_eq!(
+            contract.check_claim(
+                claimant(),
+                json_types::U128(1000u128),
+                vec![sibling],
+                1u32,
+                None,
+                None
+            ),
+            ClaimCheck::NotRegistered
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+    }
+
+    #[test]
+    fn test_refund_unused_registration() {
+        let (mut context, mut contract, _) = registration_campaign_setup();
+        let (_, _, end) = build_mock_campaign();
+
+        // A sponsor registers the claimant, who never claims
+        register(&mut context, &mut contract, non_owner(), claimant());
+        let registration = contract.get_registration(1, claimant()).unwrap();
+        assert_eq!(registration.payer, non_owner());
+
+        context.predecessor_account_id = claimant();
+        context.block_timestamp = end.0;
+        testing_env!(context.clone());
+
+        assert_eq!(
+            contract.refund_unused_registration(1, claimant()).0,
+            REGISTRATION_DEPOSIT.as_yoctonear()
+        );
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(non_owner(), REGISTRATION_DEPOSIT.as_yoctonear())]
+        );
+        assert!(contract.get_registration(1, claimant()).is_none());
+        assert_eq!(contract.get_reserved_balance().0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign has not ended yet")]
+    fn test_refund_unused_registration_before_end_failure() {
+        let (mut context, mut contract, _) = registration_campaign_setup();
+
+        register(&mut context, &mut contract, claimant(), claimant());
+        contract.refund_unused_registration(1, claimant());
+    }
+
+    #[test]
+    #[should_panic(expected = "Registration requires a deposit of at least 0.01 NEAR")]
+    fn test_register_claim_insufficient_deposit_failure() {
+        let (mut context, mut contract, _) = registration_campaign_setup();
+
+        context.predecessor_account_id = claimant();
+        context.attached_deposit = NearToken::from_millinear(1);
+        testing_env!(context);
+
+        contract.register_claim(1, None);
+    }
+
+    /// Signs a claim of the whole allocation of `signer_account()` allowing a relayer fee of up
+    /// to `max_relayer_fee`, of which the relayer takes `relayer_fee`.
+    fn sign_relayed_claim(
+        merkle_proof: &[CryptoHash],
+        max_relayer_fee: u128,
+        relayer_fee: u128,
+    ) -> ClaimSignature {
+        let mut message = signed_claim_message(1000, merkle_proof, 0);
+        message.extend_from_slice(&max_relayer_fee.to_le_bytes());
+
+        ClaimSignature {
+            signature: signing_key().sign(&message).to_bytes().to_vec().into(),
+            max_relayer_fee: Some(json_types::U128(max_relayer_fee)),
+            relayer_fee: Some(json_types::U128(relayer_fee)),
+            ..sign_claim(1000, merkle_proof, 0)
+        }
+    }
+
+    fn relayed_claim_setup(max_relayer_fee: Option<u128>) -> (MerkleClaim, CryptoHash) {
+        let (mut contract, sibling) = signed_claim_campaign_setup();
+        contract.config.max_relayer_fee = max_relayer_fee.map(json_types::U128);
+
+        (contract, sibling)
+    }
+
+    #[test]
+    fn test_claim_with_signature_relayer_fee() {
+        let (mut contract, sibling) = relayed_claim_setup(Some(100));
+        let mut claim = contract.verify_claim(
+            signer_account(),
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            None,
+        );
+
+        contract.claim_with_signature(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            None,
+            sign_relayed_claim(&[sibling], 50, 40),
+        );
+
+        assert_eq!(scheduled_transfers(), vec![(signer_account(), 960)]);
+        let event = confirmed_claim_event(&basic_context(), &mut contract);
+        assert_eq!(event["relayer_fee"], "40");
+        assert_eq!(event["max_relayer_fee"], "50");
+
+        // The relayer is paid once the payout succeeded
+        claim.relayer_fee = Some(RelayerFee {
+            relayer_id: non_owner(),
+            amount: json_types::U128(40),
+            max_amount: json_types::U128(50),
+        });
+        callback_context(
+            get_context(account_owner(), to_ts(GENESIS_TIME_IN_DAYS)),
+            PromiseResult::Successful(vec![]),
+        );
+        assert!(contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(0)));
+        assert_eq!(scheduled_transfers(), vec![(non_owner(), 40)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Relayer fee exceeds the signed maximum")]
+    fn test_claim_with_signature_relayer_fee_above_signed_maximum_failure() {
+        let (mut contract, sibling) = relayed_claim_setup(Some(100));
+
+        contract.claim_with_signature(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            None,
+            sign_relayed_claim(&[sibling], 50, 60),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Relayer fee exceeds the contract maximum")]
+    fn test_claim_with_signature_relayer_fee_above_contract_maximum_failure() {
+        let (mut contract, sibling) = relayed_claim_setup(Some(30));
+
+        contract.claim_with_signature(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            None,
+            sign_relayed_claim(&[sibling], 50, 40),
+        );
+    }
+
+    #[test]
+    fn test_claim_with_signature_zero_relayer_fee() {
+        let (mut contract, sibling) = relayed_claim_setup(Some(100));
+
+        // A relayer fee of zero needs no signed maximum and leaves the claim unchanged
+        contract.claim_with_signature(
+            json_types::U128(1000u128),
+            vec![sibling],
+            1u32,
+            None,
+            None,
+            ClaimSignature {
+                relayer_fee: Some(json_types::U128(0)),
+                ..sign_claim(1000, &[sibling], 0)
+            },
+        );
+
+        assert_eq!(scheduled_transfers(), vec![(signer_account(), 1000)]);
+        let event = confirmed_claim_event(&basic_context(), &mut contract);
+        assert!(event.get("relayer_fee").is_none());
+        assert!(event.get("max_relayer_fee").is_none());
+    }
+
+    fn referrer() -> AccountId {
+        AccountId::from_str("referrer").unwrap()
+    }
+
+    /// Creates a campaign of three 1000 yocto allocations whose referred claims pay their
+    /// referrer 10%, funding its referral pool with `pool`. Returns the claimants and their
+    /// proofs.
+    fn referral_campaign_setup(
+        pool: u128,
+    ) -> (VMContext, MerkleClaim, Vec<AccountId>, Vec<Vec<CryptoHash>>) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let claimants = indexed_claimants(3);
+        let leaves: Vec<CryptoHash> = claimants
+            .iter()
+            .map(|account| mock_leaf_v2(1, account, account, 1000))
+            .collect();
+        let (root, proofs) = build_tree(&leaves);
+        let (_, _, end) = build_mock_campaign();
+
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                referral: Some(ClaimReferral { bps: 1000 }),
+                ..Default::default()
+            }),
+        );
+
+        context.attached_deposit = NearToken::from_yoctonear(pool);
+        testing_env!(context.clone());
+        contract.fund_referral_pool(1);
+
+        (context, contract, claimants, proofs)
+    }
+
+    /// Makes a claim referred by `referrer()` and completes its payout, returning the claim as
+    /// passed to `on_claim_payout`.
+    fn referred_claim(
+        context: &mut VMContext,
+        contract: &mut MerkleClaim,
+        claimant: &AccountId,
+        proof: &[CryptoHash],
+    ) -> VerifiedClaim {
+        context.predecessor_account_id = claimant.clone();
+        testing_env!(context.clone());
+
+        contract.claim(
+            json_types::U128(1000u128),
+            proof.to_vec(),
+            1u32,
+            None,
+            Some(ClaimOptions {
+                referrer: Some(referrer()),
+                ..Default::default()
+            }),
+        );
+        assert_eq!(scheduled_transfers(), vec![(claimant.clone(), 1000)]);
+
+        let (claim, _, _) = pending_payout();
+        assert!(confirm_payout(context, contract));
+
+        claim
+    }
+
+    #[test]
+    fn test_referral_bonus_paid_after_payout() {
+        let (mut context, mut contract, claimants, proofs) = referral_campaign_setup(1000);
+
+        let claim = referred_claim(&mut context, &mut contract, &claimants[0], &proofs[0]);
+
+        assert_eq!(claim.referral_bonus, Some(json_types::U128(100)));
+        assert_eq!(scheduled_transfers(), vec![(referrer(), 100)]);
+        assert_eq!(event_name(&get_logs()[0]), "claim");
+        let event: serde_json::Value = event_data(&get_logs()[1]);
+        assert_eq!(event["referrer"], referrer().to_string());
+        assert_eq!(event["bonus"], "100");
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.referral_pool.0,
+            900
+        );
+        assert_eq!(contract.get_reserved_balance().0, 900);
+    }
+
+    #[test]
+    fn test_referral_pool_exhausted() {
+        let (mut context, mut contract, claimants, proofs) = referral_campaign_setup(150);
+
+        referred_claim(&mut context, &mut contract, &claimants[0], &proofs[0]);
+        assert_eq!(scheduled_transfers(), vec![(referrer(), 100)]);
+
+        // The pool only covers half of the second bonus
+        referred_claim(&mut context, &mut contract, &claimants[1], &proofs[1]);
+        assert_eq!(scheduled_transfers(), vec![(referrer(), 50)]);
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.referral_pool.0,
+            0
+        );
+
+        // The claim itself is still paid in full once the pool is empty
+        let claim = referred_claim(&mut context, &mut contract, &claimants[2], &proofs[2]);
+        assert!(claim.referral_bonus.is_none());
+        assert!(scheduled_transfers().is_empty());
+        assert_eq!(get_logs().len(), 1);
+        assert!(contract.has_claimed(1, claimants[2].clone()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Self-referrals are not allowed")]
+    fn test_self_referral_failure() {
+        let (mut context, mut contract, claimants, proofs) = referral_campaign_setup(1000);
+
+        context.predecessor_account_id = claimants[0].clone();
+        testing_env!(context);
+
+        contract.claim(
+            json_types::U128(1000u128),
+            proofs[0].clone(),
+            1u32,
+            None,
+            Some(ClaimOptions {
+                referrer: Some(claimants[0].clone()),
+                ..Default::default()
+            }),
+        );
+    }
+
+    /// A xorshift generator, enough to draw random trees in tests without a dependency.
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    fn random_hash(state: &mut u64) -> CryptoHash {
+        env::keccak256_array(&next_random(state).to_le_bytes())
+    }
+
+    /// Builds the multiproof of the leaves at `indices`, in ascending order, of a tree with a
+    /// power of two leaves.
+    fn build_multi_proof(leaves: &[CryptoHash], indices: &[usize]) -> (Vec<CryptoHash>, Vec<bool>) {
+        let (mut proof, mut proof_flags) = (vec![], vec![]);
+        let mut known = indices.to_vec();
+        let mut level = leaves.to_vec();
+
+        while level.len() > 1 {
+            let mut parents = vec![];
+            let mut i = 0;
+            while i < known.len() {
+                let position = known[i];
+                if known.get(i + 1) == Some(&(position ^ 1)) {
+                    proof_flags.push(true);
+                    i += 2;
+                } else {
+                    proof.push(level[position ^ 1]);
+                    proof_flags.push(false);
+                    i += 1;
+                }
+                parents.push(position / 2);
+            }
+
+            known = parents;
+            level = level
+                .chunks(2)
+                .map(|pair| hash_pair(&pair[0], &pair[1]))
+                .collect();
+        }
+
+        (proof, proof_flags)
+    }
+
+    #[test]
+    fn test_multi_proof_matches_single_proofs() {
+        let mut state = 0x2545_f491_4f6c_dd1d;
+
+        for _ in 0..200 {
+            // Every tree starts with fresh gas for its hashes
+            testing_env!(basic_context());
+            let leaves: Vec<CryptoHash> = (0..1 << (next_random(&mut state) % 7))
+                .map(|_| random_hash(&mut state))
+                .collect();
+            let (root, proofs) = build_tree(&leaves);
+            let mut indices: Vec<usize> = (0..leaves.len())
+                .filter(|_| next_random(&mut state) % 3 == 0)
+                .collect();
+            if indices.is_empty() {
+                indices.push(next_random(&mut state) as usize % leaves.len());
+            }
+
+            let claimed: Vec<CryptoHash> = indices.iter().map(|&i| leaves[i]).collect();
+            let (proof, proof_flags) = build_multi_proof(&leaves, &indices);
+
+            assert!(MerkleClaim::verify_multi_proof(
+                claimed.clone(),
+                proof.clone(),
+                proof_flags.clone(),
+                root
+            ));
+            for &i in &indices {
+                assert!(MerkleClaim::verify_proof(
+                    leaves[i],
+                    proofs[i].clone(),
+                    root
+                ));
+            }
+
+            // A leaf that is not in the tree fails both verifiers
+            let tampered = next_random(&mut state) as usize % indices.len();
+            let mut forged = claimed.clone();
+            forged[tampered] = random_hash(&mut state);
+            assert!(!MerkleClaim::verify_multi_proof(
+                forged.clone(),
+                proof.clone(),
+                proof_flags.clone(),
+                root
+            ));
+            assert!(!MerkleClaim::verify_proof(
+                forged[tampered],
+                proofs[indices[tampered]].clone(),
+                root
+            ));
+
+            // Changing a flag or dropping a proof hash is rejected rather than panicking
+            if !proof_flags.is_empty() {
+                let mut flipped = proof_flags.clone();
+                let flag = next_random(&mut state) as usize % flipped.len();
+                flipped[flag] = !flipped[flag];
+                assert!(!MerkleClaim::verify_multi_proof(
+                    claimed.clone(),
+                    proof.clone(),
+                    flipped,
+                    root
+                ));
+            }
+            if !proof.is_empty() {
+                assert!(!MerkleClaim::verify_multi_proof(
+                    claimed,
+                    proof[1..].to_vec(),
+                    proof_flags,
+                    root
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_proof_malformed() {
+        let [a, b, c, d] = [[1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]];
+        let root = hash_pair(&hash_pair(&a, &b), &hash_pair(&c, &d));
+
+        assert!(MerkleClaim::verify_multi_proof(
+            vec![a, b],
+            vec![hash_pair(&c, &d)],
+            vec![true, false],
+            root
+        ));
+        // No leaves
+        assert!(!MerkleClaim::verify_multi_proof(
+            vec![],
+            vec![root],
+            vec![],
+            root
+        ));
+        // Flags not matching the number of leaves and proof hashes
+        assert!(!MerkleClaim::verify_multi_proof(
+            vec![a, b],
+            vec![hash_pair(&c, &d)],
+            vec![true, false, false],
+            root
+        ));
+        // Unused proof hashes
+        assert!(!MerkleClaim::verify_multi_proof(
+            vec![a, b],
+            vec![hash_pair(&c, &d), c],
+            vec![true, false, true],
+            root
+        ));
+        // A flag pairing a hash that has not been computed yet
+        assert!(!MerkleClaim::verify_multi_proof(
+            vec![a, b],
+            vec![c, d],
+            vec![true, true, false],
+            root
+        ));
+    }
+
+    /// Creates a `V5` campaign of four 1000 yocto allocations, three of them held by the
+    /// claimant with nonces 0 to 2. Returns the leaves of the tree.
+    fn multi_claim_campaign_setup() -> (MerkleClaim, Vec<CryptoHash>) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaves = vec![
+            mock_leaf_v5(1, &claimant(), &claimant(), 1000, 0),
+            mock_leaf_v5(1, &claimant(), &claimant(), 1000, 1),
+            mock_leaf_v5(1, &claimant(), &claimant(), 1000, 2),
+            mock_leaf_v5(1, &non_owner(), &non_owner(), 1000, 0),
+        ];
+        let (root, _) = build_tree(&leaves);
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                leaf_version: LeafVersion::V5,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+
+        (contract, leaves)
+    }
+
+    fn nonce_allocation(nonce: u32) -> ClaimAllocation {
+        ClaimAllocation {
+            amount: json_types::U128(1000),
+            lockup_contract: None,
+            options: Some(ClaimOptions {
+                nonce: Some(nonce),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_claim_multi() {
+        let (mut contract, leaves) = multi_claim_campaign_setup();
+        let (proof, proof_flags) = build_multi_proof(&leaves, &[0, 2]);
+
+        contract.claim_multi(
+            1,
+            vec![nonce_allocation(0), nonce_allocation(2)],
+            proof,
+            proof_flags,
+        );
+
+        assert!(contract.is_nonce_claimed(1, claimant(), 0));
+        assert!(!contract.is_nonce_claimed(1, claimant(), 1));
+        assert!(contract.is_nonce_claimed(1, claimant(), 2));
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(claimant(), 1000), (claimant(), 1000)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_multi_invalid_proof_failure() {
+        let (mut contract, leaves) = multi_claim_campaign_setup();
+        let (proof, proof_flags) = build_multi_proof(&leaves, &[0, 2]);
+
+        // The proof is for the allocations with nonces 0 and 2
+        contract.claim_multi(
+            1,
+            vec![nonce_allocation(0), nonce_allocation(1)],
+            proof,
+            proof_flags,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Allocations must be distinct")]
+    fn test_claim_multi_same_allocation_twice_failure() {
+        let (mut contract, leaves) = multi_claim_campaign_setup();
+        let (proof, proof_flags) = build_multi_proof(&leaves, &[0, 1]);
+
+        contract.claim_multi(
+            1,
+            vec![nonce_allocation(0), nonce_allocation(0)],
+            proof,
+            proof_flags,
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Multi claims are not supported by campaigns whose claimants pay for storage"
+    )]
+    fn test_claim_multi_claimant_pays_storage_failure() {
+        let (mut context, mut contract, sibling) = claimant_pays_storage_setup();
+
+        set_caller(&mut context, claimant());
+        contract.claim_multi(
+            1,
+            vec![ClaimAllocation {
+                amount: json_types::U128(1000),
+                lockup_contract: None,
+                options: None,
+            }],
+            vec![sibling],
+            vec![false],
+        );
+    }
+
+    /// The proof verification before it moved to allocation-free free functions.
+    fn reference_verify_proof(leaf: CryptoHash, proof: Vec<CryptoHash>, root: CryptoHash) -> bool {
+        let mut computed_hash = leaf;
+
+        for hash in proof {
+            let (low, high) = if computed_hash < hash {
+                (computed_hash, hash)
+            } else {
+                (hash, computed_hash)
+            };
+            computed_hash = env::keccak256_array(&[low.as_slice(), high.as_slice()].concat());
+        }
+
+        root == computed_hash
+    }
+
+    #[test]
+    fn test_verify_proof_matches_reference() {
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+
+        for _ in 0..100 {
+            testing_env!(basic_context());
+            let leaves: Vec<CryptoHash> = (0..1 + next_random(&mut state) % 40)
+                .map(|_| random_hash(&mut state))
+                .collect();
+            let (root, proofs) = build_tree(&leaves);
+            let i = next_random(&mut state) as usize % leaves.len();
+            let other = random_hash(&mut state);
+
+            for leaf in [leaves[i], other] {
+                let expected = reference_verify_proof(leaf, proofs[i].clone(), root);
+                assert_eq!(verify_proof(&leaf, &proofs[i], &root), expected);
+                assert_eq!(
+                    MerkleClaim::verify_proof(leaf, proofs[i].clone(), root),
+                    expected
+                );
+            }
+            assert!(verify_proof(&leaves[i], &proofs[i], &root));
+        }
+    }
+
+    /// Creates a keccak256 campaign 1 and a sha256 campaign 2 with the same allocations, and
+    /// returns the proofs of the claimant's allocation in each.
+    fn mixed_hash_campaigns_setup() -> (MerkleClaim, Vec<CryptoHash>, Vec<CryptoHash>) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, _, end) = build_mock_campaign();
+        let mut proofs = vec![];
+        for (campaign_id, hash_algo) in [(1, HashAlgo::Keccak256), (2, HashAlgo::Sha256)] {
+            let leaves: Vec<_> = [claimant(), non_owner(), system_account()]
+                .iter()
+                .map(|account| mock_leaf_v2_with(hash_algo, campaign_id, account, account, 1000))
+                .collect();
+            let (root, leaf_proofs) = build_tree_with(hash_algo.into(), &leaves);
+            proofs.push(leaf_proofs[0].clone());
+
+            contract.create_campaign(
+                root.into(),
+                Some(end),
+                Some(CampaignOptions {
+                    hash_algo,
+                    ..Default::default()
+                }),
+            );
+        }
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+
+        let sha256_proof = proofs.pop().unwrap();
+        (contract, proofs.pop().unwrap(), sha256_proof)
+    }
+
+    #[test]
+    fn test_claim_sha256_campaign_alongside_keccak256() {
+        let (mut contract, keccak256_proof, sha256_proof) = mixed_hash_campaigns_setup();
+
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.hash_algo,
+            HashAlgo::Keccak256
+        );
+        assert_eq!(
+            contract.get_campaign(2).unwrap().campaign.hash_algo,
+            HashAlgo::Sha256
+        );
+        assert_eq!(
+            contract.check_claim(
+                claimant(),
+                json_types::U128(1000),
+                sha256_proof.clone(),
+                2,
+                None,
+                None
+            ),
+            ClaimCheck::Claimable
+        );
+
+        contract.claim(json_types::U128(1000), keccak256_proof, 1, None, None);
+        contract.claim(json_types::U128(1000), sha256_proof, 2, None, None);
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert!(contract.has_claimed(2, claimant()));
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(claimant(), 1000), (claimant(), 1000)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_keccak256_proof_on_sha256_campaign_failure() {
+        let (mut contract, keccak256_proof, _) = mixed_hash_campaigns_setup();
+
+        contract.claim(json_types::U128(1000), keccak256_proof, 2, None, None);
+    }
+
+    #[test]
+    fn test_sha256_multi_proof() {
+        testing_env!(basic_context());
+        let leaves: Vec<_> = indexed_claimants(4)
+            .iter()
+            .map(|account| mock_leaf_v2_with(HashAlgo::Sha256, 1, account, account, 1000))
+            .collect();
+        let sha256 = TreeHasher::from(HashAlgo::Sha256);
+        let (root, _) = build_tree_with(sha256, &leaves);
+
+        // The leaves at 0 and 1 are siblings, and the proof is the node above the others
+        let proof = [sha256.hash_pair(&leaves[2], &leaves[3])];
+        let selected = [leaves[0], leaves[1]];
+
+        assert!(sha256.verify_multi_proof(&selected, &proof, &[true, false], &root));
+        assert!(!verify_multi_proof(
+            &selected,
+            &proof,
+            &[true, false],
+            &root
+        ));
+    }
+
+    /// An `(account, lockup, amount)` value of a fixture tree and its proof as hex.
+    type OzStandardValue = (&'static str, &'static str, u128, &'static [&'static str]);
+
+    /// A `StandardMerkleTree` of `["string", "string", "uint256"]` values as
+    /// `@openzeppelin/merkle-tree` builds it, with leaves sorted by hash: its root, and some of
+    /// its values with their proofs. `tests/fixtures/oz/generate.mjs` prints them with the
+    /// library.
+    const OZ_STANDARD_FIXTURE: (&str, [OzStandardValue; 3]) = (
+        "1391a436834335d7788ad9a3e9d3b047bf9c707336301e4fe32fbee95f5c7d83",
+        [
+            (
+                "claimant",
+                "claimant",
+                1000,
+                &[
+                    "8e8cfeec6decb553be416fe392d33a5b2249788558c89f66df46ef910c73996e",
+                    "6578d6877e7fa76b9a29d32706d7f16f77a229072bebef63064c70801488ed06",
+                ],
+            ),
+            (
+                "claimant",
+                "lockup-contract",
+                3000,
+                &[
+                    "368d066eef19b7cd4607d6e5c968f641a39a2a813595e3757f4f24bace706c1a",
+                    "bd0a91dcb9601fd4d6dbed5c3ed28ac0e318311b61095607145caff043e94cc4",
+                    "10e96d3b879c0f96a41c43b494372308780c74ad420eafe37cf51ce59442e946",
+                ],
+            ),
+            (
+                "some-account.near",
+                "some-lockup.lockup.near",
+                1_000_000_000_000_000_000_000_000,
+                &[
+                    "0d1727830c594bd9d7070739f88904469526576d7e7c3215ada1211be20bef3d",
+                    "bd0a91dcb9601fd4d6dbed5c3ed28ac0e318311b61095607145caff043e94cc4",
+                    "10e96d3b879c0f96a41c43b494372308780c74ad420eafe37cf51ce59442e946",
+                ],
+            ),
+        ],
+    );
+
+    fn fixture_hash(hex: &str) -> CryptoHash {
+        merkle::decode_hex(hex, "Invalid fixture")
+    }
+
+    fn oz_standard_campaign_setup() -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(
+            fixture_hash(OZ_STANDARD_FIXTURE.0).into(),
+            Some(end),
+            Some(CampaignOptions {
+                leaf_encoding: LeafEncoding::OzStandard,
+                leaf_version: LeafVersion::V1,
+                ..Default::default()
+            }),
+        );
+
+        (context, contract)
+    }
+
+    #[test]
+    fn test_oz_standard_fixture_proofs() {
+        testing_env!(basic_context());
+        let root = fixture_hash(OZ_STANDARD_FIXTURE.0);
+
+        for (account, lockup, amount, proof) in OZ_STANDARD_FIXTURE.1 {
+            let leaf = LeafHashing::Double
+                .hash_leaf(&merkle::oz_standard_payload(account, lockup, amount));
+            let proof: Vec<_> = proof.iter().map(|hash| fixture_hash(hash)).collect();
+
+            assert!(verify_proof(&leaf, &proof, &root));
+        }
+    }
+
+    #[test]
+    fn test_claim_oz_standard_leaves() {
+        let (mut context, mut contract) = oz_standard_campaign_setup();
+        let (_, lockup, amount, proof) = OZ_STANDARD_FIXTURE.1[1];
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+
+        contract.claim(
+            json_types::U128(amount),
+            proof.iter().map(|hash| fixture_hash(hash)).collect(),
+            1,
+            Some(lockup.parse().unwrap()),
+            None,
+        );
+
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.leaf_encoding,
+            LeafEncoding::OzStandard
+        );
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(AccountId::from_str(lockup).unwrap(), 3000)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_oz_standard_wrong_amount_failure() {
+        let (mut context, mut contract) = oz_standard_campaign_setup();
+        let (_, _, _, proof) = OZ_STANDARD_FIXTURE.1[0];
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+
+        contract.claim(
+            json_types::U128(2000),
+            proof.iter().map(|hash| fixture_hash(hash)).collect(),
+            1,
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "OpenZeppelin leaves require V1 leaves, double hashing, keccak256 and per leaf amounts"
+    )]
+    fn test_create_campaign_oz_standard_v2_leaves_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context);
+
+        let (_, root, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                leaf_encoding: LeafEncoding::OzStandard,
+                ..Default::default()
+            }),
+        );
+    }
+
+    /// Builds a directional keccak tree, returning its root and the path of every leaf. An odd
+    /// node at the end of a level is carried up unchanged.
+    fn build_directional_tree(
+        leaves: &[CryptoHash],
+    ) -> (CryptoHash, Vec<Vec<(CryptoHash, Direction)>>) {
+        build_directional_tree_with(TreeHasher::default(), leaves)
+    }
+
+    fn build_directional_tree_with(
+        hasher: TreeHasher,
+        leaves: &[CryptoHash],
+    ) -> (CryptoHash, Vec<Vec<(CryptoHash, Direction)>>) {
+        let mut paths = vec![vec![]; leaves.len()];
+        let mut positions: Vec<usize> = (0..leaves.len()).collect();
+        let mut level = leaves.to_vec();
+
+        while level.len() > 1 {
+            for (path, position) in paths.iter_mut().zip(positions.iter_mut()) {
+                if let Some(sibling) = level.get(*position ^ 1) {
+                    let direction = if *position % 2 == 0 {
+                        Direction::Right
+                    } else {
+                        Direction::Left
+                    };
+                    path.push((*sibling, direction));
+                }
+                *position /= 2;
+            }
+
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hasher.hash_ordered(left, right),
+                    [node] => *node,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+
+        (level[0], paths)
+    }
+
+    /// Creates a commutative campaign 1 and a directional campaign 2 over the same three `V1`
+    /// leaves, and returns the claimant's commutative proof and directional path.
+    fn directional_campaigns_setup() -> (MerkleClaim, Vec<CryptoHash>, Vec<(CryptoHash, Direction)>)
+    {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let mut leaves: Vec<_> = [claimant(), non_owner(), system_account()]
+            .iter()
+            .map(|account| mock_leaf(account, account, 1000, LeafHashing::Double))
+            .collect();
+        let claimant_leaf = leaves[0];
+        // Descending siblings hash differently in both trees, so neither proof is valid in the
+        // other
+        leaves.sort_unstable_by(|a, b| b.cmp(a));
+        let position = leaves
+            .iter()
+            .position(|leaf| *leaf == claimant_leaf)
+            .unwrap();
+        let (commutative_root, proofs) = build_tree(&leaves);
+        let (directional_root, paths) = build_directional_tree(&leaves);
+        assert_ne!(commutative_root, directional_root);
+
+        let (_, _, end) = build_mock_campaign();
+        for (root, proof_format) in [
+            (commutative_root, ProofFormat::Commutative),
+            (directional_root, ProofFormat::Directional),
+        ] {
+            contract.create_campaign(
+                root.into(),
+                Some(end),
+                Some(CampaignOptions {
+                    proof_format,
+                    leaf_version: LeafVersion::V1,
+                    ..Default::default()
+                }),
+            );
+        }
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+
+        (contract, proofs[position].clone(), paths[position].clone())
+    }
+
+    fn directional_options(path: &[(CryptoHash, Direction)]) -> Option<ClaimOptions> {
+        Some(ClaimOptions {
+            directions: Some(path.iter().map(|(_, direction)| *direction).collect()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_verify_directional_proof() {
+        let mut state = 0x2545_f491_4f6c_dd1d;
+
+        for _ in 0..50 {
+            testing_env!(basic_context());
+            let leaves: Vec<CryptoHash> = (0..2 + next_random(&mut state) % 20)
+                .map(|_| random_hash(&mut state))
+                .collect();
+            let (root, paths) = build_directional_tree(&leaves);
+
+            for (leaf, path) in leaves.iter().zip(&paths) {
+                assert!(verify_directional_proof(leaf, path, &root));
+
+                let mut flipped = path.clone();
+                flipped[0].1 = match flipped[0].1 {
+                    Direction::Left => Direction::Right,
+                    Direction::Right => Direction::Left,
+                };
+                assert!(!verify_directional_proof(leaf, &flipped, &root));
+            }
+        }
+    }
+
+    #[test]
+    fn test_claim_directional_campaign() {
+        let (mut contract, proof, path) = directional_campaigns_setup();
+
+        assert_eq!(
+            contract.get_campaign(2).unwrap().campaign.proof_format,
+            ProofFormat::Directional
+        );
+
+        contract.claim(json_types::U128(1000), proof, 1, None, None);
+        contract.claim(
+            json_types::U128(1000),
+            path.iter().map(|(hash, _)| *hash).collect(),
+            2,
+            None,
+            directional_options(&path),
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert!(contract.has_claimed(2, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_commutative_proof_on_directional_campaign_failure() {
+        let (mut contract, proof, path) = directional_campaigns_setup();
+
+        contract.claim(
+            json_types::U128(1000),
+            proof,
+            2,
+            None,
+            directional_options(&path),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_directional_proof_on_commutative_campaign_failure() {
+        let (mut contract, _, path) = directional_campaigns_setup();
+
+        contract.claim(
+            json_types::U128(1000),
+            path.iter().map(|(hash, _)| *hash).collect(),
+            1,
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Proof directions are required by directional campaigns")]
+    fn test_claim_directional_campaign_without_directions_failure() {
+        let (mut contract, _, path) = directional_campaigns_setup();
+
+        contract.claim(
+            json_types::U128(1000),
+            path.iter().map(|(hash, _)| *hash).collect(),
+            2,
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Proof directions are only accepted by directional campaigns")]
+    fn test_claim_commutative_campaign_with_directions_failure() {
+        let (mut contract, proof, path) = directional_campaigns_setup();
+
+        contract.claim(
+            json_types::U128(1000),
+            proof,
+            1,
+            None,
+            directional_options(&path),
+        );
+    }
+
+    /// Builds a tree with `rs_merkle::MerkleTree::<Sha256>::from_leaves`, returning its root and
+    /// the `proof_hashes()` of every leaf's proof.
+    fn build_rs_merkle_tree(leaves: &[CryptoHash]) -> (CryptoHash, Vec<Vec<CryptoHash>>) {
+        let tree = rs_merkle::MerkleTree::<rs_merkle::algorithms::Sha256>::from_leaves(leaves);
+        let proofs = (0..leaves.len())
+            .map(|position| tree.proof(&[position]).proof_hashes().to_vec())
+            .collect();
+
+        (tree.root().unwrap(), proofs)
+    }
+
+    /// Creates an `RsMerkle` campaign over five `V1` leaves, the claimant's last so it is carried
+    /// up twice, and returns the claimant's proof.
+    fn rs_merkle_campaign_setup() -> (MerkleClaim, Vec<CryptoHash>) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let leaves: Vec<_> = [
+            non_owner(),
+            system_account(),
+            account_owner(),
+            referrer(),
+            claimant(),
+        ]
+        .iter()
+        .map(|account| {
+            let data = MerkleTreeData {
+                account: account.to_string(),
+                lockup: account.to_string(),
+                amount: 1000,
+            };
+            LeafHashing::Single.hash_leaf_with(HashAlgo::Sha256, &borsh::to_vec(&data).unwrap())
+        })
+        .collect();
+        let (root, proofs) = build_rs_merkle_tree(&leaves);
+
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                hash_algo: HashAlgo::Sha256,
+                proof_format: ProofFormat::RsMerkle,
+                leaf_count: Some(5),
+                leaf_hashing: LeafHashing::Single,
+                leaf_version: LeafVersion::V1,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+
+        (contract, proofs[4].clone())
+    }
+
+    fn leaf_position_options(leaf_position: u32) -> Option<ClaimOptions> {
+        Some(ClaimOptions {
+            leaf_position: Some(leaf_position),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_verify_rs_merkle_proof() {
+        testing_env!(basic_context());
+        let leaves: Vec<_> = ["a", "b", "c", "d", "e", "f"]
+            .iter()
+            .map(|value| env::sha256_array(value.as_bytes()))
+            .collect();
+        let (root, proofs) = build_rs_merkle_tree(&leaves);
+        let sha256 = TreeHasher::from(HashAlgo::Sha256);
+
+        // The root of this tree in the rs-merkle documentation
+        assert_eq!(
+            encode_hex_hash(&root),
+            "0x1f7379539707bcaea00564168d1d4d626b09b73f8a2a365234c62d763f854da2"
+        );
+        for (position, (leaf, proof)) in leaves.iter().zip(&proofs).enumerate() {
+            let position = position as u32;
+            assert!(sha256.verify_rs_merkle_proof(leaf, position, 6, proof, &root));
+            assert!(!sha256.verify_rs_merkle_proof(leaf, position ^ 1, 6, proof, &root));
+            assert!(!sha256.verify_rs_merkle_proof(leaf, position, 6, &proof[1..], &root));
+        }
+        assert!(!sha256.verify_rs_merkle_proof(&leaves[0], 6, 6, &proofs[0], &root));
+    }
+
+    #[test]
+    fn test_rs_merkle_trees_match_directional_trees() {
+        testing_env!(basic_context());
+        let sha256 = TreeHasher::from(HashAlgo::Sha256);
+
+        for leaf_count in 1..=33u32 {
+            let leaves: Vec<_> = (0..leaf_count)
+                .map(|i| env::sha256_array(&i.to_le_bytes()))
+                .collect();
+            let (root, proofs) = build_rs_merkle_tree(&leaves);
+            let (directional_root, paths) = build_directional_tree_with(sha256, &leaves);
+
+            // rs-merkle carries odd nodes up like directional trees, with the sides implied
+            assert_eq!(root, directional_root, "{leaf_count} leaves");
+            for (position, (leaf, proof)) in leaves.iter().zip(&proofs).enumerate() {
+                let hashes: Vec<_> = paths[position].iter().map(|(hash, _)| *hash).collect();
+                assert_eq!(*proof, hashes);
+                assert!(sha256.verify_rs_merkle_proof(
+                    leaf,
+                    position as u32,
+                    leaf_count,
+                    proof,
+                    &root
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_claim_rs_merkle_campaign() {
+        let (mut contract, proof) = rs_merkle_campaign_setup();
+
+        // The claimant's leaf is carried up past two levels, so the proof has a single hash
+        assert_eq!(proof.len(), 1);
+        assert_eq!(
+            contract.check_claim(
+                claimant(),
+                json_types::U128(1000),
+                proof.clone(),
+                1,
+                None,
+                leaf_position_options(4)
+            ),
+            ClaimCheck::Claimable
+        );
+
+        contract.claim(
+            json_types::U128(1000),
+            proof,
+            1,
+            None,
+            leaf_position_options(4),
+        );
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert_eq!(scheduled_transfers(), vec![(claimant(), 1000)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid Proof")]
+    fn test_claim_rs_merkle_wrong_position_failure() {
+        let (mut contract, proof) = rs_merkle_campaign_setup();
+
+        contract.claim(
+            json_types::U128(1000),
+            proof,
+            1,
+            None,
+            leaf_position_options(3),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Leaf position is required by RsMerkle campaigns")]
+    fn test_claim_rs_merkle_without_position_failure() {
+        let (mut contract, proof) = rs_merkle_campaign_setup();
+
+        contract.claim(json_types::U128(1000), proof, 1, None, None);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "RsMerkle campaigns require a positive leaf count, which other campaigns do not accept"
+    )]
+    fn test_create_rs_merkle_campaign_without_leaf_count_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context);
+
+        let (_, root, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                proof_format: ProofFormat::RsMerkle,
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn test_compute_leaf_known_vectors() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context);
+
+        let (_, root, end) = build_mock_campaign();
+        for options in [
+            CampaignOptions::default(),
+            CampaignOptions {
+                leaf_hashing: LeafHashing::Single,
+                leaf_version: LeafVersion::V1,
+                ..Default::default()
+            },
+            CampaignOptions {
+                leaf_encoding: LeafEncoding::OzStandard,
+                leaf_version: LeafVersion::V1,
+                ..Default::default()
+            },
+        ] {
+            contract.create_campaign(root.into(), Some(end), Some(options));
+        }
+
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        // keccak256(keccak256(borsh((1u32, "claimant", "lockup-contract", 1000u128))))
+        let leaf = contract.compute_leaf(
+            claimant(),
+            json_types::U128(1000),
+            1,
+            Some(lockup.clone()),
+            None,
+        );
+        assert_eq!(
+            leaf.leaf_hex,
+            "0xb989009bab184e24c7775adb42121f5cb0d4f0ce6bbf7673bda3bd7f961c256b"
+        );
+        assert_eq!(
+            leaf.leaf_b58,
+            Base58CryptoHash::from(mock_leaf_v2(1, &claimant(), &lockup, 1000))
+        );
+
+        // keccak256(borsh(("claimant", "claimant", 10^24 as u128)))
+        let leaf =
+            contract.compute_leaf(claimant(), json_types::U128(10u128.pow(24)), 2, None, None);
+        assert_eq!(
+            leaf.leaf_hex,
+            "0x66a9cc253cfa4a89f8f3722cf8f6de610b415c3b4123963fe68466ded3ccca48"
+        );
+
+        // keccak256(keccak256(abi.encode("claimant", "claimant", 1000)))
+        let leaf = contract.compute_leaf(claimant(), json_types::U128(1000), 3, None, None);
+        assert_eq!(
+            leaf.leaf_hex,
+            "0x425e70d4069dba57316819117f43d557a6902b76bccf695cd1829fb9cade898f"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Index is required by bitmap campaigns")]
+    fn test_compute_leaf_bitmap_without_index_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context);
+
+        let (_, root, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                claim_tracking: ClaimTracking::Bitmap,
+                ..Default::default()
+            }),
+        );
+
+        contract.compute_leaf(claimant(), json_types::U128(1000), 1, None, None);
+    }
+
+    fn prefixed_hasher() -> TreeHasher {
+        TreeHasher {
+            hash_algo: HashAlgo::Keccak256,
+            leaf_hashing: LeafHashing::Prefixed,
+        }
+    }
+
+    #[test]
+    fn test_prefixed_hashing_format() {
+        testing_env!(basic_context());
+        let hasher = prefixed_hasher();
+        let payload = b"payload";
+        let (a, b) = ([1u8; 32], [2u8; 32]);
+
+        assert_eq!(
+            hasher.hash_leaf(payload),
+            env::keccak256_array(&[&[0x00], payload.as_slice()].concat())
+        );
+        assert_eq!(
+            hasher.hash_pair(&b, &a),
+            env::keccak256_array(&[&[0x01], a.as_slice(), b.as_slice()].concat())
+        );
+        assert_eq!(
+            hasher.hash_ordered(&b, &a),
+            env::keccak256_array(&[&[0x01], b.as_slice(), a.as_slice()].concat())
+        );
+    }
+
+    #[test]
+    fn test_prefixed_leaf_cannot_be_internal_node() {
+        testing_env!(basic_context());
+        let hasher = prefixed_hasher();
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+        let leaves: Vec<_> = [claimant(), non_owner(), system_account(), account_owner()]
+            .iter()
+            .map(|account| {
+                let data = MerkleTreeDataV2 {
+                    campaign_id: 1,
+                    account: account.to_string(),
+                    lockup: lockup.to_string(),
+                    amount: 1000,
+                };
+                hasher.hash_leaf(&borsh::to_vec(&data).unwrap())
+            })
+            .collect();
+        let (root, _) = build_tree_with(hasher, &leaves);
+        let right = hasher.hash_pair(&leaves[2], &leaves[3]);
+        assert!(hasher.verify_proof(&hasher.hash_pair(&leaves[0], &leaves[1]), &[right], &root));
+
+        // Present the internal node above the first two leaves as a leaf, with and without the
+        // node prefix in the payload
+        let preimage = merkle::commutative_preimage(&leaves[0], &leaves[1]);
+        for forged_payload in [preimage.to_vec(), [&[0x01], preimage.as_slice()].concat()] {
+            let forged_leaf = hasher.hash_leaf(&forged_payload);
+            assert!(!hasher.verify_proof(&forged_leaf, &[right], &root));
+        }
+    }
+
+    #[test]
+    fn test_claim_prefixed_campaign() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let hasher = prefixed_hasher();
+        let leaves: Vec<_> = [claimant(), non_owner(), system_account()]
+            .iter()
+            .map(|account| {
+                let data = MerkleTreeDataV2 {
+                    campaign_id: 1,
+                    account: account.to_string(),
+                    lockup: account.to_string(),
+                    amount: 1000,
+                };
+                hasher.hash_leaf(&borsh::to_vec(&data).unwrap())
+            })
+            .collect();
+        let (root, proofs) = build_tree_with(hasher, &leaves);
+
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                leaf_hashing: LeafHashing::Prefixed,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+
+        assert_eq!(
+            contract
+                .compute_leaf(claimant(), json_types::U128(1000), 1, None, None)
+                .leaf_hex,
+            encode_hex_hash(&leaves[0])
+        );
+
+        contract.claim(json_types::U128(1000), proofs[0].clone(), 1, None, None);
+
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    /// Generates `V1` payloads for trees of 1 to 512 leaves, always including the edge sizes 1,
+    /// 2, 3 and 512, about a quarter of them copies of earlier ones.
+    fn tree_data_strategy() -> impl Strategy<Value = Vec<MerkleTreeData>> {
+        let entry = (
+            0..1000u32,
+            0..1000u32,
+            any::<u128>(),
+            prop::option::weighted(0.25, any::<prop::sample::Index>()),
+        );
+        let count = prop_oneof![Just(1usize), Just(2), Just(3), Just(512), 1..=512usize];
+
+        count
+            .prop_flat_map(move |count| prop::collection::vec(entry.clone(), count))
+            .prop_map(|entries| {
+                let mut data: Vec<MerkleTreeData> = Vec::with_capacity(entries.len());
+                for (account, lockup, amount, copy) in entries {
+                    let entry = match copy {
+                        Some(index) if !data.is_empty() => data[index.index(data.len())].clone(),
+                        _ => MerkleTreeData {
+                            account: format!("account-{account}.near"),
+                            lockup: format!("lockup-{lockup}.lockup.near"),
+                            amount,
+                        },
+                    };
+                    data.push(entry);
+                }
+                data
+            })
+    }
+
+    proptest! {
+        // Failing cases are saved to `proptest-regressions/lib.txt`, which is checked in so they
+        // are replayed before the random ones
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_reference_tree_proofs(
+            data in tree_data_strategy(),
+            hash_algo in prop::sample::select(vec![HashAlgo::Keccak256, HashAlgo::Sha256]),
+            leaf_hashing in prop::sample::select(vec![
+                LeafHashing::Single,
+                LeafHashing::Double,
+                LeafHashing::Prefixed,
+            ]),
+            tampered in prop::collection::vec(
+                (
+                    any::<prop::sample::Index>(),
+                    any::<[u8; 32]>(),
+                    any::<prop::sample::Index>(),
+                    0..32usize,
+                ),
+                8,
+            ),
+        ) {
+            // Every tree starts with fresh gas for its hashes
+            testing_env!(basic_context());
+            let hasher = TreeHasher {
+                hash_algo,
+                leaf_hashing,
+            };
+            let tree = MerkleTree::build(
+                data,
+                LeafFormat {
+                    leaf_version: LeafVersion::V1,
+                    hash_algo,
+                    leaf_hashing,
+                    ..Default::default()
+                },
+            );
+            let root = tree.root();
+
+            for (i, leaf) in tree.leaves().iter().enumerate() {
+                prop_assert!(hasher.verify_proof(leaf, &tree.proof(i), &root));
+            }
+
+            for (leaf_index, other, proof_index, byte) in tampered {
+                let i = leaf_index.index(tree.leaves().len());
+                let leaf = tree.leaves()[i];
+                let proof = tree.proof(i);
+
+                prop_assert!(!hasher.verify_proof(&other, &proof, &root));
+                prop_assert!(!hasher.verify_proof(
+                    &leaf,
+                    &[proof.clone(), vec![other]].concat(),
+                    &root
+                ));
+                if let Some(last) = proof.len().checked_sub(1) {
+                    let mut flipped = proof.clone();
+                    flipped[proof_index.index(proof.len())][byte] ^= 1;
+
+                    prop_assert!(!hasher.verify_proof(&leaf, &flipped, &root));
+                    prop_assert!(!hasher.verify_proof(&leaf, &proof[..last], &root));
+                }
+            }
+        }
+    }
+
+    /// A `SimpleMerkleTree` of `@openzeppelin/merkle-tree` over the `V5` leaves of campaign 1
+    /// where the claimant holds 1000 yocto with nonces 0 to 2, and `non_owner` and
+    /// `system_account` 1000 yocto with nonce 0. Its root and the output of `getMultiProof` for
+    /// some of the claimant's leaves: the nonces of the `leaves` in their order, `proof` and
+    /// `proofFlags`. `tests/fixtures/oz/generate.mjs` prints them with the library.
+    const OZ_MULTI_PROOF_ROOT: &str =
+        "ff5e21cb0c0f96878d51b8d52c72bcf0fe378a6de9995504c7a0dbf2bedb33db";
+    const OZ_MULTI_PROOFS: [(&[u32], &[&str], &[bool]); 2] = [
+        (
+            &[2, 0],
+            &[
+                "09c3064ffb2138e4b7f75e6fdc68bf795ee2f2d0c71de7f82952cb1578fcee0e",
+                "46a2c3041f401ad468db7a6313e4c23b504191c8c1a03d2bc9166ce4a2ea170a",
+            ],
+            &[false, true, false],
+        ),
+        (
+            &[1, 2, 0],
+            &["46a2c3041f401ad468db7a6313e4c23b504191c8c1a03d2bc9166ce4a2ea170a"],
+            &[true, true, false],
+        ),
+    ];
+
+    /// Claims the allocations of an `OZ_MULTI_PROOFS` fixture with `claim_multi`.
+    fn claim_oz_multi_proof_fixture(fixture: usize) {
+        let (mut context, mut contract) = claims_contract_setup();
+        let (nonces, proof, proof_flags) = OZ_MULTI_PROOFS[fixture];
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(
+            fixture_hash(OZ_MULTI_PROOF_ROOT).into(),
+            Some(end),
+            Some(CampaignOptions {
+                leaf_version: LeafVersion::V5,
+                ..Default::default()
+            }),
+        );
+        let campaign_id = contract.get_last_campaign_id();
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+
+        // The library lays the five leaves out as a complete binary tree, which carries no odd
+        // node up
+        contract.claim_multi(
+            campaign_id,
+            nonces
+                .iter()
+                .map(|nonce| nonce_allocation(*nonce))
+                .collect(),
+            proof.iter().map(|hash| fixture_hash(hash)).collect(),
+            proof_flags.to_vec(),
+        );
+
+        for nonce in 0..3 {
+            assert_eq!(
+                contract.is_nonce_claimed(campaign_id, claimant(), nonce),
+                nonces.contains(&nonce)
+            );
+        }
+    }
+
+    #[test]
+    fn test_claim_multi_oz_multi_proof_fixture() {
+        claim_oz_multi_proof_fixture(0);
+    }
+
+    #[test]
+    fn test_claim_multi_oz_multi_proof_fixture_all_leaves() {
+        claim_oz_multi_proof_fixture(1);
+    }
+
+    #[test]
+    fn test_multi_proof_random_flags_fail_without_panicking() {
+        let mut state = 0x6a09_e667_f3bc_c908;
+
+        for _ in 0..500 {
+            testing_env!(basic_context());
+            let leaves: Vec<_> = (0..1 + next_random(&mut state) % 5)
+                .map(|_| random_hash(&mut state))
+                .collect();
+            let proof: Vec<_> = (0..next_random(&mut state) % 5)
+                .map(|_| random_hash(&mut state))
+                .collect();
+            // Mostly flags of the right length, which only fail on the hashes they consume
+            let flag_count = match next_random(&mut state) % 4 {
+                0 => next_random(&mut state) as usize % 10,
+                _ => leaves.len() + proof.len() - 1,
+            };
+            let proof_flags: Vec<_> = (0..flag_count)
+                .map(|_| next_random(&mut state) % 2 == 0)
+                .collect();
+            let root = random_hash(&mut state);
+
+            assert!(!verify_multi_proof(&leaves, &proof, &proof_flags, &root));
+        }
+    }
+
+    /// `JsonCanonical` leaves computed by a Python pipeline, as
+    /// `keccak256(keccak256(json.dumps(fields, separators=(",", ":"))))` with the fields in
+    /// declaration order and integers above u32 as decimal strings.
+    const JSON_CANONICAL_FIXTURES: [(&str, &str); 2] = [
+        (
+            r#"{"campaign_id":1,"account":"claimant","lockup":"lockup-contract","amount":"1000"}"#,
+            "0xd087d6c0147b93a1a87cd214395aa6b9206dc8f817261b57b54e1dedad451bf6",
+        ),
+        (
+            r#"{"campaign_id":2,"account":"claimant","lockup":"claimant","amount":"340282366920938463463374607431768211455","nonce":7}"#,
+            "0x9590d2b15dbac0bf58c804c17baf771c2a80156278d7a3823a26a7c3cc9cfe77",
+        ),
+    ];
+
+    fn json_canonical_campaign_setup() -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, root, end) = build_mock_campaign();
+        for leaf_version in [LeafVersion::V2, LeafVersion::V5] {
+            contract.create_campaign(
+                root.into(),
+                Some(end),
+                Some(CampaignOptions {
+                    leaf_encoding: LeafEncoding::JsonCanonical,
+                    leaf_version,
+                    ..Default::default()
+                }),
+            );
+        }
+
+        (context, contract)
+    }
+
+    #[test]
+    fn test_compute_leaf_json_canonical_fixtures() {
+        let (_, contract) = json_canonical_campaign_setup();
+        let lockup = AccountId::from_str("lockup-contract").unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&MerkleTreeDataV2 {
+                campaign_id: 1,
+                account: claimant().to_string(),
+                lockup: lockup.to_string(),
+                amount: 1000,
+            })
+            .unwrap(),
+            JSON_CANONICAL_FIXTURES[0].0
+        );
+        assert_eq!(
+            contract
+                .compute_leaf(claimant(), json_types::U128(1000), 1, Some(lockup), None)
+                .leaf_hex,
+            JSON_CANONICAL_FIXTURES[0].1
+        );
+
+        assert_eq!(
+            serde_json::to_string(&MerkleTreeDataV5 {
+                campaign_id: 2,
+                account: claimant().to_string(),
+                lockup: claimant().to_string(),
+                amount: u128::MAX,
+                nonce: 7,
+            })
+            .unwrap(),
+            JSON_CANONICAL_FIXTURES[1].0
+        );
+        assert_eq!(
+            contract
+                .compute_leaf(
+                    claimant(),
+                    json_types::U128(u128::MAX),
+                    2,
+                    None,
+                    Some(ClaimOptions {
+                        nonce: Some(7),
+                        ..Default::default()
+                    }),
+                )
+                .leaf_hex,
+            JSON_CANONICAL_FIXTURES[1].1
+        );
+    }
+
+    #[test]
+    fn test_claim_json_canonical_leaf() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let mut state = 0x3c6e_f372_fe94_f82b;
+
+        let leaf = fixture_hash(&JSON_CANONICAL_FIXTURES[0].1[2..]);
+        let mut leaves = vec![leaf, random_hash(&mut state), random_hash(&mut state)];
+        leaves.sort();
+        let (root, proofs) = build_tree(&leaves);
+        let position = leaves.iter().position(|other| *other == leaf).unwrap();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let (_, _, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                leaf_encoding: LeafEncoding::JsonCanonical,
+                ..Default::default()
+            }),
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+
+        contract.claim(
+            json_types::U128(1000),
+            proofs[position].clone(),
+            1,
+            Some(AccountId::from_str("lockup-contract").unwrap()),
+            None,
+        );
+
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(AccountId::from_str("lockup-contract").unwrap(), 1000)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "JSON leaves do not support V6 and V7 leaves")]
+    fn test_create_campaign_json_canonical_v6_leaves_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context);
+
+        let (_, root, end) = build_mock_campaign();
+        contract.create_campaign(
+            root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                leaf_encoding: LeafEncoding::JsonCanonical,
+                leaf_version: LeafVersion::V6,
+                ..Default::default()
+            }),
+        );
+    }
+
+    fn tree_builder_entries() -> Vec<MerkleTreeData> {
+        (0..5)
+            .map(|i| MerkleTreeData {
+                account: format!("claimant-{i}"),
+                lockup: if i % 2 == 0 {
+                    format!("claimant-{i}")
+                } else {
+                    format!("lockup-{i}")
+                },
+                amount: 1000 + i as u128,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tree_builder_round_trip() {
+        let (context, mut contract) = claims_contract_setup();
+
+        for (campaign_id, options) in (1..).zip([
+            CampaignOptions::default(),
+            CampaignOptions {
+                leaf_version: LeafVersion::V3,
+                hash_algo: HashAlgo::Sha256,
+                leaf_hashing: LeafHashing::Prefixed,
+                ..Default::default()
+            },
+            CampaignOptions {
+                leaf_version: LeafVersion::V1,
+                leaf_encoding: LeafEncoding::OzStandard,
+                ..Default::default()
+            },
+        ]) {
+            let tree = MerkleTree::build(
+                tree_builder_entries(),
+                LeafFormat {
+                    campaign_id,
+                    allocation_mode: options.allocation_mode,
+                    leaf_version: options.leaf_version,
+                    leaf_encoding: options.leaf_encoding,
+                    hash_algo: options.hash_algo,
+                    leaf_hashing: options.leaf_hashing,
+                },
+            );
+
+            let mut owner_context = context.clone();
+            owner_context.predecessor_account_id = account_owner();
+            testing_env!(owner_context);
+
+            let (_, _, end) = build_mock_campaign();
+            contract.create_campaign(tree.root().into(), Some(end), Some(options.clone()));
+
+            for entry in tree_builder_entries() {
+                let account = AccountId::from_str(&entry.account).unwrap();
+                let lockup = AccountId::from_str(&entry.lockup).unwrap();
+                // V3 leaves take the lockup of an entry as their receiver
+                let (lockup_contract, options) = match options.leaf_version {
+                    LeafVersion::V3 => (
+                        None,
+                        Some(ClaimOptions {
+                            receiver_id: Some(lockup),
+                            ..Default::default()
+                        }),
+                    ),
+                    _ => ((lockup != account).then_some(lockup), None),
+                };
+                let proof = tree.proof_for(&entry.account, &entry.lockup).unwrap();
+
+                let mut context = context.clone();
+                context.predecessor_account_id = account.clone();
+                testing_env!(context);
+
+                assert_eq!(
+                    contract.check_claim(
+                        account.clone(),
+                        json_types::U128(entry.amount),
+                        proof.clone(),
+                        campaign_id,
+                        lockup_contract.clone(),
+                        options.clone(),
+                    ),
+                    ClaimCheck::Claimable
+                );
+                contract.claim(
+                    json_types::U128(entry.amount),
+                    proof,
+                    campaign_id,
+                    lockup_contract,
+                    options,
+                );
+                assert!(contract.has_claimed(campaign_id, account));
+            }
+        }
+    }
+
+    #[test]
+    fn test_tree_builder_json_export() {
+        testing_env!(basic_context());
+        let tree = MerkleTree::build(tree_builder_entries(), LeafFormat::default());
+        let export: serde_json::Value = serde_json::from_str(&tree.to_json()).unwrap();
+
+        assert_eq!(export["root"], encode_hex_hash(&tree.root()));
+        assert_eq!(export["claims"].as_array().unwrap().len(), 5);
+        assert_eq!(export["claims"][1]["lockup"], "lockup-1");
+        assert_eq!(export["claims"][1]["amount"], "1001");
+        assert_eq!(
+            serde_json::from_value::<Vec<Base58CryptoHash>>(export["claims"][1]["proof"].clone())
+                .unwrap(),
+            tree.proof(1)
+                .into_iter()
+                .map(Base58CryptoHash::from)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(tree.proof_for("claimant-1", "claimant-1"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The tree builder supports V1, V2 and V3 leaves")]
+    fn test_tree_builder_v5_leaves_failure() {
+        testing_env!(basic_context());
+        MerkleTree::build(
+            tree_builder_entries(),
+            LeafFormat {
+                leaf_version: LeafVersion::V5,
+                ..Default::default()
+            },
+        );
+    }
+
+    const ALLOCATIONS_CSV: &str = include_str!("../tests/fixtures/allocations.csv");
+
+    #[test]
+    fn test_campaign_file_golden() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let file = CampaignFile::from_csv(
+            ALLOCATIONS_CSV,
+            LeafFormat {
+                campaign_id: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // Regenerate with `cargo run --features tree --bin merkle-tree -- tests/fixtures/allocations.csv
+        // tests/fixtures/allocations.json '{"campaign_id": 1}'`
+        let golden = include_str!("../tests/fixtures/allocations.json");
+        assert_eq!(file.to_json() + "\n", golden);
+
+        let golden: serde_json::Value = serde_json::from_str(golden).unwrap();
+        let args: serde_json::Value = serde_json::from_str(&file.create_campaign_args()).unwrap();
+        assert_eq!(args["merkle_root"], golden["root"]);
+        assert_eq!(args["options"]["expected_campaign_id"], 1);
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.create_campaign(
+            serde_json::from_value(args["merkle_root"].clone()).unwrap(),
+            None,
+            Some(serde_json::from_value(args["options"].clone()).unwrap()),
+        );
+
+        let claim = &golden["claims"][1];
+        context.predecessor_account_id = "bob.near".parse().unwrap();
+        testing_env!(context);
+
+        contract.claim_b58(
+            json_types::U128(2500),
+            serde_json::from_value(claim["proof"].clone()).unwrap(),
+            1,
+            Some("bob.lockup.near".parse().unwrap()),
+            None,
+        );
+        assert!(contract.has_claimed(1, "bob.near".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_parse_csv_malformed_rows() {
+        assert_eq!(parse_csv(ALLOCATIONS_CSV).unwrap().len(), 5);
+        assert_eq!(
+            parse_csv("alice.near,,1000\n\nbob.near,1000").unwrap_err(),
+            "line 3: expected account,lockup,amount but found 2 fields"
+        );
+        assert!(parse_csv("alice.near,,1000\nBob.near,,1000")
+            .unwrap_err()
+            .starts_with("line 2: invalid account id \"Bob.near\""));
+        assert!(parse_csv("alice.near,lockup..near,1000")
+            .unwrap_err()
+            .starts_with("line 1: invalid account id \"lockup..near\""));
+        assert_eq!(
+            parse_csv("account,lockup,amount\nalice.near,,-1").unwrap_err(),
+            "line 2: invalid amount \"-1\""
+        );
+        assert_eq!(
+            parse_csv("alice.near,,1.5").unwrap_err(),
+            "line 1: invalid amount \"1.5\""
+        );
+        assert_eq!(
+            CampaignFile::from_csv("account,lockup,amount\n", LeafFormat::default())
+                .err()
+                .unwrap(),
+            "The CSV holds no allocations"
+        );
+    }
+
+    fn fixture_export(name: &str) -> serde_json::Value {
+        let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_validate_export_fixtures() {
+        testing_env!(basic_context());
+
+        // A `StandardMerkleTree` dump of the allocations of `allocations.csv`, as
+        // `tests/fixtures/oz/generate.mjs` writes it
+        let report = validate_export(&fixture_export("oz-tree.json")).unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.entry_count, 5);
+        assert_eq!(
+            encode_hex_hash(&report.root),
+            "0x74e02ff9063d9ad615fc08c786ca2a02a61391bdc6879fa7ca1812cc0d002096"
+        );
+
+        let report = validate_export_file(format!(
+            "{}/tests/fixtures/allocations.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.entry_count, 5);
+    }
+
+    #[test]
+    fn test_validate_export_corrupted_fixtures() {
+        testing_env!(basic_context());
+
+        // The amount of dave.near was changed after the tree was built
+        let report = validate_export(&fixture_export("oz-tree-corrupted.json")).unwrap();
+        assert_eq!(
+            report.mismatches,
+            vec![EntryMismatch {
+                index: 3,
+                account: "dave.near".to_string(),
+                error: EntryError::LeafMismatch,
+            }]
+        );
+
+        // The first proof hash of bob.near was replaced by alice.near's
+        let report = validate_export(&fixture_export("allocations-corrupted.json")).unwrap();
+        assert_eq!(
+            report.mismatches,
+            vec![EntryMismatch {
+                index: 1,
+                account: "bob.near".to_string(),
+                error: EntryError::InvalidProof,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_export_malformed() {
+        testing_env!(basic_context());
+
+        let mut export = fixture_export("allocations.json");
+        export["claims"][2]["account"] = "Carol.near".into();
+        export["claims"][4]["amount"] = "-7".into();
+        let report = validate_export(&export).unwrap();
+        assert_eq!(report.mismatches.len(), 2);
+        assert!(matches!(
+            &report.mismatches[0],
+            EntryMismatch { index: 2, error: EntryError::Malformed(error), .. }
+                if error.starts_with("Invalid account id \"Carol.near\"")
+        ));
+        assert_eq!(
+            report.mismatches[1].error,
+            EntryError::Malformed("Invalid amount \"-7\"".to_string())
+        );
+
+        assert_eq!(
+            validate_export(&serde_json::json!({ "root": "0x00" })).unwrap_err(),
+            "Unknown tree export format"
+        );
+        let mut export = fixture_export("oz-tree.json");
+        export["tree"][0] = "0x1234".into();
+        assert!(validate_export(&export)
+            .unwrap_err()
+            .starts_with("Invalid hash \"0x1234\""));
+    }
+
+    #[test]
+    fn test_wasm_client_compute_leaf() {
+        testing_env!(basic_context());
+
+        // The leaves pinned by `test_compute_leaf_known_vectors` and the JSON fixtures
+        assert_eq!(
+            wasm_client::compute_leaf(
+                r#"{"campaign_id": 1}"#,
+                "claimant",
+                "lockup-contract",
+                "1000",
+                None
+            )
+            .unwrap(),
+            "0xb989009bab184e24c7775adb42121f5cb0d4f0ce6bbf7673bda3bd7f961c256b"
+        );
+        assert_eq!(
+            wasm_client::compute_leaf(
+                r#"{"campaign_id": 2, "leaf_version": "V5", "leaf_encoding": "JsonCanonical"}"#,
+                "claimant",
+                "claimant",
+                &u128::MAX.to_string(),
+                Some(r#"{"nonce": 7}"#.to_string())
+            )
+            .unwrap(),
+            JSON_CANONICAL_FIXTURES[1].1
+        );
+
+        assert_eq!(
+            wasm_client::compute_leaf(
+                r#"{"leaf_version": "V5"}"#,
+                "claimant",
+                "claimant",
+                "1",
+                None
+            )
+            .unwrap_err(),
+            "Nonce is required by V5 leaves"
+        );
+        assert_eq!(
+            wasm_client::compute_leaf("{}", "claimant", "claimant", "1e3", None).unwrap_err(),
+            "Invalid amount \"1e3\""
+        );
+        assert!(
+            wasm_client::compute_leaf("{}", "Claimant", "claimant", "1", None)
+                .unwrap_err()
+                .starts_with("Invalid account id \"Claimant\"")
+        );
+    }
+
+    #[test]
+    fn test_wasm_client_verify_proof() {
+        testing_env!(basic_context());
+        let mut state = 0x510e_527f_ade6_82d1;
+
+        for format in [
+            None,
+            Some(r#"{"hash_algo": "Sha256", "leaf_hashing": "Prefixed"}"#),
+        ] {
+            let hasher = match format {
+                Some(_) => TreeHasher {
+                    hash_algo: HashAlgo::Sha256,
+                    leaf_hashing: LeafHashing::Prefixed,
+                },
+                None => TreeHasher::default(),
+            };
+            let leaves: Vec<CryptoHash> = (0..5).map(|_| random_hash(&mut state)).collect();
+            let tree = MerkleTree::new(hasher, leaves.clone());
+            let format = format.map(str::to_string);
+
+            let root = encode_hex_hash(&tree.root());
+            let leaf = encode_hex_hash(&leaves[3]);
+            // Proofs may mix hex and base58 hashes
+            let proof: Vec<String> = tree
+                .proof(3)
+                .iter()
+                .enumerate()
+                .map(|(i, hash)| match i % 2 {
+                    0 => encode_hex_hash(hash),
+                    _ => String::from(&Base58CryptoHash::from(*hash)),
+                })
+                .collect();
+
+            assert!(
+                wasm_client::verify_proof(&leaf, proof.clone(), &root, format.clone()).unwrap()
+            );
+            assert!(!wasm_client::verify_proof(
+                &encode_hex_hash(&leaves[2]),
+                proof.clone(),
+                &root,
+                format.clone()
+            )
+            .unwrap());
+            assert_eq!(
+                wasm_client::verify_proof(&leaf, vec!["0x12".to_string()], &root, format)
+                    .unwrap_err(),
+                "Invalid hex hash \"0x12\""
+            );
+        }
+    }
+
+    #[test]
+    fn test_propose_and_accept_ownership() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.propose_owner(non_owner());
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["owner_account_id"], account_owner().to_string());
+        assert_eq!(event["pending_owner"], non_owner().to_string());
+        assert_eq!(contract.get_pending_owner(), Some(non_owner()));
+        assert_eq!(contract.get_config().owner_account_id, account_owner());
+
+        context.predecessor_account_id = non_owner();
+        testing_env!(context.clone());
+
+        contract.accept_ownership();
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["previous_owner"], account_owner().to_string());
+        assert_eq!(event["owner_account_id"], non_owner().to_string());
+        assert_eq!(contract.get_pending_owner(), None);
+        assert_eq!(contract.get_config().owner_account_id, non_owner());
+
+        // The new owner can use owner methods
+        contract.pause();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the proposed owner can accept ownership")]
+    fn test_accept_ownership_wrong_account_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.propose_owner(non_owner());
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the proposed owner can accept ownership")]
+    fn test_propose_owner_overwrites_pending_proposal() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.propose_owner(non_owner());
+        contract.propose_owner(claimant());
+        assert_eq!(contract.get_pending_owner(), Some(claimant()));
+
+        context.predecessor_account_id = non_owner();
+        testing_env!(context);
+
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_propose_owner_non_owner_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = non_owner();
+        testing_env!(context);
+
+        contract.propose_owner(non_owner());
+    }
+
+    /// Transfers ownership from the current owner `from` to `to` at `timestamp`.
+    fn transfer_ownership(
+        context: &mut VMContext,
+        contract: &mut MerkleClaim,
+        from: AccountId,
+        to: AccountId,
+        timestamp: u64,
+    ) {
+        context.block_timestamp = timestamp;
+        set_caller(context, from);
+        contract.propose_owner(to.clone());
+        set_caller(context, to);
+        contract.accept_ownership();
+    }
+
+    #[test]
+    fn test_owner_history() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let initialized_at = context.block_timestamp;
+
+        assert_eq!(contract.get_owner(), account_owner());
+        transfer_ownership(
+            &mut context,
+            &mut contract,
+            account_owner(),
+            non_owner(),
+            initialized_at + 10,
+        );
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["previous_owner"], account_owner().to_string());
+        assert_eq!(event["owner_account_id"], non_owner().to_string());
+
+        transfer_ownership(
+            &mut context,
+            &mut contract,
+            non_owner(),
+            claimant(),
+            initialized_at + 20,
+        );
+        assert_eq!(contract.get_owner(), claimant());
+
+        let history: Vec<(AccountId, u64)> = contract
+            .get_owner_history()
+            .iter()
+            .map(|change| (change.account_id.clone(), change.timestamp.0))
+            .collect();
+        assert_eq!(
+            history,
+            vec![
+                (account_owner(), initialized_at),
+                (non_owner(), initialized_at + 10),
+                (claimant(), initialized_at + 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_owner_history_is_capped() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let owners = [non_owner(), claimant()];
+
+        for i in 0..OWNER_HISTORY_CAPACITY {
+            let from = contract.get_owner();
+            let to = owners[i % 2].clone();
+            transfer_ownership(&mut context, &mut contract, from, to, i as u64);
+        }
+
+        let history = contract.get_owner_history();
+        assert_eq!(history.len(), OWNER_HISTORY_CAPACITY);
+        // The initial owner was dropped
+        assert_eq!(history[0].account_id, non_owner());
+        assert_eq!(history[0].timestamp.0, 0);
+        assert_eq!(history.last().unwrap().account_id, contract.get_owner());
+    }
+
+    #[test]
+    fn test_propose_owner_keeps_owner_history() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.propose_owner(non_owner());
+
+        assert_eq!(contract.get_owner(), account_owner());
+        assert_eq!(contract.get_owner_history().len(), 1);
+    }
+
+    fn config_patch(patch: &str) -> ConfigPatch {
+        serde_json::from_str(patch).unwrap()
+    }
+
+    #[test]
+    fn test_update_config_partial_patches() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.update_config(config_patch(
+            r#"{"max_proof_length": 16, "claim_fee_bps": 250, "fee_receiver": "fees.near"}"#,
+        ));
+
+        let config = contract.get_config();
+        assert_eq!(config.max_proof_length, 16);
+        assert_eq!(config.claim_fee_bps, 250);
+        assert_eq!(config.fee_receiver, Some("fees.near".parse().unwrap()));
+        assert_eq!(config.min_storage_deposit, MIN_STORAGE_DEPOSIT);
+        assert_eq!(config.owner_account_id, account_owner());
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(
+            event["changed_fields"],
+            serde_json::json!(["max_proof_length", "claim_fee_bps", "fee_receiver"])
+        );
+        assert_eq!(event["config"]["claim_fee_bps"], 250);
+
+        testing_env!(context);
+
+        // `null` unsets a field, unchanged values are not reported
+        contract.update_config(config_patch(
+            r#"{"fee_receiver": null, "claim_fee_bps": 250, "allowed_lockup_suffix": ".lockup.near"}"#,
+        ));
+
+        let config = contract.get_config();
+        assert_eq!(config.fee_receiver, None);
+        assert_eq!(config.claim_fee_bps, 250);
+        assert_eq!(config.max_proof_length, 16);
+        assert_eq!(config.allowed_lockup_suffix.as_deref(), Some("lockup.near"));
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(
+            event["changed_fields"],
+            serde_json::json!(["allowed_lockup_suffix", "fee_receiver"])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim fee must not exceed 1000 bps")]
+    fn test_update_config_fee_above_cap_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context);
+
+        contract.update_config(config_patch(r#"{"claim_fee_bps": 1001}"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Minimum storage deposit must not exceed the contract balance")]
+    fn test_update_config_storage_deposit_above_balance_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context);
+
+        let balance = env::account_balance().as_yoctonear();
+        contract.update_config(ConfigPatch {
+            min_storage_deposit: Some(NearToken::from_yoctonear(balance + 1)),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Minimum claim amount must not exceed the maximum")]
+    fn test_update_config_validates_with_current_values() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context);
+
+        contract.update_config(config_patch(r#"{"max_claim_amount": "100"}"#));
+        contract.update_config(config_patch(r#"{"min_claim_amount": "101"}"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_update_config_non_owner_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = non_owner();
+        testing_env!(context);
+
+        contract.update_config(ConfigPatch::default());
+    }
+
+    fn set_paused(context: &mut VMContext, contract: &mut MerkleClaim, paused: bool) {
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        if paused {
+            contract.pause();
+        } else {
+            contract.unpause();
+        }
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["pause_flags"], if paused { 7 } else { 0 });
+        assert_eq!(event["account_id"], account_owner().to_string());
+        assert_eq!(contract.is_paused(), paused);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+    }
+
+    #[test]
+    #[should_panic(expected = "Claims are paused")]
+    fn test_partial_claim_paused_failure() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        partial_claim(&mut contract, sibling, 400);
+        set_paused(&mut context, &mut contract, true);
+
+        partial_claim(&mut contract, sibling, 600);
+    }
+
+    #[test]
+    fn test_claim_after_unpause() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        partial_claim(&mut contract, sibling, 400);
+        set_paused(&mut context, &mut contract, true);
+
+        // Views keep working while paused
+        assert_eq!(contract.get_claimed_amount(1, claimant()).0, 400);
+
+        set_paused(&mut context, &mut contract, false);
+
+        partial_claim(&mut contract, sibling, 600);
+        assert_eq!(contract.get_claimed_amount(1, claimant()).0, 1000);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Contract is not paused")]
+    fn test_unpause_unpaused_failure() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        set_paused(&mut context, &mut contract, false);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a guardian can call this method")]
+    fn test_pause_non_owner_failure() {
+        let (_, mut contract, _) = partial_claim_campaign_setup();
+
+        contract.pause();
+    }
+
+    fn guardian() -> AccountId {
+        AccountId::from_str("guardian").unwrap()
+    }
+
+    /// Makes `guardian()` a guardian of the partial claim campaign's contract, and the caller.
+    fn guardian_setup() -> (VMContext, MerkleClaim, CryptoHash) {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.add_guardian(guardian());
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["account_id"], "guardian");
+        assert_eq!(event["added"], true);
+        assert_eq!(contract.get_guardians(), vec![guardian()]);
+
+        context.predecessor_account_id = guardian();
+        testing_env!(context.clone());
+
+        (context, contract, sibling)
+    }
+
+    #[test]
+    fn test_guardian_pause() {
+        let (mut context, mut contract, sibling) = guardian_setup();
+
+        contract.pause();
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["pause_flags"], 7);
+        assert_eq!(event["account_id"], "guardian");
+        assert!(contract.is_paused());
+
+        // Only the owner can resume claims
+        set_paused(&mut context, &mut contract, false);
+        partial_claim(&mut contract, sibling, 1000);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_guardian_unpause_failure() {
+        let (_, mut contract, _) = guardian_setup();
+
+        contract.pause();
+        contract.unpause();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_guardian_withdraw_failure() {
+        let (_, mut contract, _) = guardian_setup();
+
+        contract.withdraw(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_guardian_create_campaign_failure() {
+        let (_, mut contract, _) = guardian_setup();
+        let mock_campaign = build_mock_campaign();
+
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_guardian_add_guardian_failure() {
+        let (_, mut contract, _) = guardian_setup();
+
+        contract.add_guardian(claimant());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a guardian can call this method")]
+    fn test_removed_guardian_pause_failure() {
+        let (mut context, mut contract, _) = guardian_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.remove_guardian(guardian());
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["account_id"], "guardian");
+        assert_eq!(event["added"], false);
+        assert!(contract.get_guardians().is_empty());
+
+        context.predecessor_account_id = guardian();
+        testing_env!(context.clone());
+
+        contract.pause();
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is already a guardian")]
+    fn test_add_guardian_twice_failure() {
+        let (mut context, mut contract, _) = guardian_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.add_guardian(guardian());
+    }
+
+    #[test]
+    #[should_panic(expected = "Account is not a guardian")]
+    fn test_remove_unknown_guardian_failure() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.remove_guardian(guardian());
+    }
+
+    fn set_pause_flags(context: &mut VMContext, contract: &mut MerkleClaim, flags: PauseFlags) {
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.set_pause_flags(flags);
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["pause_flags"], flags.0);
+        assert_eq!(contract.get_pause_flags(), flags);
+    }
+
+    #[test]
+    fn test_pause_claims_only() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_pause_flags(&mut context, &mut contract, PauseFlags::CLAIMS);
+        assert!(contract.is_paused());
+
+        // Campaigns can still be staged and funds withdrawn
+        let mock_campaign = build_mock_campaign();
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+        contract.withdraw(None);
+
+        set_pause_flags(&mut context, &mut contract, PauseFlags::NONE);
+        assert!(!contract.is_paused());
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+        partial_claim(&mut contract, sibling, 1000);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Claims are paused")]
+    fn test_pause_claims_only_claim_failure() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_pause_flags(&mut context, &mut contract, PauseFlags::CLAIMS);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+        partial_claim(&mut contract, sibling, 1000);
+    }
+
+    #[test]
+    fn test_pause_campaign_creation_only() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_pause_flags(&mut context, &mut contract, PauseFlags::CAMPAIGN_CREATION);
+        contract.withdraw(None);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+        partial_claim(&mut contract, sibling, 1000);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign creation is paused")]
+    fn test_pause_campaign_creation_only_create_failure() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        set_pause_flags(&mut context, &mut contract, PauseFlags::CAMPAIGN_CREATION);
+
+        let mock_campaign = build_mock_campaign();
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+    }
+
+    #[test]
+    fn test_pause_withdrawals_only() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_pause_flags(&mut context, &mut contract, PauseFlags::WITHDRAWALS);
+
+        let mock_campaign = build_mock_campaign();
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context.clone());
+        partial_claim(&mut contract, sibling, 1000);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawals are paused")]
+    fn test_pause_withdrawals_only_withdraw_failure() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        set_pause_flags(&mut context, &mut contract, PauseFlags::WITHDRAWALS);
+
+        contract.withdraw(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawals are paused")]
+    fn test_pause_withdrawals_only_sweep_failure() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        set_pause_flags(&mut context, &mut contract, PauseFlags::WITHDRAWALS);
+
+        contract.sweep_campaign(1);
+    }
+
+    #[test]
+    fn test_pause_sets_all_flags() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        set_pause_flags(&mut context, &mut contract, PauseFlags::CLAIMS);
+        contract.pause();
+        assert_eq!(contract.get_pause_flags(), PauseFlags::ALL);
+
+        contract.unpause();
+        assert_eq!(contract.get_pause_flags(), PauseFlags::NONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown pause flags")]
+    fn test_set_pause_flags_unknown_failure() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.set_pause_flags(PauseFlags(8));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_set_pause_flags_guardian_failure() {
+        let (_, mut contract, _) = guardian_setup();
+
+        contract.set_pause_flags(PauseFlags::CLAIMS);
+    }
+
+    fn financial_operator() -> AccountId {
+        AccountId::from_str("treasury").unwrap()
+    }
+
+    /// Sets up a bonus campaign with `guardian()` as guardian and `financial_operator()` as
+    /// financial operator, and makes `caller` the caller.
+    fn roles_setup(caller: AccountId) -> (VMContext, MerkleClaim) {
+        let bonus = ClaimBonus {
+            bps: 1000,
+            max_claims: Some(3),
+            window_end: None,
+        };
+        let (mut context, mut contract, _, _) = bonus_campaign_setup(bonus, 150);
+
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        contract.add_guardian(guardian());
+        contract.grant_financial_operator(financial_operator());
+
+        let event: serde_json::Value = event_data(&get_logs()[1]);
+        assert_eq!(event["account_id"], "treasury");
+        assert_eq!(event["granted"], true);
+        assert!(contract.is_financial_operator(financial_operator()));
+
+        context.predecessor_account_id = caller;
+        testing_env!(context.clone());
+
+        (context, contract)
+    }
+
+    #[test]
+    fn test_financial_operator_fund_and_sweep() {
+        let (mut context, mut contract) = roles_setup(financial_operator());
+
+        context.attached_deposit = NearToken::from_yoctonear(50);
+        testing_env!(context.clone());
+        assert_eq!(contract.fund_bonus_pool(1).0, 200);
+
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        testing_env!(context.clone());
+
+        // Swept funds go to the owner, not to the operator
+        assert_eq!(contract.sweep_campaign(1).0, 200);
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 200)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_financial_operator_create_campaign_failure() {
+        let (_, mut contract) = roles_setup(financial_operator());
+        let mock_campaign = build_mock_campaign();
+
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_financial_operator_update_config_failure() {
+        let (_, mut contract) = roles_setup(financial_operator());
+
+        contract.set_max_proof_length(8);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_financial_operator_withdraw_failure() {
+        let (_, mut contract) = roles_setup(financial_operator());
+
+        contract.withdraw(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a guardian can call this method")]
+    fn test_financial_operator_pause_failure() {
+        let (_, mut contract) = roles_setup(financial_operator());
+
+        contract.pause();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a financial operator can call this method")]
+    fn test_guardian_fund_bonus_pool_failure() {
+        let (mut context, mut contract) = roles_setup(guardian());
+
+        context.attached_deposit = NearToken::from_yoctonear(50);
+        testing_env!(context.clone());
+
+        contract.fund_bonus_pool(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a financial operator can call this method")]
+    fn test_guardian_sweep_failure() {
+        let (mut context, mut contract) = roles_setup(guardian());
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        testing_env!(context.clone());
+
+        contract.sweep_campaign(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a financial operator can call this method")]
+    fn test_revoked_financial_operator_failure() {
+        let (mut context, mut contract) = roles_setup(account_owner());
+
+        contract.revoke_financial_operator(financial_operator());
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["granted"], false);
+        assert!(!contract.is_financial_operator(financial_operator()));
+
+        context.predecessor_account_id = financial_operator();
+        context.attached_deposit = NearToken::from_yoctonear(50);
+        testing_env!(context.clone());
+
+        contract.fund_referral_pool(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_financial_operator_grant_failure() {
+        let (_, mut contract) = roles_setup(financial_operator());
+
+        contract.grant_financial_operator(claimant());
+    }
+
+    #[test]
+    fn test_admin_log_records_privileged_actions() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        // Claims are not privileged actions
+        partial_claim(&mut contract, sibling, 400);
+
+        context.predecessor_account_id = account_owner();
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 1u64);
+        testing_env!(context.clone());
+
+        contract.set_max_proof_length(8);
+        contract.pause();
+        contract.unpause();
+        contract.withdraw(None);
+
+        let log = contract.get_admin_log(None, None);
+        let methods: Vec<&str> = log.iter().map(|action| action.method.as_str()).collect();
+        assert_eq!(
+            methods,
+            vec![
+                "create_campaign",
+                "set_max_proof_length",
+                "pause",
+                "unpause",
+                "withdraw"
+            ]
+        );
+        assert!(log
+            .iter()
+            .enumerate()
+            .all(|(id, action)| action.id.0 == id as u64 && action.caller == account_owner()));
+        assert_eq!(log[0].timestamp.0, to_ts(GENESIS_TIME_IN_DAYS));
+        assert_eq!(log[1].timestamp.0, to_ts(GENESIS_TIME_IN_DAYS + 1u64));
+        assert_eq!(log[1].summary, "8");
+        assert_eq!(log[2].summary, "pause flags 7");
+
+        let page = contract.get_admin_log(Some(json_types::U64(2)), Some(2));
+        assert_eq!(
+            page.iter().map(|action| action.id.0).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn test_admin_log_wraps_around() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        for length in 0..ADMIN_LOG_CAPACITY + 10 {
+            contract.set_max_proof_length(1 + length as u32 % 64);
+        }
+
+        // The 10 oldest actions were overwritten
+        let log = contract.get_admin_log(None, Some(1000));
+        assert_eq!(log.len() as u64, ADMIN_LOG_CAPACITY);
+        assert_eq!(log[0].id.0, 10);
+        assert_eq!(log.last().unwrap().id.0, ADMIN_LOG_CAPACITY + 9);
+        assert!(log.windows(2).all(|pair| pair[1].id.0 == pair[0].id.0 + 1));
+
+        let page = contract.get_admin_log(Some(json_types::U64(0)), Some(3));
+        assert_eq!(
+            page.iter().map(|action| action.id.0).collect::<Vec<_>>(),
+            vec![10, 11, 12]
+        );
+        assert!(contract
+            .get_admin_log(Some(json_types::U64(ADMIN_LOG_CAPACITY + 10)), None)
+            .is_empty());
+    }
+
+    fn signer(index: usize) -> AccountId {
+        AccountId::from_str(&format!("signer{index}")).unwrap()
+    }
+
+    /// Hands owner actions to `signers` signers confirming `threshold` of them, and makes the
+    /// first signer the caller.
+    fn multisig_setup(signers: usize, threshold: u32) -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        testing_env!(context.clone());
+
+        let multisig = Multisig {
+            signers: (0..signers).map(signer).collect(),
+            threshold,
+            proposal_lifetime: json_types::U64(DEFAULT_PROPOSAL_LIFETIME),
+        };
+        contract.enable_multisig(multisig.clone());
+
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["multisig"]["threshold"], threshold);
+        assert_eq!(contract.get_multisig(), Some(&multisig));
+
+        context.predecessor_account_id = signer(0);
+        testing_env!(context.clone());
+
+        (context, contract)
+    }
+
+    fn set_caller(context: &mut VMContext, account_id: AccountId) {
+        context.predecessor_account_id = account_id;
+        testing_env!(context.clone());
+    }
+
+    fn mock_create_campaign_action() -> OwnerAction {
+        let mock_campaign = build_mock_campaign();
+
+        OwnerAction::CreateCampaign {
+            merkle_root: mock_campaign.1.into(),
+            claim_end: Some(mock_campaign.2),
+            options: None,
+        }
+    }
+
+    #[test]
+    fn test_multisig_executes_at_threshold() {
+        let (mut context, mut contract) = multisig_setup(3, 2);
+
+        let proposal_id = contract.propose_action(mock_create_campaign_action());
+        assert_eq!(proposal_id, 1);
+
+        let proposed: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(proposed["proposer"], "signer0");
+        assert!(proposed["action"]["CreateCampaign"].is_object());
+        let confirmed: serde_json::Value = event_data(&get_logs()[1]);
+        assert_eq!(confirmed["confirmations"], 1);
+        assert_eq!(confirmed["executed"], false);
+
+        assert!(contract.get_campaign(1).is_none());
+        assert_eq!(
+            contract.get_proposal(proposal_id).unwrap().confirmations,
+            vec![signer(0)]
+        );
+
+        set_caller(&mut context, signer(2));
+        contract.confirm_action(proposal_id);
+
+        let confirmed: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(confirmed["account_id"], "signer2");
+        assert_eq!(confirmed["confirmations"], 2);
+        assert_eq!(confirmed["executed"], true);
+
+        assert!(contract.get_campaign(1).is_some());
+        assert!(contract.get_proposal(proposal_id).is_none());
+        assert_eq!(
+            contract.get_admin_log(None, None).last().unwrap().caller,
+            signer(2)
+        );
+    }
+
+    #[test]
+    fn test_multisig_cancel_and_update_config() {
+        let (mut context, mut contract) = multisig_setup(2, 2);
+
+        let create_id = contract.propose_action(mock_create_campaign_action());
+        let config_id = contract.propose_action(OwnerAction::UpdateConfig {
+            patch: config_patch(r#"{"max_proof_length": 16}"#),
+        });
+
+        set_caller(&mut context, signer(1));
+        contract.confirm_action(create_id);
+        let cancel_id = contract.propose_action(OwnerAction::CancelCampaign { campaign_id: 1 });
+        contract.confirm_action(config_id);
+        assert_eq!(contract.get_config().max_proof_length, 16);
+
+        set_caller(&mut context, signer(0));
+        contract.confirm_action(cancel_id);
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.claim_end,
+            Some(json_types::U64(context.block_timestamp))
+        );
+    }
+
+    #[test]
+    fn test_multisig_withdraw_pays_owner() {
+        let (mut context, mut contract) = multisig_setup(2, 2);
+
+        let proposal_id = contract.propose_action(OwnerAction::Withdraw);
+        assert!(scheduled_transfers().is_empty());
+
+        set_caller(&mut context, signer(1));
+        contract.confirm_action(proposal_id);
+
+        let transfers = scheduled_transfers();
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].0, account_owner());
+    }
+
+    #[test]
+    fn test_multisig_single_signer_behaves_like_owner() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.enable_multisig(Multisig {
+            signers: vec![account_owner()],
+            threshold: 1,
+            proposal_lifetime: json_types::U64(DEFAULT_PROPOSAL_LIFETIME),
+        });
+
+        let proposal_id = contract.propose_action(mock_create_campaign_action());
+
+        assert!(contract.get_campaign(1).is_some());
+        assert!(contract.get_proposal(proposal_id).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner actions must be proposed to the multisig signers")]
+    fn test_multisig_direct_owner_action_failure() {
+        let (mut context, mut contract) = multisig_setup(3, 2);
+
+        set_caller(&mut context, account_owner());
+        contract.withdraw(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner actions must be proposed to the multisig signers")]
+    fn test_multisig_direct_config_setter_failure() {
+        let (mut context, mut contract) = multisig_setup(3, 2);
+
+        set_caller(&mut context, account_owner());
+        contract.set_max_proof_length(8);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only multisig signers can call this method")]
+    fn test_multisig_non_signer_propose_failure() {
+        let (mut context, mut contract) = multisig_setup(3, 2);
+
+        set_caller(&mut context, account_owner());
+        contract.propose_action(OwnerAction::Withdraw);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_multisig_propose_without_deposit_failure() {
+        let (mut context, mut contract) = multisig_setup(3, 2);
+
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        testing_env!(context.clone());
+        contract.propose_action(OwnerAction::Withdraw);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_multisig_confirm_without_deposit_failure() {
+        let (mut context, mut contract) = multisig_setup(3, 2);
+
+        let proposal_id = contract.propose_action(OwnerAction::Withdraw);
+
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        set_caller(&mut context, signer(1));
+        contract.confirm_action(proposal_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Action already confirmed by this signer")]
+    fn test_multisig_confirm_twice_failure() {
+        let (_, mut contract) = multisig_setup(3, 2);
+
+        let proposal_id = contract.propose_action(OwnerAction::Withdraw);
+        contract.confirm_action(proposal_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposal has expired")]
+    fn test_multisig_confirm_expired_failure() {
+        let (mut context, mut contract) = multisig_setup(3, 2);
+
+        let proposal_id = contract.propose_action(OwnerAction::Withdraw);
+
+        context.block_timestamp += DEFAULT_PROPOSAL_LIFETIME + 1;
+        set_caller(&mut context, signer(1));
+        contract.confirm_action(proposal_id);
+    }
+
+    #[test]
+    fn test_multisig_remove_expired_proposal() {
+        let (mut context, mut contract) = multisig_setup(3, 2);
+
+        let proposal_id = contract.propose_action(OwnerAction::Withdraw);
+
+        context.block_timestamp += DEFAULT_PROPOSAL_LIFETIME + 1;
+        set_caller(&mut context, claimant());
+        contract.remove_expired_proposal(proposal_id);
+
+        assert!(contract.get_proposal(proposal_id).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposal has not expired yet")]
+    fn test_multisig_remove_pending_proposal_failure() {
+        let (_, mut contract) = multisig_setup(3, 2);
+
+        let proposal_id = contract.propose_action(OwnerAction::Withdraw);
+        contract.remove_expired_proposal(proposal_id);
+    }
+
+    #[test]
+    fn test_multisig_disable_restores_owner() {
+        let (mut context, mut contract) = multisig_setup(2, 2);
+
+        let proposal_id = contract.propose_action(OwnerAction::SetMultisig { multisig: None });
+        set_caller(&mut context, signer(1));
+        contract.confirm_action(proposal_id);
+        assert_eq!(contract.get_multisig(), None);
+
+        set_caller(&mut context, account_owner());
+        contract.set_max_proof_length(8);
+        assert_eq!(contract.get_config().max_proof_length, 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "Multisig threshold must be between 1 and the number of signers")]
+    fn test_enable_multisig_threshold_failure() {
+        multisig_setup(2, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_enable_multisig_non_owner_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, signer(0));
+        contract.enable_multisig(Multisig {
+            signers: vec![signer(0)],
+            threshold: 1,
+            proposal_lifetime: json_types::U64(DEFAULT_PROPOSAL_LIFETIME),
+        });
+    }
+
+    /// Sets a withdraw timelock of one day on the partial claim campaign's contract and makes
+    /// the owner the caller.
+    fn withdraw_timelock_setup() -> (VMContext, MerkleClaim, CryptoHash) {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(r#"{"withdraw_timelock": "86400000000000"}"#));
+
+        (context, contract, sibling)
+    }
+
+    #[test]
+    fn test_timelocked_withdraw() {
+        let (mut context, mut contract, sibling) = withdraw_timelock_setup();
+
+        contract.request_withdraw(json_types::U128(1000));
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["amount"], "1000");
+        let unlocks_at = context.block_timestamp + 86_400_000_000_000;
+        assert_eq!(event["unlocks_at"], unlocks_at.to_string());
+        assert_eq!(
+            contract.get_pending_withdrawal().unwrap().unlocks_at.0,
+            unlocks_at
+        );
+
+        // Claims keep working while the withdrawal is pending
+        set_caller(&mut context, claimant());
+        partial_claim(&mut contract, sibling, 1000);
+        assert!(contract.has_claimed(1, claimant()));
+
+        context.block_timestamp = unlocks_at;
+        set_caller(&mut context, account_owner());
+        contract.execute_withdraw();
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 1000)]);
+        assert!(contract.get_pending_withdrawal().is_none());
+
+        let event = withdraw_callback(&context, &mut contract, 1000, account_owner(), true);
+        assert_eq!(event["withdrawn"], "1000");
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal is still timelocked")]
+    fn test_timelocked_withdraw_early_execution_failure() {
+        let (mut context, mut contract, _) = withdraw_timelock_setup();
+
+        contract.request_withdraw(json_types::U128(1000));
+
+        context.block_timestamp += 86_400_000_000_000 - 1;
+        testing_env!(context.clone());
+        contract.execute_withdraw();
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawals are timelocked, use request_withdraw")]
+    fn test_timelocked_direct_withdraw_failure() {
+        let (_, mut contract, _) = withdraw_timelock_setup();
+
+        contract.withdraw(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "No withdrawal is pending")]
+    fn test_guardian_cancel_withdraw() {
+        let (mut context, mut contract, _) = withdraw_timelock_setup();
+
+        contract.add_guardian(guardian());
+        contract.request_withdraw(json_types::U128(1000));
+
+        set_caller(&mut context, guardian());
+        contract.cancel_withdraw();
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["amount"], "1000");
+        assert_eq!(event["account_id"], "guardian");
+        assert!(contract.get_pending_withdrawal().is_none());
+
+        context.block_timestamp += 86_400_000_000_000;
+        set_caller(&mut context, account_owner());
+        contract.execute_withdraw();
+    }
+
+    #[test]
+    #[should_panic(expected = "A withdrawal is already pending")]
+    fn test_request_withdraw_twice_failure() {
+        let (_, mut contract, _) = withdraw_timelock_setup();
+
+        contract.request_withdraw(json_types::U128(1000));
+        contract.request_withdraw(json_types::U128(1000));
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal amount exceeds the available balance")]
+    fn test_request_withdraw_above_available_failure() {
+        let (_, mut contract, _) = withdraw_timelock_setup();
+        let available = env::account_balance().as_yoctonear() - MIN_STORAGE_DEPOSIT.as_yoctonear();
+
+        contract.request_withdraw(json_types::U128(available + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_execute_withdraw_non_owner_failure() {
+        let (mut context, mut contract, _) = withdraw_timelock_setup();
+
+        contract.request_withdraw(json_types::U128(1000));
+
+        context.block_timestamp += 86_400_000_000_000;
+        set_caller(&mut context, guardian());
+        contract.execute_withdraw();
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawals are timelocked, use request_withdraw")]
+    fn test_lower_withdraw_timelock_direct_withdraw_failure() {
+        let (_, mut contract, _) = withdraw_timelock_setup();
+
+        contract.update_config(config_patch(r#"{"withdraw_timelock": "0"}"#));
+        assert_eq!(
+            contract.get_config().withdraw_timelock.0,
+            86_400_000_000_000
+        );
+
+        contract.withdraw(None);
+    }
+
+    #[test]
+    fn test_lower_withdraw_timelock() {
+        let (mut context, mut contract, _) = withdraw_timelock_setup();
+
+        contract.update_config(config_patch(
+            r#"{"withdraw_timelock": "0", "claim_fee_bps": 100}"#,
+        ));
+
+        // The rest of the patch applies immediately
+        assert_eq!(contract.get_config().claim_fee_bps, 100);
+        let logs = get_logs();
+        let event: serde_json::Value = event_data(&logs[logs.len() - 2]);
+        let executes_at = context.block_timestamp + 86_400_000_000_000;
+        assert_eq!(event["withdraw_timelock"], "0");
+        assert_eq!(event["executes_at"], executes_at.to_string());
+        assert_eq!(
+            contract.get_pending_config_change().unwrap().executes_at.0,
+            executes_at
+        );
+
+        context.block_timestamp = executes_at;
+        testing_env!(context.clone());
+        contract.execute_config_change();
+        assert_eq!(contract.get_config().withdraw_timelock.0, 0);
+        assert!(contract.get_pending_config_change().is_none());
+
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 1000)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Config change is still timelocked")]
+    fn test_lower_withdraw_timelock_early_execution_failure() {
+        let (mut context, mut contract, _) = withdraw_timelock_setup();
+
+        contract.update_config(config_patch(r#"{"withdraw_timelock": "0"}"#));
+
+        context.block_timestamp += 86_400_000_000_000 - 1;
+        testing_env!(context.clone());
+        contract.execute_config_change();
+    }
+
+    #[test]
+    #[should_panic(expected = "A config change is already pending")]
+    fn test_lower_withdraw_timelock_twice_failure() {
+        let (_, mut contract, _) = withdraw_timelock_setup();
+
+        contract.update_config(config_patch(r#"{"withdraw_timelock": "1000"}"#));
+        contract.update_config(config_patch(r#"{"withdraw_timelock": "0"}"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "No config change is pending")]
+    fn test_guardian_cancel_config_change() {
+        let (mut context, mut contract, _) = withdraw_timelock_setup();
+
+        contract.add_guardian(guardian());
+        contract.update_config(config_patch(r#"{"withdraw_timelock": "0"}"#));
+
+        set_caller(&mut context, guardian());
+        contract.cancel_config_change();
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["account_id"], "guardian");
+        assert!(contract.get_pending_config_change().is_none());
+
+        context.block_timestamp += 86_400_000_000_000;
+        set_caller(&mut context, account_owner());
+        contract.execute_config_change();
+    }
+
+    #[test]
+    fn test_raise_withdraw_timelock_immediately() {
+        let (_, mut contract, _) = withdraw_timelock_setup();
+
+        contract.update_config(config_patch(r#"{"withdraw_timelock": "172800000000000"}"#));
+
+        assert_eq!(
+            contract.get_config().withdraw_timelock.0,
+            172_800_000_000_000
+        );
+        assert!(contract.get_pending_config_change().is_none());
+    }
+
+    #[test]
+    fn test_withdraw_amount_exact_available() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        let balance = env::account_balance().as_yoctonear();
+        // The storage deposit and the campaign's total allocation stay in the contract
+        let available = balance - MIN_STORAGE_DEPOSIT.as_yoctonear() - 1500;
+
+        contract.withdraw_amount(NearToken::from_yoctonear(available), None);
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), available)]);
+
+        let event = withdraw_callback(&context, &mut contract, available, account_owner(), true);
+        assert_eq!(event["withdrawn"], available.to_string());
+        assert_eq!(
+            event["balance"],
+            (MIN_STORAGE_DEPOSIT.as_yoctonear() + 1500).to_string()
+        );
+    }
+
+    #[test]
+    fn test_withdraw_amount_partial() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        let balance = env::account_balance().as_yoctonear();
+
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 1000)]);
+
+        let event = withdraw_callback(&context, &mut contract, 1000, account_owner(), true);
+        assert_eq!(event["withdrawn"], "1000");
+        assert_eq!(event["balance"], (balance - 1000).to_string());
+    }
+
+    #[test]
+    fn test_withdraw_transfer_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+
+        let event = withdraw_callback(&context, &mut contract, 1000, account_owner(), false);
+        assert_eq!(event["amount"], "1000");
+        assert_eq!(event["receiver_id"], account_owner().to_string());
+        assert!(event.get("balance").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal amount exceeds the available balance")]
+    fn test_withdraw_amount_reserved_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        let balance = env::account_balance().as_yoctonear();
+        // One yoctoNEAR more would touch the campaign's reserved allocation
+        let available = balance - MIN_STORAGE_DEPOSIT.as_yoctonear() - 1500;
+
+        contract.withdraw_amount(NearToken::from_yoctonear(available + 1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal amount must not be zero")]
+    fn test_withdraw_amount_zero_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        contract.withdraw_amount(NearToken::from_yoctonear(0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_withdraw_amount_non_owner_failure() {
+        let (_, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+    }
+
+    /// Resolves the transfer of a withdrawal of `amount` to `receiver_id`, keeping the balance
+    /// left after it, and returns the event `on_withdraw` logs.
+    fn withdraw_callback(
+        context: &VMContext,
+        contract: &mut MerkleClaim,
+        amount: u128,
+        receiver_id: AccountId,
+        succeeded: bool,
+    ) -> serde_json::Value {
+        let mut context = context.clone();
+        context.account_balance = env::account_balance();
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        let result = if succeeded {
+            PromiseResult::Successful(vec![])
+        } else {
+            PromiseResult::Failed
+        };
+        callback_context(context, result);
+
+        let window_withdrawn = contract
+            .get_withdraw_allowance()
+            .map(|_| json_types::U128(contract.withdraw_window.withdrawn));
+        assert_eq!(
+            contract.on_withdraw(
+                NearToken::from_yoctonear(amount),
+                receiver_id,
+                window_withdrawn
+            ),
+            succeeded
+        );
+        event_data(get_logs().last().unwrap())
+    }
+
+    fn hot_wallet() -> AccountId {
+        AccountId::from_str("hot-wallet").unwrap()
+    }
+
+    #[test]
+    fn test_withdraw_to_receiver() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        let available = contract.available_balance();
+
+        contract.withdraw_to(hot_wallet());
+        assert_eq!(scheduled_transfers(), vec![(hot_wallet(), available)]);
+
+        let event = withdraw_callback(&context, &mut contract, available, hot_wallet(), true);
+        assert_eq!(event["receiver_id"], "hot-wallet");
+        assert_eq!(event["withdrawn"], available.to_string());
+    }
+
+    #[test]
+    fn test_withdraw_amount_to_receiver() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        contract.withdraw_amount_to(NearToken::from_yoctonear(1000), hot_wallet());
+        assert_eq!(scheduled_transfers(), vec![(hot_wallet(), 1000)]);
+
+        let event = withdraw_callback(&context, &mut contract, 1000, hot_wallet(), true);
+        assert_eq!(event["receiver_id"], "hot-wallet");
+        assert_eq!(event["withdrawn"], "1000");
+    }
+
+    #[test]
+    fn test_withdraw_to_default_receiver() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(
+            r#"{"default_withdraw_receiver": "hot-wallet"}"#,
+        ));
+        let available = contract.available_balance();
+
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+        contract.withdraw(None);
+
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(hot_wallet(), 1000), (hot_wallet(), available - 1000)]
+        );
+    }
+
+    #[test]
+    fn test_withdraw_to_overrides_default_receiver() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(
+            r#"{"default_withdraw_receiver": "hot-wallet"}"#,
+        ));
+
+        contract.withdraw_amount_to(NearToken::from_yoctonear(1000), account_owner());
+
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 1000)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_withdraw_to_non_owner_failure() {
+        let (_, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        contract.withdraw_to(claimant());
+    }
+
+    /// Withdraws everything but the reserved total allocation of 1500 of a running campaign.
+    fn reserved_withdraw_setup() -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        contract.withdraw(None);
+        assert_eq!(contract.available_balance(), 0);
+        assert_eq!(
+            env::account_balance().as_yoctonear(),
+            MIN_STORAGE_DEPOSIT.as_yoctonear() + 1500
+        );
+
+        (context, contract)
+    }
+
+    #[test]
+    #[should_panic(expected = "The remaining balance is required for contract storage")]
+    fn test_withdraw_keeps_active_campaign_reserve() {
+        let (_, mut contract) = reserved_withdraw_setup();
+
+        contract.withdraw(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal amount exceeds the available balance")]
+    fn test_withdraw_amount_keeps_active_campaign_reserve() {
+        let (_, mut contract) = reserved_withdraw_setup();
+
+        contract.withdraw_amount(NearToken::from_yoctonear(1), None);
+    }
+
+    #[test]
+    fn test_sweep_unlocks_reserved_funds() {
+        let (mut context, mut contract) = reserved_withdraw_setup();
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        testing_env!(context.clone());
+
+        assert_eq!(contract.sweep_campaign(1).0, 1500);
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 1500)]);
+        assert_eq!(contract.get_reserved_balance().0, 0);
+    }
+
+    #[test]
+    fn test_cancel_unlocks_reserved_funds() {
+        let (_, mut contract) = reserved_withdraw_setup();
+
+        contract.cancel_campaign(1);
+        contract.withdraw(None);
+
+        assert_eq!(scheduled_transfers().last(), Some(&(account_owner(), 1500)));
+    }
+
+    #[test]
+    fn test_forced_withdraw_takes_reserve() {
+        let (context, mut contract) = reserved_withdraw_setup();
+
+        contract.withdraw(Some(true));
+
+        let forced: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(forced["reserved_withdrawn"], "1500");
+        assert_eq!(forced["reserved_balance"], "1500");
+        assert_eq!(forced["receiver_id"], account_owner().to_string());
+        assert_eq!(
+            env::account_balance().as_yoctonear(),
+            MIN_STORAGE_DEPOSIT.as_yoctonear()
+        );
+
+        let withdraw = withdraw_callback(&context, &mut contract, 1500, account_owner(), true);
+        assert_eq!(withdraw["withdrawn"], "1500");
+        assert_eq!(
+            withdraw["balance"],
+            MIN_STORAGE_DEPOSIT.as_yoctonear().to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal amount exceeds the available balance")]
+    fn test_forced_withdraw_keeps_storage_deposit() {
+        let (_, mut contract) = reserved_withdraw_setup();
+
+        contract.withdraw_amount(NearToken::from_yoctonear(1501), Some(true));
+    }
+
+    /// Makes the owner the caller without the 1 yoctoNEAR owner actions require.
+    fn owner_without_deposit(context: &mut VMContext) {
+        context.predecessor_account_id = account_owner();
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        testing_env!(context.clone());
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_withdraw_without_deposit_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        owner_without_deposit(&mut context);
+        contract.withdraw(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_withdraw_amount_without_deposit_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        owner_without_deposit(&mut context);
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_execute_withdraw_without_deposit_failure() {
+        let (mut context, mut contract, _) = withdraw_timelock_setup();
+
+        contract.request_withdraw(json_types::U128(1000));
+
+        context.block_timestamp += 86_400_000_000_000;
+        owner_without_deposit(&mut context);
+        contract.execute_withdraw();
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_create_campaign_without_deposit_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let mock_campaign = build_mock_campaign();
+
+        owner_without_deposit(&mut context);
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_cancel_campaign_without_deposit_failure() {
+        let (mut context, mut contract, _) = partial_claim_campaign_setup();
+
+        owner_without_deposit(&mut context);
+        contract.cancel_campaign(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_update_config_without_deposit_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        owner_without_deposit(&mut context);
+        contract.update_config(config_patch(r#"{"max_proof_length": 32}"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_config_setter_without_deposit_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        owner_without_deposit(&mut context);
+        contract.set_max_proof_length(32);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_propose_owner_without_deposit_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        owner_without_deposit(&mut context);
+        contract.propose_owner(non_owner());
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_accept_ownership_without_deposit_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.propose_owner(non_owner());
+
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        set_caller(&mut context, non_owner());
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_withdraw_with_larger_deposit_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = account_owner();
+        context.attached_deposit = NearToken::from_yoctonear(2);
+        testing_env!(context.clone());
+        contract.withdraw(None);
+    }
+
+    #[test]
+    fn test_claim_without_deposit() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        context.predecessor_account_id = claimant();
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        testing_env!(context.clone());
+
+        partial_claim(&mut contract, sibling, 1000);
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    fn dao_treasury() -> AccountId {
+        AccountId::from_str("dao-treasury").unwrap()
+    }
+
+    #[test]
+    fn test_treasury_defaults_to_owner() {
+        let mut config = fee_config(250, None);
+        assert_eq!(config.treasury(), &account_owner());
+        assert_eq!(config.fee_destination(), None);
+        assert_eq!(config.claim_fee(10_000), 0);
+
+        config.treasury_account_id = Some(dao_treasury());
+        assert_eq!(config.treasury(), &dao_treasury());
+        assert_eq!(config.fee_destination(), Some(&dao_treasury()));
+        assert_eq!(config.claim_fee(10_000), 250);
+
+        // A fee receiver takes precedence over the treasury
+        config.fee_receiver = Some(non_owner());
+        assert_eq!(config.fee_destination(), Some(&non_owner()));
+    }
+
+    #[test]
+    fn test_sweep_to_treasury() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(r#"{"treasury_account_id": "dao-treasury"}"#));
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        testing_env!(context.clone());
+        assert_eq!(contract.sweep_campaign(1).0, 1500);
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["receiver_id"], "dao-treasury");
+        assert_eq!(scheduled_transfers(), vec![(dao_treasury(), 1500)]);
+    }
+
+    #[test]
+    fn test_sweep_without_treasury_to_owner() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        context.predecessor_account_id = account_owner();
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        testing_env!(context.clone());
+        contract.sweep_campaign(1);
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["receiver_id"], account_owner().to_string());
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 1500)]);
+    }
+
+    #[test]
+    fn test_claim_fee_to_treasury() {
+        let (mut context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(
+            r#"{"treasury_account_id": "dao-treasury", "claim_fee_bps": 250}"#,
+        ));
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["fee"], "25");
+        assert_eq!(event["fee_receiver"], "dao-treasury");
+        assert_eq!(scheduled_transfers(), vec![(dao_treasury(), 25)]);
+    }
+
+    #[test]
+    fn test_withdraw_to_treasury() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(r#"{"treasury_account_id": "dao-treasury"}"#));
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+
+        // The default withdraw receiver takes precedence over the treasury
+        contract.update_config(config_patch(
+            r#"{"default_withdraw_receiver": "hot-wallet"}"#,
+        ));
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(dao_treasury(), 1000), (hot_wallet(), 1000)]
+        );
+    }
+
+    #[test]
+    fn test_treasury_updated_mid_lifecycle() {
+        let (mut context, mut contract, sibling) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(r#"{"treasury_account_id": "dao-treasury"}"#));
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        // Sweeps go to the treasury configured when they happen
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(r#"{"treasury_account_id": "new-treasury"}"#));
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        testing_env!(context.clone());
+        contract.sweep_campaign(1);
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(AccountId::from_str("new-treasury").unwrap(), 500)]
+        );
+
+        // Unsetting the treasury sends withdrawals to the owner again
+        contract.update_config(config_patch(r#"{"treasury_account_id": null}"#));
+        assert_eq!(contract.get_config().treasury_account_id, None);
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+        assert_eq!(scheduled_transfers().last(), Some(&(account_owner(), 1000)));
+    }
+
+    /// Creates `count` running campaigns reserving a total allocation of 1000 each.
+    fn emergency_setup(count: u32) -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract) = claims_contract_setup();
+        let (_, merkle_root, end) = build_mock_campaign();
+
+        set_caller(&mut context, account_owner());
+        for _ in 0..count {
+            contract.create_campaign(
+                merkle_root.into(),
+                Some(end),
+                Some(CampaignOptions {
+                    total_allocation: Some(json_types::U128(1000)),
+                    ..Default::default()
+                }),
+            );
+        }
+        assert_eq!(contract.get_reserved_balance().0, 1000 * u128::from(count));
+
+        (context, contract)
+    }
+
+    fn active_campaigns(contract: &MerkleClaim) -> Vec<CampaignId> {
+        (1..=contract.last_campaign_id)
+            .filter(|campaign_id| {
+                contract.get_campaign_status(*campaign_id) == Some(CampaignStatus::Active)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_emergency_shutdown_in_batches() {
+        let (_, mut contract) = emergency_setup(12);
+        let balance = env::account_balance().as_yoctonear();
+
+        assert!(!contract.emergency_shutdown(Some(5)));
+        assert!(contract.is_paused());
+        assert_eq!(active_campaigns(&contract), (6..=12).collect::<Vec<_>>());
+        assert_eq!(
+            contract.get_emergency_shutdown().unwrap().next_campaign_id,
+            6
+        );
+        assert!(scheduled_transfers().is_empty());
+
+        assert!(!contract.emergency_shutdown(Some(5)));
+        assert_eq!(active_campaigns(&contract), vec![11, 12]);
+
+        assert!(contract.emergency_shutdown(Some(5)));
+        assert!(active_campaigns(&contract).is_empty());
+        assert_eq!(contract.get_reserved_balance().0, 0);
+        let sent = balance - MIN_STORAGE_DEPOSIT.as_yoctonear();
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), sent)]);
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["amount"], sent.to_string());
+        assert_eq!(event["receiver_id"], account_owner().to_string());
+        assert!(contract
+            .get_emergency_shutdown()
+            .unwrap()
+            .completed_at
+            .is_some());
+
+        // Further calls change nothing
+        let logs = get_logs().len();
+        assert!(contract.emergency_shutdown(None));
+        assert_eq!(scheduled_transfers().len(), 1);
+        assert_eq!(get_logs().len(), logs);
+    }
+
+    #[test]
+    fn test_emergency_shutdown_cancels_with_events() {
+        let (_, mut contract) = emergency_setup(3);
+
+        assert!(contract.emergency_shutdown(None));
+
+        let cancelled: Vec<serde_json::Value> = get_logs()
+            .iter()
+            .map(|log| event_data(log))
+            .filter(|event| event.get("released").is_some())
+            .collect();
+        assert_eq!(cancelled.len(), 3);
+        for (campaign_id, event) in (1..=3).zip(cancelled) {
+            assert_eq!(event["campaign_id"], campaign_id);
+            assert_eq!(event["released"], "1000");
+        }
+    }
+
+    #[test]
+    fn test_emergency_shutdown_needs_guardian_approval() {
+        let (mut context, mut contract) = emergency_setup(2);
+
+        contract.add_guardian(guardian());
+        contract.update_config(config_patch(r#"{"treasury_account_id": "dao-treasury"}"#));
+
+        assert!(!contract.emergency_shutdown(None));
+        assert!(!contract.is_paused());
+        assert_eq!(active_campaigns(&contract), vec![1, 2]);
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["account_id"], account_owner().to_string());
+
+        set_caller(&mut context, guardian());
+        assert!(contract.emergency_shutdown(None));
+        assert!(active_campaigns(&contract).is_empty());
+        let approval = contract.get_emergency_shutdown().unwrap();
+        assert_eq!(approval.owner_approved, Some(account_owner()));
+        assert_eq!(approval.guardian_approved, Some(guardian()));
+        assert_eq!(scheduled_transfers().last().unwrap().0, dao_treasury());
+    }
+
+    #[test]
+    fn test_emergency_shutdown_guardian_alone_waits_for_owner() {
+        let (mut context, mut contract) = emergency_setup(2);
+
+        contract.add_guardian(guardian());
+
+        set_caller(&mut context, guardian());
+        assert!(!contract.emergency_shutdown(None));
+        assert!(!contract.emergency_shutdown(None));
+        assert_eq!(active_campaigns(&contract), vec![1, 2]);
+
+        set_caller(&mut context, account_owner());
+        assert!(contract.emergency_shutdown(None));
+    }
+
+    #[test]
+    fn test_emergency_shutdown_releases_ended_campaigns() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        set_caller(&mut context, account_owner());
+        assert_eq!(contract.available_balance(), 20001 - 1000 - 1500);
+
+        assert!(contract.emergency_shutdown(None));
+        assert_eq!(contract.get_reserved_balance().0, 0);
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 20001 - 1000)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign creation is paused")]
+    fn test_emergency_shutdown_blocks_campaign_creation() {
+        let (_, mut contract) = emergency_setup(1);
+        let (_, merkle_root, end) = build_mock_campaign();
+
+        contract.emergency_shutdown(None);
+        contract.create_campaign(merkle_root.into(), Some(end), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a guardian can call this method")]
+    fn test_emergency_shutdown_non_owner_failure() {
+        let (mut context, mut contract) = emergency_setup(1);
+
+        set_caller(&mut context, financial_operator());
+        contract.emergency_shutdown(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_emergency_shutdown_without_deposit_failure() {
+        let (mut context, mut contract) = emergency_setup(1);
+
+        owner_without_deposit(&mut context);
+        contract.emergency_shutdown(None);
+    }
+
+    #[test]
+    fn test_emergency_shutdown_removed_guardian_approval_lapses() {
+        let (mut context, mut contract) = emergency_setup(2);
+
+        contract.add_guardian(guardian());
+        contract.add_guardian(non_owner());
+        set_caller(&mut context, guardian());
+        assert!(!contract.emergency_shutdown(None));
+
+        set_caller(&mut context, account_owner());
+        contract.remove_guardian(guardian());
+        assert!(!contract.emergency_shutdown(None));
+        assert_eq!(active_campaigns(&contract), vec![1, 2]);
+        let approval = contract.get_emergency_shutdown().unwrap();
+        assert_eq!(approval.owner_approved, Some(account_owner()));
+        assert!(approval.guardian_approved.is_none());
+
+        set_caller(&mut context, non_owner());
+        assert!(contract.emergency_shutdown(None));
+    }
+
+    #[test]
+    fn test_emergency_shutdown_previous_owner_approval_lapses() {
+        let (mut context, mut contract) = emergency_setup(2);
+
+        contract.add_guardian(guardian());
+        assert!(!contract.emergency_shutdown(None));
+        contract.propose_owner(non_owner());
+        set_caller(&mut context, non_owner());
+        contract.accept_ownership();
+
+        set_caller(&mut context, guardian());
+        assert!(!contract.emergency_shutdown(None));
+        assert_eq!(active_campaigns(&contract), vec![1, 2]);
+        assert!(contract
+            .get_emergency_shutdown()
+            .unwrap()
+            .owner_approved
+            .is_none());
+
+        set_caller(&mut context, non_owner());
+        assert!(contract.emergency_shutdown(None));
+    }
+
+    #[test]
+    fn test_revoke_emergency_shutdown() {
+        let (mut context, mut contract) = emergency_setup(2);
+
+        contract.add_guardian(guardian());
+        assert!(!contract.emergency_shutdown(None));
+        contract.revoke_emergency_shutdown();
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["account_id"], account_owner().to_string());
+        assert!(contract.get_emergency_shutdown().is_none());
+
+        set_caller(&mut context, guardian());
+        assert!(!contract.emergency_shutdown(None));
+        assert_eq!(active_campaigns(&contract), vec![1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Emergency shutdown has already started")]
+    fn test_revoke_started_emergency_shutdown_failure() {
+        let (_, mut contract) = emergency_setup(2);
+
+        assert!(!contract.emergency_shutdown(Some(1)));
+        contract.revoke_emergency_shutdown();
+    }
+
+    #[test]
+    #[should_panic(expected = "No emergency shutdown approval to revoke")]
+    fn test_revoke_other_guardian_approval_failure() {
+        let (mut context, mut contract) = emergency_setup(2);
+
+        contract.add_guardian(guardian());
+        contract.add_guardian(non_owner());
+        set_caller(&mut context, guardian());
+        assert!(!contract.emergency_shutdown(None));
+
+        set_caller(&mut context, non_owner());
+        contract.revoke_emergency_shutdown();
+    }
+
+    const WITHDRAW_PERIOD: u64 = 7 * 86_400_000_000_000;
+
+    /// Limits withdrawals of a contract with 17501 available to 5000 per 7 days.
+    fn withdraw_limit_setup() -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(
+            r#"{"withdraw_limit": {"amount": "5000", "period": "604800000000000"}}"#,
+        ));
+        assert_eq!(contract.available_balance(), 17501);
+        assert_eq!(
+            contract.get_withdraw_allowance(),
+            Some(json_types::U128(5000))
+        );
+
+        (context, contract)
+    }
+
+    #[test]
+    fn test_withdraw_limit_running_total() {
+        let (mut context, mut contract) = withdraw_limit_setup();
+
+        contract.withdraw_amount(NearToken::from_yoctonear(3000), None);
+        assert_eq!(
+            contract.get_withdraw_allowance(),
+            Some(json_types::U128(2000))
+        );
+
+        let event = withdraw_callback(&context, &mut contract, 3000, account_owner(), true);
+        assert_eq!(event["window_withdrawn"], "3000");
+
+        set_caller(&mut context, account_owner());
+        contract.withdraw_amount_to(NearToken::from_yoctonear(2000), hot_wallet());
+        assert_eq!(contract.get_withdraw_allowance(), Some(json_types::U128(0)));
+
+        let event = withdraw_callback(&context, &mut contract, 2000, hot_wallet(), true);
+        assert_eq!(event["window_withdrawn"], "5000");
+    }
+
+    #[test]
+    fn test_withdraw_capped_by_limit() {
+        let (_, mut contract) = withdraw_limit_setup();
+
+        contract.withdraw(None);
+
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 5000)]);
+        assert_eq!(contract.get_withdraw_allowance(), Some(json_types::U128(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal exceeds the withdraw limit")]
+    fn test_withdraw_amount_over_limit_failure() {
+        let (_, mut contract) = withdraw_limit_setup();
+
+        contract.withdraw_amount(NearToken::from_yoctonear(5001), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal exceeds the withdraw limit")]
+    fn test_withdraw_at_exhausted_limit_failure() {
+        let (_, mut contract) = withdraw_limit_setup();
+
+        contract.withdraw_to(hot_wallet());
+        contract.withdraw(None);
+    }
+
+    #[test]
+    fn test_withdraw_limit_window_edges() {
+        let (mut context, mut contract) = withdraw_limit_setup();
+        let start = context.block_timestamp;
+
+        contract.withdraw_amount(NearToken::from_yoctonear(5000), None);
+
+        // The window includes its last nanosecond
+        context.block_timestamp = start + WITHDRAW_PERIOD - 1;
+        testing_env!(context.clone());
+        assert_eq!(contract.get_withdraw_allowance(), Some(json_types::U128(0)));
+
+        // and ends exactly one period after it started
+        context.block_timestamp = start + WITHDRAW_PERIOD;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.get_withdraw_allowance(),
+            Some(json_types::U128(5000))
+        );
+
+        // The next window starts with the next withdrawal
+        context.block_timestamp = start + WITHDRAW_PERIOD + 100;
+        testing_env!(context.clone());
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+
+        context.block_timestamp = start + 2 * WITHDRAW_PERIOD + 99;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.get_withdraw_allowance(),
+            Some(json_types::U128(4000))
+        );
+        contract.withdraw_amount(NearToken::from_yoctonear(4000), None);
+        assert_eq!(contract.get_withdraw_allowance(), Some(json_types::U128(0)));
+
+        context.block_timestamp = start + 2 * WITHDRAW_PERIOD + 100;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.get_withdraw_allowance(),
+            Some(json_types::U128(5000))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal exceeds the withdraw limit")]
+    fn test_withdraw_limit_before_window_end_failure() {
+        let (mut context, mut contract) = withdraw_limit_setup();
+
+        contract.withdraw_amount(NearToken::from_yoctonear(5000), None);
+
+        context.block_timestamp += WITHDRAW_PERIOD - 1;
+        testing_env!(context.clone());
+        contract.withdraw_amount(NearToken::from_yoctonear(1), None);
+    }
+
+    #[test]
+    fn test_failed_withdrawal_restores_allowance() {
+        let (context, mut contract) = withdraw_limit_setup();
+
+        contract.withdraw_amount(NearToken::from_yoctonear(3000), None);
+        withdraw_callback(&context, &mut contract, 3000, account_owner(), false);
+
+        assert_eq!(
+            contract.get_withdraw_allowance(),
+            Some(json_types::U128(5000))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal exceeds the withdraw limit")]
+    fn test_execute_withdraw_over_limit_failure() {
+        let (mut context, mut contract, _) = withdraw_timelock_setup();
+
+        contract.update_config(config_patch(
+            r#"{"withdraw_limit": {"amount": "500", "period": "604800000000000"}}"#,
+        ));
+        contract.request_withdraw(json_types::U128(1000));
+
+        context.block_timestamp += 86_400_000_000_000;
+        testing_env!(context.clone());
+        contract.execute_withdraw();
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdraw limit period must not be zero")]
+    fn test_withdraw_limit_zero_period_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(
+            r#"{"withdraw_limit": {"amount": "5000", "period": "0"}}"#,
+        ));
+    }
+
+    #[test]
+    fn test_withdraw_without_limit_omits_running_total() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, account_owner());
+        assert_eq!(contract.get_withdraw_allowance(), None);
+        contract.withdraw_amount(NearToken::from_yoctonear(1000), None);
+
+        let event = withdraw_callback(&context, &mut contract, 1000, account_owner(), true);
+        assert!(event.get("window_withdrawn").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal exceeds the withdraw limit")]
+    fn test_remove_withdraw_limit_bypass_failure() {
+        let (_, mut contract) = withdraw_limit_setup();
+
+        contract.update_config(config_patch(r#"{"withdraw_limit": null}"#));
+        assert!(contract.get_config().withdraw_limit.is_some());
+
+        contract.withdraw_amount(NearToken::from_yoctonear(5001), None);
+    }
+
+    #[test]
+    fn test_remove_withdraw_limit() {
+        let (mut context, mut contract) = withdraw_limit_setup();
+
+        contract.update_config(config_patch(r#"{"withdraw_limit": null}"#));
+
+        // Without a timelock the change waits for a window of the current limit
+        let logs = get_logs();
+        let event: serde_json::Value = event_data(&logs[logs.len() - 2]);
+        let executes_at = context.block_timestamp + WITHDRAW_PERIOD;
+        assert!(event["withdraw_limit"].is_null());
+        assert!(event.get("withdraw_timelock").is_none());
+        assert_eq!(event["executes_at"], executes_at.to_string());
+
+        context.block_timestamp = executes_at;
+        testing_env!(context.clone());
+        contract.execute_config_change();
+        assert!(contract.get_config().withdraw_limit.is_none());
+
+        contract.withdraw_amount(NearToken::from_yoctonear(5001), None);
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 5001)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Config change is still timelocked")]
+    fn test_raise_withdraw_limit_early_execution_failure() {
+        let (mut context, mut contract) = withdraw_limit_setup();
+
+        contract.update_config(config_patch(
+            r#"{"withdraw_limit": {"amount": "10000", "period": "604800000000000"}}"#,
+        ));
+        assert_eq!(
+            contract.get_withdraw_allowance(),
+            Some(json_types::U128(5000))
+        );
+
+        context.block_timestamp += WITHDRAW_PERIOD - 1;
+        testing_env!(context.clone());
+        contract.execute_config_change();
+    }
+
+    #[test]
+    fn test_shorten_withdraw_limit_period_deferred() {
+        let (_, mut contract) = withdraw_limit_setup();
+
+        contract.update_config(config_patch(
+            r#"{"withdraw_limit": {"amount": "5000", "period": "1000"}}"#,
+        ));
+
+        assert_eq!(
+            contract
+                .get_config()
+                .withdraw_limit
+                .as_ref()
+                .unwrap()
+                .period
+                .0,
+            WITHDRAW_PERIOD
+        );
+        assert!(contract.get_pending_config_change().is_some());
+    }
+
+    #[test]
+    fn test_lower_withdraw_limit_immediately() {
+        let (_, mut contract) = withdraw_limit_setup();
+
+        contract.update_config(config_patch(
+            r#"{"withdraw_limit": {"amount": "1000", "period": "1209600000000000"}}"#,
+        ));
+
+        assert_eq!(
+            contract.get_withdraw_allowance(),
+            Some(json_types::U128(1000))
+        );
+        assert!(contract.get_pending_config_change().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdraw limit period must not be zero")]
+    fn test_loosen_withdraw_limit_zero_period_failure() {
+        let (_, mut contract) = withdraw_limit_setup();
+
+        contract.update_config(config_patch(
+            r#"{"withdraw_limit": {"amount": "5000", "period": "0"}}"#,
+        ));
+    }
+
+    fn operator_key() -> PublicKey {
+        public_key(7)
+    }
+
+    /// Adds `operator_key()` and makes the next calls signed with it on the contract's account,
+    /// which function call access keys make without a deposit.
+    fn operator_key_setup() -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.add_operator_key(operator_key(), NearToken::from_near(1));
+
+        context.predecessor_account_id = context.current_account_id.clone();
+        context.signer_account_id = context.current_account_id.clone();
+        context.signer_account_pk = operator_key();
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        testing_env!(context.clone());
+
+        (context, contract)
+    }
+
+    #[test]
+    fn test_add_operator_key() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.add_operator_key(operator_key(), NearToken::from_near(1));
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["public_key"], String::from(&operator_key()));
+        assert_eq!(event["added"], true);
+
+        let keys = contract.get_operator_keys();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].public_key, operator_key());
+        assert_eq!(keys[0].allowance, NearToken::from_near(1));
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, context.current_account_id);
+        assert!(matches!(
+            &receipts[0].actions[..],
+            [MockAction::AddKeyWithFunctionCall {
+                allowance: Some(allowance),
+                receiver_id,
+                method_names,
+                ..
+            }] if *allowance == NearToken::from_near(1)
+                && *receiver_id == context.current_account_id
+                && *method_names == vec!["create_campaign".to_string()]
+        ));
+    }
+
+    #[test]
+    fn test_operator_key_creates_campaign() {
+        let (_, mut contract) = operator_key_setup();
+        let mock_campaign = build_mock_campaign();
+
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+
+        assert!(contract.get_campaign(1).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_operator_key_withdraw_failure() {
+        let (_, mut contract) = operator_key_setup();
+
+        contract.withdraw(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_unknown_key_create_campaign_failure() {
+        let (mut context, mut contract) = operator_key_setup();
+        let mock_campaign = build_mock_campaign();
+
+        context.signer_account_pk = public_key(8);
+        testing_env!(context.clone());
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_operator_key_of_other_account_failure() {
+        let (mut context, mut contract) = operator_key_setup();
+        let mock_campaign = build_mock_campaign();
+
+        // The key only counts when signing for the contract's own account
+        context.predecessor_account_id = non_owner();
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        testing_env!(context.clone());
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_removed_operator_key_failure() {
+        let (mut context, mut contract) = operator_key_setup();
+        let mock_campaign = build_mock_campaign();
+
+        context.signer_account_pk = public_key(123);
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        testing_env!(context.clone());
+        contract.remove_operator_key(operator_key());
+
+        assert!(contract.get_operator_keys().is_empty());
+        assert!(matches!(
+            &get_created_receipts()[0].actions[..],
+            [MockAction::DeleteKey { .. }]
+        ));
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["added"], false);
+
+        context.signer_account_pk = operator_key();
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        testing_env!(context.clone());
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_add_operator_key_non_owner_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, non_owner());
+        contract.add_operator_key(operator_key(), NearToken::from_near(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Operator key allowance must not be zero")]
+    fn test_add_operator_key_zero_allowance_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.add_operator_key(operator_key(), NearToken::from_yoctonear(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Public key is not an operator key")]
+    fn test_remove_unknown_operator_key_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.remove_operator_key(operator_key());
+    }
+
+    /// Sets a storage deposit of 10 NEAR for storage of 1000 bytes, which costs far less.
+    fn storage_surplus_setup() -> (VMContext, MerkleClaim, u128) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.account_balance = NearToken::from_near(100);
+        context.account_locked_balance = NearToken::from_yoctonear(0);
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(
+            r#"{"min_storage_deposit": "10000000000000000000000000"}"#,
+        ));
+
+        context.storage_usage = 1000;
+        testing_env!(context.clone());
+        let storage_cost = 1000 * env::storage_byte_cost().as_yoctonear();
+        let surplus = NearToken::from_near(10).as_yoctonear() - storage_cost;
+        assert_eq!(contract.get_storage_surplus().0, surplus);
+
+        (context, contract, surplus)
+    }
+
+    #[test]
+    fn test_withdraw_storage_surplus_exact_surplus() {
+        let (_, mut contract, surplus) = storage_surplus_setup();
+
+        contract.withdraw_storage_surplus(Some(NearToken::from_yoctonear(surplus)));
+
+        let event: serde_json::Value = event_data(get_logs().last().unwrap());
+        assert_eq!(event["amount"], surplus.to_string());
+        // The deposit left covers exactly the storage the contract had
+        let storage_cost = NearToken::from_near(10).as_yoctonear() - surplus;
+        assert_eq!(
+            contract.get_config().min_storage_deposit.as_yoctonear(),
+            storage_cost
+        );
+        assert_eq!(event["min_storage_deposit"], storage_cost.to_string());
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), surplus)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal amount exceeds the storage surplus")]
+    fn test_withdraw_storage_surplus_below_storage_cost_failure() {
+        let (_, mut contract, surplus) = storage_surplus_setup();
+
+        contract.withdraw_storage_surplus(Some(NearToken::from_yoctonear(surplus + 1)));
+    }
+
+    #[test]
+    fn test_withdraw_storage_surplus_defaults_to_surplus() {
+        let (_, mut contract, surplus) = storage_surplus_setup();
+
+        contract.withdraw_storage_surplus(None);
+
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), surplus)]);
+    }
+
+    #[test]
+    fn test_withdraw_storage_surplus_leaves_available_balance() {
+        let (_, mut contract, _) = storage_surplus_setup();
+        let available = contract.available_balance();
+
+        contract.withdraw_storage_surplus(Some(NearToken::from_near(1)));
+
+        assert_eq!(contract.available_balance(), available);
+        assert_eq!(
+            contract.get_config().min_storage_deposit,
+            NearToken::from_near(9)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "There is no storage surplus to withdraw")]
+    fn test_withdraw_storage_surplus_without_surplus_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        // The test storage costs far more than the 1000 yoctoNEAR deposit
+        context.account_locked_balance = NearToken::from_yoctonear(0);
+        set_caller(&mut context, account_owner());
+        assert_eq!(contract.get_storage_surplus().0, 0);
+        contract.withdraw_storage_surplus(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_withdraw_storage_surplus_non_owner_failure() {
+        let (mut context, mut contract, _) = storage_surplus_setup();
+
+        set_caller(&mut context, non_owner());
+        contract.withdraw_storage_surplus(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawals are paused")]
+    fn test_withdraw_storage_surplus_paused_failure() {
+        let (mut context, mut contract, _) = storage_surplus_setup();
+
+        contract.pause();
+        set_caller(&mut context, account_owner());
+        contract.withdraw_storage_surplus(None);
+    }
+
+    /// Deposits `deposit` paid by `payer` to the storage balance of `account_id`, which the
+    /// contract balance then holds on top of the default balance.
+    fn storage_deposit(
+        context: &mut VMContext,
+        contract: &mut MerkleClaim,
+        payer: AccountId,
+        account_id: Option<AccountId>,
+        deposit: NearToken,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        context.predecessor_account_id = payer;
+        context.attached_deposit = deposit;
+        testing_env!(context.clone());
+
+        let balance = contract.storage_deposit(account_id, registration_only);
+
+        context.account_balance = context.account_balance.saturating_add(deposit);
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        balance
+    }
+
+    #[test]
+    fn test_storage_balance_bounds() {
+        let (_, contract) = claims_contract_setup();
+
+        assert_eq!(
+            serde_json::to_value(contract.storage_balance_bounds()).unwrap(),
+            serde_json::json!({"min": "10000000000000000000000", "max": null})
+        );
+    }
+
+    #[test]
+    fn test_storage_deposit_registers_account() {
+        let (mut context, mut contract) = claims_contract_setup();
+        assert_eq!(contract.storage_balance_of(claimant()), None);
+
+        let balance = storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            NearToken::from_near(1),
+            None,
+        );
+
+        assert_eq!(
+            serde_json::to_value(&balance).unwrap(),
+            serde_json::json!({
+                "total": "1000000000000000000000000",
+                "available": "990000000000000000000000"
+            })
+        );
+        assert_eq!(contract.storage_balance_of(claimant()), Some(balance));
+        assert!(scheduled_transfers().is_empty());
+
+        let event = event_data(&get_logs()[0]);
+        assert_eq!(event_name(&get_logs()[0]), "storage_deposit");
+        assert_eq!(event["depositor"], claimant().to_string());
+        assert_eq!(event["account_id"], claimant().to_string());
+        assert_eq!(event["attached"], "1000000000000000000000000");
+        assert_eq!(event["credited"], "1000000000000000000000000");
+        assert_eq!(event["refunded"], "0");
+        assert!(event.get("min_storage_deposit").is_none());
+    }
+
+    #[test]
+    fn test_storage_deposit_adds_to_balance() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            REGISTRATION_DEPOSIT,
+            None,
+        );
+        // A sponsor tops up the claimant's balance
+        let balance = storage_deposit(
+            &mut context,
+            &mut contract,
+            non_owner(),
+            Some(claimant()),
+            NearToken::from_near(1),
+            None,
+        );
+
+        assert_eq!(
+            balance.total,
+            NearToken::from_near(1).saturating_add(REGISTRATION_DEPOSIT)
+        );
+        assert_eq!(contract.storage_balance_of(non_owner()), None);
+
+        let event = event_data(&get_logs()[0]);
+        assert_eq!(event["depositor"], non_owner().to_string());
+        assert_eq!(event["account_id"], claimant().to_string());
+    }
+
+    #[test]
+    fn test_storage_deposit_registration_only_refunds_excess() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        let balance = storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            NearToken::from_near(1),
+            Some(true),
+        );
+
+        let refund = NearToken::from_near(1).as_yoctonear() - REGISTRATION_DEPOSIT.as_yoctonear();
+        assert_eq!(balance.total, REGISTRATION_DEPOSIT);
+        assert_eq!(scheduled_transfers(), vec![(claimant(), refund)]);
+
+        let event = event_data(&get_logs()[0]);
+        assert_eq!(event["attached"], "1000000000000000000000000");
+        assert_eq!(
+            event["credited"],
+            REGISTRATION_DEPOSIT.as_yoctonear().to_string()
+        );
+        assert_eq!(event["refunded"], refund.to_string());
+    }
+
+    #[test]
+    fn test_storage_deposit_registration_only_when_registered() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            REGISTRATION_DEPOSIT,
+            None,
+        );
+        let balance = storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            NearToken::from_near(1),
+            Some(true),
+        );
+
+        // The deposit is refunded in full
+        assert_eq!(balance.total, REGISTRATION_DEPOSIT);
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(claimant(), NearToken::from_near(1).as_yoctonear())]
+        );
+        let event = event_data(&get_logs()[0]);
+        assert_eq!(event["credited"], "0");
+        assert_eq!(event["refunded"], "1000000000000000000000000");
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+    fn test_storage_deposit_below_minimum_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            NearToken::from_millinear(1),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_storage_deposit_not_available_to_withdraw() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.predecessor_account_id = claimant();
+        context.attached_deposit = NearToken::from_near(1);
+        testing_env!(context.clone());
+        // The attached deposit is part of the balance already
+        let available = contract.available_balance();
+
+        contract.storage_deposit(None, None);
+
+        assert_eq!(
+            contract.available_balance(),
+            available - NearToken::from_near(1).as_yoctonear()
+        );
+        assert_eq!(
+            contract.withdrawable_balance(true),
+            available - NearToken::from_near(1).as_yoctonear()
+        );
+    }
+
+    #[test]
+    fn test_storage_withdraw() {
+        let (mut context, mut contract) = claims_contract_setup();
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            NearToken::from_near(1),
+            None,
+        );
+
+        context.predecessor_account_id = claimant();
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        testing_env!(context.clone());
+        let balance = contract.storage_withdraw(Some(NearToken::from_millinear(400)));
+
+        assert_eq!(balance.available, NearToken::from_millinear(590));
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(claimant(), NearToken::from_millinear(400).as_yoctonear())]
+        );
+        assert_eq!(event_name(&get_logs()[0]), "storage_withdraw");
+        let event = event_data(&get_logs()[0]);
+        assert_eq!(event["account_id"], claimant().to_string());
+        assert_eq!(
+            event["amount"],
+            NearToken::from_millinear(400).as_yoctonear().to_string()
+        );
+        assert_eq!(
+            event["remaining"],
+            NearToken::from_millinear(600).as_yoctonear().to_string()
+        );
+        assert_eq!(event["unregistered"], false);
+
+        testing_env!(context.clone());
+        let balance = contract.storage_withdraw(None);
+
+        // The account stays registered with the minimum balance
+        assert_eq!(balance.total, MIN_STORAGE_BALANCE);
+        assert_eq!(balance.available, NearToken::from_yoctonear(0));
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(claimant(), NearToken::from_millinear(590).as_yoctonear())]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount is greater than the available storage balance")]
+    fn test_storage_withdraw_exceeding_balance_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            REGISTRATION_DEPOSIT,
+            None,
+        );
+
+        context.predecessor_account_id = claimant();
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        testing_env!(context);
+        contract.storage_withdraw(Some(NearToken::from_near(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "The amount is greater than the available storage balance")]
+    fn test_storage_withdraw_minimum_balance_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            MIN_STORAGE_BALANCE,
+            None,
+        );
+        assert_eq!(
+            contract.storage_balance_of(claimant()).unwrap().available,
+            NearToken::from_yoctonear(0)
+        );
+
+        context.predecessor_account_id = claimant();
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        testing_env!(context);
+        contract.storage_withdraw(Some(NearToken::from_yoctonear(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account is not registered")]
+    fn test_storage_withdraw_unregistered_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        set_caller(&mut context, claimant());
+        contract.storage_withdraw(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_storage_withdraw_without_deposit_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            REGISTRATION_DEPOSIT,
+            None,
+        );
+
+        context.predecessor_account_id = claimant();
+        testing_env!(context);
+        contract.storage_withdraw(None);
+    }
+
+    #[test]
+    fn test_storage_unregister() {
+        let (mut context, mut contract) = claims_contract_setup();
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            NearToken::from_near(1),
+            None,
+        );
+
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        set_caller(&mut context, claimant());
+        assert!(contract.storage_unregister(None));
+
+        assert_eq!(contract.storage_balance_of(claimant()), None);
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(claimant(), NearToken::from_near(1).as_yoctonear())]
+        );
+        let event = event_data(&get_logs()[0]);
+        assert_eq!(event["amount"], "1000000000000000000000000");
+        assert_eq!(event["remaining"], "0");
+        assert_eq!(event["unregistered"], true);
+
+        testing_env!(context);
+        assert!(!contract.storage_unregister(Some(true)));
+    }
+
+    #[test]
+    fn test_storage_deposit_tops_up_contract() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let min_storage_deposit = contract.get_config().min_storage_deposit;
+
+        let balance = storage_deposit(
+            &mut context,
+            &mut contract,
+            account_owner(),
+            Some(account_owner()),
+            NearToken::from_near(1),
+            None,
+        );
+
+        let min_storage_deposit = min_storage_deposit.saturating_add(NearToken::from_near(1));
+        assert_eq!(
+            contract.get_config().min_storage_deposit,
+            min_storage_deposit
+        );
+        assert_eq!(balance.total, min_storage_deposit);
+        assert_eq!(contract.storage_balance_of(account_owner()), Some(balance));
+    }
+
+    #[test]
+    fn test_storage_deposit_by_sponsors() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let min_storage_deposit = contract.get_config().min_storage_deposit;
+
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            non_owner(),
+            Some(account_owner()),
+            NearToken::from_near(1),
+            None,
+        );
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["depositor"], "non_owner");
+        assert_eq!(event["account_id"], account_owner().to_string());
+        assert_eq!(
+            event["credited"],
+            NearToken::from_near(1).as_yoctonear().to_string()
+        );
+        assert_eq!(event["refunded"], "0");
+        assert_eq!(
+            event["min_storage_deposit"],
+            min_storage_deposit
+                .saturating_add(NearToken::from_near(1))
+                .as_yoctonear()
+                .to_string()
+        );
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            Some(account_owner()),
+            NearToken::from_near(2),
+            None,
+        );
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            non_owner(),
+            Some(account_owner()),
+            NearToken::from_near(1),
+            None,
+        );
+
+        assert_eq!(
+            contract.storage_deposit_of(non_owner()),
+            NearToken::from_near(2)
+        );
+        assert_eq!(
+            contract.storage_deposit_of(claimant()),
+            NearToken::from_near(2)
+        );
+        assert_eq!(
+            contract.storage_deposit_of(account_owner()),
+            NearToken::from_yoctonear(0)
+        );
+        assert_eq!(
+            contract.get_config().min_storage_deposit,
+            min_storage_deposit.saturating_add(NearToken::from_near(4))
+        );
+        // Contract deposits are no NEP-145 storage balances
+        assert_eq!(contract.storage_balance_of(non_owner()), None);
+    }
+
+    /// Has two sponsors deposit 1 and 2 NEAR to the contract's storage deposit.
+    fn storage_deposits_setup() -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        for (account_id, amount) in [(non_owner(), 1), (claimant(), 2)] {
+            storage_deposit(
+                &mut context,
+                &mut contract,
+                account_id,
+                Some(account_owner()),
+                NearToken::from_near(amount),
+                None,
+            );
+        }
+        context.attached_deposit = NearToken::from_yoctonear(1);
+
+        (context, contract)
+    }
+
+    #[test]
+    fn test_reclaim_storage_deposit() {
+        let (mut context, mut contract) = storage_deposits_setup();
+        let min_storage_deposit = contract.get_config().min_storage_deposit;
+
+        set_caller(&mut context, non_owner());
+        contract.reclaim_storage_deposit(NearToken::from_millinear(400));
+
+        assert_eq!(
+            contract.storage_deposit_of(non_owner()),
+            NearToken::from_millinear(600)
+        );
+        assert_eq!(
+            contract.get_config().min_storage_deposit,
+            min_storage_deposit.saturating_sub(NearToken::from_millinear(400))
+        );
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(non_owner(), NearToken::from_millinear(400).as_yoctonear())]
+        );
+        assert_eq!(event_name(&get_logs()[0]), "storage_deposit_reclaimed");
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(
+            event["amount"],
+            NearToken::from_millinear(400).as_yoctonear().to_string()
+        );
+        assert_eq!(
+            event["credited"],
+            NearToken::from_millinear(600).as_yoctonear().to_string()
+        );
+
+        testing_env!(context);
+        contract.reclaim_storage_deposit(NearToken::from_millinear(600));
+        assert_eq!(
+            contract.storage_deposit_of(non_owner()),
+            NearToken::from_yoctonear(0)
+        );
+        assert_eq!(
+            contract.storage_deposit_of(claimant()),
+            NearToken::from_near(2)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount exceeds the storage deposit of the account")]
+    fn test_reclaim_storage_deposit_over_credit_failure() {
+        let (mut context, mut contract) = storage_deposits_setup();
+
+        // Enough is deposited, but not by this account
+        set_caller(&mut context, non_owner());
+        contract.reclaim_storage_deposit(NearToken::from_near(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Storage deposit left would not cover the contract's storage")]
+    fn test_reclaim_storage_deposit_without_margin_failure() {
+        let (mut context, mut contract) = storage_deposits_setup();
+
+        // The contract's storage costs more than was deposited
+        context.account_locked_balance = NearToken::from_yoctonear(0);
+        set_caller(&mut context, non_owner());
+        contract.reclaim_storage_deposit(NearToken::from_near(1));
+    }
+
+    #[test]
+    fn test_register_claim_from_storage_balance() {
+        let (mut context, mut contract, _) = registration_campaign_setup();
+        let (_, _, end) = build_mock_campaign();
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            NearToken::from_millinear(50),
+            None,
+        );
+
+        set_caller(&mut context, claimant());
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        testing_env!(context.clone());
+        contract.register_claim(1, None);
+
+        assert_eq!(
+            contract.get_registration(1, claimant()).unwrap().deposit.0,
+            REGISTRATION_DEPOSIT.as_yoctonear()
+        );
+        assert_eq!(
+            contract.storage_balance_of(claimant()).unwrap().total,
+            NearToken::from_millinear(40)
+        );
+        assert_eq!(
+            contract.get_reserved_balance().0,
+            REGISTRATION_DEPOSIT.as_yoctonear()
+        );
+
+        // The unused registration is refunded to the storage balance
+        context.block_timestamp = end.0;
+        testing_env!(context.clone());
+        contract.refund_unused_registration(1, claimant());
+
+        assert!(scheduled_transfers().is_empty());
+        assert_eq!(
+            contract.storage_balance_of(claimant()).unwrap().total,
+            NearToken::from_millinear(50)
+        );
+        assert_eq!(contract.get_reserved_balance().0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Registration requires a deposit of at least 0.01 NEAR")]
+    fn test_register_claim_insufficient_storage_balance_failure() {
+        let (mut context, mut contract, _) = registration_campaign_setup();
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            claimant(),
+            None,
+            REGISTRATION_DEPOSIT,
+            None,
+        );
+        // The registration of another account uses up the storage balance
+        context.predecessor_account_id = claimant();
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        testing_env!(context);
+        contract.register_claim(1, Some(non_owner()));
+
+        contract.register_claim(1, None);
+    }
+
+    /// Gives the contract 100 NEAR, no locked balance to cover its storage and `count` campaigns.
+    fn storage_cost_setup(count: u32) -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        context.account_balance = NearToken::from_near(100);
+        context.account_locked_balance = NearToken::from_yoctonear(0);
+        set_caller(&mut context, account_owner());
+        create_campaigns(&mut context, &mut contract, count);
+
+        (context, contract)
+    }
+
+    /// Creates `count` campaigns, writing them to storage.
+    fn create_campaigns(context: &mut VMContext, contract: &mut MerkleClaim, count: u32) {
+        let (_, merkle_root, end) = build_mock_campaign();
+
+        for _ in 0..count {
+            contract.create_campaign(merkle_root.into(), Some(end), None);
+            contract.campaigns.flush();
+            // Keeps the grown storage while resetting the logs
+            context.storage_usage = env::storage_usage();
+            testing_env!(context.clone());
+        }
+    }
+
+    #[test]
+    fn test_required_storage_cost_grows_with_campaigns() {
+        let (mut context, mut contract) = storage_cost_setup(1);
+        let storage_cost = contract.get_required_storage_cost().0;
+        assert_eq!(
+            storage_cost,
+            Balance::from(env::storage_usage()) * env::storage_byte_cost().as_yoctonear()
+        );
+
+        create_campaigns(&mut context, &mut contract, 50);
+        let grown = contract.get_required_storage_cost().0;
+        assert!(grown > storage_cost);
+        // The storage cost exceeds `min_storage_deposit` and is kept instead
+        assert!(grown > MIN_STORAGE_DEPOSIT.as_yoctonear());
+        assert_eq!(
+            contract.available_balance(),
+            env::account_balance().as_yoctonear() - grown
+        );
+    }
+
+    #[test]
+    fn test_withdraw_leaves_storage_cost() {
+        let (mut context, mut contract) = storage_cost_setup(50);
+        let balance = env::account_balance().as_yoctonear();
+
+        contract.withdraw(None);
+
+        let storage_cost = contract.get_required_storage_cost().0;
+        let withdrawn = balance - storage_cost;
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), withdrawn)]);
+        assert!(env::account_balance().as_yoctonear() >= storage_cost);
+
+        // Forced withdrawals leave the storage cost as well
+        context.account_balance = env::account_balance();
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        set_caller(&mut context, account_owner());
+        assert_eq!(contract.withdrawable_balance(true), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal amount exceeds the available balance")]
+    fn test_withdraw_amount_into_storage_cost_failure() {
+        let (_, mut contract) = storage_cost_setup(50);
+        let available =
+            env::account_balance().as_yoctonear() - contract.get_required_storage_cost().0;
+
+        contract.withdraw_amount(NearToken::from_yoctonear(available + 1), None);
+    }
+
+    #[test]
+    fn test_storage_reserve_keeps_min_storage_deposit_floor() {
+        let (mut context, mut contract) = storage_cost_setup(1);
+
+        contract.update_config(config_patch(
+            r#"{"min_storage_deposit": "50000000000000000000000000"}"#,
+        ));
+
+        set_caller(&mut context, account_owner());
+        assert!(contract.get_required_storage_cost().0 < NearToken::from_near(50).as_yoctonear());
+        // The balance includes the attached yoctoNEAR
+        assert_eq!(
+            contract.available_balance(),
+            NearToken::from_near(50).as_yoctonear() + 1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient contract balance to fulfill claim")]
+    fn test_claim_cannot_use_storage_cost() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+
+        // The balance covers the claim but not the storage on top
+        context.account_locked_balance = NearToken::from_yoctonear(0);
+        set_caller(&mut context, account_owner());
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), None, None);
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000), vec![sibling], 1, None, None);
+    }
+
+    #[test]
+    fn test_purge_my_claim_refunds_storage() {
+        let (mut context, mut contract) = claimed_campaigns_setup();
+        let claimed_total = contract.get_campaign(1).unwrap().campaign.claimed_total;
+
+        flush_claim_records(&mut contract);
+        context.account_balance = NearToken::from_near(1);
+        context.storage_usage = env::storage_usage();
+        set_caller(&mut context, claimant());
+        let before = env::storage_usage();
+        let refund = contract.purge_my_claim(1, None).0;
+        flush_claim_records(&mut contract);
+
+        let released = before - env::storage_usage();
+        assert!(released > 0);
+        assert_eq!(
+            refund,
+            Balance::from(released) * env::storage_byte_cost().as_yoctonear()
+        );
+        assert_eq!(scheduled_transfers(), vec![(claimant(), refund)]);
+        let event: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(event["receiver_id"], "claimant");
+        assert_eq!(event["refund"], refund.to_string());
+
+        assert!(!contract.has_claimed(1, claimant()));
+        assert!(contract.has_claimed(1, non_owner()));
+        assert!(contract.has_claimed(2, claimant()));
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.claimed_total,
+            claimed_total
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign has not ended yet")]
+    fn test_purge_my_claim_before_end_failure() {
+        let (mut context, mut contract) = claimed_campaigns_setup();
+
+        context.block_timestamp -= 1;
+        set_caller(&mut context, claimant());
+        contract.purge_my_claim(1, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "No claim record to purge")]
+    fn test_purge_my_claim_without_claim_failure() {
+        let (mut context, mut contract) = claimed_campaigns_setup();
+
+        set_caller(&mut context, system_account());
+        contract.purge_my_claim(1, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "No claim record to purge")]
+    fn test_purge_my_claim_twice_failure() {
+        let (mut context, mut contract) = claimed_campaigns_setup();
+
+        // The first purge refunds the storage of the claim record
+        context.account_balance = NearToken::from_near(1);
+        set_caller(&mut context, claimant());
+        contract.purge_my_claim(1, None);
+        contract.purge_my_claim(1, None);
+    }
+
+    #[test]
+    fn test_purge_my_claim_of_each_period() {
+        let (mut context, mut contract, sibling) = periodic_campaign_setup();
+
+        for period in [0, 2] {
+            context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 7 * period);
+            testing_env!(context.clone());
+            contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        }
+
+        // After the last period ends, each claimed period's record is purged on its own
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 28u64);
+        context.account_balance = NearToken::from_near(1);
+        set_caller(&mut context, claimant());
+        assert!(contract.purge_my_claim(1, Some(0)).0 > 0);
+        assert!(contract.purge_my_claim(1, Some(2)).0 > 0);
+        assert_eq!(contract.get_campaign(1).unwrap().campaign.claim_count, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "No claim record to purge")]
+    fn test_purge_my_claim_of_unclaimed_period_failure() {
+        let (mut context, mut contract, sibling) = periodic_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 28u64);
+        context.account_balance = NearToken::from_near(1);
+        set_caller(&mut context, claimant());
+        contract.purge_my_claim(1, Some(1));
+    }
+
+    #[test]
+    fn test_purge_my_claim_of_nonce() {
+        let (mut contract, proofs) = nonce_campaign_setup(&claimant());
+
+        for (nonce, amount) in [(0, 1000), (1, 500)] {
+            contract.claim(
+                json_types::U128(amount),
+                proofs[nonce as usize].clone(),
+                1u32,
+                None,
+                nonce_options(nonce),
+            );
+        }
+
+        let mut context = basic_context();
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        context.account_balance = NearToken::from_near(1);
+        set_caller(&mut context, claimant());
+        assert!(contract.purge_my_claim(1, Some(1)).0 > 0);
+
+        assert!(contract.is_nonce_claimed(1, claimant(), 0));
+        assert!(!contract.is_nonce_claimed(1, claimant(), 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "No claim record to purge")]
+    fn test_purge_my_claim_without_nonce_failure() {
+        let (mut contract, proofs) = nonce_campaign_setup(&claimant());
+
+        contract.claim(
+            json_types::U128(500u128),
+            proofs[1].clone(),
+            1u32,
+            None,
+            nonce_options(1),
+        );
+
+        let mut context = basic_context();
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        set_caller(&mut context, claimant());
+        contract.purge_my_claim(1, None);
+    }
+
+    #[test]
+    fn test_purge_my_claim_refunds_sponsor() {
+        let (mut context, mut contract, sibling) = registration_campaign_setup();
+        let (_, _, end) = build_mock_campaign();
+
+        // A sponsor registers the claimant, who claims
+        register(&mut context, &mut contract, non_owner(), claimant());
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        callback_context(context.clone(), PromiseResult::Successful(vec![]));
+        let claim = VerifiedClaim {
+            campaign_id: 1,
+            account_id: claimant(),
+            lockup_contract: None,
+            receiver_id: claimant(),
+            allocation: json_types::U128(1000),
+            amount: json_types::U128(1000),
+            paid_amount: json_types::U128(1000),
+            index: None,
+            nonce: None,
+            liquid_bps: None,
+            new_account_key: None,
+            relayer_fee: None,
+            referrer: None,
+            referral_bonus: None,
+            donated: None,
+            claim_index: None,
+        };
+        assert!(contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(0)));
+
+        // Writes the claim record and the sponsor as the end of the call would
+        flush_claim_records(&mut contract);
+        contract.record_payers.flush();
+        context.storage_usage = env::storage_usage();
+        context.account_balance = NearToken::from_near(1);
+        context.block_timestamp = end.0;
+        set_caller(&mut context, claimant());
+        let refund = contract.purge_my_claim(1, None).0;
+
+        assert!(refund > 0);
+        assert_eq!(scheduled_transfers(), vec![(non_owner(), refund)]);
+        assert!(!contract.has_claimed(1, claimant()));
+    }
+
+    /// Creates a campaign whose claimants pay for storage, with a balance covering it.
+    fn claimant_pays_storage_setup() -> (VMContext, MerkleClaim, CryptoHash) {
+        let (mut context, mut contract) = claims_contract_setup();
+
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        context.account_balance = NearToken::from_near(1);
+        set_caller(&mut context, account_owner());
+        contract.create_campaign(
+            hash_pair(&leaf, &sibling).into(),
+            None,
+            Some(CampaignOptions {
+                claimant_pays_storage: true,
+                ..Default::default()
+            }),
+        );
+        flush_claim_records(&mut contract);
+        context.storage_usage = env::storage_usage();
+
+        (context, contract, sibling)
+    }
+
+    /// Claims the claimant's allocation with `deposit` attached and returns the storage used.
+    fn claim_paying_storage(
+        context: &mut VMContext,
+        contract: &mut MerkleClaim,
+        sibling: CryptoHash,
+        deposit: Balance,
+    ) -> Balance {
+        context.predecessor_account_id = claimant();
+        context.attached_deposit = NearToken::from_yoctonear(deposit);
+        testing_env!(context.clone());
+
+        let before = env::storage_usage();
+        contract.claim(json_types::U128(1000), vec![sibling], 1, None, None);
+        flush_claim_records(contract);
+
+        Balance::from(env::storage_usage() - before) * env::storage_byte_cost().as_yoctonear()
+    }
+
+    #[test]
+    fn test_claim_paying_storage_excess_deposit() {
+        let (mut context, mut contract, sibling) = claimant_pays_storage_setup();
+        let deposit = contract.get_claim_storage_deposit(1).as_yoctonear();
+
+        let used = claim_paying_storage(&mut context, &mut contract, sibling, deposit);
+
+        assert_eq!(used, CLAIM_STORAGE_COST);
+        assert_eq!(
+            scheduled_transfers(),
+            vec![(claimant(), deposit - used), (claimant(), 1000)]
+        );
+        assert!(contract.has_claimed(1, claimant()));
+    }
+
+    /// The storage the claim record of the claimant in `claimant_pays_storage_setup` uses
+    const CLAIM_STORAGE_COST: Balance = 2_530_000_000_000_000_000_000;
+
+    #[test]
+    fn test_claim_paying_storage_exact_deposit() {
+        let (mut context, mut contract, sibling) = claimant_pays_storage_setup();
+
+        claim_paying_storage(&mut context, &mut contract, sibling, CLAIM_STORAGE_COST);
+
+        // Only the payout is sent
+        assert_eq!(scheduled_transfers(), vec![(claimant(), 1000)]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Claim requires a storage deposit of 2530000000000000000000 yoctoNEAR"
+    )]
+    fn test_claim_paying_storage_insufficient_deposit_failure() {
+        let (mut context, mut contract, sibling) = claimant_pays_storage_setup();
+
+        claim_paying_storage(&mut context, &mut contract, sibling, CLAIM_STORAGE_COST - 1);
+    }
+
+    #[test]
+    fn test_claim_paying_storage_rollback_refunds_storage() {
+        let (mut context, mut contract, sibling) = claimant_pays_storage_setup();
+        claim_paying_storage(&mut context, &mut contract, sibling, CLAIM_STORAGE_COST);
+
+        context.storage_usage = env::storage_usage();
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        callback_context(context, PromiseResult::Failed);
+        let claim = VerifiedClaim {
+            campaign_id: 1,
+            account_id: claimant(),
+            lockup_contract: None,
+            receiver_id: claimant(),
+            allocation: json_types::U128(1000),
+            amount: json_types::U128(1000),
+            paid_amount: json_types::U128(1000),
+            index: None,
+            nonce: None,
+            liquid_bps: None,
+            new_account_key: None,
+            relayer_fee: None,
+            referrer: None,
+            referral_bonus: None,
+            donated: None,
+            claim_index: None,
+        };
+        let before = env::storage_usage();
+        assert!(!contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(0)));
+        flush_claim_records(&mut contract);
+
+        let released =
+            Balance::from(before - env::storage_usage()) * env::storage_byte_cost().as_yoctonear();
+        assert!(!contract.has_claimed(1, claimant()));
+        assert_eq!(scheduled_transfers(), vec![(claimant(), released)]);
+        // The campaign's collection of claim records stays
+        assert!(released > 0 && released < CLAIM_STORAGE_COST);
+    }
+
+    #[test]
+    fn test_claim_storage_deposit_of_other_campaigns() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let (_, merkle_root, end) = build_mock_campaign();
+
+        set_caller(&mut context, account_owner());
+        contract.create_campaign(merkle_root.into(), Some(end), None);
+
+        assert_eq!(
+            contract.get_claim_storage_deposit(1),
+            NearToken::from_yoctonear(0)
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Claimant paid storage does not support lockup owner or receiver checks"
+    )]
+    fn test_claimant_pays_storage_with_lockup_owner_check_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let (_, merkle_root, end) = build_mock_campaign();
+
+        set_caller(&mut context, account_owner());
+        contract.create_campaign(
+            merkle_root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                claimant_pays_storage: true,
+                verify_lockup_owner: true,
+                ..Default::default()
+            }),
+        );
+    }
+
+    /// Checks that the parts of the balance breakdown add up to the balance and that it
+    /// matches what `withdraw` sends.
+    fn balance_breakdown(contract: &MerkleClaim) -> BalanceBreakdown {
+        let breakdown = contract.get_balance_breakdown();
+
+        assert_eq!(
+            breakdown.total_balance.0,
+            breakdown.storage_reserve.0
+                + breakdown.reserved_for_campaigns.0
+                + breakdown.storage_balances.0
+                + breakdown.fee_accruals.0
+                + breakdown.withdrawable.0
+        );
+        assert_eq!(
+            breakdown.withdrawable.0,
+            contract.withdrawable_balance(false)
+        );
+        breakdown
+    }
+
+    #[test]
+    fn test_balance_breakdown() {
+        let (mut context, mut contract, sibling) = total_allocation_campaign_setup(1500);
+        let breakdown = balance_breakdown(&contract);
+        assert_eq!(
+            breakdown.storage_reserve.0,
+            MIN_STORAGE_DEPOSIT.as_yoctonear()
+        );
+        assert_eq!(breakdown.reserved_for_campaigns.0, 1500);
+        assert_eq!(
+            serde_json::to_value(&breakdown).unwrap()["reserved_for_campaigns"],
+            "1500"
+        );
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        let claimed = balance_breakdown(&contract);
+        assert_eq!(claimed.reserved_for_campaigns.0, 500);
+        assert_eq!(claimed.total_balance.0, breakdown.total_balance.0 - 1000);
+        assert_eq!(claimed.withdrawable, breakdown.withdrawable);
+
+        storage_deposit(
+            &mut context,
+            &mut contract,
+            non_owner(),
+            None,
+            REGISTRATION_DEPOSIT,
+            None,
+        );
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        set_caller(&mut context, account_owner());
+        let deposited = balance_breakdown(&contract);
+        assert_eq!(
+            deposited.storage_balances.0,
+            REGISTRATION_DEPOSIT.as_yoctonear()
+        );
+
+        contract.sweep_campaign(1);
+        let swept = balance_breakdown(&contract);
+        assert_eq!(swept.reserved_for_campaigns.0, 0);
+        assert_eq!(swept.total_balance.0, deposited.total_balance.0 - 500);
+        assert_eq!(swept.withdrawable, deposited.withdrawable);
+        assert_eq!(swept.fee_accruals.0, 0);
+    }
+
+    /// Donates `amount` to a campaign as `donor`, keeping the donation in the contract balance.
+    fn donate(
+        context: &mut VMContext,
+        contract: &mut MerkleClaim,
+        donor: AccountId,
+        campaign_id: CampaignId,
+        amount: Balance,
+    ) -> U128 {
+        let attached_deposit = context.attached_deposit;
+        context.predecessor_account_id = donor;
+        context.attached_deposit = NearToken::from_yoctonear(amount);
+        testing_env!(context.clone());
+
+        let donation_pool = contract.donate_to_campaign(campaign_id);
+
+        context.account_balance = context
+            .account_balance
+            .saturating_add(context.attached_deposit);
+        context.attached_deposit = attached_deposit;
+        donation_pool
+    }
+
+    #[test]
+    fn test_donate_to_campaign() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        assert_eq!(
+            donate(&mut context, &mut contract, non_owner(), 1, 700).0,
+            700
+        );
+        let funded: serde_json::Value = event_data(&get_logs()[0]);
+        assert_eq!(funded["campaign_id"], 1);
+        assert_eq!(funded["donor"], "non_owner");
+        assert_eq!(funded["amount"], "700");
+        assert_eq!(funded["new_total"], "700");
+
+        assert_eq!(
+            donate(&mut context, &mut contract, claimant(), 1, 300).0,
+            1000
+        );
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.donation_pool.0,
+            1000
+        );
+        assert_eq!(contract.get_reserved_balance().0, 2500);
+    }
+
+    #[test]
+    fn test_claim_paid_from_donations() {
+        let (mut context, mut contract) = reserved_withdraw_setup();
+        donate(&mut context, &mut contract, non_owner(), 1, 600);
+
+        set_caller(&mut context, claimant());
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        // The donations pay 600 of the claim, releasing as much of the reserved allocation
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.donation_pool.0,
+            0
+        );
+        assert_eq!(contract.get_reserved_balance().0, 500);
+
+        callback_context(context.clone(), PromiseResult::Failed);
+        let mut claim = claimant_claim(1);
+        claim.donated = Some(json_types::U128(600));
+        contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(0));
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.donation_pool.0,
+            600
+        );
+        assert_eq!(contract.get_reserved_balance().0, 2100);
+    }
+
+    #[test]
+    fn test_sweep_includes_donations() {
+        let (mut context, mut contract) = reserved_withdraw_setup();
+        donate(&mut context, &mut contract, non_owner(), 1, 500);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        set_caller(&mut context, account_owner());
+
+        assert_eq!(contract.sweep_campaign(1).0, 2000);
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), 2000)]);
+        assert_eq!(contract.get_reserved_balance().0, 0);
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.donation_pool.0,
+            0
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign has already ended")]
+    fn test_donate_to_ended_campaign_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        context.block_timestamp = to_ts(GENESIS_TIME_IN_DAYS + 30u64);
+        donate(&mut context, &mut contract, non_owner(), 1, 500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign has already ended")]
+    fn test_donate_to_cancelled_campaign_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+        cancel_campaign_as_owner(&context, &mut contract);
+
+        donate(&mut context, &mut contract, non_owner(), 1, 500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign does not declare its total allocation")]
+    fn test_donate_without_total_allocation_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+        set_caller(&mut context, account_owner());
+        let (_, merkle_root, end) = build_mock_campaign();
+        contract.create_campaign(merkle_root.into(), Some(end), None);
+
+        donate(&mut context, &mut contract, non_owner(), 1, 500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach a deposit to donate")]
+    fn test_donate_without_deposit_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        donate(&mut context, &mut contract, non_owner(), 1, 0);
+    }
+
+    fn campaign_storage_bytes(contract: &MerkleClaim, campaign_id: CampaignId) -> u64 {
+        contract
+            .get_campaign_stats(campaign_id)
+            .unwrap()
+            .storage_bytes
+    }
+
+    #[test]
+    fn test_campaign_stats_measure_creation() {
+        let (mut context, mut contract) = claims_contract_setup();
+        set_caller(&mut context, account_owner());
+        let (_, merkle_root, end) = build_mock_campaign();
+
+        let before = env::storage_usage();
+        contract.create_campaign(merkle_root.into(), Some(end), None);
+        flush_claim_records(&mut contract);
+        let used = env::storage_usage() - before;
+
+        let stats = contract.get_campaign_stats(1).unwrap();
+        assert!(used > 0);
+        assert_eq!(stats.storage_bytes, used);
+        assert_eq!(
+            stats.storage_cost,
+            env::storage_byte_cost().saturating_mul(u128::from(used))
+        );
+        assert_eq!(stats.claim_count, 0);
+        assert_eq!(contract.get_campaign_stats(2), None);
+    }
+
+    #[test]
+    fn test_campaign_stats_count_claims() {
+        let (mut context, mut contract, sibling) = total_allocation_campaign_setup(1500);
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), 1000);
+        let created = campaign_storage_bytes(&contract, 1);
+
+        let before = env::storage_usage();
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        flush_claim_records(&mut contract);
+        let first = env::storage_usage() - before;
+        assert!(first > 0);
+        assert_eq!(campaign_storage_bytes(&contract, 1), created + first);
+
+        set_caller(&mut context, non_owner());
+        let before = env::storage_usage();
+        contract.claim(json_types::U128(500u128), vec![leaf], 1u32, None, None);
+        flush_claim_records(&mut contract);
+        let second = env::storage_usage() - before;
+        assert_eq!(
+            campaign_storage_bytes(&contract, 1),
+            created + first + second
+        );
+
+        let stats = contract.get_campaign_stats(1).unwrap();
+        assert_eq!(stats.claim_count, 2);
+        assert_eq!(stats.claimed_total.0, 1500);
+    }
+
+    #[test]
+    fn test_campaign_stats_rolled_back_claim() {
+        let (context, mut contract, sibling) = total_allocation_campaign_setup(1500);
+        let created = campaign_storage_bytes(&contract, 1);
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        let claimed = campaign_storage_bytes(&contract, 1);
+        assert!(claimed > created);
+
+        callback_context(context, PromiseResult::Failed);
+        let before = env::storage_usage();
+        contract.on_claim_payout(claimant_claim(1), json_types::U128(0), json_types::U128(0));
+        flush_claim_records(&mut contract);
+
+        // Only the campaign's record collection, created by the claim, stays
+        let released = before - env::storage_usage();
+        assert!(released > 0);
+        assert_eq!(campaign_storage_bytes(&contract, 1), claimed - released);
+        assert!(campaign_storage_bytes(&contract, 1) > created);
+    }
+
+    #[test]
+    fn test_campaign_stats_purged_and_deleted_claims() {
+        let (mut context, mut contract) = claimed_campaigns_setup();
+        let claimed = campaign_storage_bytes(&contract, 1);
+
+        context.account_balance = NearToken::from_near(1);
+        set_caller(&mut context, claimant());
+        let refund = contract.purge_my_claim(1, None).0;
+        let released = u64::try_from(refund / env::storage_byte_cost().as_yoctonear()).unwrap();
+        assert!(released > 0);
+        assert_eq!(campaign_storage_bytes(&contract, 1), claimed - released);
+        assert_eq!(campaign_storage_bytes(&contract, 2), claimed);
+
+        set_caller(&mut context, account_owner());
+        let before = env::storage_usage();
+        assert!(!contract.delete_campaign(2, Some(1)));
+        flush_claim_records(&mut contract);
+        assert_eq!(
+            campaign_storage_bytes(&contract, 2),
+            claimed - (before - env::storage_usage())
+        );
+    }
+
+    /// Creates a campaign of 1000 for the claimant and 500 for `non_owner` with a
+    /// `low_balance_threshold` of 18000, with an available balance of 19000.
+    fn circuit_breaker_setup() -> (VMContext, MerkleClaim, CryptoHash, CryptoHash) {
+        let (mut context, mut contract) = claims_contract_setup();
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+
+        set_caller(&mut context, account_owner());
+        contract.update_config(config_patch(r#"{"low_balance_threshold": "18000"}"#));
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), None, None);
+
+        (context, contract, leaf, sibling)
+    }
+
+    /// Pays out a claim of `amount` by `account_id`, leaving the contract with `balance`.
+    fn settle_claim(
+        context: &mut VMContext,
+        contract: &mut MerkleClaim,
+        account_id: AccountId,
+        amount: Balance,
+        balance: Balance,
+        result: PromiseResult,
+    ) -> bool {
+        context.account_balance = NearToken::from_yoctonear(balance);
+        callback_context(context.clone(), result);
+        let mut claim = claimant_claim(1);
+        claim.account_id = account_id.clone();
+        claim.receiver_id = account_id;
+        claim.allocation = json_types::U128(amount);
+        claim.amount = json_types::U128(amount);
+        claim.paid_amount = json_types::U128(amount);
+        contract.on_claim_payout(claim, json_types::U128(0), json_types::U128(0))
+    }
+
+    #[test]
+    fn test_circuit_breaker_trips_mid_claims() {
+        let (mut context, mut contract, leaf, sibling) = circuit_breaker_setup();
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(settle_claim(
+            &mut context,
+            &mut contract,
+            claimant(),
+            1000,
+            19_000,
+            PromiseResult::Successful(vec![])
+        ));
+        assert_eq!(get_logs().len(), 1);
+        assert_eq!(event_name(&get_logs()[0]), "claim");
+        assert!(!contract.is_paused());
+
+        set_caller(&mut context, non_owner());
+        contract.claim(json_types::U128(500u128), vec![leaf], 1u32, None, None);
+        assert!(settle_claim(
+            &mut context,
+            &mut contract,
+            non_owner(),
+            500,
+            18_500,
+            PromiseResult::Successful(vec![])
+        ));
+
+        assert_eq!(contract.get_pause_flags(), PauseFlags::CLAIMS);
+        let tripped: serde_json::Value = event_data(&get_logs()[1]);
+        assert_eq!(tripped["low_balance_threshold"], "18000");
+        assert_eq!(
+            tripped["available_balance"],
+            contract.available_balance().to_string()
+        );
+        assert_eq!(tripped["pause_flags"], PauseFlags::CLAIMS.0);
+        assert_eq!(
+            contract.get_admin_log(None, None).last().unwrap().method,
+            "circuit_breaker"
+        );
+
+        // Only the owner resumes claims once the contract is topped up
+        context.account_balance = NearToken::from_near(1);
+        set_caller(&mut context, account_owner());
+        contract.unpause();
+        assert!(!contract.is_paused());
+    }
+
+    #[test]
+    #[should_panic(expected = "Claims are paused")]
+    fn test_circuit_breaker_blocks_later_claims() {
+        let (mut context, mut contract, leaf, sibling) = circuit_breaker_setup();
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        settle_claim(
+            &mut context,
+            &mut contract,
+            claimant(),
+            1000,
+            18_500,
+            PromiseResult::Successful(vec![]),
+        );
+
+        set_caller(&mut context, non_owner());
+        contract.claim(json_types::U128(500u128), vec![leaf], 1u32, None, None);
+    }
+
+    #[test]
+    fn test_circuit_breaker_trips_on_failed_payout() {
+        let (mut context, mut contract, _, sibling) = circuit_breaker_setup();
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        // The balance dropped for other reasons while the payout failed
+        assert!(!settle_claim(
+            &mut context,
+            &mut contract,
+            claimant(),
+            1000,
+            15_000,
+            PromiseResult::Failed
+        ));
+
+        assert!(!contract.has_claimed(1, claimant()));
+        assert_eq!(contract.get_pause_flags(), PauseFlags::CLAIMS);
+    }
+
+    #[test]
+    fn test_circuit_breaker_keeps_other_pause_flags() {
+        let (mut context, mut contract, _, sibling) = circuit_breaker_setup();
+        contract.set_pause_flags(PauseFlags::WITHDRAWALS);
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        settle_claim(
+            &mut context,
+            &mut contract,
+            claimant(),
+            1000,
+            10_000,
+            PromiseResult::Successful(vec![]),
+        );
+
+        assert_eq!(
+            contract.get_pause_flags(),
+            PauseFlags(PauseFlags::CLAIMS.0 | PauseFlags::WITHDRAWALS.0)
+        );
+    }
+
+    #[test]
+    fn test_circuit_breaker_unset_threshold() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), 1000);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        set_caller(&mut context, account_owner());
+        contract.create_campaign(hash_pair(&leaf, &sibling).into(), None, None);
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        settle_claim(
+            &mut context,
+            &mut contract,
+            claimant(),
+            1000,
+            2_000,
+            PromiseResult::Successful(vec![]),
+        );
+
+        assert!(!contract.is_paused());
+    }
+
+    #[test]
+    fn test_events_use_nep297_envelope() {
+        let (mut context, mut contract, _, sibling) = circuit_breaker_setup();
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(confirm_payout(&context, &mut contract));
+
+        let log = get_logs()[0].clone();
+        let envelope: serde_json::Value =
+            serde_json::from_str(log.strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(envelope["standard"], "merkle_claim");
+        assert_eq!(envelope["version"], "6.2.0");
+        assert_eq!(envelope["event"], "claim");
+        // The data keeps the fields of the former plain JSON logs
+        assert_eq!(envelope["data"][0]["campaign_id"], 1);
+        assert_eq!(envelope["data"][0]["account_id"], "claimant");
+        assert_eq!(envelope["data"][0], event_data(&log));
+    }
+
+    /// Parses an amount logged in an event back into yoctoNEAR.
+    fn event_amount(value: &serde_json::Value) -> Balance {
+        value
+            .as_str()
+            .expect("Amounts are logged as strings")
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_event_amounts_are_exact_strings() {
+        testing_env!(basic_context());
+
+        Event::VestingWithdraw(crate::vesting::VestingWithdrawEvent {
+            campaign_id: 1,
+            account_id: claimant(),
+            receiver_id: claimant(),
+            amount: json_types::U128(u128::MAX),
+        })
+        .emit();
+        Event::Withdraw(WithdrawEvent {
+            balance: NearToken::from_yoctonear(u128::MAX - 1),
+            withdrawn: NearToken::from_yoctonear((1 << 53) + 1),
+            receiver_id: account_owner(),
+            window_withdrawn: Some(json_types::U128(10u128.pow(24) + 1)),
+        })
+        .emit();
+
+        let logs = get_logs();
+        let vested = event_data(&logs[0]);
+        assert_eq!(event_amount(&vested["amount"]), u128::MAX);
+        let withdraw = event_data(&logs[1]);
+        assert_eq!(event_amount(&withdraw["balance"]), u128::MAX - 1);
+        assert_eq!(event_amount(&withdraw["withdrawn"]), (1 << 53) + 1);
+        assert_eq!(
+            event_amount(&withdraw["window_withdrawn"]),
+            10u128.pow(24) + 1
+        );
+    }
+
+    #[test]
+    fn test_claim_event_amounts_are_exact_strings() {
+        // An allocation of 1 NEAR and 7 yoctoNEAR, far above what a JSON number holds exactly
+        let amount = 10u128.pow(24) + 7;
+        let (mut context, mut contract) = claims_contract_setup();
+        let leaf = mock_leaf_v2(1, &claimant(), &claimant(), amount);
+        let sibling = mock_leaf_v2(1, &non_owner(), &non_owner(), 500);
+        context.account_balance = NearToken::from_near(10);
+        set_caller(&mut context, account_owner());
+        contract.create_campaign(
+            hash_pair(&leaf, &sibling).into(),
+            None,
+            Some(CampaignOptions {
+                total_allocation: Some(json_types::U128(amount + 500)),
+                ..Default::default()
+            }),
+        );
+
+        let donated = amount + 2;
+        donate(&mut context, &mut contract, non_owner(), 1, donated);
+        let funded = event_data(&get_logs()[0]);
+        assert_eq!(event_amount(&funded["amount"]), donated);
+        assert_eq!(event_amount(&funded["new_total"]), donated);
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(amount), vec![sibling], 1u32, None, None);
+        let claimed = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event_amount(&claimed["amount"]), amount);
+    }
+
+    /// Returns the NEP-297 event name of a log.
+    fn event_name(log: &str) -> String {
+        let log: serde_json::Value =
+            serde_json::from_str(log.strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        log["event"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_lifecycle_event_serialization() {
+        testing_env!(basic_context());
+        let prefix = r#"EVENT_JSON:{"standard":"merkle_claim","version":"6.2.0","event":"#;
+
+        Event::CampaignCancelled(crate::reserve::CampaignCancelledEvent {
+            campaign_id: 1,
+            released: json_types::U128(1500),
+            block_timestamp: json_types::U64(7),
+        })
+        .emit();
+        Event::CampaignExtended(crate::reserve::CampaignExtendedEvent {
+            campaign_id: 1,
+            old_end: json_types::U64(10),
+            new_end: json_types::U64(20),
+            block_timestamp: json_types::U64(7),
+        })
+        .emit();
+        Event::CampaignFunded(crate::donation::CampaignFundedEvent {
+            campaign_id: 1,
+            donor: non_owner(),
+            amount: json_types::U128(500),
+            new_total: json_types::U128(700),
+            block_timestamp: json_types::U64(7),
+        })
+        .emit();
+        Event::CampaignSwept(crate::reserve::CampaignSweptEvent {
+            campaign_id: 1,
+            amount: json_types::U128(1000),
+            receiver_id: account_owner(),
+            block_timestamp: json_types::U64(7),
+        })
+        .emit();
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                format!(
+                    r#"{prefix}"campaign_cancelled","data":[{{"campaign_id":1,"released":"1500","block_timestamp":"7"}}]}}"#
+                ),
+                format!(
+                    r#"{prefix}"campaign_extended","data":[{{"campaign_id":1,"old_end":"10","new_end":"20","block_timestamp":"7"}}]}}"#
+                ),
+                format!(
+                    r#"{prefix}"campaign_funded","data":[{{"campaign_id":1,"donor":"non_owner","amount":"500","new_total":"700","block_timestamp":"7"}}]}}"#
+                ),
+                format!(
+                    r#"{prefix}"campaign_swept","data":[{{"campaign_id":1,"amount":"1000","receiver_id":"account_owner","block_timestamp":"7"}}]}}"#
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lifecycle_events_logged() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+        let (_, _, end) = build_mock_campaign();
+        let new_end = json_types::U64(end.0 + to_nanos(10));
+
+        donate(&mut context, &mut contract, non_owner(), 1, 500);
+        assert_eq!(event_name(&get_logs()[0]), "campaign_funded");
+
+        set_caller(&mut context, account_owner());
+        contract.extend_campaign(1, new_end);
+        assert_eq!(event_name(&get_logs()[0]), "campaign_extended");
+        let extended = event_data(&get_logs()[0]);
+        assert_eq!(extended["campaign_id"], 1);
+        assert_eq!(extended["old_end"], end.0.to_string());
+        assert_eq!(extended["new_end"], new_end.0.to_string());
+        assert_eq!(
+            extended["block_timestamp"],
+            context.block_timestamp.to_string()
+        );
+
+        // The campaign still runs at its former end
+        context.block_timestamp = end.0;
+        set_caller(&mut context, account_owner());
+        assert_eq!(
+            contract.get_campaign_status(1),
+            Some(CampaignStatus::Active)
+        );
+
+        context.block_timestamp = new_end.0;
+        set_caller(&mut context, account_owner());
+        contract.sweep_campaign(1);
+        let swept = get_logs().pop().unwrap();
+        assert_eq!(event_name(&swept), "campaign_swept");
+        assert_eq!(event_data(&swept)["amount"], "2000");
+    }
+
+    #[test]
+    fn test_cancel_campaign_logs_event() {
+        let (context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        cancel_campaign_as_owner(&context, &mut contract);
+
+        let cancelled = get_logs().pop().unwrap();
+        assert_eq!(event_name(&cancelled), "campaign_cancelled");
+        assert_eq!(event_data(&cancelled)["released"], "1500");
+        assert_eq!(
+            event_data(&cancelled)["block_timestamp"],
+            context.block_timestamp.to_string()
+        );
+    }
+
+    #[test]
+    fn test_extend_campaign_by_multisig() {
+        let (mut context, mut contract) = multisig_setup(3, 1);
+        let (_, merkle_root, end) = build_mock_campaign();
+        contract.propose_action(OwnerAction::CreateCampaign {
+            merkle_root: merkle_root.into(),
+            claim_end: Some(end),
+            options: None,
+        });
+
+        set_caller(&mut context, signer(1));
+        let new_end = json_types::U64(end.0 + 1);
+        contract.propose_action(OwnerAction::ExtendCampaign {
+            campaign_id: 1,
+            claim_end: new_end,
+        });
+
+        assert_eq!(
+            contract.get_campaign(1).unwrap().campaign.claim_end,
+            Some(new_end)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "New claim end must be later than the current one")]
+    fn test_extend_campaign_earlier_end_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+        let (_, _, end) = build_mock_campaign();
+
+        set_caller(&mut context, account_owner());
+        contract.extend_campaign(1, end);
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign has already ended")]
+    fn test_extend_ended_campaign_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+        let (_, _, end) = build_mock_campaign();
+
+        context.block_timestamp = end.0;
+        set_caller(&mut context, account_owner());
+        contract.extend_campaign(1, json_types::U64(end.0 + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Periodic campaigns end with their last period")]
+    fn test_extend_periodic_campaign_failure() {
+        let (mut context, mut contract, _) = periodic_campaign_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.extend_campaign(1, json_types::U64(u64::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaigns without a claim end cannot be extended")]
+    fn test_extend_perpetual_campaign_failure() {
+        let (mut context, mut contract, _, _) = circuit_breaker_setup();
+
+        set_caller(&mut context, account_owner());
+        contract.extend_campaign(1, json_types::U64(u64::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_extend_campaign_non_owner_failure() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+
+        set_caller(&mut context, non_owner());
+        contract.extend_campaign(1, json_types::U64(u64::MAX));
+    }
+
+    #[test]
+    fn test_claim_event_logged_once_payout_confirmed() {
+        let (context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(get_logs().is_empty());
+        let (claim, _, _) = pending_payout();
+        assert_eq!(claim.claim_index, Some(json_types::U64(0)));
+
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(get_logs().len(), 1);
+        assert_eq!(event["account_id"], claimant().to_string());
+        assert_eq!(event["amount"], "1000");
+        assert_eq!(event["claim_index"], "0");
+    }
+
+    #[test]
+    fn test_failed_payout_logs_claim_failed() {
+        let (context, mut contract, sibling) = partial_claim_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        let (claim, fee, bonus) = pending_payout();
+        callback_context(context, PromiseResult::Failed);
+        assert!(!contract.on_claim_payout(claim, fee, bonus));
+
+        // Only the failure is logged, the claim itself never was
+        assert_eq!(get_logs().len(), 1);
+        assert_eq!(event_name(&get_logs()[0]), "claim_failed");
+        let event = event_data(&get_logs()[0]);
+        assert_eq!(event["campaign_id"], 1);
+        assert_eq!(event["account_id"], claimant().to_string());
+        assert!(event["lockup_contract"].is_null());
+        assert_eq!(event["amount"], "1000");
+        assert_eq!(event["reason"], "Payout transfer failed");
+        assert!(!contract.has_claimed(1, claimant()));
+    }
+
+    /// Returns one event of every variant, with fixed data.
+    fn event_samples() -> Vec<Event> {
+        let root = [0x11; 32];
+        let amount = json_types::U128(1000);
+        let timestamp = json_types::U64(7);
+
+        vec![
+            Event::CampaignCreated(CampaignCreatedEvent {
+                campaign_id: 1,
+                merkle_root: root,
+                merkle_root_hex: encode_hex_hash(&root),
+                claim_end: Some(json_types::U64(10)),
+                hash_algo: Default::default(),
+                proof_format: Default::default(),
+                leaf_count: Some(2),
+                leaf_encoding: Default::default(),
+                leaf_hashing: Default::default(),
+                leaf_version: Default::default(),
+                claim_tracking: Default::default(),
+                verify_lockup_owner: false,
+                verify_receiver_exists: false,
+                allow_partial_claims: false,
+                vesting_end: None,
+                decay: None,
+                bonus: None,
+                allow_liquid_split: false,
+                periods: None,
+                allocation_mode: Default::default(),
+                total_allocation: Some(amount),
+                payout_call: None,
+                require_registration: false,
+                referral: None,
+                claimant_pays_storage: false,
+                memo: Some("Season 1".to_string()),
+            }),
+            Event::Claim(ClaimEvent {
+                campaign_id: 1,
+                account_id: claimant(),
+                lockup_contract: None,
+                receiver_id: claimant(),
+                amount,
+                paid_amount: None,
+                bonus: None,
+                fee: Some(json_types::U128(25)),
+                fee_receiver: Some(dao_treasury()),
+                net_amount: Some(json_types::U128(975)),
+                lockup_amount: None,
+                liquid_amount: None,
+                relayer_fee: None,
+                max_relayer_fee: None,
+                period: None,
+                claim_index: json_types::U64(0),
+                memo: Some("Season 1".to_string()),
+                block_timestamp: timestamp,
+                block_height: json_types::U64(3),
+            }),
+            Event::ClaimFailed(crate::payout::ClaimFailedEvent {
+                campaign_id: 1,
+                account_id: claimant(),
+                lockup_contract: None,
+                amount,
+                reason: "Payout transfer failed".to_string(),
+            }),
+            Event::ClaimPayoutFailed(crate::payout::ClaimPayoutFailedEvent {
+                campaign_id: 1,
+                account_id: claimant(),
+                undelivered: amount,
+            }),
+            Event::ClaimRegistered(crate::registration::ClaimRegisteredEvent {
+                campaign_id: 1,
+                account_id: claimant(),
+                payer: non_owner(),
+                deposit: amount,
+            }),
+            Event::ClaimPurged(crate::record::ClaimPurgedEvent {
+                campaign_id: 1,
+                account_id: claimant(),
+                receiver_id: non_owner(),
+                refund: amount,
+            }),
+            Event::Referral(crate::referral::ReferralEvent {
+                campaign_id: 1,
+                account_id: claimant(),
+                referrer: referrer(),
+                bonus: amount,
+            }),
+            Event::VestingWithdraw(crate::vesting::VestingWithdrawEvent {
+                campaign_id: 1,
+                account_id: claimant(),
+                receiver_id: claimant(),
+                amount,
+            }),
+            Event::BonusPoolFunded(crate::bonus::BonusPoolFundedEvent {
+                campaign_id: 1,
+                amount,
+                bonus_pool: amount,
+            }),
+            Event::ReferralPoolFunded(crate::referral::ReferralPoolFundedEvent {
+                campaign_id: 1,
+                amount,
+                referral_pool: amount,
+            }),
+            Event::CampaignFunded(crate::donation::CampaignFundedEvent {
+                campaign_id: 1,
+                donor: non_owner(),
+                amount,
+                new_total: amount,
+                block_timestamp: timestamp,
+            }),
+            Event::CampaignCancelled(crate::reserve::CampaignCancelledEvent {
+                campaign_id: 1,
+                released: amount,
+                block_timestamp: timestamp,
+            }),
+            Event::CampaignExtended(crate::reserve::CampaignExtendedEvent {
+                campaign_id: 1,
+                old_end: json_types::U64(10),
+                new_end: json_types::U64(20),
+                block_timestamp: timestamp,
+            }),
+            Event::CampaignMemoUpdated(crate::memo::CampaignMemoUpdatedEvent {
+                campaign_id: 1,
+                old_memo: Some("Season 1".to_string()),
+                new_memo: None,
+            }),
+            Event::CampaignSwept(crate::reserve::CampaignSweptEvent {
+                campaign_id: 1,
+                amount,
+                receiver_id: account_owner(),
+                block_timestamp: timestamp,
+            }),
+            Event::CampaignDeleted(crate::record::CampaignDeletedEvent { campaign_id: 1 }),
+            Event::LeafInvalidated(crate::invalidation::LeafInvalidatedEvent {
+                campaign_id: 1,
+                account_id: claimant(),
+            }),
+            Event::AccountBlocked(crate::blocklist::AccountBlockedEvent {
+                account_id: claimant(),
+                blocked: true,
+            }),
+            Event::Withdraw(WithdrawEvent {
+                balance: NearToken::from_yoctonear(5000),
+                withdrawn: NearToken::from_yoctonear(1000),
+                receiver_id: account_owner(),
+                window_withdrawn: None,
+            }),
+            Event::WithdrawFailed(crate::withdrawal::WithdrawFailedEvent {
+                amount: NearToken::from_yoctonear(1000),
+                receiver_id: account_owner(),
+            }),
+            Event::WithdrawRequested(crate::withdrawal::WithdrawRequestedEvent {
+                amount,
+                unlocks_at: timestamp,
+            }),
+            Event::WithdrawCancelled(crate::withdrawal::WithdrawCancelledEvent {
+                amount,
+                account_id: account_owner(),
+            }),
+            Event::ReservedFundsWithdrawn(crate::withdrawal::ReservedFundsWithdrawnEvent {
+                reserved_withdrawn: NearToken::from_yoctonear(1000),
+                reserved_balance: json_types::U128(0),
+                receiver_id: account_owner(),
+            }),
+            Event::StorageDeposit(crate::storage::StorageDepositEvent {
+                depositor: non_owner(),
+                account_id: claimant(),
+                attached: NearToken::from_yoctonear(1500),
+                credited: NearToken::from_yoctonear(1000),
+                refunded: NearToken::from_yoctonear(500),
+                min_storage_deposit: None,
+            }),
+            Event::StorageDepositReclaimed(crate::storage::StorageDepositReclaimedEvent {
+                account_id: non_owner(),
+                amount: NearToken::from_yoctonear(1000),
+                credited: NearToken::from_yoctonear(500),
+                min_storage_deposit: MIN_STORAGE_DEPOSIT,
+            }),
+            Event::StorageWithdraw(crate::storage::StorageWithdrawEvent {
+                account_id: claimant(),
+                amount: NearToken::from_yoctonear(1000),
+                remaining: NearToken::from_yoctonear(0),
+                unregistered: true,
+            }),
+            Event::StorageSurplusWithdrawn(crate::storage::StorageSurplusWithdrawnEvent {
+                amount: NearToken::from_yoctonear(1000),
+                storage_cost: NearToken::from_yoctonear(2000),
+                min_storage_deposit: MIN_STORAGE_DEPOSIT,
+            }),
+            Event::ConfigUpdated(crate::config::ConfigUpdatedEvent {
+                changed_fields: vec!["claim_fee_bps"],
+                config: fee_config(250, None),
+            }),
+            Event::ConfigChangeRequested(crate::config::PendingConfigChange {
+                withdraw_timelock: Some(U64(0)),
+                withdraw_limit: Some(None),
+                requested_at: U64(1000),
+                executes_at: U64(2000),
+            }),
+            Event::ConfigChangeCancelled(crate::config::ConfigChangeCancelledEvent {
+                account_id: guardian(),
+            }),
+            Event::Paused(crate::pause::PausedEvent {
+                pause_flags: PauseFlags::CLAIMS,
+                account_id: account_owner(),
+            }),
+            Event::CircuitBreakerTripped(crate::pause::CircuitBreakerTrippedEvent {
+                available_balance: amount,
+                low_balance_threshold: NearToken::from_yoctonear(2000),
+                pause_flags: PauseFlags::CLAIMS,
+            }),
+            Event::Guardian(crate::guardian::GuardianEvent {
+                account_id: non_owner(),
+                added: true,
+            }),
+            Event::FinancialOperator(crate::operator::FinancialOperatorEvent {
+                account_id: non_owner(),
+                granted: true,
+            }),
+            Event::OperatorKey(crate::access_key::OperatorKeyEvent {
+                public_key: operator_key(),
+                added: true,
+            }),
+            Event::OwnershipProposed(crate::ownership::OwnershipProposedEvent {
+                owner_account_id: account_owner(),
+                pending_owner: non_owner(),
+            }),
+            Event::OwnershipTransferred(crate::ownership::OwnershipTransferredEvent {
+                previous_owner: account_owner(),
+                owner_account_id: non_owner(),
+            }),
+            Event::ActionProposed(crate::admin::ActionProposedEvent {
+                proposal_id: 1,
+                proposer: account_owner(),
+                action: OwnerAction::Withdraw,
+                expires_at: timestamp,
+            }),
+            Event::ActionConfirmed(crate::admin::ActionConfirmedEvent {
+                proposal_id: 1,
+                account_id: non_owner(),
+                confirmations: 2,
+                executed: true,
+            }),
+            Event::MultisigUpdated(crate::admin::MultisigUpdatedEvent { multisig: None }),
+            Event::EmergencyShutdownApproved(crate::emergency::EmergencyShutdownApprovedEvent {
+                account_id: non_owner(),
+            }),
+            Event::EmergencyShutdownRevoked(crate::emergency::EmergencyShutdownRevokedEvent {
+                account_id: non_owner(),
+            }),
+            Event::EmergencyShutdown(crate::emergency::EmergencyShutdownEvent {
+                amount,
+                receiver_id: account_owner(),
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_event_log_snapshots() {
+        testing_env!(basic_context());
+
+        let samples = event_samples();
+        let names: Vec<_> = samples.iter().map(Event::name).collect();
+        assert_eq!(names, Event::NAMES);
+        samples.into_iter().for_each(Event::emit);
+
+        // Any change to these strings changes what indexers parse, and needs a version bump
+        let prefix = r#"EVENT_JSON:{"standard":"merkle_claim","version":"6.2.0","event":"#;
+        let snapshots = [
+            r#""campaign_created","data":[{"campaign_id":1,"merkle_root":"29d2S7vB453rNYFdR5Ycwt7y9haRT5fwVwL9zTmBhfV2","merkle_root_hex":"0x1111111111111111111111111111111111111111111111111111111111111111","claim_end":"10","hash_algo":"Keccak256","proof_format":"Commutative","leaf_count":2,"leaf_encoding":"Borsh","leaf_hashing":"Double","leaf_version":"V2","claim_tracking":"Keys","verify_lockup_owner":false,"verify_receiver_exists":false,"allow_partial_claims":false,"vesting_end":null,"decay":null,"bonus":null,"allow_liquid_split":false,"periods":null,"allocation_mode":"PerLeaf","total_allocation":"1000","payout_call":null,"require_registration":false,"referral":null,"claimant_pays_storage":false,"memo":"Season 1"}]}"#,
+            r#""claim","data":[{"campaign_id":1,"account_id":"claimant","lockup_contract":null,"receiver_id":"claimant","amount":"1000","fee":"25","fee_receiver":"dao-treasury","net_amount":"975","claim_index":"0","memo":"Season 1","block_timestamp":"7","block_height":"3"}]}"#,
+            r#""claim_failed","data":[{"campaign_id":1,"account_id":"claimant","lockup_contract":null,"amount":"1000","reason":"Payout transfer failed"}]}"#,
+            r#""claim_payout_failed","data":[{"campaign_id":1,"account_id":"claimant","undelivered":"1000"}]}"#,
+            r#""claim_registered","data":[{"campaign_id":1,"account_id":"claimant","payer":"non_owner","deposit":"1000"}]}"#,
+            r#""claim_purged","data":[{"campaign_id":1,"account_id":"claimant","receiver_id":"non_owner","refund":"1000"}]}"#,
+            r#""referral","data":[{"campaign_id":1,"account_id":"claimant","referrer":"referrer","bonus":"1000"}]}"#,
+            r#""vesting_withdraw","data":[{"campaign_id":1,"account_id":"claimant","receiver_id":"claimant","amount":"1000"}]}"#,
+            r#""bonus_pool_funded","data":[{"campaign_id":1,"amount":"1000","bonus_pool":"1000"}]}"#,
+            r#""referral_pool_funded","data":[{"campaign_id":1,"amount":"1000","referral_pool":"1000"}]}"#,
+            r#""campaign_funded","data":[{"campaign_id":1,"donor":"non_owner","amount":"1000","new_total":"1000","block_timestamp":"7"}]}"#,
+            r#""campaign_cancelled","data":[{"campaign_id":1,"released":"1000","block_timestamp":"7"}]}"#,
+            r#""campaign_extended","data":[{"campaign_id":1,"old_end":"10","new_end":"20","block_timestamp":"7"}]}"#,
+            r#""campaign_memo_updated","data":[{"campaign_id":1,"old_memo":"Season 1","new_memo":null}]}"#,
+            r#""campaign_swept","data":[{"campaign_id":1,"amount":"1000","receiver_id":"account_owner","block_timestamp":"7"}]}"#,
+            r#""campaign_deleted","data":[{"campaign_id":1}]}"#,
+            r#""leaf_invalidated","data":[{"campaign_id":1,"account_id":"claimant"}]}"#,
+            r#""account_blocked","data":[{"account_id":"claimant","blocked":true}]}"#,
+            r#""withdraw","data":[{"balance":"5000","withdrawn":"1000","receiver_id":"account_owner"}]}"#,
+            r#""withdraw_failed","data":[{"amount":"1000","receiver_id":"account_owner"}]}"#,
+            r#""withdraw_requested","data":[{"amount":"1000","unlocks_at":"7"}]}"#,
+            r#""withdraw_cancelled","data":[{"amount":"1000","account_id":"account_owner"}]}"#,
+            r#""reserved_funds_withdrawn","data":[{"reserved_withdrawn":"1000","reserved_balance":"0","receiver_id":"account_owner"}]}"#,
+            r#""storage_deposit","data":[{"depositor":"non_owner","account_id":"claimant","attached":"1500","credited":"1000","refunded":"500"}]}"#,
+            r#""storage_deposit_reclaimed","data":[{"account_id":"non_owner","amount":"1000","credited":"500","min_storage_deposit":"1000"}]}"#,
+            r#""storage_withdraw","data":[{"account_id":"claimant","amount":"1000","remaining":"0","unregistered":true}]}"#,
+            r#""storage_surplus_withdrawn","data":[{"amount":"1000","storage_cost":"2000","min_storage_deposit":"1000"}]}"#,
+            r#""config_updated","data":[{"changed_fields":["claim_fee_bps"],"config":{"owner_account_id":"account_owner","min_storage_deposit":"1000","allowed_lockup_suffix":null,"max_proof_length":64,"claim_fee_bps":250,"fee_receiver":null,"min_claim_amount":null,"max_claim_amount":null,"max_relayer_fee":null,"withdraw_timelock":"0","default_withdraw_receiver":null,"treasury_account_id":null,"withdraw_limit":null,"low_balance_threshold":null,"max_total_extension":null}}]}"#,
+            r#""config_change_requested","data":[{"withdraw_timelock":"0","withdraw_limit":null,"requested_at":"1000","executes_at":"2000"}]}"#,
+            r#""config_change_cancelled","data":[{"account_id":"guardian"}]}"#,
+            r#""paused","data":[{"pause_flags":1,"account_id":"account_owner"}]}"#,
+            r#""circuit_breaker_tripped","data":[{"available_balance":"1000","low_balance_threshold":"2000","pause_flags":1}]}"#,
+            r#""guardian","data":[{"account_id":"non_owner","added":true}]}"#,
+            r#""financial_operator","data":[{"account_id":"non_owner","granted":true}]}"#,
+            r#""operator_key","data":[{"public_key":"ed25519:US517G5965aydkZ46HS38QLi7UQiSojurfbQfKCELFx","added":true}]}"#,
+            r#""ownership_proposed","data":[{"owner_account_id":"account_owner","pending_owner":"non_owner"}]}"#,
+            r#""ownership_transferred","data":[{"previous_owner":"account_owner","owner_account_id":"non_owner"}]}"#,
+            r#""action_proposed","data":[{"proposal_id":1,"proposer":"account_owner","action":"Withdraw","expires_at":"7"}]}"#,
+            r#""action_confirmed","data":[{"proposal_id":1,"account_id":"non_owner","confirmations":2,"executed":true}]}"#,
+            r#""multisig_updated","data":[{"multisig":null}]}"#,
+            r#""emergency_shutdown_approved","data":[{"account_id":"non_owner"}]}"#,
+            r#""emergency_shutdown_revoked","data":[{"account_id":"non_owner"}]}"#,
+            r#""emergency_shutdown","data":[{"amount":"1000","receiver_id":"account_owner"}]}"#,
+        ];
+        let logs = get_logs();
+        assert_eq!(logs.len(), snapshots.len());
+        for (log, snapshot) in logs.iter().zip(snapshots) {
+            assert_eq!(*log, format!("{prefix}{snapshot}"));
+        }
+    }
+
+    /// Creates a campaign with the root of `build_mock_campaign`.
+    fn root_campaign_setup() -> MerkleClaim {
+        let (mut context, mut contract) = claims_contract_setup();
+        set_caller(&mut context, account_owner());
+        let (_, root, end) = build_mock_campaign();
+        contract.create_campaign(root.into(), Some(end), None);
+
+        contract
+    }
+
+    #[test]
+    fn test_campaign_root_serialized_as_base58() {
+        let contract = root_campaign_setup();
+        let root = contract.get_campaign(1).unwrap().campaign.merkle_root;
+        let b58 = String::from(&Base58CryptoHash::from(root));
+
+        let view = serde_json::to_value(contract.get_campaign(1).unwrap()).unwrap();
+        assert_eq!(view["merkle_root"], b58);
+        assert_eq!(view["merkle_root_b58"], b58);
+
+        // The JSON of a campaign reads back into the same root
+        let campaign: RewardCampaign = serde_json::from_value(view).unwrap();
+        assert_eq!(campaign.merkle_root, root);
+
+        let event = event_data(&get_logs()[0]);
+        assert_eq!(event_name(&get_logs()[0]), "campaign_created");
+        assert_eq!(event["merkle_root"], b58);
+        assert!(event.get("merkle_root_b58").is_none());
+    }
+
+    #[test]
+    fn test_malformed_base58_root_rejected() {
+        let contract = root_campaign_setup();
+        let view = serde_json::to_value(contract.get_campaign(1).unwrap()).unwrap();
+
+        for root in [
+            serde_json::json!("not base58!"),
+            // Valid base58, but not 32 bytes
+            serde_json::json!("3mJr7AoUXx2Wqd"),
+            serde_json::json!([0u8; 32].to_vec()),
+        ] {
+            let mut view = view.clone();
+            view["merkle_root"] = root;
+            assert!(serde_json::from_value::<RewardCampaign>(view).is_err());
+        }
+    }
+
+    #[test]
+    fn test_merkle_root_input_round_trip() {
+        let root = [0x11; 32];
+        let json = serde_json::to_value(MerkleRootInput::Bytes(root)).unwrap();
+        assert_eq!(json, "29d2S7vB453rNYFdR5Ycwt7y9haRT5fwVwL9zTmBhfV2");
+
+        // Read back as an encoded root, which decodes to the same bytes
+        let input: MerkleRootInput = serde_json::from_value(json).unwrap();
+        assert_eq!(input.to_hash(), root);
+
+        // Roots are still accepted as bytes
+        let input: MerkleRootInput = serde_json::from_value(serde_json::json!(root)).unwrap();
+        assert_eq!(input.to_hash(), root);
+    }
+
+    #[test]
+    fn test_new_logs_storage_deposit() {
+        let mut context = basic_context();
+        context.attached_deposit =
+            MIN_STORAGE_DEPOSIT.saturating_add(NearToken::from_yoctonear(500));
+        testing_env!(context);
+
+        MerkleClaim::new(fee_config(0, None));
+
+        // Only the storage deposit is kept, the rest goes back to the deployer
+        assert_eq!(scheduled_transfers(), vec![(system_account(), 500)]);
+        assert_eq!(event_name(&get_logs()[0]), "storage_deposit");
+        let event = event_data(&get_logs()[0]);
+        assert_eq!(event["depositor"], system_account().to_string());
+        assert_eq!(event["account_id"], account_owner().to_string());
+        assert_eq!(event["attached"], "1500");
+        assert_eq!(event["credited"], "1000");
+        assert_eq!(event["refunded"], "500");
+        assert_eq!(event["min_storage_deposit"], "1000");
+    }
+
+    fn memo_campaign_setup(memo: Option<&str>) -> (VMContext, MerkleClaim, CryptoHash) {
+        options_campaign_setup(CampaignOptions {
+            memo: memo.map(str::to_string),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_campaign_memo_set_at_create() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let (_, merkle_root, end) = build_mock_campaign();
+
+        set_caller(&mut context, account_owner());
+        contract.create_campaign(
+            merkle_root.into(),
+            Some(end),
+            Some(CampaignOptions {
+                memo: Some("Season 1 rewards".to_string()),
+                ..Default::default()
+            }),
+        );
+
+        let campaign = contract.get_campaign(1).unwrap().campaign;
+        assert_eq!(campaign.memo.as_deref(), Some("Season 1 rewards"));
+
+        let created = get_logs().into_iter().next_back().unwrap();
+        assert_eq!(event_name(&created), "campaign_created");
+        assert_eq!(event_data(&created)["memo"], "Season 1 rewards");
+    }
+
+    #[test]
+    fn test_set_campaign_memo() {
+        let (mut context, mut contract, _) = memo_campaign_setup(Some("Season 1"));
+        let storage_bytes = contract.get_campaign(1).unwrap().campaign.storage_bytes;
+
+        set_caller(&mut context, account_owner());
+        contract.set_campaign_memo(1, Some("Season 1, extended to all holders".to_string()));
+
+        let campaign = contract.get_campaign(1).unwrap().campaign;
+        assert_eq!(
+            campaign.memo.as_deref(),
+            Some("Season 1, extended to all holders")
+        );
+        assert!(campaign.storage_bytes > storage_bytes);
+
+        let event = event_data(&get_logs()[0]);
+        assert_eq!(event_name(&get_logs()[0]), "campaign_memo_updated");
+        assert_eq!(event["old_memo"], "Season 1");
+        assert_eq!(event["new_memo"], "Season 1, extended to all holders");
+
+        set_caller(&mut context, account_owner());
+        contract.set_campaign_memo(1, None);
+        assert_eq!(contract.get_campaign(1).unwrap().campaign.memo, None);
+        assert!(contract.get_campaign(1).unwrap().campaign.storage_bytes < storage_bytes);
+    }
+
+    #[test]
+    fn test_claim_event_echoes_campaign_memo() {
+        let (mut context, mut contract, sibling) = memo_campaign_setup(Some("Season 1"));
+        set_caller(&mut context, account_owner());
+        contract.set_campaign_memo(1, Some("Season 2".to_string()));
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert_eq!(event["memo"], "Season 2");
+    }
+
+    #[test]
+    fn test_claim_event_without_memo_omits_it() {
+        let (context, mut contract, sibling) = memo_campaign_setup(None);
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        let event = confirmed_claim_event(&context, &mut contract);
+        assert!(event.get("memo").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Memo must not exceed 256 bytes")]
+    fn test_campaign_memo_too_long_failure() {
+        memo_campaign_setup(Some(&"a".repeat(MAX_CAMPAIGN_MEMO_LENGTH + 1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Memo must not exceed 256 bytes")]
+    fn test_set_campaign_memo_too_long_failure() {
+        let (mut context, mut contract, _) = memo_campaign_setup(None);
+
+        set_caller(&mut context, account_owner());
+        contract.set_campaign_memo(1, Some("a".repeat(MAX_CAMPAIGN_MEMO_LENGTH + 1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign has already ended")]
+    fn test_set_ended_campaign_memo_failure() {
+        let (mut context, mut contract, _) = memo_campaign_setup(None);
+        let (_, _, end) = build_mock_campaign();
+
+        context.block_timestamp = end.0;
+        set_caller(&mut context, account_owner());
+        contract.set_campaign_memo(1, Some("Too late".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_set_campaign_memo_non_owner_failure() {
+        let (mut context, mut contract, _) = memo_campaign_setup(None);
+
+        set_caller(&mut context, non_owner());
+        contract.set_campaign_memo(1, Some("Season 1".to_string()));
+    }
+
+    #[test]
+    fn test_claim_context_unclaimed() {
+        let (context, contract, _) = total_allocation_campaign_setup(1500);
+        let (_, _, end) = build_mock_campaign();
+
+        let claim_context = contract.get_claim_context(1, claimant()).unwrap();
+        assert_eq!(claim_context.status, CampaignStatus::Active);
+        assert!(!claim_context.has_claimed);
+        assert_eq!(
+            claim_context.seconds_remaining,
+            Some(json_types::U64(
+                (end.0 - context.block_timestamp) / 1_000_000_000
+            ))
+        );
+        assert!(!claim_context.paused);
+        assert!(!claim_context.claims_paused);
+        assert_eq!(claim_context.claimed_amount, None);
+        assert_eq!(claim_context.claimed_at, None);
+
+        let view = serde_json::to_value(&claim_context).unwrap();
+        let root = contract.get_campaign(1).unwrap().campaign.merkle_root;
+        assert_eq!(
+            view["campaign"]["merkle_root"],
+            String::from(&Base58CryptoHash::from(root))
+        );
+        assert_eq!(view["campaign"]["claim_end"], end.0.to_string());
+        assert_eq!(view["status"], "Active");
+        assert!(view["seconds_remaining"].is_string());
+    }
+
+    #[test]
+    fn test_claim_context_claimed() {
+        let (context, mut contract, sibling) = total_allocation_campaign_setup(1500);
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(confirm_payout(&context, &mut contract));
+
+        let claim_context = contract.get_claim_context(1, claimant()).unwrap();
+        assert!(claim_context.has_claimed);
+        assert_eq!(claim_context.claimed_amount, Some(json_types::U128(1000)));
+        assert_eq!(
+            claim_context.claimed_at,
+            Some(json_types::U64(context.block_timestamp))
+        );
+
+        let other = contract.get_claim_context(1, non_owner()).unwrap();
+        assert!(!other.has_claimed);
+        assert_eq!(other.claimed_amount, None);
+    }
+
+    #[test]
+    fn test_claim_context_expired_and_paused() {
+        let (mut context, mut contract, _) = total_allocation_campaign_setup(1500);
+        let (_, _, end) = build_mock_campaign();
+
+        set_caller(&mut context, account_owner());
+        contract.set_pause_flags(PauseFlags::CLAIMS);
+        context.block_timestamp = end.0 + 1;
+        testing_env!(context);
+
+        let claim_context = contract.get_claim_context(1, claimant()).unwrap();
+        assert_eq!(claim_context.status, CampaignStatus::Ended);
+        assert_eq!(claim_context.seconds_remaining, Some(json_types::U64(0)));
+        assert!(claim_context.paused);
+        assert!(claim_context.claims_paused);
+    }
+
+    #[test]
+    fn test_claim_context_perpetual_and_missing_campaign() {
+        let (_, contract, _, _) = circuit_breaker_setup();
+
+        let claim_context = contract.get_claim_context(1, claimant()).unwrap();
+        assert_eq!(claim_context.seconds_remaining, None);
+        assert!(contract.get_claim_context(2, claimant()).is_none());
+    }
+
+    #[test]
+    fn test_has_claimed_batch() {
+        let (context, mut contract, sibling) = total_allocation_campaign_setup(1500);
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(confirm_payout(&context, &mut contract));
+
+        let account_ids = vec![claimant(), non_owner(), account_owner(), claimant()];
+        assert_eq!(
+            contract.has_claimed_batch(1, account_ids),
+            vec![true, false, false, true]
+        );
+        assert_eq!(contract.has_claimed_batch(2, vec![claimant()]), vec![false]);
+        assert!(contract.has_claimed_batch(1, vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_has_claimed_many() {
+        let (mut context, mut contract, sibling) = total_allocation_campaign_setup(1500);
+        let root = contract.get_campaign(1).unwrap().campaign.merkle_root;
+        let leaf = mock_leaf_v2(3, &claimant(), &claimant(), 1000);
+        let other_sibling = mock_leaf_v2(3, &non_owner(), &non_owner(), 500);
+        let (_, _, end) = build_mock_campaign();
+
+        set_caller(&mut context, account_owner());
+        contract.create_campaign(root.into(), Some(end), None);
+        contract.create_campaign(hash_pair(&leaf, &other_sibling).into(), Some(end), None);
+
+        set_caller(&mut context, claimant());
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+        assert!(confirm_payout(&context, &mut contract));
+        testing_env!(context.clone());
+        contract.claim(
+            json_types::U128(1000u128),
+            vec![other_sibling],
+            3u32,
+            None,
+            None,
+        );
+
+        assert_eq!(
+            contract.has_claimed_many(claimant(), vec![1, 2, 3, 4]),
+            vec![true, false, true, false]
+        );
+        assert_eq!(
+            contract.has_claimed_many(non_owner(), vec![1, 2, 3]),
+            vec![false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_has_claimed_batch_periodic_campaign() {
+        let (_, mut contract, sibling) = periodic_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        assert_eq!(
+            contract.has_claimed_batch(1, vec![claimant(), non_owner()]),
+            vec![true, false]
+        );
+        assert_eq!(contract.has_claimed_many(claimant(), vec![1]), vec![true]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Claims of V5 campaigns are looked up by nonce with is_nonce_claimed"
+    )]
+    fn test_has_claimed_batch_v5_campaign_failure() {
+        let (contract, _) = nonce_campaign_setup(&claimant());
+
+        contract.has_claimed_batch(1, vec![claimant()]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Claims of V5 campaigns are looked up by nonce with is_nonce_claimed"
+    )]
+    fn test_has_claimed_many_v5_campaign_failure() {
+        let (contract, _) = nonce_campaign_setup(&claimant());
+
+        contract.has_claimed_many(claimant(), vec![2, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "A batch may look up at most 500 entries")]
+    fn test_has_claimed_batch_too_large_failure() {
+        let (_, contract, _) = total_allocation_campaign_setup(1500);
+
+        let account_ids = vec![claimant(); crate::record::MAX_HAS_CLAIMED_BATCH + 1];
+        contract.has_claimed_batch(1, account_ids);
+    }
+
+    #[test]
+    #[should_panic(expected = "A batch may look up at most 500 entries")]
+    fn test_has_claimed_many_too_large_failure() {
+        let (_, contract, _) = total_allocation_campaign_setup(1500);
+
+        let campaign_ids = vec![1; crate::record::MAX_HAS_CLAIMED_BATCH + 1];
+        contract.has_claimed_many(claimant(), campaign_ids);
+    }
+
+    #[test]
+    fn test_contract_source_metadata() {
+        let metadata = ContractSourceMetadata::embedded();
+
+        // Unless overridden at build time the version tracks Cargo.toml
+        let version = option_env!("NEP330_VERSION").unwrap_or(env!("CARGO_PKG_VERSION"));
+        assert_eq!(metadata.version.as_deref(), Some(version));
+        let link = option_env!("NEP330_LINK").unwrap_or(env!("CARGO_PKG_REPOSITORY"));
+        assert_eq!(metadata.link.as_deref(), Some(link));
+
+        let standards: Vec<_> = metadata
+            .standards
+            .iter()
+            .map(|standard| (standard.standard.as_str(), standard.version.as_str()))
+            .collect();
+        assert_eq!(
+            standards,
+            [
+                ("nep145", "1.0.0"),
+                ("nep297", "1.0.0"),
+                ("nep330", "1.3.0")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_contract_source_metadata_serialization() {
+        let metadata = ContractSourceMetadata {
+            version: Some("1.2.0".to_string()),
+            link: Some("https://github.com/voteagora/near-merkle-claim/tree/abc123".to_string()),
+            standards: vec![crate::metadata::Standard {
+                standard: "nep330".to_string(),
+                version: "1.3.0".to_string(),
+            }],
+            build_info: Some(crate::metadata::BuildInfo {
+                build_environment: "sourcescan/cargo-near:0.16.0-rust-1.86.0".to_string(),
+                build_command: vec!["cargo".to_string(), "near".to_string()],
+                contract_path: String::new(),
+                source_code_snapshot:
+                    "git+https://github.com/voteagora/near-merkle-claim?rev=abc123".to_string(),
+                output_wasm_path: None,
+            }),
+        };
+
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":"1.2.0","link":"https://github.com/voteagora/near-merkle-claim/tree/abc123","standards":[{"standard":"nep330","version":"1.3.0"}],"build_info":{"build_environment":"sourcescan/cargo-near:0.16.0-rust-1.86.0","build_command":["cargo","near"],"contract_path":"","source_code_snapshot":"git+https://github.com/voteagora/near-merkle-claim?rev=abc123","output_wasm_path":null}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ContractSourceMetadata>(&json).unwrap(),
+            metadata
+        );
+
+        // The embedded metadata reads back into the same JSON
+        let embedded: serde_json::Value = serde_json::from_str(CONTRACT_SOURCE_METADATA).unwrap();
+        assert_eq!(
+            serde_json::to_value(ContractSourceMetadata::embedded()).unwrap(),
+            embedded
+        );
+    }
+
+    #[test]
+    fn test_expected_campaign_id() {
+        let (mut context, mut contract) = claims_contract_setup();
+        set_caller(&mut context, account_owner());
+        let (_, root, end) = build_mock_campaign();
+        let expecting = |campaign_id| {
+            Some(CampaignOptions {
+                expected_campaign_id: Some(campaign_id),
+                ..Default::default()
+            })
+        };
+
+        contract.create_campaign(root.into(), Some(end), expecting(1));
+        contract.create_campaign(root.into(), Some(end), None);
+        contract.create_campaign(root.into(), Some(end), expecting(3));
+        assert_eq!(contract.get_last_campaign_id(), 3);
+
+        // V1 and OzStandard leaves can be claimed in any campaign
+        let unbound = [
+            LeafFormat {
+                leaf_version: LeafVersion::V1,
+                ..Default::default()
+            },
+            LeafFormat {
+                leaf_version: LeafVersion::V1,
+                leaf_encoding: LeafEncoding::OzStandard,
+                ..Default::default()
+            },
+        ];
+        assert!(unbound.iter().all(|format| !format.binds_campaign_id()));
+        assert!(LeafFormat::default().binds_campaign_id());
+        assert!(LeafFormat {
+            leaf_version: LeafVersion::V1,
+            allocation_mode: AllocationMode::Fixed(json_types::U128(1000)),
+            ..Default::default()
+        }
+        .binds_campaign_id());
+    }
+
+    #[test]
+    #[should_panic(expected = "Campaign would be created with id 2, not the expected id 1")]
+    fn test_expected_campaign_id_taken_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+        set_caller(&mut context, account_owner());
+        let (_, root, end) = build_mock_campaign();
+        let options = CampaignOptions {
+            expected_campaign_id: Some(1),
+            ..Default::default()
+        };
+
+        // Another campaign took the id the tree was built for
+        contract.create_campaign(root.into(), Some(end), None);
+        contract.create_campaign(root.into(), Some(end), Some(options));
+    }
+
+    /// Hands owner actions of `operator_key_setup()`'s contract to a single signer.
+    fn operator_key_multisig_setup() -> (VMContext, MerkleClaim) {
+        let (mut context, mut contract) = operator_key_setup();
+
+        set_caller(&mut context, account_owner());
+        context.signer_account_pk = public_key(123);
+        context.attached_deposit = NearToken::from_yoctonear(1);
+        testing_env!(context.clone());
+        contract.enable_multisig(Multisig {
+            signers: vec![signer(0)],
+            threshold: 1,
+            proposal_lifetime: json_types::U64(DEFAULT_PROPOSAL_LIFETIME),
+        });
+        set_caller(&mut context, signer(0));
+
+        (context, contract)
+    }
+
+    #[test]
+    fn test_multisig_manages_operator_keys() {
+        let (_, mut contract) = operator_key_multisig_setup();
+
+        contract.propose_action(OwnerAction::RemoveOperatorKey {
+            public_key: operator_key(),
+        });
+
+        assert!(contract.get_operator_keys().is_empty());
+        assert!(matches!(
+            &get_created_receipts()[0].actions[..],
+            [MockAction::DeleteKey { .. }]
+        ));
+        assert_eq!(
+            contract.get_admin_log(None, None).last().unwrap().method,
+            "remove_operator_key"
+        );
+
+        contract.propose_action(OwnerAction::AddOperatorKey {
+            public_key: public_key(8),
+            allowance: NearToken::from_near(1),
+        });
+
+        let keys = contract.get_operator_keys();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].public_key, public_key(8));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Operator keys cannot create campaigns while multisig confirmations are enabled"
+    )]
+    fn test_operator_key_create_campaign_under_multisig_failure() {
+        let (mut context, mut contract) = operator_key_multisig_setup();
+        let mock_campaign = build_mock_campaign();
+
+        context.predecessor_account_id = context.current_account_id.clone();
+        context.signer_account_pk = operator_key();
+        context.attached_deposit = NearToken::from_yoctonear(0);
+        testing_env!(context.clone());
+        contract.create_campaign(mock_campaign.1.into(), Some(mock_campaign.2), None);
+    }
+
+    /// Every method gated by `assert_owner_action`, with the action the multisig signers
+    /// propose instead. `enable_multisig` is replaced by `SetMultisig` and the config setters
+    /// by `UpdateConfig` with the matching patch.
+    fn gated_methods() -> Vec<(&'static str, OwnerAction)> {
+        let mock_campaign = mock_create_campaign_action();
+        let update_config = OwnerAction::UpdateConfig {
+            patch: ConfigPatch::default(),
+        };
+
+        vec![
+            ("withdraw", OwnerAction::Withdraw),
+            (
+                "withdraw_to",
+                OwnerAction::WithdrawTo {
+                    receiver: non_owner(),
+                },
+            ),
+            (
+                "withdraw_amount",
+                OwnerAction::WithdrawAmount {
+                    amount: NearToken::from_near(1),
+                    receiver: None,
+                },
+            ),
+            (
+                "withdraw_amount_to",
+                OwnerAction::WithdrawAmount {
+                    amount: NearToken::from_near(1),
+                    receiver: Some(non_owner()),
+                },
+            ),
+            (
+                "request_withdraw",
+                OwnerAction::RequestWithdraw {
+                    amount: json_types::U128(1),
+                },
+            ),
+            (
+                "withdraw_storage_surplus",
+                OwnerAction::WithdrawStorageSurplus { amount: None },
+            ),
+            ("create_campaign", mock_campaign),
+            (
+                "cancel_campaign",
+                OwnerAction::CancelCampaign { campaign_id: 1 },
+            ),
+            (
+                "extend_campaign",
+                OwnerAction::ExtendCampaign {
+                    campaign_id: 1,
+                    claim_end: json_types::U64(0),
+                },
+            ),
+            (
+                "set_campaign_memo",
+                OwnerAction::SetCampaignMemo {
+                    campaign_id: 1,
+                    memo: None,
+                },
+            ),
+            (
+                "add_operator_key",
+                OwnerAction::AddOperatorKey {
+                    public_key: operator_key(),
+                    allowance: NearToken::from_near(1),
+                },
+            ),
+            (
+                "remove_operator_key",
+                OwnerAction::RemoveOperatorKey {
+                    public_key: operator_key(),
+                },
+            ),
+            ("update_config", update_config.clone()),
+            ("set_allowed_lockup_suffix", update_config.clone()),
+            ("set_max_proof_length", update_config.clone()),
+            ("set_claim_fee", update_config.clone()),
+            ("set_claim_amount_bounds", update_config.clone()),
+            ("set_max_relayer_fee", update_config),
+            (
+                "enable_multisig",
+                OwnerAction::SetMultisig { multisig: None },
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_owner_actions_cover_gated_methods() {
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let gate = [
+            "self.assert_owner_action()",
+            "self.assert_campaign_creator()",
+        ];
+        let mut gated = vec![];
+        for entry in std::fs::read_dir(src).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|extension| extension != "rs") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            // Leaves out these tests, which name the gates themselves
+            let source = source.split("#[cfg(test)]").next().unwrap();
+
+            let mut method = "";
+            for line in source.lines() {
+                if let Some((_, rest)) = line.split_once("fn ") {
+                    method = rest.split(['(', '<']).next().unwrap();
+                }
+                if gate.iter().any(|gate| line.contains(gate)) && !method.starts_with("assert_") {
+                    gated.push(method.to_string());
+                }
+            }
+        }
+        gated.sort();
+
+        let mut covered: Vec<String> = gated_methods()
+            .into_iter()
+            .map(|(method, _)| method.to_string())
+            .collect();
+        covered.sort();
+        // A method gated without a multisig action fails here
+        assert_eq!(gated, covered);
+    }
+
+    #[test]
+    fn test_multisig_withdraw_to() {
+        let (_, mut contract) = multisig_setup(1, 1);
+
+        contract.propose_action(OwnerAction::WithdrawTo {
+            receiver: non_owner(),
+        });
+
+        let transfers = scheduled_transfers();
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].0, non_owner());
+    }
+
+    #[test]
+    fn test_multisig_withdraw_storage_surplus() {
+        let (mut context, mut contract, surplus) = storage_surplus_setup();
+        contract.enable_multisig(Multisig {
+            signers: vec![signer(0)],
+            threshold: 1,
+            proposal_lifetime: json_types::U64(DEFAULT_PROPOSAL_LIFETIME),
+        });
+        set_caller(&mut context, signer(0));
+
+        contract.propose_action(OwnerAction::WithdrawStorageSurplus { amount: None });
+
+        assert_eq!(scheduled_transfers(), vec![(account_owner(), surplus)]);
+        assert_eq!(
+            contract.get_admin_log(None, None).last().unwrap().method,
+            "withdraw_storage_surplus"
+        );
+    }
+
+    /// Creates a campaign under a `max_total_extension` of 100 nanoseconds and returns its
+    /// original claim end.
+    fn capped_extension_setup(context: &mut VMContext, contract: &mut MerkleClaim) -> U64 {
+        let (_, merkle_root, end) = build_mock_campaign();
+
+        set_caller(context, account_owner());
+        contract.update_config(config_patch(r#"{"max_total_extension": "100"}"#));
+        contract.create_campaign(merkle_root.into(), Some(end), None);
+
+        end
+    }
+
+    #[test]
+    fn test_extend_campaign_up_to_max_total_extension() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let end = capped_extension_setup(&mut context, &mut contract);
+
+        contract.extend_campaign(1, json_types::U64(end.0 + 100));
+
+        let campaign = contract.get_campaign(1).unwrap().campaign;
+        assert_eq!(campaign.claim_end, Some(json_types::U64(end.0 + 100)));
+        assert_eq!(campaign.original_claim_end, Some(end));
+    }
+
+    #[test]
+    #[should_panic(expected = "New claim end exceeds the maximum total extension")]
+    fn test_extend_campaign_past_max_total_extension_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let end = capped_extension_setup(&mut context, &mut contract);
+
+        contract.extend_campaign(1, json_types::U64(end.0 + 101));
+    }
+
+    #[test]
+    #[should_panic(expected = "New claim end exceeds the maximum total extension")]
+    fn test_repeated_extensions_past_max_total_extension_failure() {
+        let (mut context, mut contract) = claims_contract_setup();
+        let end = capped_extension_setup(&mut context, &mut contract);
+
+        // Each extension stays within the cap, their sum does not
+        contract.extend_campaign(1, json_types::U64(end.0 + 60));
+        contract.extend_campaign(1, json_types::U64(end.0 + 100));
+        contract.extend_campaign(1, json_types::U64(end.0 + 101));
+    }
+
+    #[test]
+    #[should_panic(expected = "New claim end exceeds the maximum total extension")]
+    fn test_extend_campaign_by_multisig_past_max_total_extension_failure() {
+        let (_, mut contract) = multisig_setup(3, 1);
+        let (_, merkle_root, end) = build_mock_campaign();
+        contract.propose_action(OwnerAction::UpdateConfig {
+            patch: config_patch(r#"{"max_total_extension": "100"}"#),
+        });
+        contract.propose_action(OwnerAction::CreateCampaign {
+            merkle_root: merkle_root.into(),
+            claim_end: Some(end),
+            options: None,
+        });
+        contract.propose_action(OwnerAction::ExtendCampaign {
+            campaign_id: 1,
+            claim_end: json_types::U64(end.0 + 100),
+        });
+
+        contract.propose_action(OwnerAction::ExtendCampaign {
+            campaign_id: 1,
+            claim_end: json_types::U64(end.0 + 101),
+        });
+    }
+
+    #[test]
+    fn test_periodic_claim_views_report_current_period() {
+        let (mut context, mut contract, sibling) = periodic_campaign_setup();
+
+        contract.claim(json_types::U128(1000u128), vec![sibling], 1u32, None, None);
+
+        assert!(contract.has_claimed(1, claimant()));
+        assert!(!contract.has_claimed(1, non_owner()));
+        let key: CryptoHash = contract.get_claim_key(1, claimant()).unwrap().into();
+        assert_eq!(
+            key,
+            compute_claim_key(ClaimKeyVersion::V3, &claimant(), 1, Some(0))
+        );
+        let claim_context = contract.get_claim_context(1, claimant()).unwrap();
+        assert!(claim_context.has_claimed);
+        assert!(claim_context.claimed_amount.is_some());
+
+        // The next period's claim is still open
+        context.block_timestamp += to_nanos(7);
+        testing_env!(context.clone());
+        assert!(!contract.has_claimed(1, claimant()));
+        assert_eq!(
+            CryptoHash::from(contract.get_claim_key(1, claimant()).unwrap()),
+            compute_claim_key(ClaimKeyVersion::V3, &claimant(), 1, Some(1))
         );
     }
 }
@@ -0,0 +1,63 @@
+use crate::*;
+use near_sdk::{ext_contract, Gas, PromiseError};
+
+/// Gas attached to the `get_owner_account_id` view of a lockup contract.
+const GAS_FOR_GET_OWNER: Gas = Gas::from_tgas(5);
+/// Gas attached to the callback recording a claim once the lockup owner is known, which also
+/// schedules the payout and its callback.
+const GAS_FOR_ON_LOCKUP_OWNER: Gas = Gas::from_tgas(25);
+
+/// The subset of the lockup contract interface used to verify ownership.
+#[ext_contract(ext_lockup)]
+#[allow(dead_code)]
+trait Lockup {
+    fn get_owner_account_id(&self) -> AccountId;
+}
+
+impl MerkleClaim {
+    /// Asks `lockup_contract` for its owner and records the claim in `on_lockup_owner` if it is
+    /// the claimant. Nothing is recorded until then, so a failed check leaves the claim intact.
+    pub(crate) fn claim_after_lockup_owner_check(claim: VerifiedClaim) -> Promise {
+        let lockup_contract = claim
+            .lockup_contract
+            .clone()
+            .expect("Lockup contract is required to check its owner");
+
+        ext_lockup::ext(lockup_contract)
+            .with_static_gas(GAS_FOR_GET_OWNER)
+            .get_owner_account_id()
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_ON_LOCKUP_OWNER)
+                    .on_lockup_owner(claim),
+            )
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Records a claim whose proof was verified once the lockup contract reported its owner.
+    #[private]
+    pub fn on_lockup_owner(
+        &mut self,
+        claim: VerifiedClaim,
+        #[callback_result] owner: Result<AccountId, PromiseError>,
+    ) {
+        self.assert_not_paused(PauseFlags::CLAIMS);
+
+        let owner = owner.unwrap_or_else(|_| env::panic_str("Failed to fetch the lockup owner"));
+        require!(
+            owner == claim.account_id,
+            "Lockup contract is not owned by the claimant"
+        );
+
+        // Another claim of the same allocation may have completed while the owner was queried
+        let campaign = self
+            .campaigns
+            .get(&claim.campaign_id)
+            .expect("Campaign does not exist");
+        self.assert_unclaimed(campaign, &claim.account_id, claim.index, claim.nonce);
+
+        self.record_claim(claim);
+    }
+}
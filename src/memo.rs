@@ -0,0 +1,70 @@
+use crate::*;
+
+/// The longest memo a campaign can carry, in bytes.
+pub const MAX_CAMPAIGN_MEMO_LENGTH: usize = 256;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CampaignMemoUpdatedEvent {
+    pub campaign_id: CampaignId,
+    pub old_memo: Option<String>,
+    pub new_memo: Option<String>,
+}
+
+impl RewardCampaign {
+    /// Panics if `memo` is longer than `MAX_CAMPAIGN_MEMO_LENGTH`.
+    pub(crate) fn assert_valid_memo(memo: &Option<String>) {
+        require!(
+            memo.as_ref()
+                .is_none_or(|memo| memo.len() <= MAX_CAMPAIGN_MEMO_LENGTH),
+            "Memo must not exceed 256 bytes"
+        );
+    }
+}
+
+impl MerkleClaim {
+    /// Replaces a campaign's memo for `set_campaign_memo` or a confirmed multisig action.
+    pub(crate) fn internal_set_campaign_memo(
+        &mut self,
+        campaign_id: CampaignId,
+        memo: Option<String>,
+    ) {
+        RewardCampaign::assert_valid_memo(&memo);
+        let campaign = self
+            .campaigns
+            .get_mut(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(
+            !campaign.has_ended(env::block_timestamp()),
+            "Campaign has already ended"
+        );
+
+        let storage_before = env::storage_usage();
+        let old_memo = std::mem::replace(&mut campaign.memo, memo.clone());
+        self.campaigns.flush();
+        let storage_after = env::storage_usage();
+        let campaign = self.campaigns.get_mut(&campaign_id).unwrap();
+        campaign.storage_bytes =
+            (campaign.storage_bytes + storage_after).saturating_sub(storage_before);
+        self.record_admin_action("set_campaign_memo", format!("campaign {campaign_id}"));
+
+        let updated = CampaignMemoUpdatedEvent {
+            campaign_id,
+            old_memo,
+            new_memo: memo,
+        };
+
+        Event::CampaignMemoUpdated(updated).emit();
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Replaces the memo of a running campaign, which later claim events echo. `None` removes
+    /// it.
+    #[payable]
+    pub fn set_campaign_memo(&mut self, campaign_id: CampaignId, memo: Option<String>) {
+        self.assert_owner_action();
+        self.internal_set_campaign_memo(campaign_id, memo);
+    }
+}
@@ -1,27 +1,522 @@
 use crate::*;
-use near_sdk::env::keccak256_array;
+use near_sdk::json_types::Base58CryptoHash;
 use near_sdk::CryptoHash;
 
-impl MerkleClaim {
-    pub fn verify_proof(
-        leaf: CryptoHash,
-        merkle_proof: Vec<CryptoHash>,
-        merkle_root: CryptoHash,
+#[cfg(any(test, all(feature = "tree", not(target_arch = "wasm32"))))]
+pub mod tree;
+
+/// The hash function a campaign's tree is built with, for leaves and internal nodes alike.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub enum HashAlgo {
+    #[default]
+    Keccak256,
+    /// For tree tooling that only produces sha256 trees
+    Sha256,
+}
+
+/// How a campaign's proofs combine a node with its sibling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub enum ProofFormat {
+    /// `hash(min(a, b) || max(a, b))`, proofs are bare hashes
+    #[default]
+    Commutative,
+    /// `hash(left || right)` in tree order, every proof hash comes with its `Direction` as in
+    /// near-primitives' `MerklePath`, so a pair of siblings has a single parent
+    Directional,
+    /// `hash(left || right)` in tree order as built by the `rs-merkle` crate, where an odd node is
+    /// carried up unchanged and the sides of the proof hashes follow from the leaf's position and
+    /// the number of leaves
+    RsMerkle,
+}
+
+/// The side of the node computed so far a hash of a directional proof sits on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+/// How the borsh-serialized leaf payload is turned into a leaf of the merkle tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub enum LeafHashing {
+    /// `hash(payload)`, used by campaigns created before double hashing was introduced
+    Single,
+    /// `hash(hash(payload))`, a leaf can never be mistaken for an internal node
+    #[default]
+    Double,
+    /// `hash(0x00 || payload)`, with internal nodes hashed as `hash(0x01 || left || right)`, so
+    /// leaves and internal nodes are told apart by construction
+    Prefixed,
+}
+
+/// The byte `Prefixed` leaves are hashed with in front of their payload.
+pub const LEAF_PREFIX: u8 = 0x00;
+/// The byte internal nodes of `Prefixed` trees are hashed with in front of their children.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// How a leaf payload is serialized before it is hashed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub enum LeafEncoding {
+    /// Borsh, laid out according to the campaign's leaf version
+    #[default]
+    Borsh,
+    /// `abi.encode(account, lockup, amount)` as `(string, string, uint256)`, the `standard-v1`
+    /// leaves of `@openzeppelin/merkle-tree`'s `StandardMerkleTree`
+    OzStandard,
+    /// Canonical JSON of the leaf version's fields, for pipelines that cannot produce borsh
+    JsonCanonical,
+}
+
+/// Which data a leaf payload commits to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub enum LeafVersion {
+    /// `(account, lockup, amount)`, used by campaigns created before leaves committed to the
+    /// campaign
+    V1,
+    /// `(campaign_id, account, lockup, amount)`, a proof is only valid for the campaign it was
+    /// generated for
+    #[default]
+    V2,
+    /// `(campaign_id, claimer, receiver, amount)`, the claimer triggers the claim and the receiver
+    /// is paid, neither has to be a lockup contract
+    V3,
+    /// `(campaign_id, account, lockup, amount, deadline)`, a `V2` leaf that can only be claimed
+    /// before its own deadline
+    V4,
+    /// `(campaign_id, account, lockup, amount, nonce)`, a `V2` leaf distinguished by a nonce so
+    /// an account can hold several allocations, each claimed separately
+    V5,
+    /// `(campaign_id, eth_address, amount)`, allocations of 20 byte Ethereum addresses claimed
+    /// with `claim_eth` and paid to a receiver the address signed for
+    V6,
+    /// `(campaign_id, public_key, amount)`, allocations of ed25519 public keys claimed with
+    /// `claim_with_key` and paid to a receiver the key signed for
+    V7,
+}
+
+/// A merkle root given either as its 32 bytes, or as a base58 or `0x`-prefixed hex string.
+#[derive(Clone)]
+#[near(serializers=[borsh, json])]
+#[serde(untagged)]
+pub enum MerkleRootInput {
+    /// Read from the 32 bytes, but written as base58 like every other hash
+    Bytes(#[serde(serialize_with = "serialize_b58_hash")] CryptoHash),
+    Encoded(String),
+}
+
+impl MerkleRootInput {
+    /// Decodes the root, panicking if a string is not 32 bytes of base58 or `0x`-prefixed hex.
+    pub fn to_hash(&self) -> CryptoHash {
+        match self {
+            Self::Bytes(hash) => *hash,
+            Self::Encoded(encoded) => match encoded.strip_prefix("0x") {
+                Some(hex) => decode_hex(
+                    hex,
+                    "Hex merkle root must be 0x followed by 64 hex characters",
+                ),
+                None => Base58CryptoHash::try_from(encoded.as_str())
+                    .unwrap_or_else(|_| {
+                        env::panic_str(
+                            "Merkle root must be 32 bytes encoded as base58 or 0x-prefixed hex",
+                        )
+                    })
+                    .into(),
+            },
+        }
+    }
+}
+
+impl From<CryptoHash> for MerkleRootInput {
+    fn from(hash: CryptoHash) -> Self {
+        Self::Bytes(hash)
+    }
+}
+
+/// Decodes `2 * N` hex characters of either case, panicking with `message` otherwise.
+pub(crate) fn decode_hex<const N: usize>(hex: &str, message: &str) -> [u8; N] {
+    if hex.len() != 2 * N || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        panic_str(message);
+    }
+
+    let mut bytes = [0u8; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+    }
+    bytes
+}
+
+/// Returns the Solidity ABI encoding of `(account, lockup, amount)` as `(string, string, uint256)`:
+/// the offsets of both strings and the amount as 32 byte big-endian words, followed by the length
+/// and zero padded bytes of each string.
+pub fn oz_standard_payload(account: &str, lockup: &str, amount: Balance) -> Vec<u8> {
+    fn word(value: u128) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[16..].copy_from_slice(&value.to_be_bytes());
+        word
+    }
+    fn padded_len(value: &str) -> usize {
+        value.len().div_ceil(32) * 32
+    }
+
+    let account_offset = 3 * 32;
+    let lockup_offset = account_offset + 32 + padded_len(account);
+    let mut payload = Vec::with_capacity(lockup_offset + 32 + padded_len(lockup));
+
+    payload.extend_from_slice(&word(account_offset as u128));
+    payload.extend_from_slice(&word(lockup_offset as u128));
+    payload.extend_from_slice(&word(amount));
+    for value in [account, lockup] {
+        payload.extend_from_slice(&word(value.len() as u128));
+        payload.extend_from_slice(value.as_bytes());
+        payload.resize(payload.len() + padded_len(value) - value.len(), 0);
+    }
+    payload
+}
+
+/// Serializes an integer of a `JsonCanonical` leaf as a decimal string, since JavaScript numbers
+/// cannot hold every u64 and u128.
+pub(crate) fn serialize_decimal<T: ToString, S: near_sdk::serde::Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+/// Serializes a hash as a base58 string, as NEAR tools show hashes, instead of an array of 32
+/// numbers. Borsh keeps the raw bytes.
+pub(crate) fn serialize_b58_hash<S: near_sdk::serde::Serializer>(
+    hash: &CryptoHash,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    near_sdk::serde::Serialize::serialize(&Base58CryptoHash::from(*hash), serializer)
+}
+
+/// Reads a hash written by `serialize_b58_hash`, rejecting strings that are not 32 bytes of
+/// base58.
+pub(crate) fn deserialize_b58_hash<'de, D: near_sdk::serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<CryptoHash, D::Error> {
+    <Base58CryptoHash as near_sdk::serde::Deserialize>::deserialize(deserializer).map(Into::into)
+}
+
+/// Encodes a hash as `0x`-prefixed lowercase hex.
+pub fn encode_hex_hash(hash: &CryptoHash) -> String {
+    let hex: String = hash.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("0x{hex}")
+}
+
+impl LeafHashing {
+    /// Hashes a leaf of a keccak256 tree.
+    pub fn hash_leaf(&self, payload: &[u8]) -> CryptoHash {
+        self.hash_leaf_with(HashAlgo::Keccak256, payload)
+    }
+
+    pub fn hash_leaf_with(&self, hash_algo: HashAlgo, payload: &[u8]) -> CryptoHash {
+        match self {
+            Self::Single => hash_algo.hash(payload),
+            Self::Double => hash_algo.hash(&hash_algo.hash(payload)),
+            Self::Prefixed => hash_algo.hash(&[&[LEAF_PREFIX], payload].concat()),
+        }
+    }
+}
+
+impl HashAlgo {
+    #[cfg(not(all(
+        not(test),
+        any(
+            all(feature = "tree", not(target_arch = "wasm32")),
+            all(feature = "wasm-client", target_arch = "wasm32")
+        )
+    )))]
+    pub fn hash(&self, data: &[u8]) -> CryptoHash {
+        match self {
+            Self::Keccak256 => env::keccak256_array(data),
+            Self::Sha256 => env::sha256_array(data),
+        }
+    }
+
+    /// Hashes natively, as off-chain and browser builds have no host functions to call.
+    #[cfg(all(
+        not(test),
+        any(
+            all(feature = "tree", not(target_arch = "wasm32")),
+            all(feature = "wasm-client", target_arch = "wasm32")
+        )
+    ))]
+    pub fn hash(&self, data: &[u8]) -> CryptoHash {
+        use sha2::Digest;
+
+        match self {
+            Self::Keccak256 => sha3::Keccak256::digest(data).into(),
+            Self::Sha256 => sha2::Sha256::digest(data).into(),
+        }
+    }
+}
+
+/// Panics with `message` through the host.
+#[cfg(not(all(
+    not(test),
+    any(
+        all(feature = "tree", not(target_arch = "wasm32")),
+        all(feature = "wasm-client", target_arch = "wasm32")
+    )
+)))]
+pub(crate) fn panic_str(message: &str) -> ! {
+    env::panic_str(message)
+}
+
+/// Panics with `message` as a Rust panic, as off-chain and browser builds have no host.
+#[cfg(all(
+    not(test),
+    any(
+        all(feature = "tree", not(target_arch = "wasm32")),
+        all(feature = "wasm-client", target_arch = "wasm32")
+    )
+))]
+pub(crate) fn panic_str(message: &str) -> ! {
+    panic!("{message}")
+}
+
+/// How a campaign's tree hashes its leaves and internal nodes, which are domain separated in
+/// `Prefixed` trees.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TreeHasher {
+    pub hash_algo: HashAlgo,
+    pub leaf_hashing: LeafHashing,
+}
+
+impl From<HashAlgo> for TreeHasher {
+    fn from(hash_algo: HashAlgo) -> Self {
+        Self {
+            hash_algo,
+            ..Default::default()
+        }
+    }
+}
+
+impl TreeHasher {
+    pub fn hash_leaf(&self, payload: &[u8]) -> CryptoHash {
+        self.leaf_hashing.hash_leaf_with(self.hash_algo, payload)
+    }
+
+    /// Hashes the preimage of an internal node, prefixed with `NODE_PREFIX` in `Prefixed` trees.
+    fn hash_node(&self, preimage: [u8; 64]) -> CryptoHash {
+        if self.leaf_hashing != LeafHashing::Prefixed {
+            return self.hash_algo.hash(&preimage);
+        }
+
+        let mut prefixed = [0u8; 65];
+        prefixed[0] = NODE_PREFIX;
+        prefixed[1..].copy_from_slice(&preimage);
+        self.hash_algo.hash(&prefixed)
+    }
+
+    /// Returns the internal node above `a` and `b`, which does not depend on their order.
+    pub fn hash_pair(&self, a: &CryptoHash, b: &CryptoHash) -> CryptoHash {
+        self.hash_node(commutative_preimage(a, b))
+    }
+
+    /// Verifies that `leaf` is part of the tree of `root`, hashing it with every hash of `proof`
+    /// in turn.
+    pub fn verify_proof(&self, leaf: &CryptoHash, proof: &[CryptoHash], root: &CryptoHash) -> bool {
+        proof
+            .iter()
+            .fold(*leaf, |node, sibling| self.hash_pair(&node, sibling))
+            == *root
+    }
+
+    /// Returns the internal node above `left` and `right` of a directional tree.
+    pub fn hash_ordered(&self, left: &CryptoHash, right: &CryptoHash) -> CryptoHash {
+        self.hash_node(ordered_preimage(left, right))
+    }
+
+    /// Verifies that `leaf` is part of the directional tree of `root`, hashing it with every hash
+    /// of `path` on the side it is given for.
+    pub fn verify_directional_proof(
+        &self,
+        leaf: &CryptoHash,
+        path: &[(CryptoHash, Direction)],
+        root: &CryptoHash,
     ) -> bool {
-        let mut computed_hash = leaf;
+        path.iter()
+            .fold(*leaf, |node, (sibling, direction)| match direction {
+                Direction::Left => self.hash_ordered(sibling, &node),
+                Direction::Right => self.hash_ordered(&node, sibling),
+            })
+            == *root
+    }
+
+    /// Verifies an `rs-merkle` proof that the leaf at `position` of a tree of `leaf_count` leaves
+    /// is `leaf`, with the sibling hashes from the leaf up and none for levels where the node is
+    /// carried up.
+    pub fn verify_rs_merkle_proof(
+        &self,
+        leaf: &CryptoHash,
+        position: u32,
+        leaf_count: u32,
+        proof: &[CryptoHash],
+        root: &CryptoHash,
+    ) -> bool {
+        if position >= leaf_count {
+            return false;
+        }
 
-        for hash in merkle_proof {
-            computed_hash = keccak256_array(&Self::commutative_keccak256(&computed_hash, &hash));
+        let mut path = Vec::with_capacity(proof.len());
+        let mut hashes = proof.iter().copied();
+        let (mut position, mut width) = (position, leaf_count);
+        while width > 1 {
+            let direction = if position % 2 == 1 {
+                Some(Direction::Left)
+            } else {
+                (position + 1 < width).then_some(Direction::Right)
+            };
+            if let Some(direction) = direction {
+                let Some(sibling) = hashes.next() else {
+                    return false;
+                };
+                path.push((sibling, direction));
+            }
+            position /= 2;
+            width = width.div_ceil(2);
         }
 
-        merkle_root == computed_hash
+        hashes.next().is_none() && self.verify_directional_proof(leaf, &path, root)
     }
 
-    fn commutative_keccak256(a: &CryptoHash, b: &CryptoHash) -> Vec<u8> {
-        if a < b {
-            [a.as_slice(), b.as_slice()].concat()
+    /// Same as the free `verify_multi_proof`, for trees hashed this way.
+    pub fn verify_multi_proof(
+        &self,
+        leaves: &[CryptoHash],
+        proof: &[CryptoHash],
+        proof_flags: &[bool],
+        root: &CryptoHash,
+    ) -> bool {
+        process_multi_proof(*self, leaves, proof, proof_flags).as_ref() == Some(root)
+    }
+}
+
+/// Returns `min(a, b) || max(a, b)`, the preimage of the internal node above `a` and `b`.
+pub(crate) fn commutative_preimage(a: &CryptoHash, b: &CryptoHash) -> [u8; 64] {
+    if a < b {
+        ordered_preimage(a, b)
+    } else {
+        ordered_preimage(b, a)
+    }
+}
+
+/// Returns `left || right`, the preimage of the internal node above them in a directional tree.
+fn ordered_preimage(left: &CryptoHash, right: &CryptoHash) -> [u8; 64] {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(left);
+    preimage[32..].copy_from_slice(right);
+    preimage
+}
+
+/// Returns the internal node of a keccak256 tree above `a` and `b`, which does not depend on their
+/// order.
+pub fn hash_pair(a: &CryptoHash, b: &CryptoHash) -> CryptoHash {
+    TreeHasher::default().hash_pair(a, b)
+}
+
+/// Verifies that `leaf` is part of the keccak256 tree of `root`, hashing it with every hash of
+/// `proof` in turn.
+pub fn verify_proof(leaf: &CryptoHash, proof: &[CryptoHash], root: &CryptoHash) -> bool {
+    TreeHasher::default().verify_proof(leaf, proof, root)
+}
+
+/// Verifies that `leaf` is part of the directional keccak256 tree of `root`, given the path of
+/// sibling hashes and the sides they sit on from the leaf up.
+pub fn verify_directional_proof(
+    leaf: &CryptoHash,
+    path: &[(CryptoHash, Direction)],
+    root: &CryptoHash,
+) -> bool {
+    TreeHasher::default().verify_directional_proof(leaf, path, root)
+}
+
+/// Verifies that all `leaves` are part of the keccak256 tree of `root` with an OpenZeppelin style
+/// multiproof. Every flag stands for one hash: `true` pairs the next pending node with the one
+/// after it, `false` pairs it with the next hash of `proof`, where pending nodes are the leaves,
+/// in the order of their position in the tree, followed by the hashes computed so far.
+///
+/// Returns `false` for malformed proofs, i.e. unless the flags consume exactly all leaves,
+/// computed hashes and proof hashes but the root, and for an empty set of leaves.
+pub fn verify_multi_proof(
+    leaves: &[CryptoHash],
+    proof: &[CryptoHash],
+    proof_flags: &[bool],
+    root: &CryptoHash,
+) -> bool {
+    TreeHasher::default().verify_multi_proof(leaves, proof, proof_flags, root)
+}
+
+/// Computes the root of a multiproof, or `None` if it is malformed.
+fn process_multi_proof(
+    hasher: TreeHasher,
+    leaves: &[CryptoHash],
+    proof: &[CryptoHash],
+    proof_flags: &[bool],
+) -> Option<CryptoHash> {
+    if leaves.is_empty() || leaves.len() + proof.len() != proof_flags.len() + 1 {
+        return None;
+    }
+
+    let mut hashes: Vec<CryptoHash> = Vec::with_capacity(proof_flags.len());
+    let (mut leaf_pos, mut hash_pos, mut proof_pos) = (0, 0, 0);
+
+    // Takes the next leaf, or the next hash computed so far once the leaves are used up
+    let mut next_node = |hashes: &Vec<CryptoHash>| {
+        if leaf_pos < leaves.len() {
+            leaf_pos += 1;
+            Some(leaves[leaf_pos - 1])
         } else {
-            [b.as_slice(), a.as_slice()].concat()
+            hash_pos += 1;
+            hashes.get(hash_pos - 1).copied()
         }
+    };
+
+    for &flag in proof_flags {
+        let a = next_node(&hashes)?;
+        let b = if flag {
+            next_node(&hashes)?
+        } else {
+            proof_pos += 1;
+            *proof.get(proof_pos - 1)?
+        };
+
+        hashes.push(hasher.hash_pair(&a, &b));
+    }
+
+    if proof_pos != proof.len() {
+        return None;
+    }
+
+    hashes.last().or(leaves.first()).copied()
+}
+
+impl MerkleClaim {
+    /// Same as the free `verify_proof`, kept for callers going through the contract type.
+    pub fn verify_proof(
+        leaf: CryptoHash,
+        merkle_proof: Vec<CryptoHash>,
+        merkle_root: CryptoHash,
+    ) -> bool {
+        verify_proof(&leaf, &merkle_proof, &merkle_root)
+    }
+
+    /// Same as the free `verify_multi_proof`, kept for callers going through the contract type.
+    pub fn verify_multi_proof(
+        leaves: Vec<CryptoHash>,
+        proof: Vec<CryptoHash>,
+        proof_flags: Vec<bool>,
+        merkle_root: CryptoHash,
+    ) -> bool {
+        verify_multi_proof(&leaves, &proof, &proof_flags, &merkle_root)
     }
 }
@@ -0,0 +1,488 @@
+//! An off-chain builder of the commutative trees campaigns verify, computing leaves with the same
+//! code the contract does.
+
+use crate::merkle::{decode_hex, encode_hex_hash, TreeHasher};
+use crate::{AllocationMode, Balance, LeafEncoding, LeafFormat, LeafVersion, MerkleTreeData};
+use near_sdk::json_types::{Base58CryptoHash, U128};
+use near_sdk::serde::Serialize;
+use near_sdk::serde_json::Value;
+use near_sdk::{serde_json, AccountId, CryptoHash};
+use std::path::Path;
+use std::str::FromStr;
+
+/// A commutative merkle tree, where an odd node at the end of a level is carried up unchanged.
+pub struct MerkleTree {
+    /// The allocations of the leaves, empty for trees of raw leaves
+    entries: Vec<MerkleTreeData>,
+    /// Every level of the tree, from the leaves up to the root
+    levels: Vec<Vec<CryptoHash>>,
+}
+
+/// The JSON export of a tree, with the root as accepted by `create_campaign` and every proof as
+/// accepted by `claim_b58`.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct TreeExport {
+    root: String,
+    claims: Vec<TreeClaim>,
+}
+
+/// An allocation of a tree with its proof.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct TreeClaim {
+    account: String,
+    lockup: String,
+    amount: U128,
+    proof: Vec<Base58CryptoHash>,
+}
+
+/// The file the `merkle-tree` binary writes for a CSV of allocations.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CampaignFile {
+    root: String,
+    leaf_format: LeafFormat,
+    claim_count: u32,
+    total_amount: U128,
+    claims: Vec<TreeClaim>,
+}
+
+impl MerkleTree {
+    /// Builds the tree of at least one raw leaf.
+    pub fn new(hasher: TreeHasher, leaves: Vec<CryptoHash>) -> Self {
+        assert!(!leaves.is_empty(), "A tree needs at least one leaf");
+
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hasher.hash_pair(left, right),
+                    [node] => *node,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(level);
+        }
+
+        Self {
+            entries: vec![],
+            levels,
+        }
+    }
+
+    /// Builds the tree of a campaign's allocations in the given order, with leaves computed as
+    /// the campaign of `format` computes them. Only `V1`, `V2` and `V3` leaves of campaigns
+    /// tracking claims by key are supported, `V3` leaves taking `lockup` as the receiver.
+    pub fn build(entries: Vec<MerkleTreeData>, format: LeafFormat) -> Self {
+        assert!(
+            matches!(
+                format.leaf_version,
+                LeafVersion::V1 | LeafVersion::V2 | LeafVersion::V3
+            ),
+            "The tree builder supports V1, V2 and V3 leaves"
+        );
+
+        let leaves = entries
+            .iter()
+            .map(|entry| {
+                let account: AccountId = entry.account.parse().expect("Invalid account id");
+                let lockup: AccountId = entry.lockup.parse().expect("Invalid lockup account id");
+                format.compute_leaf(None, &account, &lockup, entry.amount, None, None)
+            })
+            .collect();
+
+        Self {
+            entries,
+            ..Self::new(format.tree_hasher(), leaves)
+        }
+    }
+
+    pub fn leaves(&self) -> &[CryptoHash] {
+        &self.levels[0]
+    }
+
+    pub fn root(&self) -> CryptoHash {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// Returns the proof of the leaf at `index`, the siblings of its path from the leaf up,
+    /// leaving out levels where the node is carried up.
+    pub fn proof(&self, mut index: usize) -> Vec<CryptoHash> {
+        let mut proof = vec![];
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+
+    /// Returns the proof of the first allocation of `account` with `lockup`, which is the
+    /// account itself for claims without a lockup contract.
+    pub fn proof_for(&self, account: &str, lockup: &str) -> Option<Vec<CryptoHash>> {
+        self.entries
+            .iter()
+            .position(|entry| entry.account == account && entry.lockup == lockup)
+            .map(|index| self.proof(index))
+    }
+
+    /// Exports the root as `0x`-prefixed hex and every allocation with its proof of base58 hashes
+    /// as JSON.
+    pub fn to_json(&self) -> String {
+        let export = TreeExport {
+            root: encode_hex_hash(&self.root()),
+            claims: self.claims(),
+        };
+
+        serde_json::to_string(&export).expect("Failed to serialize tree")
+    }
+
+    fn claims(&self) -> Vec<TreeClaim> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| TreeClaim {
+                account: entry.account.clone(),
+                lockup: entry.lockup.clone(),
+                amount: U128(entry.amount),
+                proof: self.proof(index).into_iter().map(Into::into).collect(),
+            })
+            .collect()
+    }
+}
+
+/// Parses a CSV of `account,lockup,amount` rows with an optional header of those names. An empty
+/// lockup stands for the account itself. Errors name the line of the first malformed row.
+pub fn parse_csv(csv: &str) -> Result<Vec<MerkleTreeData>, String> {
+    let mut entries = vec![];
+    for (index, line) in csv.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || (index == 0 && line == "account,lockup,amount") {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [account, lockup, amount] = fields[..] else {
+            return Err(format!(
+                "line {line_number}: expected account,lockup,amount but found {} fields",
+                fields.len()
+            ));
+        };
+        let lockup = if lockup.is_empty() { account } else { lockup };
+        for account in [account, lockup] {
+            AccountId::from_str(account).map_err(|error| {
+                format!("line {line_number}: invalid account id {account:?}: {error}")
+            })?;
+        }
+        let amount = Balance::from_str(amount)
+            .map_err(|_| format!("line {line_number}: invalid amount {amount:?}"))?;
+
+        entries.push(MerkleTreeData {
+            account: account.to_string(),
+            lockup: lockup.to_string(),
+            amount,
+        });
+    }
+
+    Ok(entries)
+}
+
+impl CampaignFile {
+    /// Builds the tree of the allocations of a CSV, see `parse_csv`, for a campaign of `format`.
+    pub fn from_csv(csv: &str, format: LeafFormat) -> Result<Self, String> {
+        check_leaf_version(&format)?;
+        let entries = parse_csv(csv)?;
+        if entries.is_empty() {
+            return Err("The CSV holds no allocations".to_string());
+        }
+        let claim_count = u32::try_from(entries.len()).map_err(|_| "Too many allocations")?;
+        let total_amount = match format.allocation_mode {
+            AllocationMode::PerLeaf => entries
+                .iter()
+                .try_fold(0u128, |total, entry| total.checked_add(entry.amount)),
+            AllocationMode::Fixed(amount) => amount.0.checked_mul(claim_count.into()),
+        }
+        .ok_or("The total amount overflows u128")?;
+
+        let tree = MerkleTree::build(entries, format);
+        Ok(Self {
+            root: encode_hex_hash(&tree.root()),
+            leaf_format: format,
+            claim_count,
+            total_amount: U128(total_amount),
+            claims: tree.claims(),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize campaign file")
+    }
+
+    /// Returns the arguments of the `create_campaign` call of this tree, without a claim end.
+    /// Trees whose leaves commit to the campaign id expect it, so the call fails instead of
+    /// creating a campaign no leaf can be claimed in when another campaign took the id first.
+    pub fn create_campaign_args(&self) -> String {
+        let format = &self.leaf_format;
+        let mut args = serde_json::json!({
+            "merkle_root": self.root,
+            "claim_end": null,
+            "options": {
+                "allocation_mode": format.allocation_mode,
+                "leaf_version": format.leaf_version,
+                "leaf_encoding": format.leaf_encoding,
+                "hash_algo": format.hash_algo,
+                "leaf_hashing": format.leaf_hashing,
+            },
+        });
+        if format.binds_campaign_id() {
+            args["options"]["expected_campaign_id"] = format.campaign_id.into();
+        }
+
+        serde_json::to_string_pretty(&args).expect("Failed to serialize arguments")
+    }
+}
+
+fn check_leaf_version(format: &LeafFormat) -> Result<(), String> {
+    match format.leaf_version {
+        LeafVersion::V1 | LeafVersion::V2 | LeafVersion::V3 => Ok(()),
+        _ => Err("The tree builder supports V1, V2 and V3 leaves".to_string()),
+    }
+}
+
+/// Why an entry of a tree export does not verify.
+#[derive(Debug, PartialEq, Eq)]
+pub enum EntryError {
+    /// The entry could not be parsed
+    Malformed(String),
+    /// The leaf listed in the export differs from the leaf the contract computes
+    LeafMismatch,
+    /// The proof does not lead from the contract's leaf to the root
+    InvalidProof,
+}
+
+/// An entry of a tree export that does not verify.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EntryMismatch {
+    /// The position of the entry in the export
+    pub index: usize,
+    pub account: String,
+    pub error: EntryError,
+}
+
+/// The outcome of checking every entry of a tree export against its root.
+#[derive(Debug)]
+pub struct ValidationReport {
+    pub root: CryptoHash,
+    pub entry_count: usize,
+    pub mismatches: Vec<EntryMismatch>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Checks every entry of a tree export produced by other tooling before a campaign is created
+/// with its root. Leaves are recomputed as the contract computes them and every proof is verified
+/// against the export's root.
+///
+/// Accepts the `standard-v1` dumps of `@openzeppelin/merkle-tree`'s `StandardMerkleTree` of
+/// `(string, string, uint256)` values, checked as `OzStandard` campaigns with their proofs derived
+/// from the dumped tree, and the files of the `merkle-tree` binary. Errors describe exports that
+/// cannot be checked at all.
+pub fn validate_export(export: &Value) -> Result<ValidationReport, String> {
+    if export["format"] == "standard-v1" {
+        validate_oz_standard_export(export)
+    } else if export.get("leaf_format").is_some() {
+        validate_campaign_file(export)
+    } else {
+        Err("Unknown tree export format".to_string())
+    }
+}
+
+/// Same as `validate_export` for an export read from a file.
+pub fn validate_export_file(path: impl AsRef<Path>) -> Result<ValidationReport, String> {
+    let path = path.as_ref();
+    let export = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+    let export = serde_json::from_str(&export)
+        .map_err(|error| format!("Invalid JSON in {}: {error}", path.display()))?;
+
+    validate_export(&export)
+}
+
+fn validate_oz_standard_export(export: &Value) -> Result<ValidationReport, String> {
+    if export["leafEncoding"] != serde_json::json!(["string", "string", "uint256"]) {
+        return Err("OpenZeppelin trees must encode (string, string, uint256) values".to_string());
+    }
+    let tree = export["tree"]
+        .as_array()
+        .filter(|tree| !tree.is_empty())
+        .ok_or("OpenZeppelin trees must hold a non-empty tree")?
+        .iter()
+        .map(parse_hex_hash)
+        .collect::<Result<Vec<_>, _>>()?;
+    let values = export["values"]
+        .as_array()
+        .ok_or("OpenZeppelin trees must hold values")?;
+
+    let format = LeafFormat {
+        leaf_version: LeafVersion::V1,
+        leaf_encoding: LeafEncoding::OzStandard,
+        ..Default::default()
+    };
+    let hasher = format.tree_hasher();
+    let root = tree[0];
+
+    let mut mismatches = vec![];
+    for (index, entry) in values.iter().enumerate() {
+        let value = &entry["value"];
+        let account = value[0].as_str().unwrap_or_default().to_string();
+        let checked = parse_oz_standard_value(value).and_then(|data| {
+            let tree_index = entry["treeIndex"]
+                .as_u64()
+                .map(|tree_index| tree_index as usize)
+                .filter(|tree_index| *tree_index < tree.len())
+                .ok_or("Invalid tree index")?;
+            let leaf = compute_leaf(&format, &data)?;
+            if leaf != tree[tree_index] {
+                return Ok(Some(EntryError::LeafMismatch));
+            }
+
+            let proof = oz_standard_proof(&tree, tree_index);
+            Ok((!hasher.verify_proof(&leaf, &proof, &root)).then_some(EntryError::InvalidProof))
+        });
+
+        let error = match checked {
+            Ok(error) => error,
+            Err(error) => Some(EntryError::Malformed(error)),
+        };
+        if let Some(error) = error {
+            mismatches.push(EntryMismatch {
+                index,
+                account,
+                error,
+            });
+        }
+    }
+
+    Ok(ValidationReport {
+        root,
+        entry_count: values.len(),
+        mismatches,
+    })
+}
+
+fn validate_campaign_file(export: &Value) -> Result<ValidationReport, String> {
+    let format: LeafFormat = serde_json::from_value(export["leaf_format"].clone())
+        .map_err(|error| format!("Invalid leaf format: {error}"))?;
+    check_leaf_version(&format)?;
+    let root = parse_hex_hash(&export["root"])?;
+    let claims = export["claims"]
+        .as_array()
+        .ok_or("Tree files must hold claims")?;
+    let hasher = format.tree_hasher();
+
+    let mut mismatches = vec![];
+    for (index, claim) in claims.iter().enumerate() {
+        let account = claim["account"].as_str().unwrap_or_default().to_string();
+        let checked = parse_claim(claim).and_then(|(data, proof)| {
+            let leaf = compute_leaf(&format, &data)?;
+            Ok((!hasher.verify_proof(&leaf, &proof, &root)).then_some(EntryError::InvalidProof))
+        });
+
+        let error = match checked {
+            Ok(error) => error,
+            Err(error) => Some(EntryError::Malformed(error)),
+        };
+        if let Some(error) = error {
+            mismatches.push(EntryMismatch {
+                index,
+                account,
+                error,
+            });
+        }
+    }
+
+    Ok(ValidationReport {
+        root,
+        entry_count: claims.len(),
+        mismatches,
+    })
+}
+
+fn compute_leaf(format: &LeafFormat, data: &MerkleTreeData) -> Result<CryptoHash, String> {
+    let account = AccountId::from_str(&data.account)
+        .map_err(|error| format!("Invalid account id {:?}: {error}", data.account))?;
+    let lockup = AccountId::from_str(&data.lockup)
+        .map_err(|error| format!("Invalid account id {:?}: {error}", data.lockup))?;
+
+    Ok(format.compute_leaf(None, &account, &lockup, data.amount, None, None))
+}
+
+/// Returns the proof of the node at `index` of a tree laid out as an array, where the children
+/// of node `i` are at `2i + 1` and `2i + 2`.
+fn oz_standard_proof(tree: &[CryptoHash], mut index: usize) -> Vec<CryptoHash> {
+    let mut proof = vec![];
+    while index > 0 {
+        let sibling = if index % 2 == 0 { index - 1 } else { index + 1 };
+        if let Some(sibling) = tree.get(sibling) {
+            proof.push(*sibling);
+        }
+        index = (index - 1) / 2;
+    }
+    proof
+}
+
+fn parse_oz_standard_value(value: &Value) -> Result<MerkleTreeData, String> {
+    let (Some(account), Some(lockup)) = (value[0].as_str(), value[1].as_str()) else {
+        return Err("Values must be [account, lockup, amount]".to_string());
+    };
+
+    Ok(MerkleTreeData {
+        account: account.to_string(),
+        lockup: lockup.to_string(),
+        amount: parse_amount(&value[2])?,
+    })
+}
+
+fn parse_claim(claim: &Value) -> Result<(MerkleTreeData, Vec<CryptoHash>), String> {
+    let (Some(account), Some(lockup)) = (claim["account"].as_str(), claim["lockup"].as_str())
+    else {
+        return Err("Claims must hold an account and a lockup".to_string());
+    };
+    let proof: Vec<Base58CryptoHash> = serde_json::from_value(claim["proof"].clone())
+        .map_err(|error| format!("Invalid proof: {error}"))?;
+
+    let data = MerkleTreeData {
+        account: account.to_string(),
+        lockup: lockup.to_string(),
+        amount: parse_amount(&claim["amount"])?,
+    };
+    Ok((data, proof.into_iter().map(Into::into).collect()))
+}
+
+/// Parses an amount given as a decimal string or a JSON number.
+fn parse_amount(amount: &Value) -> Result<Balance, String> {
+    match amount {
+        Value::String(amount) => Balance::from_str(amount).ok(),
+        Value::Number(amount) => amount.as_u64().map(Balance::from),
+        _ => None,
+    }
+    .ok_or_else(|| format!("Invalid amount {amount}"))
+}
+
+fn parse_hex_hash(hash: &Value) -> Result<CryptoHash, String> {
+    hash.as_str()
+        .and_then(|hash| hash.strip_prefix("0x"))
+        .filter(|hex| hex.len() == 64 && hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+        .map(|hex| decode_hex(hex, "Invalid hash"))
+        .ok_or_else(|| format!("Invalid hash {hash}, expected 0x followed by 64 hex characters"))
+}
@@ -0,0 +1,48 @@
+//! The contract's [NEP-330](https://github.com/near/NEPs/blob/master/neps/nep-0330.md) source
+//! metadata. The `contract_metadata` attribute of `MerkleClaim` embeds it at build time and the
+//! SDK exports the `contract_source_metadata` view returning it. Its `version` is the package
+//! version of Cargo.toml and its `link` the repository, unless `NEP330_VERSION` or `NEP330_LINK`
+//! are set when building, e.g. to the commit and a link to its source.
+
+use near_sdk::{near, serde_json};
+
+/// The source metadata returned by `contract_source_metadata`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers=[json])]
+pub struct ContractSourceMetadata {
+    pub version: Option<String>,
+    pub link: Option<String>,
+    /// The standards the contract implements, always including `nep330` itself
+    pub standards: Vec<Standard>,
+    /// How a reproducible build was made, `None` for other builds
+    #[serde(default)]
+    pub build_info: Option<BuildInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers=[json])]
+pub struct Standard {
+    pub standard: String,
+    pub version: String,
+}
+
+/// The build details `cargo near build` records for reproducible builds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers=[json])]
+pub struct BuildInfo {
+    /// The docker image the contract was built in
+    pub build_environment: String,
+    pub build_command: Vec<String>,
+    pub contract_path: String,
+    /// The source the contract was built from, e.g. a git repository at a commit
+    pub source_code_snapshot: String,
+    pub output_wasm_path: Option<String>,
+}
+
+impl ContractSourceMetadata {
+    /// Returns the metadata embedded in this build.
+    pub fn embedded() -> Self {
+        serde_json::from_str(crate::CONTRACT_SOURCE_METADATA)
+            .expect("Embedded source metadata is valid")
+    }
+}
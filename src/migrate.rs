@@ -0,0 +1,147 @@
+use crate::config::DEFAULT_MAX_PROOF_LENGTH;
+use crate::*;
+
+/// Campaign layout written by v1.0.0, before leaf hashing and format were configurable.
+#[near(serializers=[borsh])]
+struct RewardCampaignV1 {
+    id: CampaignId,
+    claim_start: U64,
+    claim_end: U64,
+    merkle_root: CryptoHash,
+}
+
+/// Configuration layout written by v1.0.0.
+#[near(serializers=[borsh])]
+struct ConfigV1 {
+    owner_account_id: AccountId,
+    min_storage_deposit: NearToken,
+}
+
+/// Contract state layout written by v1.0.0.
+#[near(serializers=[borsh])]
+struct MerkleClaimV1 {
+    config: ConfigV1,
+    claims: LookupSet<CryptoHash>,
+    campaigns: LookupMap<CampaignId, RewardCampaignV1>,
+    last_campaign_id: CampaignId,
+    paused: bool,
+}
+
+#[near]
+impl MerkleClaim {
+    /// Upgrades state written by v1.0.0. Existing campaigns keep single hashed `V1` leaves so
+    /// the roots they were created with stay valid, and `V1` claim keys so recorded claims are
+    /// still recognized as `Legacy` claim records.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old: MerkleClaimV1 = env::state_read().expect("Failed to read old contract state");
+
+        let mut campaigns = LookupMap::new(StorageKeys::Campaigns);
+
+        for campaign_id in 1..=old.last_campaign_id {
+            if let Some(campaign) = old.campaigns.get(&campaign_id) {
+                // `set` overwrites the old entry without trying to read it as the new layout
+                campaigns.set(
+                    campaign_id,
+                    Some(RewardCampaign {
+                        id: campaign.id,
+                        claim_start: campaign.claim_start,
+                        claim_end: Some(campaign.claim_end),
+                        original_claim_end: Some(campaign.claim_end),
+                        merkle_root: campaign.merkle_root,
+                        hash_algo: HashAlgo::Keccak256,
+                        proof_format: ProofFormat::Commutative,
+                        leaf_count: None,
+                        leaf_encoding: LeafEncoding::Borsh,
+                        leaf_hashing: LeafHashing::Single,
+                        leaf_version: LeafVersion::V1,
+                        claim_tracking: ClaimTracking::Keys,
+                        verify_lockup_owner: false,
+                        verify_receiver_exists: false,
+                        claim_key_version: ClaimKeyVersion::V1,
+                        allow_partial_claims: false,
+                        vesting_end: None,
+                        claim_count: 0,
+                        decay: None,
+                        bonus: None,
+                        bonus_pool: U128(0),
+                        allow_liquid_split: false,
+                        periods: None,
+                        allocation_mode: AllocationMode::PerLeaf,
+                        total_allocation: None,
+                        claimed_total: U128(0),
+                        allocation_released: false,
+                        vesting_reserved: U128(0),
+                        claim_storage: ClaimStorage::Shared,
+                        payout_call: None,
+                        require_registration: false,
+                        registration_deposits: U128(0),
+                        referral: None,
+                        referral_pool: U128(0),
+                        claimant_pays_storage: false,
+                        donation_pool: U128(0),
+                        storage_bytes: 0,
+                        memo: None,
+                    }),
+                );
+            }
+        }
+
+        Self {
+            config: Config {
+                owner_account_id: old.config.owner_account_id,
+                min_storage_deposit: old.config.min_storage_deposit,
+                allowed_lockup_suffix: None,
+                max_proof_length: DEFAULT_MAX_PROOF_LENGTH,
+                claim_fee_bps: 0,
+                fee_receiver: None,
+                min_claim_amount: None,
+                max_claim_amount: None,
+                max_relayer_fee: None,
+                withdraw_timelock: U64(0),
+                default_withdraw_receiver: None,
+                treasury_account_id: None,
+                withdraw_limit: None,
+                low_balance_threshold: None,
+                max_total_extension: None,
+            },
+            // Entries of the old claims set are empty values, which read back as `Legacy` records
+            claims: LookupMap::new(StorageKeys::Claims),
+            campaign_claims: LookupMap::new(StorageKeys::CampaignClaims),
+            campaigns,
+            claim_bitmaps: LookupMap::new(StorageKeys::ClaimBitmaps),
+            claimed_amounts: LookupMap::new(StorageKeys::ClaimedAmounts),
+            vesting_positions: LookupMap::new(StorageKeys::VestingPositions),
+            signature_nonces: LookupMap::new(StorageKeys::SignatureNonces),
+            invalidated_leaves: LookupSet::new(StorageKeys::InvalidatedLeaves),
+            blocked_accounts: LookupSet::new(StorageKeys::BlockedAccounts),
+            registrations: LookupMap::new(StorageKeys::Registrations),
+            reserved_balance: 0,
+            last_campaign_id: old.last_campaign_id,
+            pause_flags: if old.paused {
+                PauseFlags::ALL
+            } else {
+                PauseFlags::NONE
+            },
+            guardians: IterableSet::new(StorageKeys::Guardians),
+            financial_operators: LookupSet::new(StorageKeys::FinancialOperators),
+            admin_log: LookupMap::new(StorageKeys::AdminLog),
+            admin_log_length: 0,
+            multisig: None,
+            proposals: LookupMap::new(StorageKeys::Proposals),
+            last_proposal_id: 0,
+            pending_withdrawal: None,
+            pending_config_change: None,
+            pending_owner: None,
+            owner_history: Vec::new(),
+            emergency_shutdown: None,
+            withdraw_window: WithdrawWindow::default(),
+            operator_keys: IterableMap::new(StorageKeys::OperatorKeys),
+            storage_balances: LookupMap::new(StorageKeys::StorageBalances),
+            storage_balances_total: 0,
+            record_payers: LookupMap::new(StorageKeys::RecordPayers),
+            storage_deposits: LookupMap::new(StorageKeys::StorageDeposits),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FinancialOperatorEvent {
+    pub account_id: AccountId,
+    /// `false` when the role was revoked
+    pub granted: bool,
+}
+
+impl MerkleClaim {
+    /// Panics unless the caller is the owner or a financial operator.
+    pub(crate) fn assert_owner_or_financial_operator(&self) {
+        let account_id = env::predecessor_account_id();
+        require!(
+            account_id == self.config.owner_account_id
+                || self.financial_operators.contains(&account_id),
+            "Only the owner or a financial operator can call this method"
+        );
+    }
+
+    fn log_financial_operator(account_id: AccountId, granted: bool) {
+        let event = FinancialOperatorEvent {
+            account_id,
+            granted,
+        };
+
+        Event::FinancialOperator(event).emit();
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Allows `account_id` to fund bonus and referral pools and to sweep ended campaigns, e.g. a
+    /// treasury multisig. Swept funds still go to the treasury.
+    pub fn grant_financial_operator(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        require!(
+            self.financial_operators.insert(account_id.clone()),
+            "Account is already a financial operator"
+        );
+        self.record_admin_action("grant_financial_operator", account_id.to_string());
+
+        Self::log_financial_operator(account_id, true);
+    }
+
+    pub fn revoke_financial_operator(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        require!(
+            self.financial_operators.remove(&account_id),
+            "Account is not a financial operator"
+        );
+        self.record_admin_action("revoke_financial_operator", account_id.to_string());
+
+        Self::log_financial_operator(account_id, false);
+    }
+
+    pub fn is_financial_operator(&self, account_id: AccountId) -> bool {
+        self.financial_operators.contains(&account_id)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::*;
+
+/// The number of owners `get_owner_history` keeps, older ones are dropped to bound storage.
+pub const OWNER_HISTORY_CAPACITY: usize = 20;
+
+/// An owner of the contract and when it became the owner.
+#[derive(Clone)]
+#[near(serializers=[borsh, json])]
+pub struct OwnerChange {
+    pub account_id: AccountId,
+    pub timestamp: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnershipProposedEvent {
+    pub owner_account_id: AccountId,
+    pub pending_owner: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnershipTransferredEvent {
+    pub previous_owner: AccountId,
+    pub owner_account_id: AccountId,
+}
+
+#[near]
+impl MerkleClaim {
+    /// Proposes `new_owner` as the next owner, replacing any pending proposal. Ownership only
+    /// changes once `new_owner` calls `accept_ownership`, so a mistyped account never ends up
+    /// owning the contract.
+    #[payable]
+    pub fn propose_owner(&mut self, new_owner: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(
+            new_owner != self.config.owner_account_id,
+            "Account is already the owner"
+        );
+        self.pending_owner = Some(new_owner.clone());
+        self.record_admin_action("propose_owner", new_owner.to_string());
+
+        let proposed = OwnershipProposedEvent {
+            owner_account_id: self.config.owner_account_id.clone(),
+            pending_owner: new_owner,
+        };
+        Event::OwnershipProposed(proposed).emit();
+    }
+
+    /// Completes the transfer of ownership to the proposed owner, who must be the caller.
+    #[payable]
+    pub fn accept_ownership(&mut self) {
+        assert_one_yocto();
+        let new_owner = env::predecessor_account_id();
+        require!(
+            self.pending_owner.as_ref() == Some(&new_owner),
+            "Only the proposed owner can accept ownership"
+        );
+        self.pending_owner = None;
+        let previous_owner = std::mem::replace(&mut self.config.owner_account_id, new_owner);
+        self.record_owner_change();
+        self.record_admin_action("accept_ownership", format!("from {previous_owner}"));
+
+        let transferred = OwnershipTransferredEvent {
+            previous_owner,
+            owner_account_id: self.config.owner_account_id.clone(),
+        };
+        Event::OwnershipTransferred(transferred).emit();
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.config.owner_account_id.clone()
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.clone()
+    }
+
+    /// Returns the owners since the contract was initialized or migrated, oldest first, up to the
+    /// last `OWNER_HISTORY_CAPACITY`. The last entry is the current owner.
+    pub fn get_owner_history(&self) -> &Vec<OwnerChange> {
+        &self.owner_history
+    }
+}
+
+impl MerkleClaim {
+    /// Appends the current owner to the owner history, dropping the oldest entry once it is full.
+    pub(crate) fn record_owner_change(&mut self) {
+        if self.owner_history.len() == OWNER_HISTORY_CAPACITY {
+            self.owner_history.remove(0);
+        }
+        self.owner_history.push(OwnerChange {
+            account_id: self.config.owner_account_id.clone(),
+            timestamp: U64(env::block_timestamp()),
+        });
+    }
+}
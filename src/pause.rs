@@ -0,0 +1,138 @@
+use crate::*;
+
+/// Operations the owner paused, as a bitset of `PauseFlags::CLAIMS`,
+/// `PauseFlags::CAMPAIGN_CREATION` and `PauseFlags::WITHDRAWALS`, e.g. `3` for claims and
+/// campaign creation.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[near(serializers=[borsh,json])]
+pub struct PauseFlags(pub u8);
+
+impl PauseFlags {
+    /// Claims of every kind, including their callbacks, registrations and vested withdrawals
+    pub const CLAIMS: Self = Self(1);
+    pub const CAMPAIGN_CREATION: Self = Self(1 << 1);
+    /// `withdraw` and `sweep_campaign`
+    pub const WITHDRAWALS: Self = Self(1 << 2);
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(Self::CLAIMS.0 | Self::CAMPAIGN_CREATION.0 | Self::WITHDRAWALS.0);
+
+    pub fn contains(self, flags: Self) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PausedEvent {
+    /// The operations paused from now on, `0` when the contract was unpaused
+    pub pause_flags: PauseFlags,
+    /// The owner or guardian that changed the pause flags
+    pub account_id: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CircuitBreakerTrippedEvent {
+    /// The available balance left after the claim, see `get_balance_breakdown`
+    pub available_balance: U128,
+    pub low_balance_threshold: NearToken,
+    /// The operations paused from now on
+    pub pause_flags: PauseFlags,
+}
+
+impl MerkleClaim {
+    /// Panics if the owner paused the operations of `flag`.
+    pub(crate) fn assert_not_paused(&self, flag: PauseFlags) {
+        if self.pause_flags.contains(flag) {
+            env::panic_str(match flag {
+                PauseFlags::CLAIMS => "Claims are paused",
+                PauseFlags::CAMPAIGN_CREATION => "Campaign creation is paused",
+                _ => "Withdrawals are paused",
+            });
+        }
+    }
+
+    pub(crate) fn update_pause_flags(&mut self, method: &str, pause_flags: PauseFlags) {
+        self.pause_flags = pause_flags;
+        self.record_admin_action(method, format!("pause flags {}", pause_flags.0));
+
+        let event = PausedEvent {
+            pause_flags,
+            account_id: env::predecessor_account_id(),
+        };
+
+        Event::Paused(event).emit();
+    }
+
+    /// Pauses claims once a claim left the available balance below `low_balance_threshold`, so
+    /// an underfunded contract stops accepting claims before their payouts start failing. Only
+    /// the owner can resume them, e.g. after topping up the contract.
+    pub(crate) fn check_low_balance(&mut self) {
+        let Some(low_balance_threshold) = self.config.low_balance_threshold else {
+            return;
+        };
+        let available_balance = self.available_balance();
+        if available_balance >= low_balance_threshold.as_yoctonear()
+            || self.pause_flags.contains(PauseFlags::CLAIMS)
+        {
+            return;
+        }
+
+        self.pause_flags = PauseFlags(self.pause_flags.0 | PauseFlags::CLAIMS.0);
+        self.record_admin_action(
+            "circuit_breaker",
+            format!("available balance {available_balance} yoctoNEAR"),
+        );
+
+        let event = CircuitBreakerTrippedEvent {
+            available_balance: U128(available_balance),
+            low_balance_threshold,
+            pause_flags: self.pause_flags,
+        };
+
+        Event::CircuitBreakerTripped(event).emit();
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Pauses every operation, e.g. when a proof verification bug is found. Views and the
+    /// owner methods not covered by `PauseFlags` keep working. Guardians can pause as well.
+    pub fn pause(&mut self) {
+        self.assert_owner_or_guardian();
+        require!(
+            self.pause_flags != PauseFlags::ALL,
+            "Contract is already paused"
+        );
+
+        self.update_pause_flags("pause", PauseFlags::ALL);
+    }
+
+    pub fn unpause(&mut self) {
+        self.assert_owner();
+        require!(
+            self.pause_flags != PauseFlags::NONE,
+            "Contract is not paused"
+        );
+
+        self.update_pause_flags("unpause", PauseFlags::NONE);
+    }
+
+    /// Pauses exactly the operations in `pause_flags`, e.g. only claims while campaigns are
+    /// staged, and resumes the others.
+    pub fn set_pause_flags(&mut self, pause_flags: PauseFlags) {
+        self.assert_owner();
+        require!(PauseFlags::ALL.contains(pause_flags), "Unknown pause flags");
+
+        self.update_pause_flags("set_pause_flags", pause_flags);
+    }
+
+    pub fn get_pause_flags(&self) -> PauseFlags {
+        self.pause_flags
+    }
+
+    /// Whether any operation is paused.
+    pub fn is_paused(&self) -> bool {
+        self.pause_flags != PauseFlags::NONE
+    }
+}
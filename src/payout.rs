@@ -0,0 +1,344 @@
+use crate::config::apply_bps;
+use crate::*;
+use near_sdk::{Gas, PromiseResult};
+
+/// Gas attached to the callback rolling back a claim whose payout failed.
+const GAS_FOR_ON_CLAIM_PAYOUT: Gas = Gas::from_tgas(10);
+
+/// Gas a claim must be called with. Besides its own execution, it covers the lockup owner check
+/// (30 Tgas) or receiver check (25 Tgas) and the payout callback (10 Tgas) the claim may
+/// schedule, so those are never starved.
+///
+/// Measured by running the release wasm in near-vm-runner 0.31 with the fees of protocol 79, for
+/// a claim with a proof of 64 hashes, the default maximum, that pays a claim fee and a referral
+/// bonus:
+/// - with a payout call, the claim burns 5.6 Tgas and uses 17.3 Tgas on top of the call's gas,
+///   including the callback's 10 Tgas, of which `on_claim_payout` burns 2.4 Tgas
+/// - with vesting, which pays everything in the claim itself, the claim burns 6.3 Tgas and uses
+///   7.0 Tgas
+///
+/// The runner does not charge for the trie nodes storage writes touch. `tests/claim_gas.rs`
+/// makes both claims with exactly this gas in a sandbox.
+pub(crate) const MIN_CLAIM_GAS: Gas = Gas::from_tgas(60);
+/// The most gas a campaign's payout call may attach.
+const MAX_PAYOUT_CALL_GAS: Gas = Gas::from_tgas(100);
+
+/// A method claims call on their receiver with the payout attached, for receivers that do not
+/// accept bare transfers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub struct PayoutCall {
+    pub method_name: String,
+    /// The JSON arguments, in which `{account_id}` is replaced by the claimant and `{amount}` by
+    /// the attached amount in yoctoNEAR
+    pub args_template: String,
+    /// The gas attached to the call, which claims need on top of `MIN_CLAIM_GAS`
+    pub gas: Gas,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimPayoutFailedEvent {
+    pub campaign_id: CampaignId,
+    pub account_id: AccountId,
+    /// The part of the payout that was not delivered and stays in the contract
+    pub undelivered: U128,
+}
+
+/// A claim rolled back because its payout failed, so the allocation can be claimed again.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimFailedEvent {
+    pub campaign_id: CampaignId,
+    pub account_id: AccountId,
+    /// `None` when the allocation was to be paid directly to the claimant
+    pub lockup_contract: Option<AccountId>,
+    pub amount: U128,
+    pub reason: String,
+}
+
+impl PayoutCall {
+    /// Panics unless the call names a method, its arguments are a JSON template and its gas is
+    /// positive and at most 100 Tgas.
+    pub(crate) fn assert_valid(&self) {
+        require!(
+            !self.method_name.is_empty(),
+            "Payout method must not be empty"
+        );
+        require!(
+            serde_json::from_slice::<serde_json::Value>(&self.args(&env::current_account_id(), 0))
+                .is_ok(),
+            "Payout arguments must be a JSON template"
+        );
+        require!(
+            self.gas > Gas::from_gas(0) && self.gas <= MAX_PAYOUT_CALL_GAS,
+            "Payout call gas must be positive and at most 100 Tgas"
+        );
+    }
+
+    /// Returns the arguments of the call paying `amount` for `account_id`'s claim.
+    fn args(&self, account_id: &AccountId, amount: Balance) -> Vec<u8> {
+        self.args_template
+            .replace("{account_id}", account_id.as_str())
+            .replace("{amount}", &amount.to_string())
+            .into_bytes()
+    }
+}
+
+impl VerifiedClaim {
+    /// Returns what the claim pays its receiver and claimant, the paid amount minus `fee` and
+    /// the relayer fee plus `bonus`.
+    pub(crate) fn payout(&self, fee: Balance, bonus: Balance) -> Balance {
+        let relayer_fee = self
+            .relayer_fee
+            .as_ref()
+            .map_or(0, |relayer_fee| relayer_fee.amount.0);
+
+        self.paid_amount.0 - fee - relayer_fee + bonus
+    }
+
+    /// Splits a payout into the parts sent to the receiver and to the claimant, rounding the
+    /// claimant's part down so both always add up to `payout`.
+    pub(crate) fn split_payout(&self, payout: Balance) -> (Balance, Balance) {
+        let liquid = apply_bps(payout, self.liquid_bps.unwrap_or_default());
+        (payout - liquid, liquid)
+    }
+
+    /// Returns the transfers paying `payout`, skipping empty parts of a split, and whether each
+    /// of them pays the receiver rather than the claimant.
+    fn payout_transfers(&self, payout: Balance) -> Vec<(AccountId, Balance, bool)> {
+        let (receiver_amount, liquid_amount) = self.split_payout(payout);
+        let transfers: Vec<_> = [
+            (self.receiver_id.clone(), receiver_amount, true),
+            (self.account_id.clone(), liquid_amount, false),
+        ]
+        .into_iter()
+        .filter(|(_, amount, _)| *amount > 0)
+        .collect();
+
+        if transfers.is_empty() {
+            vec![(self.receiver_id.clone(), 0, true)]
+        } else {
+            transfers
+        }
+    }
+}
+
+impl MerkleClaim {
+    /// Sends the payout of a recorded claim, see `VerifiedClaim::payout`, to its receiver, or
+    /// splits it between the receiver and the claimant, rolling the claim back in
+    /// `on_claim_payout` if the transfers fail, e.g. because the receiver account does not exist.
+    /// The fees are only paid once the transfers succeeded. Claims creating their receiver send
+    /// the payout with the account creation, and campaigns with a payout call pay the receiver
+    /// through that call instead of a transfer.
+    pub(crate) fn pay_claim(&self, claim: VerifiedClaim, fee: Balance, bonus: Balance) -> Promise {
+        let payout_call = self.campaigns[&claim.campaign_id].payout_call.clone();
+
+        claim
+            .payout_transfers(claim.payout(fee, bonus))
+            .into_iter()
+            .map(|(receiver_id, amount, to_receiver)| {
+                let deposit = NearToken::from_yoctonear(amount);
+                if let Some(public_key) = claim.new_account_key.clone().filter(|_| to_receiver) {
+                    return Promise::new(receiver_id)
+                        .create_account()
+                        .add_full_access_key(public_key)
+                        .transfer(deposit);
+                }
+
+                match payout_call.as_ref().filter(|_| to_receiver) {
+                    Some(call) => Promise::new(receiver_id).function_call(
+                        call.method_name.clone(),
+                        call.args(&claim.account_id, amount),
+                        deposit,
+                        call.gas,
+                    ),
+                    None => Promise::new(receiver_id).transfer(deposit),
+                }
+            })
+            .reduce(Promise::and)
+            .unwrap()
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_ON_CLAIM_PAYOUT)
+                    .on_claim_payout(claim, U128(fee), U128(bonus)),
+            )
+    }
+
+    /// Sends a claim fee to the fee receiver.
+    pub(crate) fn pay_fee(&self, fee: Balance) {
+        if let Some(fee_receiver) = self.config.fee_destination().filter(|_| fee > 0) {
+            Promise::new(fee_receiver.clone()).transfer(NearToken::from_yoctonear(fee));
+        }
+    }
+
+    /// Builds the event of a confirmed claim that paid `fee` out of its amount and `bonus` on
+    /// top.
+    pub(crate) fn claim_event(&self, claim: &VerifiedClaim, fee: Balance, bonus: Balance) -> Event {
+        let split = claim
+            .liquid_bps
+            .map(|_| claim.split_payout(claim.payout(fee, bonus)));
+        let paid_amount = claim.paid_amount;
+        let campaign = self.campaigns.get(&claim.campaign_id);
+        Event::Claim(ClaimEvent {
+            campaign_id: claim.campaign_id,
+            account_id: claim.account_id.clone(),
+            lockup_contract: claim.lockup_contract.clone(),
+            receiver_id: claim.receiver_id.clone(),
+            amount: claim.amount,
+            paid_amount: (paid_amount != claim.amount).then_some(paid_amount),
+            bonus: (bonus > 0).then_some(U128(bonus)),
+            fee: (fee > 0).then_some(U128(fee)),
+            fee_receiver: self.config.fee_destination().filter(|_| fee > 0).cloned(),
+            net_amount: (fee > 0).then_some(U128(paid_amount.0 - fee)),
+            lockup_amount: split.map(|(lockup_amount, _)| U128(lockup_amount)),
+            liquid_amount: split.map(|(_, liquid_amount)| U128(liquid_amount)),
+            relayer_fee: claim
+                .relayer_fee
+                .as_ref()
+                .map(|relayer_fee| relayer_fee.amount),
+            max_relayer_fee: claim
+                .relayer_fee
+                .as_ref()
+                .map(|relayer_fee| relayer_fee.max_amount),
+            period: campaign
+                .and_then(|campaign| campaign.periods)
+                .and(claim.nonce),
+            claim_index: claim.claim_index.unwrap_or(U64(0)),
+            memo: campaign.and_then(|campaign| campaign.memo.clone()),
+            block_timestamp: U64(env::block_timestamp()),
+            block_height: U64(env::block_height()),
+        })
+    }
+
+    /// Returns why the payout of a claim failed.
+    fn payout_failure_reason(&self, claim: &VerifiedClaim) -> &'static str {
+        if claim.new_account_key.is_some() {
+            return "Receiver account could not be created";
+        }
+
+        match self
+            .campaigns
+            .get(&claim.campaign_id)
+            .and_then(|campaign| campaign.payout_call.as_ref())
+        {
+            Some(_) => "Payout call failed",
+            None => "Payout transfer failed",
+        }
+    }
+
+    /// Undoes what `record_claim` stored for a claim, so the allocation can be claimed again and
+    /// its bonuses and donations are back in their pools. Once the campaign's reserve was
+    /// released by a sweep or cancellation, they stay unreserved.
+    fn rollback_claim(&mut self, claim: &VerifiedClaim, bonus: Balance) {
+        let storage_before = self.claim_storage_usage(claim.campaign_id);
+        if let Some(campaign) = self.campaigns.get_mut(&claim.campaign_id) {
+            let restored = campaign.uncount_claimed(claim.amount.0);
+            if !campaign.allocation_released {
+                let referral_bonus = claim.referral_bonus.map_or(0, |bonus| bonus.0);
+                let donated = claim.donated.map_or(0, |donated| donated.0);
+                campaign.bonus_pool.0 += bonus;
+                campaign.referral_pool.0 += referral_bonus;
+                campaign.donation_pool.0 += donated;
+                self.reserved_balance += bonus + referral_bonus + donated + restored;
+            }
+        }
+        self.debug_assert_reserve_consistent();
+
+        if let Some(index) = claim.index {
+            self.unset_index_claimed(claim.campaign_id, index);
+        } else {
+            let key = self
+                .leaf_claim_key(claim.campaign_id, &claim.account_id, claim.nonce)
+                .expect("Campaign does not exist");
+            self.remove_claim_record(claim.campaign_id, &key);
+
+            if let Some(claimed) = self.claimed_amounts.get(&key).copied() {
+                let claimed = claimed.saturating_sub(claim.amount.0);
+                if claimed == 0 {
+                    self.claimed_amounts.remove(&key);
+                } else {
+                    self.claimed_amounts.insert(key, claimed);
+                }
+            }
+        }
+
+        let storage_released = self.uncount_claim_storage(claim.campaign_id, storage_before);
+        if self
+            .campaigns
+            .get(&claim.campaign_id)
+            .is_some_and(|campaign| campaign.claimant_pays_storage)
+        {
+            self.refund_claim_storage(&claim.account_id, storage_released);
+        }
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Returns the gas claims must be called with.
+    pub fn get_min_claim_gas(&self) -> Gas {
+        MIN_CLAIM_GAS
+    }
+
+    /// Pays the claim and relayer fees and the referral bonus if the payout succeeded, otherwise
+    /// rolls the claim back and returns its bonus to the bonus pool. The registration deposit of
+    /// a claim that is not rolled back is refunded. Returns whether the payout succeeded.
+    ///
+    /// The `ClaimEvent` is logged here, once the claim is confirmed. A rolled back claim logs a
+    /// `ClaimFailedEvent` instead.
+    ///
+    /// A split payout where only one of the transfers failed is not rolled back, since claiming
+    /// again would pay the delivered part twice. The undelivered part stays in the contract and
+    /// is reported in a `ClaimPayoutFailedEvent`.
+    ///
+    /// Claims are paused afterwards if the available balance dropped below
+    /// `low_balance_threshold`.
+    #[private]
+    pub fn on_claim_payout(&mut self, claim: VerifiedClaim, fee: U128, bonus: U128) -> bool {
+        let transfers = claim.payout_transfers(claim.payout(fee.0, bonus.0));
+        let failed: Vec<Balance> = transfers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| matches!(env::promise_result(*i as u64), PromiseResult::Failed))
+            .map(|(_, (_, amount, _))| *amount)
+            .collect();
+
+        let paid = if failed.len() == transfers.len() {
+            let event = ClaimFailedEvent {
+                campaign_id: claim.campaign_id,
+                account_id: claim.account_id.clone(),
+                lockup_contract: claim.lockup_contract.clone(),
+                amount: claim.amount,
+                reason: self.payout_failure_reason(&claim).to_string(),
+            };
+            self.rollback_claim(&claim, bonus.0);
+
+            Event::ClaimFailed(event).emit();
+            false
+        } else {
+            // The claim stays recorded, so its registration has been used
+            self.refund_registration(claim.campaign_id, &claim.account_id);
+            self.claim_event(&claim, fee.0, bonus.0).emit();
+
+            if failed.is_empty() {
+                self.pay_fee(fee.0);
+                self.pay_relayer_fee(&claim);
+                self.pay_referral_bonus(&claim);
+                true
+            } else {
+                let event = ClaimPayoutFailedEvent {
+                    campaign_id: claim.campaign_id,
+                    account_id: claim.account_id,
+                    undelivered: U128(failed.iter().sum()),
+                };
+
+                Event::ClaimPayoutFailed(event).emit();
+                false
+            }
+        };
+
+        self.check_low_balance();
+        paid
+    }
+}
@@ -0,0 +1,66 @@
+use crate::*;
+
+/// Splits a campaign into consecutive periods starting at its `claim_start`, in each of which
+/// every allocation can be claimed once more against the same root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub struct ClaimPeriods {
+    /// The length of a period in nanoseconds
+    pub period_length: U64,
+    pub num_periods: u32,
+}
+
+impl ClaimPeriods {
+    /// Panics unless the periods are non-empty, and returns the timestamp at which the last one
+    /// ends for a campaign starting at `claim_start`.
+    pub(crate) fn assert_valid(&self, claim_start: u64) -> u64 {
+        require!(
+            self.period_length.0 > 0 && self.num_periods > 0,
+            "Periods must have a length and a count"
+        );
+
+        self.period_length
+            .0
+            .checked_mul(u64::from(self.num_periods))
+            .and_then(|duration| claim_start.checked_add(duration))
+            .expect("Periods end overflows")
+    }
+}
+
+impl RewardCampaign {
+    /// Returns the index of the period running at `timestamp`, or `None` for campaigns without
+    /// periods.
+    pub(crate) fn current_period(&self, timestamp: u64) -> Option<u32> {
+        self.periods.map(|periods| {
+            let elapsed = timestamp.saturating_sub(self.claim_start.0);
+            u32::try_from(elapsed / periods.period_length.0).unwrap_or(u32::MAX)
+        })
+    }
+
+    /// Returns the nonce distinguishing claim keys of an account, which is the leaf nonce of
+    /// `V5` campaigns and the current period of periodic campaigns.
+    pub(crate) fn claim_nonce(&self, nonce: Option<u32>, timestamp: u64) -> Option<u32> {
+        self.leaf_nonce(nonce)
+            .or_else(|| self.current_period(timestamp))
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Returns the index of a periodic campaign's current period, starting at 0, or `None` if
+    /// the campaign does not exist, has no periods or its last period is over.
+    pub fn get_current_period(&self, campaign_id: CampaignId) -> Option<u32> {
+        let campaign = self.campaigns.get(&campaign_id)?;
+        let periods = campaign.periods?;
+
+        campaign
+            .current_period(env::block_timestamp())
+            .filter(|period| *period < periods.num_periods)
+    }
+
+    /// Returns whether `account_id` has claimed in the current period of a periodic campaign.
+    pub fn has_claimed_in_period(&self, campaign_id: CampaignId, account_id: AccountId) -> bool {
+        self.get_current_period(campaign_id)
+            .is_some_and(|period| self.is_nonce_claimed(campaign_id, account_id, period))
+    }
+}
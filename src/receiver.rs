@@ -0,0 +1,45 @@
+use crate::*;
+use near_sdk::{Gas, PromiseResult};
+
+/// Gas attached to the callback recording a claim once its receiver is known to exist, which
+/// also schedules the payout and its callback.
+const GAS_FOR_ON_RECEIVER_CHECKED: Gas = Gas::from_tgas(25);
+
+impl MerkleClaim {
+    /// Sends an empty transfer to the receiver of `claim`, which fails if the account does not
+    /// exist, and records the claim in `on_receiver_checked` if it succeeded. Nothing is recorded
+    /// until then, so a missing receiver leaves the claim intact.
+    pub(crate) fn claim_after_receiver_check(claim: VerifiedClaim) -> Promise {
+        Promise::new(claim.receiver_id.clone())
+            .transfer(NearToken::from_yoctonear(0))
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_ON_RECEIVER_CHECKED)
+                    .on_receiver_checked(claim),
+            )
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Records a claim whose proof was verified once the empty transfer to its receiver
+    /// succeeded.
+    #[private]
+    pub fn on_receiver_checked(&mut self, claim: VerifiedClaim) {
+        self.assert_not_paused(PauseFlags::CLAIMS);
+
+        require!(
+            matches!(env::promise_result(0), PromiseResult::Successful(_)),
+            "Receiver account does not exist"
+        );
+
+        // Another claim of the same allocation may have completed while the receiver was checked
+        let campaign = self
+            .campaigns
+            .get(&claim.campaign_id)
+            .expect("Campaign does not exist");
+        self.assert_unclaimed(campaign, &claim.account_id, claim.index, claim.nonce);
+
+        self.record_claim(claim);
+    }
+}
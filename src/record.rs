@@ -0,0 +1,433 @@
+use crate::registration::registration_key;
+use crate::*;
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use std::io::{self, Read, Write};
+
+/// The number of claim records `delete_campaign` removes per call unless told otherwise.
+const DEFAULT_DELETE_BATCH: u32 = 100;
+
+/// The most storage a claim record can use in bytes, with the entries indexing it and the
+/// claimed amount of partial claims, which claimants paying for storage attach the cost of.
+const MAX_CLAIM_STORAGE_BYTES: u64 = 500;
+
+/// The most accounts or campaigns `has_claimed_batch` and `has_claimed_many` look up per call.
+pub const MAX_HAS_CLAIMED_BATCH: usize = 500;
+
+/// What the contract knows about a recorded claim key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers=[json])]
+pub enum ClaimRecord {
+    /// Claimed before claim records were introduced, when only the key was stored
+    Legacy,
+    /// Claimed in full
+    Claimed {
+        /// The claimed amount, the whole allocation for partial claims
+        amount: U128,
+        /// `None` when the allocation was paid directly to the claimant
+        lockup_contract: Option<AccountId>,
+        /// When the claim completed
+        timestamp: U64,
+    },
+    /// Invalidated by the owner before it was claimed
+    Invalidated,
+}
+
+/// Where a campaign stores the records of its claims.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub enum ClaimStorage {
+    /// In the contract-wide `claims` map, used by campaigns migrated from v1.0.0 whose records
+    /// cannot be told apart from those of other campaigns
+    Shared,
+    /// In a collection of the campaign's own, which `delete_campaign` removes
+    #[default]
+    Campaign,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CampaignDeletedEvent {
+    pub campaign_id: CampaignId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimPurgedEvent {
+    pub campaign_id: CampaignId,
+    pub account_id: AccountId,
+    /// The claimant, or the sponsor that registered it
+    pub receiver_id: AccountId,
+    /// The cost of the storage the purge released
+    pub refund: U128,
+}
+
+/// `Legacy` records are stored as empty values, like the entries of the former claims set, so
+/// those read back as `Legacy` without migrating them one by one.
+impl BorshSerialize for ClaimRecord {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::Legacy => Ok(()),
+            Self::Claimed {
+                amount,
+                lockup_contract,
+                timestamp,
+            } => {
+                BorshSerialize::serialize(&0u8, writer)?;
+                BorshSerialize::serialize(amount, writer)?;
+                BorshSerialize::serialize(lockup_contract, writer)?;
+                BorshSerialize::serialize(timestamp, writer)
+            }
+            Self::Invalidated => BorshSerialize::serialize(&1u8, writer),
+        }
+    }
+}
+
+impl BorshDeserialize for ClaimRecord {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut tag = [0u8; 1];
+        if reader.read(&mut tag)? == 0 {
+            return Ok(Self::Legacy);
+        }
+
+        match tag[0] {
+            0 => Ok(Self::Claimed {
+                amount: U128::deserialize_reader(reader)?,
+                lockup_contract: Option::deserialize_reader(reader)?,
+                timestamp: U64::deserialize_reader(reader)?,
+            }),
+            1 => Ok(Self::Invalidated),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid claim record",
+            )),
+        }
+    }
+}
+
+impl VerifiedClaim {
+    /// Returns the record of this claim completing with `amount` claimed in total.
+    pub(crate) fn record(&self, amount: U128) -> ClaimRecord {
+        ClaimRecord::Claimed {
+            amount,
+            lockup_contract: self.lockup_contract.clone(),
+            timestamp: U64(env::block_timestamp()),
+        }
+    }
+}
+
+impl MerkleClaim {
+    /// Returns the record stored under `key` for a campaign's claim.
+    pub(crate) fn claim_record(
+        &self,
+        campaign_id: CampaignId,
+        key: &CryptoHash,
+    ) -> Option<&ClaimRecord> {
+        match self.campaigns.get(&campaign_id)?.claim_storage {
+            ClaimStorage::Shared => self.claims.get(key),
+            ClaimStorage::Campaign => self.campaign_claims.get(&campaign_id)?.get(key),
+        }
+    }
+
+    /// Stores the record of a campaign's claim, creating the campaign's own collection on its
+    /// first claim.
+    pub(crate) fn insert_claim_record(
+        &mut self,
+        campaign_id: CampaignId,
+        key: CryptoHash,
+        record: ClaimRecord,
+    ) {
+        match self.campaigns[&campaign_id].claim_storage {
+            ClaimStorage::Shared => {
+                self.claims.insert(key, record);
+            }
+            ClaimStorage::Campaign => {
+                self.campaign_claims
+                    .entry(campaign_id)
+                    .or_insert_with(|| {
+                        IterableMap::new(StorageKeys::CampaignClaimRecords { campaign_id })
+                    })
+                    .insert(key, record);
+            }
+        }
+    }
+
+    /// Writes the pending changes of the maps a claim is stored in, so their storage usage is
+    /// up to date.
+    fn flush_claim_storage(&mut self, campaign_id: CampaignId) {
+        self.claims.flush();
+        self.claim_bitmaps.flush();
+        if let Some(records) = self.campaign_claims.get_mut(&campaign_id) {
+            records.flush();
+        }
+        self.campaign_claims.flush();
+        self.claimed_amounts.flush();
+        self.vesting_positions.flush();
+        self.record_payers.flush();
+    }
+
+    /// Returns the storage usage once the claims of a campaign are written.
+    pub(crate) fn claim_storage_usage(&mut self, campaign_id: CampaignId) -> u64 {
+        self.flush_claim_storage(campaign_id);
+        env::storage_usage()
+    }
+
+    /// Adds the storage a campaign's claims used since `before` to its `storage_bytes` and
+    /// returns it.
+    pub(crate) fn count_claim_storage(&mut self, campaign_id: CampaignId, before: u64) -> u64 {
+        let used = self.claim_storage_usage(campaign_id).saturating_sub(before);
+        if let Some(campaign) = self.campaigns.get_mut(&campaign_id) {
+            campaign.storage_bytes += used;
+        }
+        used
+    }
+
+    /// Removes the storage a campaign's claims released since `before` from its `storage_bytes`
+    /// and returns it.
+    pub(crate) fn uncount_claim_storage(&mut self, campaign_id: CampaignId, before: u64) -> u64 {
+        let released = before.saturating_sub(self.claim_storage_usage(campaign_id));
+        if let Some(campaign) = self.campaigns.get_mut(&campaign_id) {
+            campaign.storage_bytes = campaign.storage_bytes.saturating_sub(released);
+        }
+        released
+    }
+
+    /// Charges the caller of a claim for the `used` bytes of storage its records use, refunding
+    /// the rest of the attached deposit, in campaigns whose claimants pay for storage.
+    pub(crate) fn charge_claim_storage(&self, account_id: &AccountId, used: u64) {
+        require!(
+            env::predecessor_account_id() == *account_id,
+            "Claims of this campaign must be made by the claimant"
+        );
+
+        let required = Balance::from(used) * env::storage_byte_cost().as_yoctonear();
+        let deposit = env::attached_deposit().as_yoctonear();
+        if deposit < required {
+            env::panic_str(&format!(
+                "Claim requires a storage deposit of {required} yoctoNEAR"
+            ));
+        }
+
+        if deposit > required {
+            Promise::new(account_id.clone())
+                .transfer(NearToken::from_yoctonear(deposit - required));
+        }
+    }
+
+    /// Refunds the claimant of a rolled back claim the `released` bytes of storage its records
+    /// used, in campaigns whose claimants pay for storage.
+    pub(crate) fn refund_claim_storage(&self, account_id: &AccountId, released: u64) {
+        let refund = Balance::from(released) * env::storage_byte_cost().as_yoctonear();
+        if refund > 0 {
+            Promise::new(account_id.clone()).transfer(NearToken::from_yoctonear(refund));
+        }
+    }
+
+    /// Removes the record of a campaign's claim.
+    pub(crate) fn remove_claim_record(&mut self, campaign_id: CampaignId, key: &CryptoHash) {
+        match self.campaigns[&campaign_id].claim_storage {
+            ClaimStorage::Shared => {
+                self.claims.remove(key);
+            }
+            ClaimStorage::Campaign => {
+                if let Some(records) = self.campaign_claims.get_mut(&campaign_id) {
+                    records.remove(key);
+                }
+            }
+        }
+    }
+
+    /// Panics if a batch view looks up more than `MAX_HAS_CLAIMED_BATCH` entries.
+    fn assert_has_claimed_batch(len: usize) {
+        require!(
+            len <= MAX_HAS_CLAIMED_BATCH,
+            "A batch may look up at most 500 entries"
+        );
+    }
+
+    /// Panics if the claims of a campaign are told apart by a leaf nonce, which batch views do
+    /// not take.
+    fn assert_batch_lookup(&self, campaign_id: CampaignId) {
+        require!(
+            self.campaigns
+                .get(&campaign_id)
+                .is_none_or(|campaign| campaign.leaf_version != LeafVersion::V5),
+            "Claims of V5 campaigns are looked up by nonce with is_nonce_claimed"
+        );
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Returns the record of `account_id`'s claim in a `Keys` campaign, or `None` if it has not
+    /// been claimed.
+    pub fn get_claim_record(
+        &self,
+        campaign_id: CampaignId,
+        account_id: AccountId,
+    ) -> Option<ClaimRecord> {
+        self.claim_key(campaign_id, &account_id)
+            .and_then(|key| self.claim_record(campaign_id, &key).cloned())
+    }
+
+    /// Returns `has_claimed` for each of `account_ids` in a campaign, in the same order. At most
+    /// `MAX_HAS_CLAIMED_BATCH` accounts are looked up per call, and `V5` campaigns are refused.
+    pub fn has_claimed_batch(
+        &self,
+        campaign_id: CampaignId,
+        account_ids: Vec<AccountId>,
+    ) -> Vec<bool> {
+        Self::assert_has_claimed_batch(account_ids.len());
+        self.assert_batch_lookup(campaign_id);
+        account_ids
+            .into_iter()
+            .map(|account_id| self.has_claimed(campaign_id, account_id))
+            .collect()
+    }
+
+    /// Returns `has_claimed` for `account_id` in each of `campaign_ids`, in the same order. At
+    /// most `MAX_HAS_CLAIMED_BATCH` campaigns are looked up per call, and `V5` campaigns are
+    /// refused.
+    pub fn has_claimed_many(
+        &self,
+        account_id: AccountId,
+        campaign_ids: Vec<CampaignId>,
+    ) -> Vec<bool> {
+        Self::assert_has_claimed_batch(campaign_ids.len());
+        campaign_ids
+            .into_iter()
+            .map(|campaign_id| {
+                self.assert_batch_lookup(campaign_id);
+                self.has_claimed(campaign_id, account_id.clone())
+            })
+            .collect()
+    }
+
+    /// Deletes an ended campaign that no longer reserves any funds, together with the records
+    /// of its claims and the claimed amounts and vesting positions stored under their keys, so
+    /// their storage is released. At most `limit` records, 100 by default, are removed per call,
+    /// and the campaign itself is only deleted once none are left. Returns whether it was.
+    ///
+    /// Campaigns with `Shared` claim storage are deleted without their records, which cannot be
+    /// enumerated.
+    pub fn delete_campaign(&mut self, campaign_id: CampaignId, limit: Option<u32>) -> bool {
+        self.assert_owner();
+
+        let campaign = self
+            .campaigns
+            .get(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(
+            campaign.has_ended(env::block_timestamp()),
+            "Campaign has not ended yet"
+        );
+        require!(
+            campaign.reserved_amount() == 0,
+            "Campaign still reserves funds"
+        );
+
+        let storage_before = env::storage_usage();
+        if let Some(records) = self.campaign_claims.get_mut(&campaign_id) {
+            let limit = limit.unwrap_or(DEFAULT_DELETE_BATCH) as usize;
+            // Removing the last keys first never moves any other key
+            let keys: Vec<CryptoHash> = records.keys().rev().take(limit).copied().collect();
+
+            for key in keys {
+                records.remove(&key);
+                self.claimed_amounts.remove(&key);
+                self.vesting_positions.remove(&key);
+                self.record_payers.remove(&key);
+            }
+
+            if !records.is_empty() {
+                self.uncount_claim_storage(campaign_id, storage_before);
+                return false;
+            }
+            self.campaign_claims.remove(&campaign_id);
+        }
+
+        self.campaigns.remove(&campaign_id);
+        self.record_admin_action("delete_campaign", format!("campaign {campaign_id}"));
+
+        let deleted = CampaignDeletedEvent { campaign_id };
+
+        Event::CampaignDeleted(deleted).emit();
+
+        true
+    }
+
+    /// Returns the deposit claims of a campaign whose claimants pay for storage must attach. It
+    /// covers the most a claim record can use, what is not used is refunded. `0` for other
+    /// campaigns.
+    pub fn get_claim_storage_deposit(&self, campaign_id: CampaignId) -> NearToken {
+        let campaign = self
+            .campaigns
+            .get(&campaign_id)
+            .expect("Campaign does not exist");
+        if !campaign.claimant_pays_storage {
+            return NearToken::from_yoctonear(0);
+        }
+
+        env::storage_byte_cost().saturating_mul(u128::from(MAX_CLAIM_STORAGE_BYTES))
+    }
+
+    /// Removes the caller's claim record of an ended campaign, together with its claimed amount
+    /// and its vesting position once fully released, and refunds the cost of the storage this
+    /// releases to the caller, or to the sponsor that registered it. The campaign's claimed
+    /// totals are kept. `nonce` selects the record of the leaf with that nonce in `V5` campaigns,
+    /// or of the claimed period in periodic campaigns. Returns the refund.
+    pub fn purge_my_claim(&mut self, campaign_id: CampaignId, nonce: Option<u32>) -> U128 {
+        self.assert_not_paused(PauseFlags::CLAIMS);
+
+        let account_id = env::predecessor_account_id();
+        let campaign = self
+            .campaigns
+            .get(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(
+            campaign.has_ended(env::block_timestamp()),
+            "Campaign has not ended yet"
+        );
+
+        let key = self
+            .leaf_claim_key(campaign_id, &account_id, nonce)
+            .filter(|key| {
+                matches!(
+                    self.claim_record(campaign_id, key),
+                    Some(ClaimRecord::Legacy | ClaimRecord::Claimed { .. })
+                )
+            })
+            .expect("No claim record to purge");
+        if let Some(position) = self.vesting_positions.get(&key) {
+            require!(
+                position.withdrawn == position.total,
+                "Vesting position is not fully released"
+            );
+        }
+
+        let before = env::storage_usage();
+        self.remove_claim_record(campaign_id, &key);
+        self.claimed_amounts.remove(&key);
+        self.vesting_positions.remove(&key);
+        let receiver_id = self
+            .record_payers
+            .remove(&registration_key(campaign_id, &account_id))
+            .unwrap_or_else(|| account_id.clone());
+
+        let released = self.uncount_claim_storage(campaign_id, before);
+        let refund = Balance::from(released) * env::storage_byte_cost().as_yoctonear();
+        if refund > 0 {
+            Promise::new(receiver_id.clone()).transfer(NearToken::from_yoctonear(refund));
+        }
+
+        let purged = ClaimPurgedEvent {
+            campaign_id,
+            account_id,
+            receiver_id,
+            refund: U128(refund),
+        };
+
+        Event::ClaimPurged(purged).emit();
+
+        U128(refund)
+    }
+}
@@ -0,0 +1,113 @@
+use crate::config::{apply_bps, BPS_DENOMINATOR};
+use crate::*;
+
+/// A bonus paid to the referrer of a claim, from the campaign's referral pool while it lasts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers=[borsh, json])]
+pub struct ClaimReferral {
+    /// The bonus as a share of the paid amount, in basis points
+    pub bps: u16,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReferralEvent {
+    pub campaign_id: CampaignId,
+    pub account_id: AccountId,
+    pub referrer: AccountId,
+    pub bonus: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReferralPoolFundedEvent {
+    pub campaign_id: CampaignId,
+    pub amount: U128,
+    pub referral_pool: U128,
+}
+
+impl RewardCampaign {
+    /// Panics unless `referral` is a positive share.
+    pub(crate) fn assert_valid_referral(referral: &ClaimReferral) {
+        require!(
+            referral.bps > 0 && referral.bps <= BPS_DENOMINATOR,
+            "Referral bonus must be between 1 and 10000 bps"
+        );
+    }
+
+    /// Takes the referral bonus of a referred claim paying `amount` out of the referral pool,
+    /// which may leave the last bonus short once the pool runs dry.
+    pub(crate) fn take_referral_bonus(&mut self, amount: Balance) -> Balance {
+        let Some(referral) = self.referral else {
+            return 0;
+        };
+
+        let bonus = apply_bps(amount, referral.bps).min(self.referral_pool.0);
+        self.referral_pool.0 -= bonus;
+        bonus
+    }
+}
+
+impl MerkleClaim {
+    /// Sends the referral bonus of a claim to its referrer.
+    pub(crate) fn pay_referral_bonus(&self, claim: &VerifiedClaim) {
+        let (Some(referrer), Some(bonus)) = (&claim.referrer, claim.referral_bonus) else {
+            return;
+        };
+
+        Promise::new(referrer.clone()).transfer(NearToken::from_yoctonear(bonus.0));
+
+        let event = ReferralEvent {
+            campaign_id: claim.campaign_id,
+            account_id: claim.account_id.clone(),
+            referrer: referrer.clone(),
+            bonus,
+        };
+
+        Event::Referral(event).emit();
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Adds the attached deposit to a campaign's referral pool and returns the new pool balance.
+    /// Referral bonuses are only ever paid out of the pool.
+    #[payable]
+    pub fn fund_referral_pool(&mut self, campaign_id: CampaignId) -> U128 {
+        self.assert_owner_or_financial_operator();
+
+        let amount = env::attached_deposit().as_yoctonear();
+        require!(amount > 0, "Attach a deposit to fund the referral pool");
+
+        let campaign = self
+            .campaigns
+            .get_mut(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(
+            campaign.referral.is_some(),
+            "Campaign has no referral bonus"
+        );
+
+        campaign.referral_pool.0 = campaign
+            .referral_pool
+            .0
+            .checked_add(amount)
+            .expect("Referral pool overflows");
+        let funded = ReferralPoolFundedEvent {
+            campaign_id,
+            amount: U128(amount),
+            referral_pool: campaign.referral_pool,
+        };
+        self.reserved_balance += amount;
+        self.debug_assert_reserve_consistent();
+        self.record_admin_action(
+            "fund_referral_pool",
+            format!("campaign {campaign_id}, {amount} yoctoNEAR"),
+        );
+
+        let referral_pool = funded.referral_pool;
+        Event::ReferralPoolFunded(funded).emit();
+
+        referral_pool
+    }
+}
@@ -0,0 +1,195 @@
+use crate::*;
+
+/// The smallest deposit a registration accepts, which covers the storage of the registration
+/// and of the claim record it reserves.
+const MIN_REGISTRATION_DEPOSIT: NearToken = MIN_STORAGE_BALANCE;
+
+/// A claim slot reserved in a campaign requiring registration.
+#[derive(Clone)]
+#[near(serializers=[borsh, json])]
+pub struct Registration {
+    /// The account that paid the deposit and is refunded
+    pub payer: AccountId,
+    pub deposit: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimRegisteredEvent {
+    pub campaign_id: CampaignId,
+    pub account_id: AccountId,
+    pub payer: AccountId,
+    pub deposit: U128,
+}
+
+/// Returns the key of `account_id`'s registration in a campaign, its `V2` claim key.
+pub(crate) fn registration_key(campaign_id: CampaignId, account_id: &AccountId) -> CryptoHash {
+    compute_claim_key(ClaimKeyVersion::V2, account_id, campaign_id, None)
+}
+
+impl MerkleClaim {
+    /// Returns whether the campaign requires registration and `account_id` has not registered.
+    pub(crate) fn registration_missing(
+        &self,
+        campaign: &RewardCampaign,
+        account_id: &AccountId,
+    ) -> bool {
+        campaign.require_registration
+            && !self
+                .registrations
+                .contains_key(&registration_key(campaign.id, account_id))
+    }
+
+    /// Panics if the campaign requires registration and `account_id` has not registered.
+    pub(crate) fn assert_registered(&self, campaign: &RewardCampaign, account_id: &AccountId) {
+        require!(
+            !self.registration_missing(campaign, account_id),
+            "Account is not registered for this campaign"
+        );
+    }
+
+    /// Removes `account_id`'s registration in a campaign, if any, and sends its deposit back to
+    /// the payer, or to its storage balance if it has one. A sponsor that registered an account
+    /// that claimed is remembered to refund the storage of its claim record by `purge_my_claim`.
+    /// Returns the refunded deposit.
+    pub(crate) fn refund_registration(
+        &mut self,
+        campaign_id: CampaignId,
+        account_id: &AccountId,
+    ) -> Balance {
+        let Some(registration) = self
+            .registrations
+            .remove(&registration_key(campaign_id, account_id))
+        else {
+            return 0;
+        };
+
+        if registration.payer != *account_id && self.has_claimed(campaign_id, account_id.clone()) {
+            self.record_payers.insert(
+                registration_key(campaign_id, account_id),
+                registration.payer.clone(),
+            );
+        }
+
+        let deposit = registration.deposit.0;
+        if let Some(campaign) = self.campaigns.get_mut(&campaign_id) {
+            campaign.registration_deposits.0 -= deposit;
+            self.reserved_balance -= deposit;
+        }
+        self.debug_assert_reserve_consistent();
+
+        if self.storage_balances.contains_key(&registration.payer) {
+            self.credit_storage_balance(&registration.payer, deposit);
+        } else {
+            Promise::new(registration.payer).transfer(NearToken::from_yoctonear(deposit));
+        }
+        deposit
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Registers `account_id`, the caller by default, for a claim in a campaign requiring
+    /// registration. The attached deposit of at least 0.01 NEAR pays for the claim's storage and
+    /// is sent back to the caller once the claim is paid, or by `refund_unused_registration` if the
+    /// account never claims.
+    ///
+    /// Callers registered with `storage_deposit` pay 0.01 NEAR out of their storage balance
+    /// instead, after any attached deposit was added to it, and are refunded to it.
+    #[payable]
+    pub fn register_claim(&mut self, campaign_id: CampaignId, account_id: Option<AccountId>) {
+        self.assert_not_paused(PauseFlags::CLAIMS);
+
+        let payer = env::predecessor_account_id();
+        let mut deposit = env::attached_deposit();
+        let from_storage_balance = self.storage_balances.contains_key(&payer);
+        if from_storage_balance {
+            self.credit_storage_balance(&payer, deposit.as_yoctonear());
+            deposit = MIN_REGISTRATION_DEPOSIT.min(self.storage_balances[&payer]);
+        }
+        require!(
+            deposit >= MIN_REGISTRATION_DEPOSIT,
+            "Registration requires a deposit of at least 0.01 NEAR"
+        );
+
+        let account_id = account_id.unwrap_or_else(|| payer.clone());
+        let claimed = self.has_claimed(campaign_id, account_id.clone());
+
+        let campaign = self
+            .campaigns
+            .get_mut(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(
+            campaign.require_registration,
+            "Campaign does not require registration"
+        );
+        require!(
+            !campaign.has_ended(env::block_timestamp()),
+            "Claim period has concluded"
+        );
+        require!(!claimed, "Already claimed rewards");
+
+        let key = registration_key(campaign_id, &account_id);
+        require!(
+            !self.registrations.contains_key(&key),
+            "Account is already registered"
+        );
+
+        let deposit = U128(deposit.as_yoctonear());
+        if from_storage_balance {
+            self.debit_storage_balance(&payer, deposit.0);
+        }
+        let campaign = self.campaigns.get_mut(&campaign_id).unwrap();
+        campaign.registration_deposits.0 += deposit.0;
+        self.reserved_balance += deposit.0;
+        self.registrations.insert(
+            key,
+            Registration {
+                payer: payer.clone(),
+                deposit,
+            },
+        );
+        self.debug_assert_reserve_consistent();
+
+        let registered = ClaimRegisteredEvent {
+            campaign_id,
+            account_id,
+            payer,
+            deposit,
+        };
+
+        Event::ClaimRegistered(registered).emit();
+    }
+
+    /// Refunds the deposit of a registration that was not used before the campaign ended to
+    /// the account that paid it, and returns the refunded amount. Anyone can call it.
+    pub fn refund_unused_registration(
+        &mut self,
+        campaign_id: CampaignId,
+        account_id: AccountId,
+    ) -> U128 {
+        let campaign = self
+            .campaigns
+            .get(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(
+            campaign.has_ended(env::block_timestamp()),
+            "Campaign has not ended yet"
+        );
+
+        let refunded = self.refund_registration(campaign_id, &account_id);
+        require!(refunded > 0, "Account is not registered for this campaign");
+
+        U128(refunded)
+    }
+
+    pub fn get_registration(
+        &self,
+        campaign_id: CampaignId,
+        account_id: AccountId,
+    ) -> Option<Registration> {
+        self.registrations
+            .get(&registration_key(campaign_id, &account_id))
+            .cloned()
+    }
+}
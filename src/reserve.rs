@@ -0,0 +1,317 @@
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CampaignCancelledEvent {
+    pub campaign_id: CampaignId,
+    /// The reserved funds released for withdrawal
+    pub released: U128,
+    /// When the campaign was cancelled, its new claim end
+    pub block_timestamp: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CampaignExtendedEvent {
+    pub campaign_id: CampaignId,
+    pub old_end: U64,
+    pub new_end: U64,
+    pub block_timestamp: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CampaignSweptEvent {
+    pub campaign_id: CampaignId,
+    /// The unclaimed funds sent to the treasury
+    pub amount: U128,
+    pub receiver_id: AccountId,
+    pub block_timestamp: U64,
+}
+
+/// What the contract balance is kept for, see `get_balance_breakdown`.
+#[derive(Debug, PartialEq)]
+#[near(serializers=[json])]
+pub struct BalanceBreakdown {
+    pub total_balance: U128,
+    /// The storage cost or `min_storage_deposit`, whichever is larger
+    pub storage_reserve: U128,
+    /// The funds reserved by campaigns, see `get_reserved_balance`
+    pub reserved_for_campaigns: U128,
+    /// The NEP-145 storage balances of accounts
+    pub storage_balances: U128,
+    /// Claim fees held for the fee receiver, always `0` as fees are sent with every claim
+    pub fee_accruals: U128,
+    /// What `withdraw` sends and claims of campaigns without reserves can use
+    pub withdrawable: U128,
+}
+
+impl RewardCampaign {
+    /// Returns the part of the campaign's `total_allocation` that is neither claimed nor
+    /// released by a cancellation or sweep.
+    pub(crate) fn outstanding_allocation(&self) -> Balance {
+        match self.total_allocation {
+            Some(total) if !self.allocation_released => total.0 - self.claimed_total.0,
+            _ => 0,
+        }
+    }
+
+    /// Returns all funds the campaign keeps out of withdrawals.
+    pub(crate) fn reserved_amount(&self) -> Balance {
+        self.outstanding_allocation()
+            + self.bonus_pool.0
+            + self.referral_pool.0
+            + self.donation_pool.0
+            + self.vesting_reserved.0
+            + self.registration_deposits.0
+    }
+
+    /// Panics if claiming `amount` more would exceed the campaign's `total_allocation`.
+    pub(crate) fn assert_allocation_available(&self, amount: Balance) {
+        require!(
+            self.total_allocation.is_none_or(|total| {
+                self.claimed_total
+                    .0
+                    .checked_add(amount)
+                    .is_some_and(|claimed| claimed <= total.0)
+            }),
+            "Claims exceed the campaign's total allocation"
+        );
+    }
+
+    /// Counts `amount` as claimed and returns the part of the reserve it used.
+    pub(crate) fn count_claimed(&mut self, amount: Balance) -> Balance {
+        if self.total_allocation.is_none() {
+            return 0;
+        }
+
+        self.assert_allocation_available(amount);
+        let reserved = self.outstanding_allocation().min(amount);
+        self.claimed_total.0 += amount;
+        reserved
+    }
+
+    /// Undoes `count_claimed` for a rolled back claim and returns the part of the reserve it
+    /// restored.
+    pub(crate) fn uncount_claimed(&mut self, amount: Balance) -> Balance {
+        if self.total_allocation.is_none() {
+            return 0;
+        }
+
+        let outstanding = self.outstanding_allocation();
+        self.claimed_total.0 = self.claimed_total.0.saturating_sub(amount);
+        self.outstanding_allocation() - outstanding
+    }
+
+    /// Releases the outstanding allocation and the bonus, referral and donation pools,
+    /// returning their sum.
+    pub(crate) fn release_reserve(&mut self) -> Balance {
+        let released = self.outstanding_allocation()
+            + self.bonus_pool.0
+            + self.referral_pool.0
+            + self.donation_pool.0;
+        self.allocation_released = true;
+        self.bonus_pool = U128(0);
+        self.referral_pool = U128(0);
+        self.donation_pool = U128(0);
+        released
+    }
+}
+
+impl MerkleClaim {
+    /// Returns the contract balance that is neither needed for storage nor reserved.
+    pub(crate) fn available_balance(&self) -> Balance {
+        env::account_balance()
+            .as_yoctonear()
+            .saturating_sub(self.storage_reserve())
+            .saturating_sub(self.storage_balances_total)
+            .saturating_sub(self.reserved_balance)
+    }
+
+    /// Panics unless the contract can pay `amount` for a claim of `campaign` without touching
+    /// its storage deposit or funds reserved for anything but the campaign's own allocations, so
+    /// a claim is never recorded without the means to pay it.
+    pub(crate) fn assert_sufficient_balance(&self, campaign: &RewardCampaign, amount: Balance) {
+        require!(
+            self.available_balance() + campaign.outstanding_allocation() + campaign.donation_pool.0
+                >= amount,
+            "Insufficient contract balance to fulfill claim"
+        );
+    }
+
+    /// Sums the reserves of all campaigns, which `reserved_balance` must always equal.
+    fn computed_reserved_balance(&self) -> Balance {
+        (1..=self.last_campaign_id)
+            .filter_map(|campaign_id| self.campaigns.get(&campaign_id))
+            .map(RewardCampaign::reserved_amount)
+            .sum()
+    }
+
+    /// Checks that `reserved_balance` matches the campaigns' reserves. It iterates every
+    /// campaign, so it only runs in debug builds.
+    pub(crate) fn debug_assert_reserve_consistent(&self) {
+        debug_assert_eq!(
+            self.reserved_balance,
+            self.computed_reserved_balance(),
+            "Reserved balance is inconsistent"
+        );
+    }
+
+    /// Cancels a campaign for `cancel_campaign` or a confirmed multisig action.
+    pub(crate) fn internal_cancel_campaign(&mut self, campaign_id: CampaignId) -> U128 {
+        let campaign = self
+            .campaigns
+            .get_mut(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(
+            !campaign.has_ended(env::block_timestamp()),
+            "Campaign has already ended"
+        );
+
+        campaign.claim_end = Some(U64(env::block_timestamp()));
+        let released = campaign.release_reserve();
+        self.reserved_balance -= released;
+        self.debug_assert_reserve_consistent();
+        self.record_admin_action("cancel_campaign", format!("campaign {campaign_id}"));
+
+        let cancelled = CampaignCancelledEvent {
+            campaign_id,
+            released: U128(released),
+            block_timestamp: U64(env::block_timestamp()),
+        };
+
+        Event::CampaignCancelled(cancelled).emit();
+
+        U128(released)
+    }
+
+    /// Extends a campaign for `extend_campaign` or a confirmed multisig action.
+    pub(crate) fn internal_extend_campaign(&mut self, campaign_id: CampaignId, claim_end: U64) {
+        let campaign = self
+            .campaigns
+            .get_mut(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(
+            !campaign.has_ended(env::block_timestamp()),
+            "Campaign has already ended"
+        );
+        require!(
+            campaign.periods.is_none(),
+            "Periodic campaigns end with their last period"
+        );
+        let old_end = campaign
+            .claim_end
+            .expect("Campaigns without a claim end cannot be extended");
+        require!(
+            old_end.0 < claim_end.0,
+            "New claim end must be later than the current one"
+        );
+        if let Some(max_total_extension) = self.config.max_total_extension {
+            let original_end = campaign.original_claim_end.unwrap_or(old_end);
+            require!(
+                claim_end.0 <= original_end.0.saturating_add(max_total_extension.0),
+                "New claim end exceeds the maximum total extension"
+            );
+        }
+
+        campaign.claim_end = Some(claim_end);
+        self.record_admin_action(
+            "extend_campaign",
+            format!("campaign {campaign_id}, claim end {}", claim_end.0),
+        );
+
+        let extended = CampaignExtendedEvent {
+            campaign_id,
+            old_end,
+            new_end: claim_end,
+            block_timestamp: U64(env::block_timestamp()),
+        };
+
+        Event::CampaignExtended(extended).emit();
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Returns the funds reserved for outstanding campaign allocations, bonus, referral and
+    /// donation pools, unreleased vesting positions and registration deposits.
+    pub fn get_reserved_balance(&self) -> U128 {
+        U128(self.reserved_balance)
+    }
+
+    /// Returns what the contract balance is kept for. The parts add up to `total_balance` unless
+    /// the balance cannot cover what is kept, in which case nothing is withdrawable.
+    pub fn get_balance_breakdown(&self) -> BalanceBreakdown {
+        BalanceBreakdown {
+            total_balance: U128(env::account_balance().as_yoctonear()),
+            storage_reserve: U128(self.storage_reserve()),
+            reserved_for_campaigns: U128(self.reserved_balance),
+            storage_balances: U128(self.storage_balances_total),
+            fee_accruals: U128(0),
+            withdrawable: U128(self.withdrawable_balance(false)),
+        }
+    }
+
+    /// Ends a campaign immediately and releases its reserved allocation and bonus, referral and
+    /// donation pools, which can then be withdrawn. Vesting positions keep vesting.
+    #[payable]
+    pub fn cancel_campaign(&mut self, campaign_id: CampaignId) -> U128 {
+        self.assert_owner_action();
+        self.internal_cancel_campaign(campaign_id)
+    }
+
+    /// Moves the claim end of a running campaign to the later `claim_end`, e.g. when claimants
+    /// need more time. The decay of a campaign stretches until the new claim end. Campaigns
+    /// without a claim end and periodic campaigns cannot be extended.
+    #[payable]
+    pub fn extend_campaign(&mut self, campaign_id: CampaignId, claim_end: U64) {
+        self.assert_owner_action();
+        self.internal_extend_campaign(campaign_id, claim_end);
+    }
+
+    /// Sends the unclaimed allocation and the bonus, referral and donation pools of an ended
+    /// campaign to the treasury, or the owner if none is set, as far as the contract balance
+    /// covers them, and returns the amount sent. Financial operators can sweep as well.
+    pub fn sweep_campaign(&mut self, campaign_id: CampaignId) -> U128 {
+        self.assert_owner_or_financial_operator();
+        self.assert_not_paused(PauseFlags::WITHDRAWALS);
+
+        let campaign = self
+            .campaigns
+            .get_mut(&campaign_id)
+            .expect("Campaign does not exist");
+        require!(
+            campaign.has_ended(env::block_timestamp()),
+            "Campaign has not ended yet"
+        );
+        require!(
+            !campaign.allocation_released,
+            "Campaign has already been swept or cancelled"
+        );
+
+        let released = campaign.release_reserve();
+        self.reserved_balance -= released;
+        self.debug_assert_reserve_consistent();
+
+        let amount = released.min(self.available_balance());
+        let receiver_id = self.config.treasury().clone();
+        if amount > 0 {
+            Promise::new(receiver_id.clone()).transfer(NearToken::from_yoctonear(amount));
+        }
+        self.record_admin_action(
+            "sweep_campaign",
+            format!("campaign {campaign_id}, {amount} yoctoNEAR to {receiver_id}"),
+        );
+
+        let swept = CampaignSweptEvent {
+            campaign_id,
+            amount: U128(amount),
+            receiver_id,
+            block_timestamp: U64(env::block_timestamp()),
+        };
+
+        Event::CampaignSwept(swept).emit();
+
+        U128(amount)
+    }
+}
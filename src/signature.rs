@@ -0,0 +1,298 @@
+use crate::merkle::decode_hex;
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{CurveType, PublicKey};
+
+/// The message signed to authorize `claim_with_signature`, borsh serialized.
+#[near(serializers=[borsh])]
+struct SignedClaimMessage {
+    contract_id: AccountId,
+    account_id: AccountId,
+    amount: Balance,
+    merkle_proof: Vec<CryptoHash>,
+    campaign_id: CampaignId,
+    lockup_contract: Option<AccountId>,
+    options: Option<ClaimOptions>,
+    nonce: u64,
+}
+
+/// The message signed to authorize `claim_with_key`, borsh serialized.
+#[near(serializers=[borsh])]
+pub struct KeyClaimMessage {
+    pub contract_id: AccountId,
+    pub campaign_id: CampaignId,
+    pub receiver_id: AccountId,
+}
+
+/// An ed25519 signature authorizing a claim of the implicit account of `public_key`.
+#[near(serializers=[json])]
+pub struct ClaimSignature {
+    pub public_key: PublicKey,
+    /// The signature of the borsh serialized `SignedClaimMessage`, followed by
+    /// `max_relayer_fee` as u128 little-endian when it is set
+    pub signature: Base64VecU8,
+    /// The account's current signature nonce
+    pub nonce: U64,
+    /// The largest fee the claimant allows the relayer to take out of the payout
+    pub max_relayer_fee: Option<U128>,
+    /// The fee the relayer takes, chosen by the relayer and not signed, none by default
+    pub relayer_fee: Option<U128>,
+}
+
+/// A fee paid out of a signed claim's payout to the account that submitted it.
+#[derive(Clone)]
+#[near(serializers=[json])]
+pub struct RelayerFee {
+    pub relayer_id: AccountId,
+    pub amount: U128,
+    /// The largest fee the claimant signed for
+    pub max_amount: U128,
+}
+
+/// Returns the implicit account of an ed25519 public key, the hex encoding of its bytes.
+fn implicit_account_id(public_key: &PublicKey) -> AccountId {
+    require!(
+        public_key.curve_type() == CurveType::ED25519,
+        "Only ed25519 keys can sign claims"
+    );
+
+    public_key.as_bytes()[1..]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>()
+        .parse()
+        .unwrap()
+}
+
+/// Returns the public key of an account claiming a `V7` leaf, panicking unless it is the
+/// implicit account of an ed25519 key.
+pub(crate) fn implicit_public_key(account_id: &AccountId) -> [u8; 32] {
+    decode_hex(account_id.as_str(), "V7 leaves are claimed by public keys")
+}
+
+/// Panics unless `signature` is `public_key`'s ed25519 signature of `message`.
+fn assert_ed25519_signature(public_key: &PublicKey, signature: Base64VecU8, message: &[u8]) {
+    let signature: [u8; 64] = signature
+        .0
+        .try_into()
+        .unwrap_or_else(|_| env::panic_str("Signature must be 64 bytes"));
+    let public_key: [u8; 32] = public_key.as_bytes()[1..].try_into().unwrap();
+
+    require!(
+        env::ed25519_verify(&signature, message, &public_key),
+        "Invalid signature"
+    );
+}
+
+impl MerkleClaim {
+    /// Returns the fee the relayer of a signed claim takes, panicking unless it is covered by
+    /// the signed maximum, the configured maximum and the claim's payout.
+    fn relayer_fee(
+        &self,
+        claim: &VerifiedClaim,
+        relayer_fee: Option<U128>,
+        max_relayer_fee: Option<U128>,
+    ) -> Option<RelayerFee> {
+        let amount = relayer_fee.filter(|fee| fee.0 > 0)?;
+
+        require!(
+            max_relayer_fee.is_some_and(|max| amount.0 <= max.0),
+            "Relayer fee exceeds the signed maximum"
+        );
+        require!(
+            self.config
+                .max_relayer_fee
+                .is_some_and(|max| amount.0 <= max.0),
+            "Relayer fee exceeds the contract maximum"
+        );
+        require!(
+            amount.0 <= claim.paid_amount.0 - self.config.claim_fee(claim.paid_amount.0),
+            "Relayer fee exceeds the payout"
+        );
+
+        Some(RelayerFee {
+            relayer_id: env::predecessor_account_id(),
+            amount,
+            max_amount: max_relayer_fee.unwrap(),
+        })
+    }
+
+    /// Sends the relayer fee of a claim to its relayer.
+    pub(crate) fn pay_relayer_fee(&self, claim: &VerifiedClaim) {
+        if let Some(relayer_fee) = &claim.relayer_fee {
+            Promise::new(relayer_fee.relayer_id.clone())
+                .transfer(NearToken::from_yoctonear(relayer_fee.amount.0));
+        }
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Claims the allocation of the implicit account of `signature.public_key` on its behalf,
+    /// authorized by the account's signature of the claim. Anyone can submit the claim and pay
+    /// its gas. The signature's nonce must be the account's current signature nonce, which the
+    /// claim increments.
+    ///
+    /// The submitter may take `signature.relayer_fee` out of the payout, at most the signed
+    /// `max_relayer_fee` and the configured `max_relayer_fee`. It is paid once the payout
+    /// succeeded.
+    pub fn claim_with_signature(
+        &mut self,
+        amount: U128,
+        merkle_proof: Vec<CryptoHash>,
+        campaign_id: CampaignId,
+        lockup_contract: Option<AccountId>,
+        options: Option<ClaimOptions>,
+        signature: ClaimSignature,
+    ) {
+        let ClaimSignature {
+            public_key,
+            signature,
+            nonce,
+            max_relayer_fee,
+            relayer_fee,
+        } = signature;
+
+        let account_id = implicit_account_id(&public_key);
+        require!(
+            nonce.0 == self.get_signature_nonce(account_id.clone()).0,
+            "Invalid nonce"
+        );
+
+        let mut message = borsh::to_vec(&SignedClaimMessage {
+            contract_id: env::current_account_id(),
+            account_id: account_id.clone(),
+            amount: amount.0,
+            merkle_proof: merkle_proof.clone(),
+            campaign_id,
+            lockup_contract: lockup_contract.clone(),
+            options: options.clone(),
+            nonce: nonce.0,
+        })
+        .expect("Failed to serialize claim message");
+        // Signatures without a relayer fee keep the message they had before relayer fees existed
+        if let Some(max_relayer_fee) = max_relayer_fee {
+            message.extend_from_slice(&max_relayer_fee.0.to_le_bytes());
+        }
+
+        assert_ed25519_signature(&public_key, signature, &message);
+
+        self.signature_nonces
+            .insert(account_id.clone(), nonce.0 + 1);
+
+        let mut claim = self.verify_claim(
+            account_id,
+            amount,
+            merkle_proof,
+            campaign_id,
+            lockup_contract,
+            options,
+        );
+        claim.relayer_fee = self.relayer_fee(&claim, relayer_fee, max_relayer_fee);
+
+        self.execute_claim(claim);
+    }
+
+    /// Claims the allocation of `public_key` in a campaign with `V7` leaves and pays it to
+    /// `receiver_id`, authorized by the key's signature of the borsh serialized
+    /// `KeyClaimMessage`. Anyone can submit the claim and pay its gas. Claims are recorded for the
+    /// implicit account of the key, so each key claims once whichever receiver it chose.
+    pub fn claim_with_key(
+        &mut self,
+        public_key: PublicKey,
+        amount: U128,
+        merkle_proof: Vec<CryptoHash>,
+        campaign_id: CampaignId,
+        receiver_id: AccountId,
+        signature: Base64VecU8,
+    ) {
+        require!(
+            self.campaigns
+                .get(&campaign_id)
+                .is_none_or(|campaign| campaign.leaf_version == LeafVersion::V7),
+            "Key claims require V7 leaves"
+        );
+
+        let account_id = implicit_account_id(&public_key);
+        let message = borsh::to_vec(&KeyClaimMessage {
+            contract_id: env::current_account_id(),
+            campaign_id,
+            receiver_id: receiver_id.clone(),
+        })
+        .expect("Failed to serialize claim message");
+
+        assert_ed25519_signature(&public_key, signature, &message);
+
+        self.claim_for(
+            account_id,
+            amount,
+            merkle_proof,
+            campaign_id,
+            None,
+            Some(ClaimOptions {
+                receiver_id: Some(receiver_id),
+                ..Default::default()
+            }),
+        );
+    }
+
+    /// Claims the allocation of `new_public_key` in a campaign with `V7` leaves by creating
+    /// `new_account_id`, a sub-account of this contract, with the key as its full access key and
+    /// the payout as its balance. The key authorizes the claim by signing the borsh serialized
+    /// `KeyClaimMessage` with `new_account_id` as receiver. If the account cannot be created,
+    /// e.g. because the name is taken, the claim is rolled back.
+    pub fn create_account_and_claim(
+        &mut self,
+        new_account_id: AccountId,
+        new_public_key: PublicKey,
+        amount: U128,
+        merkle_proof: Vec<CryptoHash>,
+        campaign_id: CampaignId,
+        signature: Base64VecU8,
+    ) {
+        require!(
+            self.campaigns.get(&campaign_id).is_none_or(|campaign| {
+                campaign.leaf_version == LeafVersion::V7 && campaign.vesting_end.is_none()
+            }),
+            "Account creation claims require V7 leaves and no vesting"
+        );
+        require!(
+            new_account_id.is_sub_account_of(&env::current_account_id()),
+            "New account must be a sub-account of the claim contract"
+        );
+
+        let account_id = implicit_account_id(&new_public_key);
+        let message = borsh::to_vec(&KeyClaimMessage {
+            contract_id: env::current_account_id(),
+            campaign_id,
+            receiver_id: new_account_id.clone(),
+        })
+        .expect("Failed to serialize claim message");
+
+        assert_ed25519_signature(&new_public_key, signature, &message);
+
+        let mut claim = self.verify_claim(
+            account_id,
+            amount,
+            merkle_proof,
+            campaign_id,
+            None,
+            Some(ClaimOptions {
+                receiver_id: Some(new_account_id),
+                ..Default::default()
+            }),
+        );
+        claim.new_account_key = Some(new_public_key);
+
+        self.record_claim(claim);
+    }
+
+    /// Returns the nonce the next claim signed for `account_id` must use.
+    pub fn get_signature_nonce(&self, account_id: AccountId) -> U64 {
+        U64(self
+            .signature_nonces
+            .get(&account_id)
+            .copied()
+            .unwrap_or_default())
+    }
+}
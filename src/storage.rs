@@ -0,0 +1,403 @@
+use crate::*;
+
+/// The smallest storage balance an account can register with, which covers one claim
+/// registration.
+pub(crate) const MIN_STORAGE_BALANCE: NearToken = NearToken::from_millinear(10);
+
+/// An account's storage balance as defined by NEP-145.
+#[derive(Debug, PartialEq)]
+#[near(serializers=[json])]
+pub struct StorageBalance {
+    pub total: NearToken,
+    /// The part of `total` that `storage_withdraw` can send, all but the minimum balance, which
+    /// only `storage_unregister` sends back
+    pub available: NearToken,
+}
+
+/// The storage balances accounts can register with as defined by NEP-145.
+#[derive(Debug, PartialEq)]
+#[near(serializers=[json])]
+pub struct StorageBalanceBounds {
+    pub min: NearToken,
+    pub max: Option<NearToken>,
+}
+
+/// A deposit to the storage balance of `account_id`, or to the contract's storage deposit when
+/// it is the contract's own account, including the one made when the contract is initialized.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageDepositEvent {
+    pub depositor: AccountId,
+    pub account_id: AccountId,
+    pub attached: NearToken,
+    pub credited: NearToken,
+    /// The part of `attached` sent back to the depositor
+    pub refunded: NearToken,
+    /// The storage deposit after a deposit to it, see `Config.min_storage_deposit`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_storage_deposit: Option<NearToken>,
+}
+
+/// A part of the contract's storage deposit reclaimed by the account that deposited it.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageDepositReclaimedEvent {
+    pub account_id: AccountId,
+    pub amount: NearToken,
+    /// What the account deposited and did not reclaim, see `storage_deposit_of`
+    pub credited: NearToken,
+    pub min_storage_deposit: NearToken,
+}
+
+/// A NEP-145 storage balance sent back by `storage_withdraw`, or by `storage_unregister` when
+/// `unregistered` is set.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageWithdrawEvent {
+    pub account_id: AccountId,
+    pub amount: NearToken,
+    /// The storage balance left
+    pub remaining: NearToken,
+    pub unregistered: bool,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageSurplusWithdrawnEvent {
+    pub amount: NearToken,
+    /// The cost of the contract's storage when the surplus was withdrawn
+    pub storage_cost: NearToken,
+    /// The storage deposit left, see `Config.min_storage_deposit`
+    pub min_storage_deposit: NearToken,
+}
+
+impl MerkleClaim {
+    /// Returns what the contract's current storage costs, which grows with every campaign and
+    /// claim record and which the runtime requires the balance to cover.
+    pub(crate) fn required_storage_cost() -> Balance {
+        Balance::from(env::storage_usage()) * env::storage_byte_cost().as_yoctonear()
+    }
+
+    /// Returns the part of the storage cost the balance has to cover, which the runtime lets a
+    /// locked balance cover as well.
+    fn unlocked_storage_cost() -> Balance {
+        Self::required_storage_cost().saturating_sub(env::account_locked_balance().as_yoctonear())
+    }
+
+    /// Returns the balance kept for storage: the storage cost the balance has to cover, but at
+    /// least `min_storage_deposit`.
+    pub(crate) fn storage_reserve(&self) -> Balance {
+        Self::unlocked_storage_cost().max(self.config.min_storage_deposit.as_yoctonear())
+    }
+
+    /// Returns the part of the storage deposit the contract's storage does not need.
+    pub(crate) fn storage_surplus(&self) -> Balance {
+        let deposit = env::account_balance()
+            .as_yoctonear()
+            .saturating_sub(self.storage_balances_total)
+            .saturating_sub(self.reserved_balance)
+            .min(self.config.min_storage_deposit.as_yoctonear());
+        deposit.saturating_sub(Self::unlocked_storage_cost())
+    }
+
+    /// Adds `amount` to the storage balance of `account_id`, registering it if needed.
+    pub(crate) fn credit_storage_balance(&mut self, account_id: &AccountId, amount: Balance) {
+        let balance = self
+            .storage_balances
+            .get(account_id)
+            .map_or(0, |balance| balance.as_yoctonear());
+        self.storage_balances.insert(
+            account_id.clone(),
+            NearToken::from_yoctonear(balance + amount),
+        );
+        self.storage_balances_total += amount;
+    }
+
+    /// Takes `amount` out of the storage balance of `account_id`, which must hold it.
+    pub(crate) fn debit_storage_balance(&mut self, account_id: &AccountId, amount: Balance) {
+        let balance = self
+            .storage_balances
+            .get_mut(account_id)
+            .expect("The account is not registered");
+        *balance = balance
+            .checked_sub(NearToken::from_yoctonear(amount))
+            .expect("The amount is greater than the available storage balance");
+        self.storage_balances_total -= amount;
+    }
+
+    /// Records `amount` deposited to or, if `reclaimed`, reclaimed from the contract's storage
+    /// deposit by `account_id`.
+    fn update_storage_deposit(
+        &mut self,
+        account_id: AccountId,
+        amount: NearToken,
+        reclaimed: bool,
+    ) {
+        let credited = self.storage_deposit_of(account_id.clone());
+        let credited = if reclaimed {
+            self.config.min_storage_deposit =
+                self.config.min_storage_deposit.saturating_sub(amount);
+            credited
+                .checked_sub(amount)
+                .expect("Amount exceeds the storage deposit of the account")
+        } else {
+            self.config.min_storage_deposit =
+                self.config.min_storage_deposit.saturating_add(amount);
+            credited.saturating_add(amount)
+        };
+
+        if credited.is_zero() {
+            self.storage_deposits.remove(&account_id);
+        } else {
+            self.storage_deposits.insert(account_id.clone(), credited);
+        }
+
+        if reclaimed {
+            let event = StorageDepositReclaimedEvent {
+                account_id,
+                amount,
+                credited,
+                min_storage_deposit: self.config.min_storage_deposit,
+            };
+            Event::StorageDepositReclaimed(event).emit();
+        } else {
+            let event = StorageDepositEvent {
+                depositor: account_id,
+                account_id: env::current_account_id(),
+                attached: amount,
+                credited: amount,
+                refunded: NearToken::from_yoctonear(0),
+                min_storage_deposit: Some(self.config.min_storage_deposit),
+            };
+            Event::StorageDeposit(event).emit();
+        }
+    }
+
+    /// Withdraws from the storage deposit for `withdraw_storage_surplus` or a confirmed multisig
+    /// action.
+    pub(crate) fn internal_withdraw_storage_surplus(&mut self, amount: Option<NearToken>) {
+        self.assert_direct_withdraw();
+        let surplus = self.storage_surplus();
+        let amount = amount.map_or(surplus, |amount| amount.as_yoctonear());
+        require!(amount > 0, "There is no storage surplus to withdraw");
+        require!(
+            amount <= surplus,
+            "Withdrawal amount exceeds the storage surplus"
+        );
+        self.assert_withdraw_allowance(amount);
+
+        let amount = NearToken::from_yoctonear(amount);
+        self.config.min_storage_deposit = self.config.min_storage_deposit.saturating_sub(amount);
+        self.send_withdrawal("withdraw_storage_surplus", amount, None);
+
+        let event = StorageSurplusWithdrawnEvent {
+            amount,
+            storage_cost: NearToken::from_yoctonear(Self::required_storage_cost()),
+            min_storage_deposit: self.config.min_storage_deposit,
+        };
+        Event::StorageSurplusWithdrawn(event).emit();
+    }
+
+    /// Returns the storage balance of the contract's own account, its storage deposit of which
+    /// the surplus is available to `withdraw_storage_surplus`.
+    fn contract_storage_balance(&self) -> StorageBalance {
+        StorageBalance {
+            total: self.config.min_storage_deposit,
+            available: NearToken::from_yoctonear(self.storage_surplus()),
+        }
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Withdraws `amount`, or all of the storage surplus if `None`, out of the storage deposit,
+    /// which `withdraw` never sends. The deposit is lowered accordingly but never below what
+    /// the contract's storage costs, e.g. once campaigns were deleted or `min_storage_deposit`
+    /// was set too high. Sent like `withdraw` and subject to the same pause, timelock and limit.
+    #[payable]
+    pub fn withdraw_storage_surplus(&mut self, amount: Option<NearToken>) {
+        self.assert_owner_action();
+        self.internal_withdraw_storage_surplus(amount);
+    }
+
+    /// Returns the part of the storage deposit `withdraw_storage_surplus` can send.
+    pub fn get_storage_surplus(&self) -> U128 {
+        U128(self.storage_surplus())
+    }
+
+    /// Returns what the contract's current storage costs, which withdrawals and claims never
+    /// touch even if it exceeds `min_storage_deposit`.
+    pub fn get_required_storage_cost(&self) -> U128 {
+        U128(Self::required_storage_cost())
+    }
+
+    /// NEP-145: Adds the attached deposit to the storage balance of `account_id`, the caller by
+    /// default, registering it with at least `storage_balance_bounds().min`. With
+    /// `registration_only` only the minimum is kept when registering and the whole deposit is
+    /// refunded if the account is registered already.
+    ///
+    /// Deposits for the contract's own account top up its storage deposit, raising
+    /// `min_storage_deposit`, e.g. by sponsors contributing to its operating balance. They are
+    /// credited to the caller, who can reclaim them with `reclaim_storage_deposit`.
+    #[payable]
+    pub fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let deposit = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+
+        if account_id == env::current_account_id() {
+            self.update_storage_deposit(env::predecessor_account_id(), deposit, false);
+            return self.contract_storage_balance();
+        }
+
+        let registered = self.storage_balances.contains_key(&account_id);
+        let credited = match (registered, registration_only.unwrap_or(false)) {
+            (true, true) => NearToken::from_yoctonear(0),
+            (true, false) => deposit,
+            (false, registration_only) => {
+                require!(
+                    deposit >= MIN_STORAGE_BALANCE,
+                    "The attached deposit is less than the minimum storage balance"
+                );
+                if registration_only {
+                    MIN_STORAGE_BALANCE
+                } else {
+                    deposit
+                }
+            }
+        };
+
+        self.credit_storage_balance(&account_id, credited.as_yoctonear());
+        let refund = deposit.saturating_sub(credited);
+        if !refund.is_zero() {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+
+        let event = StorageDepositEvent {
+            depositor: env::predecessor_account_id(),
+            account_id: account_id.clone(),
+            attached: deposit,
+            credited,
+            refunded: refund,
+            min_storage_deposit: None,
+        };
+        Event::StorageDeposit(event).emit();
+
+        self.storage_balance_of(account_id).unwrap()
+    }
+
+    /// Sends `amount` of what the caller deposited to the contract's storage deposit back to it,
+    /// as long as the storage deposit left still covers the contract's storage, see
+    /// `get_storage_surplus`.
+    #[payable]
+    pub fn reclaim_storage_deposit(&mut self, amount: NearToken) {
+        assert_one_yocto();
+        require!(!amount.is_zero(), "Amount must not be zero");
+        require!(
+            amount.as_yoctonear() <= self.storage_surplus(),
+            "Storage deposit left would not cover the contract's storage"
+        );
+
+        let account_id = env::predecessor_account_id();
+        self.update_storage_deposit(account_id.clone(), amount, true);
+        Promise::new(account_id).transfer(amount);
+    }
+
+    /// Returns what `account_id` deposited to the contract's storage deposit and did not
+    /// reclaim.
+    pub fn storage_deposit_of(&self, account_id: AccountId) -> NearToken {
+        self.storage_deposits
+            .get(&account_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// NEP-145: Sends `amount`, or the whole available storage balance if `None`, to the caller.
+    /// The minimum balance stays registered. Deposits of registrations it paid for are not part
+    /// of it until they are refunded.
+    #[payable]
+    pub fn storage_withdraw(&mut self, amount: Option<NearToken>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let available = self
+            .storage_balance_of(account_id.clone())
+            .expect("The account is not registered")
+            .available;
+        let amount = amount.unwrap_or(available);
+        require!(
+            amount <= available,
+            "The amount is greater than the available storage balance"
+        );
+
+        self.debit_storage_balance(&account_id, amount.as_yoctonear());
+        if !amount.is_zero() {
+            Promise::new(account_id.clone()).transfer(amount);
+        }
+
+        let balance = self.storage_balance_of(account_id.clone()).unwrap();
+        let event = StorageWithdrawEvent {
+            account_id,
+            amount,
+            remaining: balance.total,
+            unregistered: false,
+        };
+        Event::StorageWithdraw(event).emit();
+
+        balance
+    }
+
+    /// NEP-145: Unregisters the caller and sends its whole storage balance back, including the
+    /// minimum balance. Returns `false` if it was not registered. Nothing but the balance is
+    /// tied to the registration, so `force` changes nothing.
+    #[payable]
+    pub fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+        let _ = force;
+
+        let account_id = env::predecessor_account_id();
+        let Some(balance) = self.storage_balances.remove(&account_id) else {
+            return false;
+        };
+
+        self.storage_balances_total -= balance.as_yoctonear();
+        if !balance.is_zero() {
+            Promise::new(account_id.clone()).transfer(balance);
+        }
+
+        let event = StorageWithdrawEvent {
+            account_id,
+            amount: balance,
+            remaining: NearToken::from_yoctonear(0),
+            unregistered: true,
+        };
+        Event::StorageWithdraw(event).emit();
+        true
+    }
+
+    /// NEP-145: Returns the storage balance of `account_id`, or of the contract's storage deposit
+    /// for its own account.
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        if account_id == env::current_account_id() {
+            return Some(self.contract_storage_balance());
+        }
+
+        self.storage_balances
+            .get(&account_id)
+            .map(|&balance| StorageBalance {
+                total: balance,
+                available: balance.saturating_sub(MIN_STORAGE_BALANCE),
+            })
+    }
+
+    /// NEP-145: Accounts register with at least 0.01 NEAR, enough for one claim registration,
+    /// and can deposit any amount beyond.
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: MIN_STORAGE_BALANCE,
+            max: None,
+        }
+    }
+}
@@ -3,6 +3,8 @@ use std::str::FromStr;
 
 pub const GENESIS_TIME_IN_DAYS: u64 = 500;
 pub const DEFAULT_BALANCE_YOCTO: u128 = 20000;
+/// Covers the storage of test contracts, which the balance could not, see `storage_reserve`
+pub const DEFAULT_LOCKED_BALANCE: NearToken = NearToken::from_near(1000);
 
 pub fn system_account() -> AccountId {
     AccountId::from_str("system_account").unwrap()
@@ -21,12 +23,12 @@ pub fn claimant() -> AccountId {
 }
 
 pub fn to_nanos(num_days: u64) -> u64 {
-    num_days * 86400_000_000_000
+    num_days * 86_400_000_000_000
 }
 
 pub fn to_ts(num_days: u64) -> u64 {
     // 2018-08-01 UTC in nanoseconds
-    1533081600_000_000_000 + to_nanos(num_days)
+    1_533_081_600_000_000_000 + to_nanos(num_days)
 }
 
 pub fn get_context(predecessor_account_id: AccountId, block_timestamp: u64) -> VMContext {
@@ -40,7 +42,7 @@ pub fn get_context(predecessor_account_id: AccountId, block_timestamp: u64) -> V
         block_timestamp,
         epoch_height: 1,
         account_balance: NearToken::from_yoctonear(DEFAULT_BALANCE_YOCTO),
-        account_locked_balance: NearToken::from_yoctonear(DEFAULT_BALANCE_YOCTO),
+        account_locked_balance: DEFAULT_LOCKED_BALANCE,
         storage_usage: 10u64.pow(6),
         attached_deposit: NearToken::from_yoctonear(1000),
         prepaid_gas: Gas::from_gas(10u64.pow(15)),
@@ -0,0 +1,150 @@
+use crate::*;
+
+/// The allocation of a claim in a vesting campaign, released linearly until `vesting_end`.
+#[derive(Clone)]
+#[near(serializers=[borsh, json])]
+pub struct VestingPosition {
+    /// The account receiving the released funds
+    pub receiver_id: AccountId,
+    /// The whole allocation
+    pub total: U128,
+    /// The part of the allocation already sent to the receiver
+    pub withdrawn: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingWithdrawEvent {
+    pub campaign_id: CampaignId,
+    pub account_id: AccountId,
+    pub receiver_id: AccountId,
+    pub amount: U128,
+}
+
+impl RewardCampaign {
+    /// Returns how much of `total` has vested at `timestamp`, released linearly from
+    /// `claim_start` to `vesting_end`. Campaigns without vesting release everything at once.
+    pub(crate) fn vested_amount(&self, total: Balance, timestamp: u64) -> Balance {
+        let Some(vesting_end) = self.vesting_end else {
+            return total;
+        };
+
+        if timestamp >= vesting_end.0 {
+            return total;
+        }
+
+        let elapsed = Balance::from(timestamp.saturating_sub(self.claim_start.0));
+        let duration = Balance::from(vesting_end.0 - self.claim_start.0);
+
+        // Split `total * elapsed / duration` so the product never overflows
+        total / duration * elapsed + total % duration * elapsed / duration
+    }
+}
+
+impl MerkleClaim {
+    /// Opens the vesting position of a recorded claim and releases what has vested so far.
+    pub(crate) fn open_vesting_position(
+        &mut self,
+        campaign_id: CampaignId,
+        account_id: AccountId,
+        receiver_id: AccountId,
+        total: Balance,
+    ) {
+        let key = self
+            .claim_key(campaign_id, &account_id)
+            .expect("Campaign does not exist");
+
+        self.vesting_positions.insert(
+            key,
+            VestingPosition {
+                receiver_id,
+                total: U128(total),
+                withdrawn: U128(0),
+            },
+        );
+        self.campaigns
+            .get_mut(&campaign_id)
+            .expect("Campaign does not exist")
+            .vesting_reserved
+            .0 += total;
+        self.reserved_balance += total;
+
+        self.release_vested(campaign_id, account_id);
+    }
+
+    /// Sends the part of `account_id`'s position that vested since the last release and returns
+    /// its amount.
+    fn release_vested(&mut self, campaign_id: CampaignId, account_id: AccountId) -> Balance {
+        let key = self
+            .claim_key(campaign_id, &account_id)
+            .expect("Campaign does not exist");
+        let mut position = self
+            .vesting_positions
+            .get(&key)
+            .cloned()
+            .expect("No vesting position");
+
+        let vested =
+            self.campaigns[&campaign_id].vested_amount(position.total.0, env::block_timestamp());
+        let amount = vested - position.withdrawn.0;
+
+        if amount > 0 {
+            position.withdrawn = U128(vested);
+            self.campaigns
+                .get_mut(&campaign_id)
+                .expect("Campaign does not exist")
+                .vesting_reserved
+                .0 -= amount;
+            self.reserved_balance -= amount;
+            self.debug_assert_reserve_consistent();
+            let receiver_id = position.receiver_id.clone();
+            self.vesting_positions.insert(key, position);
+
+            Promise::new(receiver_id.clone()).transfer(NearToken::from_yoctonear(amount));
+
+            let withdraw = VestingWithdrawEvent {
+                campaign_id,
+                account_id,
+                receiver_id,
+                amount: U128(amount),
+            };
+
+            Event::VestingWithdraw(withdraw).emit();
+        }
+
+        amount
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Sends the part of the caller's vesting position that vested since the last withdrawal.
+    pub fn withdraw_vested(&mut self, campaign_id: CampaignId) -> U128 {
+        self.assert_not_paused(PauseFlags::CLAIMS);
+
+        let amount = self.release_vested(campaign_id, env::predecessor_account_id());
+        require!(amount > 0, "No vested amount to withdraw");
+
+        U128(amount)
+    }
+
+    pub fn get_vesting_position(
+        &self,
+        campaign_id: CampaignId,
+        account_id: AccountId,
+    ) -> Option<VestingPosition> {
+        self.claim_key(campaign_id, &account_id)
+            .and_then(|key| self.vesting_positions.get(&key).cloned())
+    }
+
+    /// Returns how much of a vesting position can be withdrawn now.
+    pub fn get_vested_claimable(&self, campaign_id: CampaignId, account_id: AccountId) -> U128 {
+        let Some(position) = self.get_vesting_position(campaign_id, account_id) else {
+            return U128(0);
+        };
+
+        let vested =
+            self.campaigns[&campaign_id].vested_amount(position.total.0, env::block_timestamp());
+        U128(vested - position.withdrawn.0)
+    }
+}
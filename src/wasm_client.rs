@@ -0,0 +1,107 @@
+//! Leaf and proof checks for browsers, exported with wasm-bindgen by the `wasm-client` feature.
+//! They run the contract's own `LeafFormat` and `TreeHasher` code, so a frontend can check a
+//! proof before submitting a claim.
+//!
+//! Hashes are given as `0x`-prefixed hex or base58, leaf formats as the JSON of `LeafFormat` and
+//! errors are thrown as strings.
+
+use crate::merkle::{decode_hex, encode_hex_hash};
+use crate::{Balance, ClaimOptions, LeafFormat, LeafVersion};
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::{serde_json, AccountId, CryptoHash};
+use std::str::FromStr;
+#[cfg(feature = "wasm-client")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Computes the leaf `claim` computes for an allocation, see the contract's `compute_leaf`, as
+/// `0x`-prefixed hex. `lockup` is the account itself for claims without a lockup contract and
+/// the receiver of `V3`, `V6` and `V7` leaves. `options` are the JSON of the claim's options,
+/// of which `index`, `deadline` and `nonce` are part of some leaves.
+#[cfg_attr(feature = "wasm-client", wasm_bindgen(js_name = computeLeaf))]
+pub fn compute_leaf(
+    format: &str,
+    account: &str,
+    lockup: &str,
+    amount: &str,
+    options: Option<String>,
+) -> Result<String, String> {
+    let format = parse_format(Some(format))?;
+    let account = parse_account(account)?;
+    let lockup = parse_account(lockup)?;
+    let amount = Balance::from_str(amount).map_err(|_| format!("Invalid amount {amount:?}"))?;
+    let ClaimOptions {
+        index,
+        deadline,
+        nonce,
+        ..
+    } = match options {
+        Some(options) => serde_json::from_str(&options)
+            .map_err(|error| format!("Invalid claim options: {error}"))?,
+        None => ClaimOptions::default(),
+    };
+
+    match format.leaf_version {
+        LeafVersion::V4 if deadline.is_none() => {
+            return Err("Deadline is required by V4 leaves".to_string())
+        }
+        LeafVersion::V5 if nonce.is_none() => {
+            return Err("Nonce is required by V5 leaves".to_string())
+        }
+        _ => (),
+    }
+
+    let leaf = format.compute_leaf(
+        index,
+        &account,
+        &lockup,
+        amount,
+        deadline.map(|deadline| deadline.0),
+        nonce,
+    );
+    Ok(encode_hex_hash(&leaf))
+}
+
+/// Verifies a proof of a commutative campaign as `claim` does. `format` is the JSON of the
+/// campaign's `LeafFormat`, of which `hash_algo` and `leaf_hashing` matter, defaulting to
+/// keccak256 trees.
+#[cfg_attr(feature = "wasm-client", wasm_bindgen(js_name = verifyProof))]
+pub fn verify_proof(
+    leaf: &str,
+    proof: Vec<String>,
+    root: &str,
+    format: Option<String>,
+) -> Result<bool, String> {
+    let hasher = parse_format(format.as_deref())?.tree_hasher();
+    let proof = proof
+        .iter()
+        .map(|hash| parse_hash(hash))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(hasher.verify_proof(&parse_hash(leaf)?, &proof, &parse_hash(root)?))
+}
+
+fn parse_format(format: Option<&str>) -> Result<LeafFormat, String> {
+    match format {
+        Some(format) => {
+            serde_json::from_str(format).map_err(|error| format!("Invalid leaf format: {error}"))
+        }
+        None => Ok(LeafFormat::default()),
+    }
+}
+
+fn parse_account(account: &str) -> Result<AccountId, String> {
+    AccountId::from_str(account).map_err(|error| format!("Invalid account id {account:?}: {error}"))
+}
+
+/// Parses a hash given as `0x`-prefixed hex or base58.
+fn parse_hash(hash: &str) -> Result<CryptoHash, String> {
+    match hash.strip_prefix("0x") {
+        Some(hex) if hex.len() == 64 && hex.bytes().all(|byte| byte.is_ascii_hexdigit()) => {
+            Ok(decode_hex(hex, "Invalid hash"))
+        }
+        Some(_) => Err(format!("Invalid hex hash {hash:?}")),
+        None => Base58CryptoHash::try_from(hash)
+            .map(Into::into)
+            .map_err(|_| format!("Invalid base58 hash {hash:?}")),
+    }
+}
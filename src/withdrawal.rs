@@ -0,0 +1,313 @@
+use crate::*;
+use near_sdk::{Gas, PromiseResult};
+
+/// Gas attached to the callback reporting the outcome of a withdrawal.
+const GAS_FOR_ON_WITHDRAW: Gas = Gas::from_tgas(5);
+
+/// Bounds how much can be withdrawn per `period`, e.g. against a compromised owner key.
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers=[borsh, json])]
+pub struct WithdrawLimit {
+    pub amount: NearToken,
+    /// The length of a withdrawal window in nanoseconds
+    pub period: U64,
+}
+
+/// The window `withdraw_limit` is enforced in. A new window starts with the first withdrawal
+/// after the previous window ended.
+#[derive(Clone, Default)]
+#[near(serializers=[borsh])]
+pub struct WithdrawWindow {
+    pub start: u64,
+    pub withdrawn: Balance,
+}
+
+impl WithdrawWindow {
+    /// Returns what was withdrawn in the window at `timestamp`, nothing once the window ended.
+    fn withdrawn_at(&self, limit: &WithdrawLimit, timestamp: u64) -> Balance {
+        if timestamp >= self.start.saturating_add(limit.period.0) {
+            0
+        } else {
+            self.withdrawn
+        }
+    }
+}
+
+/// A withdrawal requested with `request_withdraw`, which can be executed once it unlocks.
+#[derive(Clone)]
+#[near(serializers=[borsh, json])]
+pub struct PendingWithdrawal {
+    pub amount: U128,
+    pub requested_at: U64,
+    pub unlocks_at: U64,
+}
+
+/// Logged when a forced withdrawal takes funds reserved for campaigns, which can no longer pay
+/// all of their claims.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReservedFundsWithdrawnEvent {
+    /// The part of the withdrawal that was reserved
+    pub reserved_withdrawn: NearToken,
+    /// The funds campaigns still owe, see `get_reserved_balance`
+    pub reserved_balance: U128,
+    pub receiver_id: AccountId,
+}
+
+/// Logged instead of the `WithdrawEvent` when the transfer of a withdrawal failed, in which case
+/// the funds are refunded to the contract.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawFailedEvent {
+    pub amount: NearToken,
+    pub receiver_id: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawRequestedEvent {
+    pub amount: U128,
+    pub unlocks_at: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawCancelledEvent {
+    pub amount: U128,
+    /// The owner or guardian that cancelled the withdrawal
+    pub account_id: AccountId,
+}
+
+impl MerkleClaim {
+    /// Panics unless the owner can withdraw without a request, which needs withdrawals to be
+    /// neither paused nor timelocked.
+    pub(crate) fn assert_direct_withdraw(&self) {
+        self.assert_not_paused(PauseFlags::WITHDRAWALS);
+        require!(
+            self.config.withdraw_timelock.0 == 0,
+            "Withdrawals are timelocked, use request_withdraw"
+        );
+    }
+
+    /// Returns what can still be withdrawn in the current window of the `withdraw_limit`, or
+    /// `None` without a limit.
+    pub(crate) fn withdraw_allowance(&self) -> Option<Balance> {
+        self.config.withdraw_limit.as_ref().map(|limit| {
+            let withdrawn = self
+                .withdraw_window
+                .withdrawn_at(limit, env::block_timestamp());
+            limit.amount.as_yoctonear().saturating_sub(withdrawn)
+        })
+    }
+
+    /// Panics if withdrawing `amount` would exceed the `withdraw_limit`.
+    pub(crate) fn assert_withdraw_allowance(&self, amount: Balance) {
+        require!(
+            self.withdraw_allowance()
+                .is_none_or(|allowance| amount <= allowance),
+            "Withdrawal exceeds the withdraw limit"
+        );
+    }
+
+    /// Counts a withdrawal of `amount` in the window of the `withdraw_limit`, starting a new
+    /// window if the last one ended, and returns the total withdrawn in it.
+    fn count_withdrawal(&mut self, amount: Balance) -> Option<U128> {
+        let limit = self.config.withdraw_limit.as_ref()?;
+        let timestamp = env::block_timestamp();
+        if timestamp >= self.withdraw_window.start.saturating_add(limit.period.0) {
+            self.withdraw_window = WithdrawWindow {
+                start: timestamp,
+                withdrawn: 0,
+            };
+        }
+
+        self.withdraw_window.withdrawn += amount;
+        Some(U128(self.withdraw_window.withdrawn))
+    }
+
+    /// Returns what the owner can withdraw, which includes the reserved funds if `force` is set
+    /// but never the storage deposit or the storage balances of accounts.
+    pub(crate) fn withdrawable_balance(&self, force: bool) -> Balance {
+        if force {
+            env::account_balance()
+                .as_yoctonear()
+                .saturating_sub(self.storage_reserve())
+                .saturating_sub(self.storage_balances_total)
+        } else {
+            self.available_balance()
+        }
+    }
+
+    /// Sends `amount` to `receiver`, or the default withdraw receiver or treasury if `None`,
+    /// recording the withdrawal by `method`. The `WithdrawEvent` is logged by `on_withdraw` once
+    /// the transfer went through.
+    pub(crate) fn send_withdrawal(
+        &mut self,
+        method: &str,
+        amount: NearToken,
+        receiver: Option<AccountId>,
+    ) {
+        let receiver_id = receiver.unwrap_or_else(|| {
+            self.config
+                .default_withdraw_receiver
+                .clone()
+                .unwrap_or_else(|| self.config.treasury().clone())
+        });
+        let reserved_withdrawn = amount
+            .as_yoctonear()
+            .saturating_sub(self.available_balance());
+        let window_withdrawn = self.count_withdrawal(amount.as_yoctonear());
+        Promise::new(receiver_id.clone()).transfer(amount).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_ON_WITHDRAW)
+                .on_withdraw(amount, receiver_id.clone(), window_withdrawn),
+        );
+
+        if reserved_withdrawn > 0 {
+            let forced = ReservedFundsWithdrawnEvent {
+                reserved_withdrawn: NearToken::from_yoctonear(reserved_withdrawn),
+                reserved_balance: U128(self.reserved_balance),
+                receiver_id: receiver_id.clone(),
+            };
+            Event::ReservedFundsWithdrawn(forced).emit();
+        }
+        self.record_admin_action(
+            method,
+            format!(
+                "withdrew {} yoctoNEAR to {receiver_id}",
+                amount.as_yoctonear()
+            ),
+        );
+    }
+
+    /// Requests a withdrawal for `request_withdraw` or a confirmed multisig action.
+    pub(crate) fn internal_request_withdraw(&mut self, amount: U128) {
+        require!(
+            self.pending_withdrawal.is_none(),
+            "A withdrawal is already pending"
+        );
+        require!(amount.0 > 0, "Withdrawal amount must not be zero");
+        require!(
+            amount.0 <= self.available_balance(),
+            "Withdrawal amount exceeds the available balance"
+        );
+
+        let requested_at = env::block_timestamp();
+        let unlocks_at = requested_at + self.config.withdraw_timelock.0;
+        self.pending_withdrawal = Some(PendingWithdrawal {
+            amount,
+            requested_at: U64(requested_at),
+            unlocks_at: U64(unlocks_at),
+        });
+        self.record_admin_action(
+            "request_withdraw",
+            format!("{} yoctoNEAR, unlocks at {unlocks_at}", amount.0),
+        );
+
+        let requested = WithdrawRequestedEvent {
+            amount,
+            unlocks_at: U64(unlocks_at),
+        };
+        Event::WithdrawRequested(requested).emit();
+    }
+}
+
+#[near]
+impl MerkleClaim {
+    /// Requests the withdrawal of `amount` as `withdraw` does, which `execute_withdraw` sends once
+    /// the configured `withdraw_timelock` has passed. Claims keep using the funds in the meantime.
+    #[payable]
+    pub fn request_withdraw(&mut self, amount: U128) {
+        self.assert_owner_action();
+        self.internal_request_withdraw(amount);
+    }
+
+    /// Sends the pending withdrawal as `withdraw` does once it unlocked, as far as the available
+    /// balance still covers it.
+    #[payable]
+    pub fn execute_withdraw(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_not_paused(PauseFlags::WITHDRAWALS);
+        let pending = self
+            .pending_withdrawal
+            .as_ref()
+            .expect("No withdrawal is pending");
+        require!(
+            env::block_timestamp() >= pending.unlocks_at.0,
+            "Withdrawal is still timelocked"
+        );
+        require!(
+            pending.amount.0 <= self.available_balance(),
+            "Withdrawal amount exceeds the available balance"
+        );
+        self.assert_withdraw_allowance(pending.amount.0);
+
+        let withdrawn = NearToken::from_yoctonear(pending.amount.0);
+        self.pending_withdrawal = None;
+        self.send_withdrawal("execute_withdraw", withdrawn, None);
+    }
+
+    /// Cancels the pending withdrawal. Guardians can cancel withdrawals as well.
+    pub fn cancel_withdraw(&mut self) {
+        self.assert_owner_or_guardian();
+        let pending = self
+            .pending_withdrawal
+            .take()
+            .expect("No withdrawal is pending");
+        self.record_admin_action("cancel_withdraw", format!("{} yoctoNEAR", pending.amount.0));
+
+        let cancelled = WithdrawCancelledEvent {
+            amount: pending.amount,
+            account_id: env::predecessor_account_id(),
+        };
+        Event::WithdrawCancelled(cancelled).emit();
+    }
+
+    /// Logs a `WithdrawEvent` with the balance left after the transfer of a withdrawal and the
+    /// total withdrawn in the window of the `withdraw_limit`, or a `WithdrawFailedEvent` if the
+    /// transfer failed, which no longer counts towards the limit. Returns whether it succeeded.
+    #[private]
+    pub fn on_withdraw(
+        &mut self,
+        amount: NearToken,
+        receiver_id: AccountId,
+        window_withdrawn: Option<U128>,
+    ) -> bool {
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                let withdraw = WithdrawEvent {
+                    balance: env::account_balance(),
+                    withdrawn: amount,
+                    receiver_id,
+                    window_withdrawn,
+                };
+                Event::Withdraw(withdraw).emit();
+                true
+            }
+            PromiseResult::Failed => {
+                if window_withdrawn.is_some() {
+                    let withdrawn = &mut self.withdraw_window.withdrawn;
+                    *withdrawn = withdrawn.saturating_sub(amount.as_yoctonear());
+                }
+
+                let failed = WithdrawFailedEvent {
+                    amount,
+                    receiver_id,
+                };
+                Event::WithdrawFailed(failed).emit();
+                false
+            }
+        }
+    }
+
+    pub fn get_pending_withdrawal(&self) -> Option<&PendingWithdrawal> {
+        self.pending_withdrawal.as_ref()
+    }
+
+    /// Returns what can still be withdrawn before the current window of the `withdraw_limit`
+    /// ends, or `None` without a limit.
+    pub fn get_withdraw_allowance(&self) -> Option<U128> {
+        self.withdraw_allowance().map(U128)
+    }
+}
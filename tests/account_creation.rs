@@ -0,0 +1,153 @@
+//! Key claims creating their receiver account, whose creation fails if the name is taken.
+
+mod common;
+
+use common::{Sandbox, TestResult, CLAIM_GAS};
+use ed25519_dalek::{Signer, SigningKey};
+use near_merkle_claim::{LeafFormat, LeafVersion};
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::{CryptoHash, CurveType, PublicKey};
+use near_workspaces::result::ExecutionFinalResult;
+use near_workspaces::types::NearToken;
+use near_workspaces::{Account, AccountId};
+use serde_json::json;
+
+const AMOUNT: u128 = NearToken::from_near(2).as_yoctonear();
+
+fn signing_key() -> SigningKey {
+    SigningKey::from_bytes(&[7u8; 32])
+}
+
+fn public_key() -> PublicKey {
+    PublicKey::from_parts(
+        CurveType::ED25519,
+        signing_key().verifying_key().as_bytes().to_vec(),
+    )
+    .unwrap()
+}
+
+/// The implicit account of `signing_key()`, which `V7` leaves are claimed for.
+fn key_account_id() -> AccountId {
+    signing_key()
+        .verifying_key()
+        .as_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>()
+        .parse()
+        .unwrap()
+}
+
+/// Creates a `V7` campaign with an allocation of `AMOUNT` to `signing_key()` and returns its
+/// proof.
+async fn key_campaign_setup(
+    sandbox: &Sandbox,
+) -> Result<Vec<CryptoHash>, Box<dyn std::error::Error>> {
+    let format = LeafFormat {
+        campaign_id: 1,
+        leaf_version: LeafVersion::V7,
+        ..Default::default()
+    };
+    let other_key: AccountId = hex_account_id(0x22);
+    let proofs = sandbox
+        .create_campaign(
+            &format,
+            &[
+                common::leaf(&format, &key_account_id(), &key_account_id(), AMOUNT),
+                common::leaf(&format, &other_key, &other_key, AMOUNT),
+            ],
+            json!({ "leaf_version": "V7" }),
+        )
+        .await?;
+
+    Ok(proofs[0].clone())
+}
+
+fn hex_account_id(byte: u8) -> AccountId {
+    format!("{byte:02x}").repeat(32).parse().unwrap()
+}
+
+async fn create_account_and_claim(
+    sandbox: &Sandbox,
+    relayer: &Account,
+    new_account_id: &AccountId,
+    merkle_proof: &[CryptoHash],
+) -> near_workspaces::Result<ExecutionFinalResult> {
+    let message = near_sdk::borsh::to_vec(&(sandbox.contract.id(), 1u32, new_account_id)).unwrap();
+    let signature: Base64VecU8 = signing_key().sign(&message).to_bytes().to_vec().into();
+
+    relayer
+        .call(sandbox.contract.id(), "create_account_and_claim")
+        .args_json(json!({
+            "new_account_id": new_account_id,
+            "new_public_key": public_key(),
+            "amount": U128(AMOUNT),
+            "merkle_proof": merkle_proof,
+            "campaign_id": 1,
+            "signature": signature,
+        }))
+        .gas(CLAIM_GAS)
+        .transact()
+        .await
+}
+
+#[tokio::test]
+async fn test_create_account_and_claim() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let relayer = sandbox.account("relayer").await?;
+    let proof = key_campaign_setup(&sandbox).await?;
+    let new_account_id: AccountId = format!("alice.{}", sandbox.contract.id()).parse()?;
+
+    let result = create_account_and_claim(&sandbox, &relayer, &new_account_id, &proof).await?;
+
+    assert!(result.receipt_failures().is_empty());
+    assert!(sandbox.has_claimed(1, &key_account_id()).await?);
+    assert_eq!(
+        sandbox.balance(&new_account_id).await?.as_yoctonear(),
+        AMOUNT
+    );
+    let access_key = sandbox
+        .worker
+        .view_access_key(&new_account_id, &String::from(&public_key()).parse()?)
+        .await?;
+    assert!(matches!(
+        access_key.permission,
+        near_workspaces::types::AccessKeyPermission::FullAccess
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_account_and_claim_taken_name() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let relayer = sandbox.account("relayer").await?;
+    let proof = key_campaign_setup(&sandbox).await?;
+    let taken = sandbox
+        .contract
+        .as_account()
+        .create_subaccount("taken")
+        .initial_balance(NearToken::from_near(1))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let result = create_account_and_claim(&sandbox, &relayer, taken.id(), &proof).await?;
+
+    // The creation failed, so the claim is rolled back and can be made to another name
+    let failed = common::events(&result, "claim_failed");
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["reason"], "Receiver account could not be created");
+    assert!(!sandbox.has_claimed(1, &key_account_id()).await?);
+    assert_eq!(sandbox.balance(taken.id()).await?, NearToken::from_near(1));
+
+    let new_account_id: AccountId = format!("alice.{}", sandbox.contract.id()).parse()?;
+    let result = create_account_and_claim(&sandbox, &relayer, &new_account_id, &proof).await?;
+
+    assert!(result.receipt_failures().is_empty());
+    assert!(sandbox.has_claimed(1, &key_account_id()).await?);
+    Ok(())
+}
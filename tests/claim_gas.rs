@@ -0,0 +1,171 @@
+//! Claims of the heaviest payout paths sent with exactly the minimum claim gas, which must
+//! succeed. Every receipt's burnt gas is printed, run with `--nocapture` to see the profile.
+
+mod common;
+
+use common::{leaf, Sandbox, TestResult};
+use near_merkle_claim::LeafFormat;
+use near_sdk::json_types::U128;
+use near_sdk::CryptoHash;
+use near_workspaces::result::ExecutionFinalResult;
+use near_workspaces::types::{Gas, NearToken};
+use near_workspaces::{Account, AccountId};
+use serde_json::{json, Value};
+
+const AMOUNT: u128 = NearToken::from_near(2).as_yoctonear();
+/// The default `max_proof_length`, the longest proof claims may pass.
+const PROOF_LENGTH: usize = 64;
+const DAY: u64 = 86_400_000_000_000;
+
+/// Creates a campaign with `options` whose root proves `alice`'s allocation, paid to
+/// `receiver_id`, with a proof of `PROOF_LENGTH` hashes, and returns the proof. The campaign
+/// takes a claim fee and its referral pool is funded.
+async fn heaviest_campaign_setup(
+    sandbox: &Sandbox,
+    alice: &Account,
+    receiver_id: &AccountId,
+    options: Value,
+) -> Result<Vec<CryptoHash>, Box<dyn std::error::Error>> {
+    let fee_receiver = sandbox.account("fees").await?;
+    sandbox
+        .owner
+        .call(sandbox.contract.id(), "update_config")
+        .args_json(json!({
+            "patch": { "claim_fee_bps": 100, "fee_receiver": fee_receiver.id() }
+        }))
+        .deposit(NearToken::from_yoctonear(1))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // A root folding the leaf with a sibling per level, as a tree of 2^64 leaves would
+    let format = LeafFormat {
+        campaign_id: 1,
+        ..Default::default()
+    };
+    let hasher = format.tree_hasher();
+    let proof: Vec<CryptoHash> = (1..=PROOF_LENGTH as u8).map(|byte| [byte; 32]).collect();
+    let root = proof.iter().fold(
+        leaf(&format, alice.id(), receiver_id, AMOUNT),
+        |node, sibling| hasher.hash_pair(&node, sibling),
+    );
+
+    sandbox
+        .owner
+        .call(sandbox.contract.id(), "create_campaign")
+        .args_json(json!({ "merkle_root": root, "claim_end": null, "options": options }))
+        .deposit(NearToken::from_yoctonear(1))
+        .gas(Gas::from_tgas(100))
+        .transact()
+        .await?
+        .into_result()?;
+    sandbox
+        .owner
+        .call(sandbox.contract.id(), "fund_referral_pool")
+        .args_json(json!({ "campaign_id": 1 }))
+        .deposit(NearToken::from_near(1))
+        .transact()
+        .await?
+        .into_result()?;
+
+    Ok(proof)
+}
+
+/// Claims `alice`'s allocation referred by `referrer` with `extra_gas` on top of the minimum
+/// claim gas, and prints the gas every receipt burnt.
+async fn referred_claim(
+    sandbox: &Sandbox,
+    alice: &Account,
+    referrer: &Account,
+    proof: &[CryptoHash],
+    lockup_contract: Option<&AccountId>,
+    extra_gas: Gas,
+) -> Result<ExecutionFinalResult, Box<dyn std::error::Error>> {
+    let min_claim_gas: Gas = sandbox.contract.view("get_min_claim_gas").await?.json()?;
+    let result = alice
+        .call(sandbox.contract.id(), "claim")
+        .args_json(json!({
+            "amount": U128(AMOUNT),
+            "merkle_proof": proof,
+            "campaign_id": 1,
+            "lockup_contract": lockup_contract,
+            "options": { "referrer": referrer.id() },
+        }))
+        .gas(min_claim_gas.saturating_add(extra_gas))
+        .transact()
+        .await?;
+
+    for outcome in result.outcomes() {
+        eprintln!("{}: {} burnt", outcome.executor_id, outcome.gas_burnt);
+    }
+    Ok(result)
+}
+
+#[tokio::test]
+async fn test_payout_call_claim_with_min_claim_gas() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let alice = sandbox.account("alice").await?;
+    let bob = sandbox.account("bob").await?;
+    let receiver = sandbox.deploy_mock_receiver("receiver", alice.id()).await?;
+    let payout_call_gas = Gas::from_tgas(20);
+    let proof = heaviest_campaign_setup(
+        &sandbox,
+        &alice,
+        receiver.id(),
+        json!({
+            "referral": { "bps": 500 },
+            "payout_call": {
+                "method_name": "deposit",
+                "args_template": r#"{"account_id":"{account_id}"}"#,
+                "gas": payout_call_gas,
+            }
+        }),
+    )
+    .await?;
+
+    let result = referred_claim(
+        &sandbox,
+        &alice,
+        &bob,
+        &proof,
+        Some(receiver.id()),
+        payout_call_gas,
+    )
+    .await?;
+
+    assert!(result.receipt_failures().is_empty());
+    assert!(sandbox.has_claimed(1, alice.id()).await?);
+    assert_eq!(common::events(&result, "claim").len(), 1);
+    assert_eq!(common::events(&result, "referral").len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vesting_claim_with_min_claim_gas() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let alice = sandbox.account("alice").await?;
+    let bob = sandbox.account("bob").await?;
+    let now = sandbox.worker.view_block().await?.timestamp();
+    let proof = heaviest_campaign_setup(
+        &sandbox,
+        &alice,
+        alice.id(),
+        json!({
+            "referral": { "bps": 500 },
+            "vesting_end": (now + 100 * DAY).to_string(),
+        }),
+    )
+    .await?;
+
+    let result = referred_claim(&sandbox, &alice, &bob, &proof, None, Gas::from_tgas(0)).await?;
+
+    assert!(result.receipt_failures().is_empty());
+    assert!(sandbox.has_claimed(1, alice.id()).await?);
+    assert_eq!(common::events(&result, "claim").len(), 1);
+    assert_eq!(common::events(&result, "referral").len(), 1);
+    Ok(())
+}
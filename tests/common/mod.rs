@@ -0,0 +1,246 @@
+//! Helpers for the sandbox tests, which deploy the contract to a local NEAR node to check what
+//! only real receipts show: cross-contract calls, their callbacks and account creation.
+#![allow(dead_code)]
+
+use near_merkle_claim::{CampaignId, LeafFormat};
+use near_sdk::json_types::U128;
+use near_sdk::CryptoHash;
+use near_workspaces::network::Sandbox as SandboxNetwork;
+use near_workspaces::result::ExecutionFinalResult;
+use near_workspaces::types::{Gas, NearToken};
+use near_workspaces::{Account, AccountId, Contract, Worker};
+use serde_json::{json, Value};
+use tokio::sync::OnceCell;
+
+pub type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+/// The gas claims are sent with, enough for every payout path.
+pub const CLAIM_GAS: Gas = Gas::from_tgas(200);
+
+static CONTRACT_WASM: OnceCell<Vec<u8>> = OnceCell::const_new();
+static MOCK_RECEIVER_WASM: OnceCell<Vec<u8>> = OnceCell::const_new();
+
+/// Returns whether a sandbox node can be started. Builds that point `NEAR_SANDBOX_BIN_PATH` at
+/// anything but `neard`, e.g. `/bin/true` to skip downloading the node, skip the sandbox tests.
+pub fn sandbox_available() -> bool {
+    let Some(path) = std::env::var_os("NEAR_SANDBOX_BIN_PATH") else {
+        return true;
+    };
+
+    std::process::Command::new(path)
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("neard"))
+}
+
+/// A sandbox with the contract deployed to `claims.test.near`, owned by `owner.test.near`.
+pub struct Sandbox {
+    pub worker: Worker<SandboxNetwork>,
+    pub root: Account,
+    pub owner: Account,
+    pub contract: Contract,
+}
+
+impl Sandbox {
+    /// Starts a sandbox and deploys the contract with 100 NEAR, or returns `None` if no sandbox
+    /// is available.
+    pub async fn start() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        if !sandbox_available() {
+            eprintln!("Skipping sandbox test, NEAR_SANDBOX_BIN_PATH is not a sandbox node");
+            return Ok(None);
+        }
+
+        let wasm = CONTRACT_WASM
+            .get_or_try_init(|| near_workspaces::compile_project("./"))
+            .await?;
+        let worker = near_workspaces::sandbox().await?;
+        let root = worker.root_account()?;
+        let owner = create_account(&root, "owner", NearToken::from_near(10)).await?;
+        let contract = create_account(&root, "claims", NearToken::from_near(100))
+            .await?
+            .deploy(wasm)
+            .await?
+            .into_result()?;
+
+        contract
+            .call("new")
+            .args_json(json!({
+                "config": {
+                    "owner_account_id": owner.id(),
+                    "min_storage_deposit": NearToken::from_near(1),
+                }
+            }))
+            .deposit(NearToken::from_near(1))
+            .transact()
+            .await?
+            .into_result()?;
+
+        Ok(Some(Self {
+            worker,
+            root,
+            owner,
+            contract,
+        }))
+    }
+
+    /// Creates `<name>.test.near` with 10 NEAR.
+    pub async fn account(&self, name: &str) -> near_workspaces::Result<Account> {
+        create_account(&self.root, name, NearToken::from_near(10)).await
+    }
+
+    /// Deploys the mock receiver to `<name>.test.near`, reporting `owner_id` as its owner.
+    pub async fn deploy_mock_receiver(
+        &self,
+        name: &str,
+        owner_id: &AccountId,
+    ) -> Result<Contract, Box<dyn std::error::Error>> {
+        let wasm = MOCK_RECEIVER_WASM
+            .get_or_try_init(|| near_workspaces::compile_project("./tests/contracts/mock-receiver"))
+            .await?;
+        let mock = self
+            .account(name)
+            .await?
+            .deploy(wasm)
+            .await?
+            .into_result()?;
+
+        mock.call("new")
+            .args_json(json!({ "owner_account_id": owner_id }))
+            .transact()
+            .await?
+            .into_result()?;
+
+        Ok(mock)
+    }
+
+    /// Creates a campaign of `leaves` as the owner, with `options` on top of the defaults, and
+    /// returns the proof of every leaf.
+    pub async fn create_campaign(
+        &self,
+        format: &LeafFormat,
+        leaves: &[CryptoHash],
+        options: Value,
+    ) -> Result<Vec<Vec<CryptoHash>>, Box<dyn std::error::Error>> {
+        let (root, proofs) = build_tree(format, leaves);
+
+        self.owner
+            .call(self.contract.id(), "create_campaign")
+            .args_json(json!({
+                "merkle_root": root,
+                "claim_end": null,
+                "options": options,
+            }))
+            .deposit(NearToken::from_yoctonear(1))
+            .gas(Gas::from_tgas(100))
+            .transact()
+            .await?
+            .into_result()?;
+
+        Ok(proofs)
+    }
+
+    /// Claims `amount` of `account`'s allocation in `campaign_id`, paid to `lockup_contract` if
+    /// given.
+    pub async fn claim(
+        &self,
+        account: &Account,
+        campaign_id: CampaignId,
+        amount: u128,
+        merkle_proof: &[CryptoHash],
+        lockup_contract: Option<&AccountId>,
+    ) -> near_workspaces::Result<ExecutionFinalResult> {
+        account
+            .call(self.contract.id(), "claim")
+            .args_json(json!({
+                "amount": U128(amount),
+                "merkle_proof": merkle_proof,
+                "campaign_id": campaign_id,
+                "lockup_contract": lockup_contract,
+            }))
+            .gas(CLAIM_GAS)
+            .transact()
+            .await
+    }
+
+    pub async fn has_claimed(
+        &self,
+        campaign_id: CampaignId,
+        account_id: &AccountId,
+    ) -> near_workspaces::Result<bool> {
+        self.contract
+            .view("has_claimed")
+            .args_json(json!({ "campaign_id": campaign_id, "account_id": account_id }))
+            .await?
+            .json()
+    }
+
+    pub async fn balance(&self, account_id: &AccountId) -> near_workspaces::Result<NearToken> {
+        Ok(self.worker.view_account(account_id).await?.balance)
+    }
+}
+
+async fn create_account(
+    root: &Account,
+    name: &str,
+    balance: NearToken,
+) -> near_workspaces::Result<Account> {
+    Ok(root
+        .create_subaccount(name)
+        .initial_balance(balance)
+        .transact()
+        .await?
+        .into_result()?)
+}
+
+/// Returns the leaf of an allocation of `amount` to `account_id`, paid to `receiver_id`.
+pub fn leaf(
+    format: &LeafFormat,
+    account_id: &AccountId,
+    receiver_id: &AccountId,
+    amount: u128,
+) -> CryptoHash {
+    format.compute_leaf(None, account_id, receiver_id, amount, None, None)
+}
+
+/// Builds the commutative tree of `leaves`, carrying an odd node up unchanged, and returns its
+/// root with the proof of every leaf.
+pub fn build_tree(
+    format: &LeafFormat,
+    leaves: &[CryptoHash],
+) -> (CryptoHash, Vec<Vec<CryptoHash>>) {
+    let hasher = format.tree_hasher();
+    let mut proofs = vec![Vec::new(); leaves.len()];
+    let mut positions: Vec<usize> = (0..leaves.len()).collect();
+    let mut layer = leaves.to_vec();
+
+    while layer.len() > 1 {
+        for (proof, position) in proofs.iter_mut().zip(&mut positions) {
+            if let Some(sibling) = layer.get(*position ^ 1) {
+                proof.push(*sibling);
+            }
+            *position /= 2;
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hasher.hash_pair(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    (layer[0], proofs)
+}
+
+/// Returns the data of every `event` logged by `result`'s receipts.
+pub fn events(result: &ExecutionFinalResult, event: &str) -> Vec<Value> {
+    result
+        .logs()
+        .iter()
+        .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+        .map(|envelope| serde_json::from_str::<Value>(envelope).expect("Event is valid JSON"))
+        .filter(|envelope| envelope["event"] == event)
+        .map(|envelope| envelope["data"][0].clone())
+        .collect()
+}
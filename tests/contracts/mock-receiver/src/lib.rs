@@ -0,0 +1,39 @@
+//! A stand-in for the accounts claims are paid to in the sandbox tests. It answers
+//! `get_owner_account_id` like a lockup contract and records the deposits made through `deposit`
+//! like a receiver that must be paid with a method call.
+
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, AccountId, PanicOnDefault};
+
+#[near(contract_state)]
+#[derive(PanicOnDefault)]
+pub struct MockReceiver {
+    owner_account_id: AccountId,
+    deposits: Vec<(AccountId, U128)>,
+}
+
+#[near]
+impl MockReceiver {
+    #[init]
+    pub fn new(owner_account_id: AccountId) -> Self {
+        Self {
+            owner_account_id,
+            deposits: Vec::new(),
+        }
+    }
+
+    pub fn get_owner_account_id(&self) -> AccountId {
+        self.owner_account_id.clone()
+    }
+
+    /// Records the attached deposit as paid to `account_id`.
+    #[payable]
+    pub fn deposit(&mut self, account_id: AccountId) {
+        self.deposits
+            .push((account_id, U128(env::attached_deposit().as_yoctonear())));
+    }
+
+    pub fn get_deposits(&self) -> Vec<(AccountId, U128)> {
+        self.deposits.clone()
+    }
+}
@@ -0,0 +1,111 @@
+//! An emergency shutdown spread over several calls cancels every campaign once and sweeps the
+//! balance, after which it changes nothing.
+
+mod common;
+
+use common::{leaf, Sandbox, TestResult};
+use near_merkle_claim::LeafFormat;
+use near_workspaces::result::ExecutionFinalResult;
+use near_workspaces::types::{Gas, NearToken};
+use serde_json::{json, Value};
+
+const CAMPAIGNS: u32 = 12;
+
+async fn emergency_shutdown(sandbox: &Sandbox) -> near_workspaces::Result<ExecutionFinalResult> {
+    sandbox
+        .owner
+        .call(sandbox.contract.id(), "emergency_shutdown")
+        .args_json(json!({ "limit": 5 }))
+        .deposit(NearToken::from_yoctonear(1))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await
+}
+
+#[tokio::test]
+async fn test_emergency_shutdown_of_many_campaigns() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let alice = sandbox.account("alice").await?;
+    for campaign_id in 1..=CAMPAIGNS {
+        let format = LeafFormat {
+            campaign_id,
+            ..Default::default()
+        };
+        sandbox
+            .create_campaign(
+                &format,
+                &[
+                    leaf(&format, alice.id(), alice.id(), 1000),
+                    leaf(&format, sandbox.owner.id(), sandbox.owner.id(), 1000),
+                ],
+                json!({}),
+            )
+            .await?;
+    }
+    let owner_balance = sandbox.balance(sandbox.owner.id()).await?.as_yoctonear();
+
+    // 5 campaigns are cancelled per call, the last call sweeps the balance
+    let mut cancelled = Vec::new();
+    let mut completed = Vec::new();
+    let mut swept = Vec::new();
+    for _ in 0..3 {
+        let result = emergency_shutdown(&sandbox).await?;
+        assert!(result.receipt_failures().is_empty());
+        cancelled.extend(common::events(&result, "campaign_cancelled"));
+        swept.extend(common::events(&result, "emergency_shutdown"));
+        completed.push(result.json::<bool>()?);
+    }
+
+    assert_eq!(completed, vec![false, false, true]);
+    let mut cancelled_ids: Vec<u64> = cancelled
+        .iter()
+        .map(|event| event["campaign_id"].as_u64().unwrap())
+        .collect();
+    cancelled_ids.sort();
+    assert_eq!(cancelled_ids, (1..=CAMPAIGNS as u64).collect::<Vec<_>>());
+    for campaign_id in 1..=CAMPAIGNS {
+        let status: String = sandbox
+            .contract
+            .view("get_campaign_status")
+            .args_json(json!({ "campaign_id": campaign_id }))
+            .await?
+            .json()?;
+        assert_eq!(status, "Ended");
+    }
+    assert!(sandbox.contract.view("is_paused").await?.json::<bool>()?);
+
+    assert_eq!(swept.len(), 1);
+    let amount: u128 = swept[0]["amount"].as_str().unwrap().parse()?;
+    assert_eq!(swept[0]["receiver_id"], json!(sandbox.owner.id()));
+    // The owner paid at most 0.1 NEAR of gas for the three calls
+    assert!(
+        sandbox.balance(sandbox.owner.id()).await?.as_yoctonear()
+            + NearToken::from_millinear(100).as_yoctonear()
+            >= owner_balance + amount
+    );
+
+    // Calls after the shutdown completed change nothing
+    let contract_balance = sandbox.balance(sandbox.contract.id()).await?;
+    let shutdown: Value = sandbox
+        .contract
+        .view("get_emergency_shutdown")
+        .await?
+        .json()?;
+    let result = emergency_shutdown(&sandbox).await?;
+
+    assert!(result.logs().is_empty());
+    assert!(result.json::<bool>()?);
+    assert!(sandbox.balance(sandbox.contract.id()).await? >= contract_balance);
+    assert_eq!(
+        sandbox
+            .contract
+            .view("get_emergency_shutdown")
+            .await?
+            .json::<Value>()?,
+        shutdown
+    );
+    assert_eq!(shutdown["next_campaign_id"], CAMPAIGNS + 1);
+    Ok(())
+}
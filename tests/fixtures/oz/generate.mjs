@@ -0,0 +1,70 @@
+// Regenerates the @openzeppelin/merkle-tree fixtures of the tests with the library itself:
+// `oz-tree.json` and `oz-tree-corrupted.json` are written next to `allocations.csv`, and the
+// roots and proofs of `OZ_STANDARD_FIXTURE` and `OZ_MULTI_PROOFS` in `src/lib.rs` are printed.
+//
+//   cd tests/fixtures/oz && npm install && npm run generate
+import { readFileSync, writeFileSync } from "node:fs";
+import { SimpleMerkleTree, StandardMerkleTree } from "@openzeppelin/merkle-tree";
+
+const fixtures = new URL("../", import.meta.url);
+const encoding = ["string", "string", "uint256"];
+const hex = (hash) => hash.replace(/^0x/, "");
+
+// The allocations of `allocations.csv` as `(account, lockup, amount)`, where an empty lockup
+// is the account itself
+const values = readFileSync(new URL("allocations.csv", fixtures), "utf8")
+  .trim()
+  .split("\n")
+  .slice(1)
+  .map((line) => {
+    const [account, lockup, amount] = line.split(",");
+    return [account, lockup || account, amount];
+  });
+
+const write = (name, dump) =>
+  writeFileSync(new URL(name, fixtures), JSON.stringify(dump, null, 2) + "\n");
+
+const tree = StandardMerkleTree.of(values, encoding);
+write("oz-tree.json", tree.dump());
+
+// The same dump with dave.near's amount changed, so its leaf no longer matches the tree
+const corrupted = tree.dump();
+corrupted.values.find(({ value }) => value[0] === "dave.near").value[2] = "43";
+write("oz-tree-corrupted.json", corrupted);
+
+// `OZ_STANDARD_FIXTURE`: the claims tests' accounts, of which the first, third and fifth
+// values are pinned in `src/lib.rs`
+const standard = StandardMerkleTree.of(
+  [
+    ["claimant", "claimant", "1000"],
+    ["non_owner", "non_owner", "2000"],
+    ["claimant", "lockup-contract", "3000"],
+    ["system_account", "system_account", "4000"],
+    ["some-account.near", "some-lockup.lockup.near", "1000000000000000000000000"],
+  ],
+  encoding,
+);
+console.log("OZ_STANDARD_FIXTURE root", hex(standard.root));
+for (const i of [0, 2, 4]) {
+  console.log(" ", standard.at(i), standard.getProof(i).map(hex));
+}
+
+// `OZ_MULTI_PROOFS`: the `V5` leaves of campaign 1, i.e. keccak256(keccak256(borsh(1, account,
+// account, 1000, nonce))), as `mock_leaf_v5` of `src/lib.rs` computes them
+const v5Leaves = [
+  ["claimant", 0, "0x6807fb94efd5af2e3f47cac421b9f6d5b3f505b2fd6480a158a3da65be8af7bf"],
+  ["claimant", 1, "0x09c3064ffb2138e4b7f75e6fdc68bf795ee2f2d0c71de7f82952cb1578fcee0e"],
+  ["claimant", 2, "0x2a00ba05922b641bec38a90bad7de5ec8a113666e9925bb739c76bd5e72a9b61"],
+  ["non_owner", 0, "0x4f3629779160dcc6401e090316664b9cba7133c1f13745806de36f942933895a"],
+  ["system_account", 0, "0x40543a6f2cb61b6b13b68d55e5e896ca6a3e29c3d0e6170b22ca928aac83e359"],
+];
+const simple = SimpleMerkleTree.of(v5Leaves.map(([, , leaf]) => leaf));
+console.log("OZ_MULTI_PROOF_ROOT", hex(simple.root));
+for (const indices of [
+  [0, 2],
+  [0, 1, 2],
+]) {
+  const { leaves, proof, proofFlags } = simple.getMultiProof(indices);
+  const nonces = leaves.map((leaf) => v5Leaves.find(([, , l]) => l === leaf)[1]);
+  console.log(" ", nonces, proof.map(hex), proofFlags);
+}
@@ -0,0 +1,79 @@
+//! Claims of `verify_lockup_owner` campaigns against a mock lockup contract.
+
+mod common;
+
+use common::{leaf, Sandbox, TestResult};
+use near_merkle_claim::LeafFormat;
+use near_workspaces::types::NearToken;
+use serde_json::json;
+
+const AMOUNT: u128 = NearToken::from_near(2).as_yoctonear();
+
+#[tokio::test]
+async fn test_lockup_owned_by_claimant() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let alice = sandbox.account("alice").await?;
+    let lockup = sandbox.deploy_mock_receiver("lockup", alice.id()).await?;
+    let format = LeafFormat {
+        campaign_id: 1,
+        ..Default::default()
+    };
+    let proofs = sandbox
+        .create_campaign(
+            &format,
+            &[
+                leaf(&format, alice.id(), lockup.id(), AMOUNT),
+                leaf(&format, sandbox.owner.id(), sandbox.owner.id(), AMOUNT),
+            ],
+            json!({ "verify_lockup_owner": true }),
+        )
+        .await?;
+    let balance = sandbox.balance(lockup.id()).await?;
+
+    let result = sandbox
+        .claim(&alice, 1, AMOUNT, &proofs[0], Some(lockup.id()))
+        .await?;
+
+    assert!(result.receipt_failures().is_empty());
+    assert!(sandbox.has_claimed(1, alice.id()).await?);
+    assert!(sandbox.balance(lockup.id()).await?.as_yoctonear() >= balance.as_yoctonear() + AMOUNT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lockup_owned_by_another_account() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let alice = sandbox.account("alice").await?;
+    let bob = sandbox.account("bob").await?;
+    let lockup = sandbox.deploy_mock_receiver("lockup", bob.id()).await?;
+    let format = LeafFormat {
+        campaign_id: 1,
+        ..Default::default()
+    };
+    let proofs = sandbox
+        .create_campaign(
+            &format,
+            &[
+                leaf(&format, alice.id(), lockup.id(), AMOUNT),
+                leaf(&format, bob.id(), bob.id(), AMOUNT),
+            ],
+            json!({ "verify_lockup_owner": true }),
+        )
+        .await?;
+    let balance = sandbox.balance(lockup.id()).await?;
+
+    let result = sandbox
+        .claim(&alice, 1, AMOUNT, &proofs[0], Some(lockup.id()))
+        .await?;
+
+    // The callback refuses the claim, which stays claimable and pays nothing
+    assert!(format!("{:?}", result.receipt_failures())
+        .contains("Lockup contract is not owned by the claimant"));
+    assert!(!sandbox.has_claimed(1, alice.id()).await?);
+    assert!(sandbox.balance(lockup.id()).await?.as_yoctonear() < balance.as_yoctonear() + AMOUNT);
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! The `merkle-tree` binary, which refuses to build trees bound to a campaign id it was not
+//! given.
+#![cfg(feature = "tree")]
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Writes two allocations to a CSV in a fresh directory and returns its path.
+fn allocations_csv(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("merkle-tree-cli-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let csv = dir.join("allocations.csv");
+    std::fs::write(
+        &csv,
+        "account,lockup,amount\nalice.near,,1000\nbob.near,,500\n",
+    )
+    .unwrap();
+
+    csv
+}
+
+fn merkle_tree(csv: &PathBuf, format: Option<&str>) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_merkle-tree"));
+    command.arg(csv).arg(csv.with_file_name("tree.json"));
+    if let Some(format) = format {
+        command.arg(format);
+    }
+
+    command.output().unwrap()
+}
+
+#[test]
+fn test_cli_builds_tree_for_campaign_id() {
+    let csv = allocations_csv("bound");
+
+    let output = merkle_tree(&csv, Some(r#"{"campaign_id": 1}"#));
+
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains(r#""expected_campaign_id": 1"#));
+    assert!(csv.with_file_name("tree.json").exists());
+}
+
+#[test]
+fn test_cli_without_campaign_id_failure() {
+    let csv = allocations_csv("unbound");
+
+    for format in [
+        None,
+        Some("{}"),
+        Some(r#"{"campaign_id": 0, "leaf_version": "V3"}"#),
+    ] {
+        let output = merkle_tree(&csv, format);
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("The leaf format must set the campaign_id of the campaign"));
+    }
+    assert!(!csv.with_file_name("tree.json").exists());
+}
+
+#[test]
+fn test_cli_unbound_format_without_campaign_id() {
+    let csv = allocations_csv("oz");
+
+    let output = merkle_tree(&csv, Some(r#"{"leaf_encoding": "OzStandard"}"#));
+
+    assert!(output.status.success());
+}
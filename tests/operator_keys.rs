@@ -0,0 +1,136 @@
+//! Operator keys on the contract's own account, which can only create campaigns, and only while
+//! multisig confirmations are disabled.
+
+mod common;
+
+use common::{Sandbox, TestResult};
+use near_workspaces::result::ExecutionFinalResult;
+use near_workspaces::types::{Gas, KeyType, NearToken, SecretKey};
+use near_workspaces::Account;
+use serde_json::{json, Value};
+
+/// Adds a random operator key with an allowance of 1 NEAR and returns the contract's account
+/// signing with it.
+async fn add_operator_key(sandbox: &Sandbox) -> Result<Account, Box<dyn std::error::Error>> {
+    let secret_key = SecretKey::from_random(KeyType::ED25519);
+
+    sandbox
+        .owner
+        .call(sandbox.contract.id(), "add_operator_key")
+        .args_json(json!({
+            "public_key": secret_key.public_key(),
+            "allowance": NearToken::from_near(1),
+        }))
+        .deposit(NearToken::from_yoctonear(1))
+        .transact()
+        .await?
+        .into_result()?;
+
+    Ok(Account::from_secret_key(
+        sandbox.contract.id().clone(),
+        secret_key,
+        &sandbox.worker,
+    ))
+}
+
+/// Creates a perpetual campaign as `operator`, without a deposit.
+async fn create_campaign(operator: &Account) -> near_workspaces::Result<ExecutionFinalResult> {
+    let merkle_root = [7u8; 32];
+
+    operator
+        .call(operator.id(), "create_campaign")
+        .args_json(json!({ "merkle_root": merkle_root, "claim_end": null }))
+        .gas(Gas::from_tgas(100))
+        .transact()
+        .await
+}
+
+/// Whether a call signed by `operator` was refused, either by the key's method restriction
+/// before it ran or by the contract.
+fn refused(result: near_workspaces::Result<ExecutionFinalResult>) -> bool {
+    result.map_or(true, |result| result.is_failure())
+}
+
+#[tokio::test]
+async fn test_operator_key_creates_campaigns_only() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let operator = add_operator_key(&sandbox).await?;
+
+    create_campaign(&operator).await?.into_result()?;
+
+    let campaign: Value = sandbox
+        .contract
+        .view("get_campaign")
+        .args_json(json!({ "campaign_id": 1 }))
+        .await?
+        .json()?;
+    assert!(campaign.is_object());
+    let withdraw = operator
+        .call(operator.id(), "withdraw")
+        .args_json(json!({}))
+        .transact()
+        .await;
+    assert!(refused(withdraw));
+
+    sandbox
+        .owner
+        .call(sandbox.contract.id(), "remove_operator_key")
+        .args_json(json!({ "public_key": operator.secret_key().public_key() }))
+        .deposit(NearToken::from_yoctonear(1))
+        .transact()
+        .await?
+        .into_result()?;
+
+    assert!(refused(create_campaign(&operator).await));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_operator_key_under_multisig() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let operator = add_operator_key(&sandbox).await?;
+    sandbox
+        .owner
+        .call(sandbox.contract.id(), "enable_multisig")
+        .args_json(json!({
+            "multisig": {
+                "signers": [sandbox.owner.id()],
+                "threshold": 1,
+                "proposal_lifetime": "604800000000000",
+            }
+        }))
+        .deposit(NearToken::from_yoctonear(1))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let result = create_campaign(&operator).await?;
+
+    assert!(format!("{:?}", result.failures()).contains(
+        "Operator keys cannot create campaigns while multisig confirmations are enabled"
+    ));
+
+    // The signers revoke the key, which then cannot sign anything
+    sandbox
+        .owner
+        .call(sandbox.contract.id(), "propose_action")
+        .args_json(json!({
+            "action": {
+                "RemoveOperatorKey": { "public_key": operator.secret_key().public_key() }
+            }
+        }))
+        .deposit(NearToken::from_yoctonear(1))
+        .gas(Gas::from_tgas(100))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let keys: Vec<Value> = sandbox.contract.view("get_operator_keys").await?.json()?;
+    assert!(keys.is_empty());
+    assert!(create_campaign(&operator).await.is_err());
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Claims of campaigns paying their receivers through a method call, against a mock receiver
+//! that only records deposits made through `deposit`.
+
+mod common;
+
+use common::{leaf, Sandbox, TestResult};
+use near_merkle_claim::LeafFormat;
+use near_sdk::json_types::U128;
+use near_workspaces::types::{Gas, NearToken};
+use near_workspaces::AccountId;
+use serde_json::json;
+
+const AMOUNT: u128 = NearToken::from_near(2).as_yoctonear();
+
+#[tokio::test]
+async fn test_claim_paid_through_deposit_call() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let alice = sandbox.account("alice").await?;
+    let receiver = sandbox.deploy_mock_receiver("receiver", alice.id()).await?;
+    let format = LeafFormat {
+        campaign_id: 1,
+        ..Default::default()
+    };
+    let proofs = sandbox
+        .create_campaign(
+            &format,
+            &[
+                leaf(&format, alice.id(), receiver.id(), AMOUNT),
+                leaf(&format, sandbox.owner.id(), sandbox.owner.id(), AMOUNT),
+            ],
+            json!({
+                "payout_call": {
+                    "method_name": "deposit",
+                    "args_template": r#"{"account_id":"{account_id}"}"#,
+                    "gas": Gas::from_tgas(10),
+                }
+            }),
+        )
+        .await?;
+
+    let result = sandbox
+        .claim(&alice, 1, AMOUNT, &proofs[0], Some(receiver.id()))
+        .await?;
+
+    assert!(result.receipt_failures().is_empty());
+    assert!(sandbox.has_claimed(1, alice.id()).await?);
+    let deposits: Vec<(AccountId, U128)> = receiver.view("get_deposits").await?.json()?;
+    assert_eq!(deposits, vec![(alice.id().clone(), U128(AMOUNT))]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_payout_call_rolls_back() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let alice = sandbox.account("alice").await?;
+    let receiver = sandbox.deploy_mock_receiver("receiver", alice.id()).await?;
+    let format = LeafFormat {
+        campaign_id: 1,
+        ..Default::default()
+    };
+    let proofs = sandbox
+        .create_campaign(
+            &format,
+            &[
+                leaf(&format, alice.id(), receiver.id(), AMOUNT),
+                leaf(&format, sandbox.owner.id(), sandbox.owner.id(), AMOUNT),
+            ],
+            json!({
+                "payout_call": {
+                    "method_name": "deposit_and_stake",
+                    "args_template": "{}",
+                    "gas": Gas::from_tgas(10),
+                }
+            }),
+        )
+        .await?;
+
+    let result = sandbox
+        .claim(&alice, 1, AMOUNT, &proofs[0], Some(receiver.id()))
+        .await?;
+
+    // The mock has no such method, so the deposit comes back and the claim is rolled back
+    let failed = common::events(&result, "claim_failed");
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["reason"], "Payout call failed");
+    assert!(!sandbox.has_claimed(1, alice.id()).await?);
+    let deposits: Vec<(AccountId, U128)> = receiver.view("get_deposits").await?.json()?;
+    assert!(deposits.is_empty());
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! A claim whose transfer fails is rolled back, so it can be made again.
+
+mod common;
+
+use common::{leaf, Sandbox, TestResult};
+use near_merkle_claim::LeafFormat;
+use near_workspaces::types::NearToken;
+use serde_json::{json, Value};
+
+const AMOUNT: u128 = NearToken::from_near(2).as_yoctonear();
+
+#[tokio::test]
+async fn test_claim_again_after_failed_transfer() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let alice = sandbox.account("alice").await?;
+    // Alice's lockup does not exist yet, so the transfer to it fails
+    let lockup_id = format!("lockup.{}", alice.id()).parse()?;
+    let format = LeafFormat {
+        campaign_id: 1,
+        ..Default::default()
+    };
+    let proofs = sandbox
+        .create_campaign(
+            &format,
+            &[
+                leaf(&format, alice.id(), &lockup_id, AMOUNT),
+                leaf(&format, sandbox.owner.id(), sandbox.owner.id(), AMOUNT),
+            ],
+            json!({}),
+        )
+        .await?;
+
+    let result = sandbox
+        .claim(&alice, 1, AMOUNT, &proofs[0], Some(&lockup_id))
+        .await?;
+
+    assert_eq!(common::events(&result, "claim_failed").len(), 1);
+    assert!(!sandbox.has_claimed(1, alice.id()).await?);
+    let stats: Value = sandbox
+        .contract
+        .view("get_campaign_stats")
+        .args_json(json!({ "campaign_id": 1 }))
+        .await?
+        .json()?;
+    assert_eq!(stats["claim_count"], 0);
+    assert_eq!(stats["claimed_total"], "0");
+
+    let lockup = alice
+        .create_subaccount("lockup")
+        .initial_balance(NearToken::from_near(1))
+        .transact()
+        .await?
+        .into_result()?;
+    let result = sandbox
+        .claim(&alice, 1, AMOUNT, &proofs[0], Some(lockup.id()))
+        .await?;
+
+    assert!(result.receipt_failures().is_empty());
+    assert!(sandbox.has_claimed(1, alice.id()).await?);
+    assert_eq!(
+        sandbox.balance(lockup.id()).await?.as_yoctonear(),
+        NearToken::from_near(1).as_yoctonear() + AMOUNT
+    );
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Claims of `verify_receiver_exists` campaigns to existing and missing receivers.
+
+mod common;
+
+use common::{leaf, Sandbox, TestResult};
+use near_merkle_claim::LeafFormat;
+use near_workspaces::types::NearToken;
+use near_workspaces::AccountId;
+use serde_json::json;
+
+const AMOUNT: u128 = NearToken::from_near(2).as_yoctonear();
+
+#[tokio::test]
+async fn test_claim_to_existing_receiver() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let alice = sandbox.account("alice").await?;
+    let receiver = sandbox.account("receiver").await?;
+    let format = LeafFormat {
+        campaign_id: 1,
+        ..Default::default()
+    };
+    let proofs = sandbox
+        .create_campaign(
+            &format,
+            &[
+                leaf(&format, alice.id(), receiver.id(), AMOUNT),
+                leaf(&format, sandbox.owner.id(), sandbox.owner.id(), AMOUNT),
+            ],
+            json!({ "verify_receiver_exists": true }),
+        )
+        .await?;
+    let balance = sandbox.balance(receiver.id()).await?;
+
+    let result = sandbox
+        .claim(&alice, 1, AMOUNT, &proofs[0], Some(receiver.id()))
+        .await?;
+
+    assert!(result.receipt_failures().is_empty());
+    assert!(sandbox.has_claimed(1, alice.id()).await?);
+    assert_eq!(
+        sandbox.balance(receiver.id()).await?.as_yoctonear(),
+        balance.as_yoctonear() + AMOUNT
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_claim_to_missing_receiver() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let alice = sandbox.account("alice").await?;
+    let missing: AccountId = "missing.test.near".parse()?;
+    let format = LeafFormat {
+        campaign_id: 1,
+        ..Default::default()
+    };
+    let proofs = sandbox
+        .create_campaign(
+            &format,
+            &[
+                leaf(&format, alice.id(), &missing, AMOUNT),
+                leaf(&format, sandbox.owner.id(), sandbox.owner.id(), AMOUNT),
+            ],
+            json!({ "verify_receiver_exists": true }),
+        )
+        .await?;
+    let balance = sandbox.balance(sandbox.contract.id()).await?;
+
+    let result = sandbox
+        .claim(&alice, 1, AMOUNT, &proofs[0], Some(&missing))
+        .await?;
+
+    // Nothing was recorded or paid, the claim can still be made once the account exists
+    assert!(format!("{:?}", result.receipt_failures()).contains("Receiver account does not exist"));
+    assert!(common::events(&result, "claim").is_empty());
+    assert!(!sandbox.has_claimed(1, alice.id()).await?);
+    assert!(sandbox.balance(sandbox.contract.id()).await?.as_yoctonear() >= balance.as_yoctonear());
+    Ok(())
+}
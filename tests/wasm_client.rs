@@ -0,0 +1,51 @@
+//! The wasm client on wasm32, run headless by wasm-bindgen-test, against the tree the native
+//! `tree` builder wrote to `tests/fixtures/allocations.json`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm-client"))]
+
+use near_merkle_claim::wasm_client::{compute_leaf, verify_proof};
+use near_sdk::serde_json::{self, Value};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// The output of `merkle-tree` for `tests/fixtures/allocations.csv`, which
+/// `test_campaign_file_golden` pins to the native builder.
+const ALLOCATIONS: &str = include_str!("fixtures/allocations.json");
+
+#[wasm_bindgen_test]
+fn test_wasm_client_matches_native_tree() {
+    let file: Value = serde_json::from_str(ALLOCATIONS).unwrap();
+    let format = file["leaf_format"].to_string();
+    let root = file["root"].as_str().unwrap();
+    let claims = file["claims"].as_array().unwrap();
+    assert!(!claims.is_empty());
+
+    for claim in claims {
+        let account = claim["account"].as_str().unwrap();
+        let lockup = claim["lockup"].as_str().unwrap();
+        let amount = claim["amount"].as_str().unwrap();
+        let proof: Vec<String> = serde_json::from_value(claim["proof"].clone()).unwrap();
+
+        // The leaf computed in wasm is the one the native tree proved
+        let leaf = compute_leaf(&format, account, lockup, amount, None).unwrap();
+        assert!(verify_proof(&leaf, proof.clone(), root, Some(format.clone())).unwrap());
+
+        let other_amount = (amount.parse::<u128>().unwrap() + 1).to_string();
+        let other_leaf = compute_leaf(&format, account, lockup, &other_amount, None).unwrap();
+        assert!(!verify_proof(&other_leaf, proof, root, Some(format.clone())).unwrap());
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_wasm_client_known_leaf() {
+    // The leaf pinned by `test_compute_leaf_known_vectors` for the native build
+    assert_eq!(
+        compute_leaf(
+            r#"{"campaign_id": 1}"#,
+            "claimant",
+            "lockup-contract",
+            "1000",
+            None
+        )
+        .unwrap(),
+        "0xb989009bab184e24c7775adb42121f5cb0d4f0ce6bbf7673bda3bd7f961c256b"
+    );
+}
@@ -0,0 +1,41 @@
+//! The withdraw event is logged by the transfer's callback, with the balance left afterwards.
+
+mod common;
+
+use common::{Sandbox, TestResult};
+use near_workspaces::types::{Gas, NearToken};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_withdraw_event_balance() -> TestResult {
+    let Some(sandbox) = Sandbox::start().await? else {
+        return Ok(());
+    };
+    let receiver = sandbox.account("receiver").await?;
+    let receiver_balance = sandbox.balance(receiver.id()).await?;
+
+    let result = sandbox
+        .owner
+        .call(sandbox.contract.id(), "withdraw_to")
+        .args_json(json!({ "receiver": receiver.id() }))
+        .deposit(NearToken::from_yoctonear(1))
+        .gas(Gas::from_tgas(100))
+        .transact()
+        .await?;
+
+    assert!(result.receipt_failures().is_empty());
+    let withdrawn = common::events(&result, "withdraw");
+    assert_eq!(withdrawn.len(), 1);
+    let amount: u128 = withdrawn[0]["withdrawn"].as_str().unwrap().parse()?;
+    let balance: u128 = withdrawn[0]["balance"].as_str().unwrap().parse()?;
+    assert!(amount > 0);
+    assert_eq!(
+        sandbox.balance(receiver.id()).await?.as_yoctonear(),
+        receiver_balance.as_yoctonear() + amount
+    );
+    // The contract only earned its share of the callback's gas since the event was logged
+    let contract_balance = sandbox.balance(sandbox.contract.id()).await?.as_yoctonear();
+    assert!(balance <= contract_balance);
+    assert!(contract_balance - balance < NearToken::from_millinear(1).as_yoctonear());
+    Ok(())
+}